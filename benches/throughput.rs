@@ -0,0 +1,58 @@
+//! Compares the throughput of moving 1MiB chunks through shared memory against a pipe.
+//!
+//! run with:
+//! ```bash
+//! cargo bench --bench throughput
+//! ```
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::FromRawFd;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shmem_bind as shmem;
+
+const CHUNK_SIZE: usize = 1 << 20;
+
+fn pipe() -> (File, File) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+}
+
+fn bulk_transfer(c: &mut Criterion) {
+    let chunk = vec![0xa5_u8; CHUNK_SIZE];
+
+    let mut group = c.benchmark_group("bulk_transfer");
+    group.throughput(Throughput::Bytes(CHUNK_SIZE as u64));
+
+    let shared_mem = shmem::Builder::new("shmem-bench_throughput.shm")
+        .with_size(CHUNK_SIZE)
+        .open()
+        .unwrap();
+    let mut bytes = shared_mem.boxed_bytes();
+    group.bench_function(BenchmarkId::new("shmem_write_at", "1MiB"), |b| {
+        b.iter(|| bytes.write_at(0, &chunk).unwrap())
+    });
+
+    // the reader drains the pipe on a separate thread so the writer never blocks on a full
+    // pipe buffer for longer than a context switch
+    let (mut reader, mut writer) = pipe();
+    let drain = std::thread::spawn(move || {
+        let mut buf = vec![0_u8; CHUNK_SIZE];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+        }
+    });
+    group.bench_function(BenchmarkId::new("pipe_write", "1MiB"), |b| {
+        b.iter(|| writer.write_all(&chunk).unwrap())
+    });
+    drop(writer);
+    drain.join().unwrap();
+
+    group.finish();
+}
+
+criterion_group!(benches, bulk_transfer);
+criterion_main!(benches);
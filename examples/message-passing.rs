@@ -1,5 +1,5 @@
 extern crate shmem_bind;
-use shmem_bind::{self as shmem, ShmemBox};
+use shmem_bind::{self as shmem, ShmemConf};
 
 use std::error::Error;
 use std::mem;
@@ -17,27 +17,26 @@ impl Drop for Message {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // create new shared memory pointer with desired size
-    //
-    // first call to this function with the same FILE_LINK_ID would result in creating a new shared
-    // memory file and owning it. this would result in deleting the shared memory when the variable
-    // goes out of scope.
-    // the second call to this function will only open shared memory and would not delete it.
-    let shared_mem = shmem::Builder::new("shmem-example_message-passing.shm")
-        .with_size(mem::size_of::<Message>() as i64)
-        .open()?;
-
-    // wrap the raw shared memory ptr with desired Boxed type
-    // user must ensure that the data the pointer is pointing to is initialized and valid for use
-    let mut message = unsafe { shared_mem.boxed::<Message>() };
-
     let mut args = std::env::args();
     let num_args = args.len();
     match num_args {
         // parent process
         1 => {
-            // ensure that first process owns the shared memory (used for cleanup)
-            let mut message = ShmemBox::own(message);
+            // create new shared memory pointer with desired size
+            //
+            // the call to `open` creates a new shared memory file and owns it. this would result
+            // in deleting the shared memory when the variable goes out of scope.
+            let shared_mem = shmem::Builder::new("shmem-example_message-passing.shm")
+                .with_size(mem::size_of::<Message>())
+                .open()?;
+
+            // a single self-describing token telling the child how to attach
+            let handle_string = shared_mem.to_handle_string();
+
+            // wrap the raw shared memory ptr with desired Boxed type
+            // user must ensure that the data the pointer is pointing to is initialized and valid
+            // for use
+            let mut message = unsafe { shared_mem.boxed::<Message>() };
 
             // initiate the data behind the boxed pointer
             message.val = 1;
@@ -46,6 +45,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             let new_val = 5;
             // create new process to mutate the shared memory
             let mut handle = Command::new(&binary_path)
+                .arg(&handle_string)
                 .arg(format!("{new_val}"))
                 .spawn()
                 .unwrap();
@@ -57,8 +57,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             // message is dropped here, shared memory IS deallocated
         }
         // child process
-        2 => {
-            let value = std::env::args().last().unwrap().parse()?;
+        3 => {
+            let handle_string = args.nth(1).unwrap();
+            let value = args.next().unwrap().parse()?;
+
+            // attaching via the handle never claims the ownership
+            let shared_mem = ShmemConf::from_handle_string(&handle_string)?;
+            let mut message = unsafe { shared_mem.boxed::<Message>() };
 
             message.val = value;
 
@@ -0,0 +1,275 @@
+//! Derive macros for the `ShmemSafe` and `ShmemSchema` traits of `shmem-bind`, enabled by its
+//! `derive` feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, GenericArgument,
+    PathArguments, ReturnType, Type,
+};
+
+/// Implements `ShmemSafe` for a struct whose fields are all `ShmemSafe`.
+///
+/// The derive fails to compile unless
+/// - the struct is `#[repr(C)]` or `#[repr(transparent)]`, so its layout is the same in every
+///   process,
+/// - no field mentions a reference or a raw pointer, which are process-local,
+/// - every field implements `ShmemSafe`, which rules out heap-allocating types like `Box`,
+///   `Vec` or `String`.
+///
+/// Type parameters of a generic struct are required to be `ShmemSafe` as well.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::AtomicU64;
+/// use shmem_bind::{self as shmem,ShmemError,ShmemSafe};
+///
+/// #[derive(ShmemSafe)]
+/// #[repr(C)]
+/// struct Stats {
+///     hits: AtomicU64,
+///     samples: [f64; 4],
+/// }
+///
+/// fn main() -> Result<(),ShmemError>{
+///     let shared_mem = shmem::Builder::new("flink_test_derive_shmem_safe")
+///         .with_size(std::mem::size_of::<Stats>())
+///         .open()?;
+///
+///     // no unsafe needed
+///     let stats = shared_mem.boxed_safe::<Stats>()?;
+///     assert_eq!(stats.samples, [0.0; 4]);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// Heap pointers are rejected:
+/// ```compile_fail
+/// use shmem_bind::ShmemSafe;
+///
+/// #[derive(ShmemSafe)]
+/// #[repr(C)]
+/// struct Names {
+///     names: Vec<String>,
+/// }
+/// ```
+///
+/// So are references and raw pointers:
+/// ```compile_fail
+/// use shmem_bind::ShmemSafe;
+///
+/// #[derive(ShmemSafe)]
+/// #[repr(C)]
+/// struct Node {
+///     next: *const u64,
+/// }
+/// ```
+///
+/// And structs without a stable layout:
+/// ```compile_fail
+/// use shmem_bind::ShmemSafe;
+///
+/// #[derive(ShmemSafe)]
+/// struct Point {
+///     x: u32,
+///     y: u32,
+/// }
+/// ```
+#[proc_macro_derive(ShmemSafe)]
+pub fn derive_shmem_safe(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ShmemSafe can only be derived for structs",
+        ));
+    };
+    if !has_stable_layout(&input.attrs)? {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ShmemSafe requires #[repr(C)] or #[repr(transparent)]",
+        ));
+    }
+    for field in &data.fields {
+        reject_pointers(&field.ty)?;
+    }
+
+    // every field has to be `ShmemSafe`, which also bounds the type parameters it mentions
+    let field_types: Vec<Type> = data.fields.iter().map(|field| field.ty.clone()).collect();
+    let where_clause = input.generics.make_where_clause();
+    for ty in field_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::shmem_bind::ShmemSafe));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        // # Safety
+        //
+        // the layout is fixed via `repr` and every field is `ShmemSafe`
+        unsafe impl #impl_generics ::shmem_bind::ShmemSafe for #ident #ty_generics #where_clause {}
+    })
+}
+
+/// Implements `ShmemSchema` for a struct, hashing its name, field names and field types as
+/// written via `schema_hash`.
+///
+/// Adding, removing, renaming, reordering or retyping a field changes the fingerprint. Field
+/// types are hashed by their name only, so a change within a nested struct is only caught if it
+/// changes the size or alignment of the outer one. Generic structs are not supported, since the
+/// fingerprint would not depend on the type parameters, implement `ShmemSchema` by hand instead.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError,ShmemSchema};
+///
+/// #[derive(Debug, ShmemSchema)]
+/// #[repr(C)]
+/// struct Config {
+///     retries: u32,
+///     timeout_ms: u32,
+/// }
+///
+/// mod v2 {
+///     #[derive(Debug, shmem_bind::ShmemSchema)]
+///     #[repr(C)]
+///     pub struct Config {
+///         pub retries: u32,
+///         pub timeout_ms: u32,
+///         pub backoff_ms: u32,
+///     }
+/// }
+///
+/// fn main() -> Result<(),ShmemError>{
+///     let open = || shmem::Builder::new("flink_test_derive_shmem_schema")
+///         .with_size(64)
+///         .open();
+///     let _owner = unsafe { open()?.boxed_with_schema::<Config>()? };
+///
+///     // a process built against the newer version of the struct
+///     let err = unsafe { open()?.boxed_with_schema::<v2::Config>() }.unwrap_err();
+///     assert!(matches!(err, ShmemError::LayoutMismatch { .. }));
+///
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_derive(ShmemSchema)]
+pub fn derive_shmem_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_schema(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_schema(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ShmemSchema can only be derived for structs",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "ShmemSchema can not be derived for generic structs, implement it by hand",
+        ));
+    }
+
+    // e.g. `Config { retries: u32, timeout_ms: u32 }` or `Pair(u32, u64)`
+    let ident = &input.ident;
+    let fields: Vec<String> = data
+        .fields
+        .iter()
+        .map(|field| {
+            let ty = &field.ty;
+            match &field.ident {
+                Some(name) => format!("{name}: {}", quote!(#ty)),
+                None => quote!(#ty).to_string(),
+            }
+        })
+        .collect();
+    let schema = match &data.fields {
+        Fields::Named(_) => format!("{ident} {{ {} }}", fields.join(", ")),
+        Fields::Unnamed(_) => format!("{ident}({})", fields.join(", ")),
+        Fields::Unit => ident.to_string(),
+    };
+
+    Ok(quote! {
+        impl ::shmem_bind::ShmemSchema for #ident {
+            const FINGERPRINT: u64 = ::shmem_bind::schema_hash(#schema);
+        }
+    })
+}
+
+/// Returns whether `attrs` contain `#[repr(C)]` or `#[repr(transparent)]`.
+fn has_stable_layout(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut stable = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                stable = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                // skip the arguments of e.g. `align(8)` or `packed(2)`
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(stable)
+}
+
+/// Fails on references and raw pointers anywhere within `ty`, e.g. in `[&u8; 4]` or
+/// `Option<*const u8>`.
+fn reject_pointers(ty: &Type) -> syn::Result<()> {
+    match ty {
+        Type::Reference(_) | Type::Ptr(_) | Type::BareFn(_) => Err(Error::new_spanned(
+            ty,
+            "references and pointers are only valid within a single process",
+        )),
+        Type::Array(array) => reject_pointers(&array.elem),
+        Type::Slice(slice) => reject_pointers(&slice.elem),
+        Type::Group(group) => reject_pointers(&group.elem),
+        Type::Paren(paren) => reject_pointers(&paren.elem),
+        Type::Tuple(tuple) => tuple.elems.iter().try_for_each(reject_pointers),
+        Type::Path(path) => {
+            if let Some(qself) = &path.qself {
+                reject_pointers(&qself.ty)?;
+            }
+            for segment in &path.path.segments {
+                match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => {
+                        for arg in &args.args {
+                            if let GenericArgument::Type(ty) = arg {
+                                reject_pointers(ty)?;
+                            }
+                        }
+                    }
+                    PathArguments::Parenthesized(args) => {
+                        args.inputs.iter().try_for_each(reject_pointers)?;
+                        if let ReturnType::Type(_, ty) = &args.output {
+                            reject_pointers(ty)?;
+                        }
+                    }
+                    PathArguments::None => {}
+                }
+            }
+            Ok(())
+        }
+        // anything else is left to the `ShmemSafe` bound
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,123 @@
+//! Process-global list of the shared memories currently mapped, for debugging.
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::ShmemConf;
+
+/// Every live `ShmemConf` of the current process, keyed by its file descriptor, which no two of
+/// them share.
+static ACTIVE: Mutex<BTreeMap<i32, SegmentSummary>> = Mutex::new(BTreeMap::new());
+
+fn active() -> MutexGuard<'static, BTreeMap<i32, SegmentSummary>> {
+    // every update is a single insert or remove, a panic while holding the lock leaves the list
+    // consistent
+    ACTIVE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A shared memory mapped by the current process, as returned by `list_active_segments`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentSummary {
+    /// `flink_id` of the shared memory, empty for files mapped via `TryFrom<File>`.
+    pub id: String,
+    /// File descriptor held by the `ShmemConf`.
+    pub fd: i32,
+    /// Address the shared memory is mapped at.
+    pub addr: usize,
+    /// Size of the shared memory as returned by `ShmemConf::size`.
+    pub size: usize,
+}
+
+/// Records `conf` as active, or updates its address and size after a resize.
+pub(crate) fn register(conf: &ShmemConf) {
+    if conf.is_anonymous() {
+        return;
+    }
+    let summary = SegmentSummary {
+        id: conf.id.clone(),
+        fd: conf.fd,
+        addr: conf.addr.as_ptr() as usize,
+        size: conf.size,
+    };
+    active().insert(conf.fd, summary);
+}
+
+/// Forgets `conf` once it is unmapped.
+pub(crate) fn deregister(conf: &ShmemConf) {
+    active().remove(&conf.fd);
+}
+
+/// Returns a snapshot of every `ShmemConf` alive in the current process, ordered by file
+/// descriptor, e.g. to find out which shared memories a process still holds.
+///
+/// Handles which were leaked, e.g. via `ShmemBox::leak`, stay listed since their mappings are
+/// never released. Anonymous shared memories have no file descriptor and are not listed, see
+/// `BuilderWithSize::allow_anonymous_fallback`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_list_active_segments");
+///     let shared_mem = shmem::Builder::new("flink_test_list_active_segments")
+///         .with_size(4)
+///         .open()?;
+///
+///     let active = shmem::list_active_segments();
+///     assert!(active
+///         .iter()
+///         .any(|segment| segment.id == "flink_test_list_active_segments" && segment.size == 4));
+///
+///     drop(shared_mem);
+///     let active = shmem::list_active_segments();
+///     assert!(active
+///         .iter()
+///         .all(|segment| segment.id != "flink_test_list_active_segments"));
+///
+///     Ok(())
+/// }
+/// ```
+pub fn list_active_segments() -> Vec<SegmentSummary> {
+    active().values().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{page_size, Builder, TempShmem};
+
+    fn find(id: &str) -> Vec<SegmentSummary> {
+        list_active_segments()
+            .into_iter()
+            .filter(|segment| segment.id == id)
+            .collect()
+    }
+
+    #[test]
+    fn snapshot() {
+        let tmp = TempShmem::new("test-shmem-active-snapshot");
+        let id = tmp.id();
+        let mut owner = Builder::new(id).with_size(8).open().unwrap();
+        let clone = owner.try_clone().unwrap();
+
+        let found = find(id);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].fd, owner.fd);
+        assert_eq!(found[0].addr, owner.as_ptr() as usize);
+        assert_eq!(found[1].fd, clone.fd);
+
+        owner.grow(2 * page_size()).unwrap();
+        let found = find(id);
+        assert_eq!(found[0].size, 2 * page_size());
+        assert_eq!(found[0].addr, owner.as_ptr() as usize);
+
+        drop(clone);
+        assert_eq!(find(id).len(), 1);
+        let fd = owner.into_fd_keep_unmapped();
+        assert!(find(id).is_empty());
+        unsafe { libc::close(fd) };
+        assert!(crate::ShmemConf::unlink(id).unwrap());
+    }
+}
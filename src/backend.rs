@@ -0,0 +1,438 @@
+//! Backends creating the shared memory objects behind `ShmemConf`.
+//!
+//! Every backend hands out a file descriptor which is then mapped via `mmap`, so everything past
+//! opening the shared memory is backend agnostic.
+//!
+//! # Threat model
+//!
+//! `/dev/shm` is writable by every user on most Linux distributions, so another local user can
+//! plant an entry under the name of a shared memory before it is created, e.g. a symlink to a
+//! file of the victim which `shm_open` would follow and `ftruncate` would then clobber, or a
+//! FIFO. The POSIX backend therefore opens names with `O_NOFOLLOW` and checks via `fstat` and
+//! `fstatfs` that the opened object is a regular file on a memory backed file system, failing
+//! with `ShmemError::SecurityViolation` otherwise before touching the object. This does not
+//! protect against other users of the same shared memory, which can read and write it anyway,
+//! nor against an attacker running as the same user.
+
+use std::{ffi::CStr, io};
+
+use libc::{c_int, off_t};
+
+use crate::{eintr::eintr_retry, trace, ShmemError};
+
+/// Operations on named shared memory objects.
+pub(crate) trait ShmBackend {
+    /// Opens the existing shared memory object `name` for reading and writing.
+    ///
+    /// Fails with `ShmemError::NotFound` if no object named `name` exists and
+    /// `ShmemError::SecurityViolation` if `name` is not a shared memory object, e.g. a symlink.
+    fn open(name: &CStr) -> Result<c_int, ShmemError>;
+
+    /// Creates the shared memory object `name` of `len` bytes with the permission bits `mode`.
+    ///
+    /// Fails with `ShmemError::AlreadyExists` if `exclusive` is set and an object named `name`
+    /// already exists. Without `exclusive`, an existing object is opened instead and resized to
+    /// `len` bytes.
+    fn create(name: &CStr, len: off_t, mode: u32, exclusive: bool) -> Result<c_int, ShmemError>;
+
+    /// Changes the size of the shared memory object behind `fd` to `len` bytes.
+    fn resize(fd: c_int, len: off_t) -> io::Result<()>;
+
+    /// Removes the name of the shared memory object, which is freed once it is not mapped
+    /// anymore.
+    fn unlink(name: &CStr) -> io::Result<()>;
+}
+
+/// The backend of the current platform.
+#[cfg(not(target_os = "android"))]
+pub(crate) type Platform = Posix;
+/// The backend of the current platform.
+#[cfg(target_os = "android")]
+pub(crate) type Platform = Ashmem;
+
+/// Refuses to follow a symlink planted under the name of a shared memory. Other platforms reject
+/// unknown flags to `shm_open` and do not resolve names in a shared directory anyway.
+#[cfg(target_os = "linux")]
+const O_NOFOLLOW: c_int = libc::O_NOFOLLOW;
+#[cfg(all(not(target_os = "linux"), not(target_os = "android")))]
+const O_NOFOLLOW: c_int = 0;
+
+/// POSIX shared memory via `shm_open`, named objects live in `/dev/shm` on Linux.
+#[cfg(not(target_os = "android"))]
+pub(crate) struct Posix;
+
+#[cfg(not(target_os = "android"))]
+impl Posix {
+    /// Maps an error of `shm_open`, where `ELOOP` means `O_NOFOLLOW` hit a symlink.
+    fn open_error(err: io::Error) -> ShmemError {
+        match err.raw_os_error() {
+            Some(libc::ENOENT) => ShmemError::NotFound(err),
+            Some(libc::ELOOP) => ShmemError::SecurityViolation,
+            _ => ShmemError::IoError(err),
+        }
+    }
+
+    /// Closes `fd` and fails with `ShmemError::SecurityViolation` unless it refers to a
+    /// regular file on a memory backed file system.
+    #[cfg(target_os = "linux")]
+    fn verify(fd: c_int) -> Result<c_int, ShmemError> {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        let mut statfs = std::mem::MaybeUninit::<libc::statfs>::uninit();
+        let verified = unsafe {
+            libc::fstat(fd, stat.as_mut_ptr()) == 0
+                && libc::fstatfs(fd, statfs.as_mut_ptr()) == 0
+                && stat.assume_init().st_mode & libc::S_IFMT == libc::S_IFREG
+                && matches!(
+                    statfs.assume_init().f_type,
+                    libc::TMPFS_MAGIC | libc::HUGETLBFS_MAGIC
+                )
+        };
+        if !verified {
+            trace::warn!(
+                fd,
+                "refusing a shared memory name which is not a shared memory object"
+            );
+            unsafe { libc::close(fd) };
+            return Err(ShmemError::SecurityViolation);
+        }
+
+        Ok(fd)
+    }
+
+    /// Other platforms keep shared memory objects out of the file system.
+    #[cfg(not(target_os = "linux"))]
+    fn verify(fd: c_int) -> Result<c_int, ShmemError> {
+        Ok(fd)
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+impl ShmBackend for Posix {
+    fn open(name: &CStr) -> Result<c_int, ShmemError> {
+        let fd = trace::syscall!("shmem_bind::shm_open", [name = ?name], unsafe {
+            eintr_retry!(libc::shm_open(
+                name.as_ptr(),
+                libc::O_RDWR | O_NOFOLLOW,
+                libc::S_IRUSR | libc::S_IWUSR
+            ))
+        });
+        if fd < 0 {
+            return Err(Self::open_error(io::Error::last_os_error()));
+        }
+
+        Self::verify(fd)
+    }
+
+    fn create(name: &CStr, len: off_t, mode: u32, exclusive: bool) -> Result<c_int, ShmemError> {
+        let mut flags = libc::O_RDWR | libc::O_CREAT | O_NOFOLLOW;
+        if exclusive {
+            flags |= libc::O_EXCL;
+        }
+        let fd = trace::syscall!(
+            "shmem_bind::shm_open",
+            [name = ?name, size = len, is_owner = true, exclusive],
+            unsafe {
+                eintr_retry!(libc::shm_open(
+                    name.as_ptr(),
+                    flags,
+                    mode as libc::mode_t
+                ))
+            }
+        );
+        if fd < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::EEXIST) {
+                return Err(ShmemError::AlreadyExists(err));
+            }
+            if err.raw_os_error() == Some(libc::ELOOP) {
+                return Err(ShmemError::SecurityViolation);
+            }
+            return Err(ShmemError::CreateFailedErr(err));
+        }
+        // verified before `ftruncate` touches the object
+        let fd = Self::verify(fd)?;
+        // `shm_open` applies the umask, which would e.g. strip the group write bit from 0o660.
+        // An object created concurrently by another user can not be changed and keeps the
+        // permissions its creator chose.
+        unsafe { libc::fchmod(fd, mode as libc::mode_t) };
+
+        if Self::resize(fd, len).is_err() {
+            unsafe { libc::close(fd) };
+            // only an exclusively created object is known to be ours to remove
+            if exclusive {
+                let _ = Self::unlink(name);
+            }
+            return Err(ShmemError::AllocationFailedErr);
+        }
+
+        Ok(fd)
+    }
+
+    fn resize(fd: c_int, len: off_t) -> io::Result<()> {
+        if unsafe { eintr_retry!(libc::ftruncate(fd, len)) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn unlink(name: &CStr) -> io::Result<()> {
+        let ret = trace::syscall!("shmem_bind::shm_unlink", [name = ?name], unsafe {
+            eintr_retry!(libc::shm_unlink(name.as_ptr()))
+        });
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Regular files at a path of the file system, see `Builder::with_file_path`.
+///
+/// Paths are chosen by the application, so unlike names in `/dev/shm` they are followed like
+/// any other path and opened files are not verified.
+pub(crate) struct FilePath;
+
+impl FilePath {
+    fn open_flags(path: &CStr, flags: c_int, mode: u32) -> io::Result<c_int> {
+        let fd = trace::syscall!("shmem_bind::open", [path = ?path], unsafe {
+            eintr_retry!(libc::open(
+                path.as_ptr(),
+                flags | libc::O_RDWR | libc::O_CLOEXEC,
+                mode as libc::c_uint
+            ))
+        });
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(fd)
+    }
+}
+
+impl ShmBackend for FilePath {
+    fn open(path: &CStr) -> Result<c_int, ShmemError> {
+        Self::open_flags(path, 0, 0).map_err(|err| match err.raw_os_error() {
+            Some(libc::ENOENT) => ShmemError::NotFound(err),
+            _ => ShmemError::IoError(err),
+        })
+    }
+
+    fn create(path: &CStr, len: off_t, mode: u32, exclusive: bool) -> Result<c_int, ShmemError> {
+        let mut flags = libc::O_CREAT;
+        if exclusive {
+            flags |= libc::O_EXCL;
+        }
+        let fd = Self::open_flags(path, flags, mode).map_err(|err| match err.raw_os_error() {
+            Some(libc::EEXIST) => ShmemError::AlreadyExists(err),
+            _ => ShmemError::IoError(err),
+        })?;
+        // the umask applies to files as it does to shared memory objects
+        unsafe { libc::fchmod(fd, mode as libc::mode_t) };
+
+        if Self::resize(fd, len).is_err() {
+            unsafe { libc::close(fd) };
+            if exclusive {
+                let _ = Self::unlink(path);
+            }
+            return Err(ShmemError::AllocationFailedErr);
+        }
+
+        Ok(fd)
+    }
+
+    fn resize(fd: c_int, len: off_t) -> io::Result<()> {
+        if unsafe { eintr_retry!(libc::ftruncate(fd, len)) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn unlink(path: &CStr) -> io::Result<()> {
+        if unsafe { libc::unlink(path.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Anonymous shared memory on Android, where bionic offers no usable `shm_open`.
+///
+/// Regions are created via the NDK's `ASharedMemory_create` (API level 26 and up) or the
+/// `/dev/ashmem` ioctls on older devices. The name of an ashmem region is advisory only, it shows
+/// up in `/proc/<pid>/maps` but can not be used to open the region again. Every `create` therefore
+/// creates a new region, opening an existing one by name fails with `ShmemError::NotFound` and
+/// unlinking does nothing. Sharing the region with another process relies on passing its file
+/// descriptor over a Unix domain socket or binder instead. Regions can not be resized.
+#[cfg(target_os = "android")]
+pub(crate) struct Ashmem;
+
+#[cfg(target_os = "android")]
+impl ShmBackend for Ashmem {
+    fn open(_name: &CStr) -> Result<c_int, ShmemError> {
+        Err(ShmemError::NotFound(io::Error::from_raw_os_error(
+            libc::ENOENT,
+        )))
+    }
+
+    fn create(name: &CStr, len: off_t, _mode: u32, _exclusive: bool) -> Result<c_int, ShmemError> {
+        let fd = match ashmem::ndk_create() {
+            Some(create) => match unsafe { create(name.as_ptr(), len as libc::size_t) } {
+                fd if fd < 0 => Err(io::Error::last_os_error()),
+                fd => Ok(fd),
+            },
+            None => ashmem::ioctl_create(name, len as libc::size_t),
+        };
+
+        fd.map_err(ShmemError::CreateFailedErr)
+    }
+
+    fn resize(_fd: c_int, _len: off_t) -> io::Result<()> {
+        Err(io::Error::from_raw_os_error(libc::EOPNOTSUPP))
+    }
+
+    fn unlink(_name: &CStr) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "android")]
+mod ashmem {
+    use std::{ffi::CStr, io, mem, sync::OnceLock};
+
+    use libc::{c_char, c_int, c_ulong, size_t};
+
+    /// Signature of `ASharedMemory_create` from `libandroid.so`.
+    pub(super) type NdkCreate = unsafe extern "C" fn(*const c_char, size_t) -> c_int;
+
+    const ASHMEM_NAME_LEN: usize = 256;
+    const ASHMEM_SET_NAME: c_ulong = iow(1, ASHMEM_NAME_LEN);
+    const ASHMEM_SET_SIZE: c_ulong = iow(3, mem::size_of::<size_t>());
+
+    /// Encodes an ashmem `_IOW` ioctl request, the ioctl type of ashmem is `'w'`.
+    const fn iow(nr: c_ulong, size: usize) -> c_ulong {
+        (1 << 30) | ((size as c_ulong) << 16) | ((b'w' as c_ulong) << 8) | nr
+    }
+
+    /// Looks up `ASharedMemory_create` at runtime, so the crate still loads on devices older
+    /// than API level 26 which lack it.
+    pub(super) fn ndk_create() -> Option<NdkCreate> {
+        static CREATE: OnceLock<Option<NdkCreate>> = OnceLock::new();
+
+        *CREATE.get_or_init(|| unsafe {
+            let lib = libc::dlopen(c"libandroid.so".as_ptr(), libc::RTLD_NOW);
+            if lib.is_null() {
+                return None;
+            }
+            let sym = libc::dlsym(lib, c"ASharedMemory_create".as_ptr());
+            if sym.is_null() {
+                return None;
+            }
+            Some(mem::transmute::<*mut libc::c_void, NdkCreate>(sym))
+        })
+    }
+
+    /// Creates a region through the ashmem device directly.
+    pub(super) fn ioctl_create(name: &CStr, len: size_t) -> io::Result<c_int> {
+        unsafe {
+            let fd = libc::open(c"/dev/ashmem".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut buf = [0 as c_char; ASHMEM_NAME_LEN];
+            for (dst, src) in buf
+                .iter_mut()
+                .zip(name.to_bytes().iter().take(ASHMEM_NAME_LEN - 1))
+            {
+                *dst = *src as c_char;
+            }
+            if libc::ioctl(fd, ASHMEM_SET_NAME as _, buf.as_ptr()) < 0
+                || libc::ioctl(fd, ASHMEM_SET_SIZE as _, len) < 0
+            {
+                // read before `close` may overwrite it
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                return Err(err);
+            }
+
+            Ok(fd)
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "android"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ashmem_region() {
+        let len = crate::page_size();
+        let fd = Platform::create(c"test-shmem-ashmem", len as off_t, 0o600, true).unwrap();
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        assert_ne!(addr, libc::MAP_FAILED);
+        unsafe {
+            *(addr as *mut u8) = 42;
+            assert_eq!(*(addr as *const u8), 42);
+            libc::munmap(addr, len);
+            libc::close(fd);
+        }
+
+        // names are advisory, the region can not be opened again
+        let err = Platform::open(c"test-shmem-ashmem").unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{fs, os::unix::fs::symlink};
+
+    use crate::{Builder, ShmemErrorKind};
+
+    #[test]
+    fn planted_names() {
+        // a symlink to a file of the victim, which must neither be resized nor mapped
+        let target =
+            std::env::temp_dir().join(format!("test-shmem-symlink.{}", std::process::id()));
+        fs::write(&target, b"secret").unwrap();
+        let link = "/dev/shm/test-shmem-backend-symlink";
+        symlink(&target, link).unwrap();
+
+        let open = |id| Builder::new(id).with_size(4096);
+        let err = open("test-shmem-backend-symlink").open().unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::SecurityViolation);
+        let err = open("test-shmem-backend-symlink")
+            .create_exclusive()
+            .unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::AlreadyExists);
+        let err = open("test-shmem-backend-symlink")
+            .open_existing()
+            .unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::SecurityViolation);
+        assert_eq!(fs::read(&target).unwrap(), b"secret");
+        fs::remove_file(link).unwrap();
+        fs::remove_file(&target).unwrap();
+
+        // a FIFO, which is not a regular file
+        let fifo = c"/dev/shm/test-shmem-backend-fifo";
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+        let err = open("test-shmem-backend-fifo").open().unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::SecurityViolation);
+        let err = crate::stat("test-shmem-backend-fifo").unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::SecurityViolation);
+        fs::remove_file("/dev/shm/test-shmem-backend-fifo").unwrap();
+    }
+}
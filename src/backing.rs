@@ -0,0 +1,152 @@
+//! The file system backing a shared memory, see `ShmemConf::backing_fs`.
+use crate::{Builder, ShmemConf, ShmemError};
+
+/// The file system a shared memory lives on, as returned by `ShmemConf::backing_fs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BackingKind {
+    /// `tmpfs`, where POSIX shared memory lives on Linux.
+    Tmpfs,
+    /// `hugetlbfs`, memory backed by huge pages.
+    Hugetlbfs,
+    /// `ramfs`, memory which is never swapped.
+    Ramfs,
+    /// No file system, e.g. POSIX shared memory on macOS or FreeBSD, which lives in memory.
+    Anonymous,
+    /// Any other file system, identified by its magic number as reported by `fstatfs`. Files
+    /// mapped via `TryFrom<File>` usually live on a disk backed one.
+    Other(i64),
+}
+
+impl BackingKind {
+    /// Returns true if the file system keeps its data in memory only, i.e. unless it is
+    /// `Other`.
+    pub fn is_memory(&self) -> bool {
+        !matches!(self, BackingKind::Other(_))
+    }
+}
+
+/// `RAMFS_MAGIC` of `linux/magic.h`, which the libc crate does not export.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RAMFS_MAGIC: i64 = 0x8584_58f6;
+
+impl Builder {
+    /// Makes `open` fail with `ShmemError::WrongBacking` unless the shared memory lives in
+    /// memory, as judged by `BackingKind::is_memory`, e.g. to keep its data out of persistent
+    /// storage and off slow disks.
+    ///
+    /// On Linux, shared memories are opened only if they live on `tmpfs` or `hugetlbfs` anyway,
+    /// so the check matters if e.g. `/dev/shm` is not mounted as `tmpfs`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_require_tmpfs");
+    ///     let shared_mem = shmem::Builder::new("flink_test_require_tmpfs")
+    ///         .require_tmpfs(true)
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     assert!(shared_mem.backing_fs()?.is_memory());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn require_tmpfs(mut self, require_tmpfs: bool) -> Self {
+        self.options.require_tmpfs = require_tmpfs;
+        self
+    }
+}
+
+impl ShmemConf {
+    /// Returns the file system the shared memory lives on, as reported by `fstatfs`.
+    ///
+    /// Always returns `BackingKind::Anonymous` on platforms other than Linux and Android, where
+    /// POSIX shared memory lives outside of any file system. Returns `ShmemError::IoError` if
+    /// `fstatfs` fails.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn backing_fs(&self) -> Result<BackingKind, ShmemError> {
+        let mut statfs = std::mem::MaybeUninit::<libc::statfs>::uninit();
+        if unsafe { libc::fstatfs(self.fd, statfs.as_mut_ptr()) } != 0 {
+            return Err(ShmemError::IoError(std::io::Error::last_os_error()));
+        }
+        // the magic numbers are 32 bits, but sign extended by `f_type` on some targets
+        let f_type = i64::from(unsafe { statfs.assume_init() }.f_type as u32);
+
+        Ok(match f_type {
+            f_type if f_type == libc::TMPFS_MAGIC as u32 as i64 => BackingKind::Tmpfs,
+            f_type if f_type == libc::HUGETLBFS_MAGIC as u32 as i64 => BackingKind::Hugetlbfs,
+            RAMFS_MAGIC => BackingKind::Ramfs,
+            f_type => BackingKind::Other(f_type),
+        })
+    }
+
+    /// Returns the file system the shared memory lives on, as reported by `fstatfs`.
+    ///
+    /// Always returns `BackingKind::Anonymous` on platforms other than Linux and Android, where
+    /// POSIX shared memory lives outside of any file system. Returns `ShmemError::IoError` if
+    /// `fstatfs` fails.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn backing_fs(&self) -> Result<BackingKind, ShmemError> {
+        Ok(BackingKind::Anonymous)
+    }
+
+    /// Fails with `ShmemError::WrongBacking` if `Builder::require_tmpfs` is enabled and the
+    /// shared memory does not live in memory.
+    pub(crate) fn check_backing(&self) -> Result<(), ShmemError> {
+        // anonymous shared memory lives in memory by definition
+        if !self.options.require_tmpfs || self.is_anonymous() {
+            return Ok(());
+        }
+        match self.backing_fs()? {
+            backing if backing.is_memory() => Ok(()),
+            backing => Err(ShmemError::WrongBacking(backing)),
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn tmpfs() {
+        let tmp = TempShmem::new("test-shmem-backing-tmpfs");
+        let tmp2 = TempShmem::new("test-shmem-backing-file");
+        let shmconf = tmp
+            .builder()
+            .require_tmpfs(true)
+            .with_size(8)
+            .open()
+            .unwrap();
+        assert_eq!(shmconf.backing_fs().unwrap(), BackingKind::Tmpfs);
+
+        // a file in the current directory, which lives on a disk unless the target directory
+        // is on a memory backed file system
+        let path = std::env::current_dir().unwrap().join(tmp2.id());
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.set_len(8).unwrap();
+        let mut conf = ShmemConf::try_from(file).unwrap();
+        fs::remove_file(&path).unwrap();
+        let backing = conf.backing_fs().unwrap();
+        conf.options.require_tmpfs = true;
+        match conf.check_backing() {
+            Ok(()) => assert!(backing.is_memory()),
+            Err(ShmemError::WrongBacking(found)) => {
+                assert_eq!(found, backing);
+                assert!(matches!(found, BackingKind::Other(_)));
+            }
+            Err(err) => panic!("unexpected error {err:?}"),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! `shmemctl`, a small tool for inspecting and cleaning up shared memories during development.
+//!
+//! Built with the `cli` feature, e.g. via `cargo run --features cli --bin shmemctl -- list`.
+//! Every subcommand prints human-readable output, or JSON if `--json` is passed.
+use std::{
+    env,
+    fmt::Write as _,
+    io,
+    process::ExitCode,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+use shmem_bind::{self as shmem, CleanupPolicy, ShmemConf, ShmemError};
+
+const USAGE: &str = "\
+usage: shmemctl [--json] <command>
+
+commands:
+    list [prefix]                           list shared memories, optionally by name prefix
+    stat <id>                               show the size, permissions and header of <id>
+    rm <id>                                 unlink <id>
+    dump <id> [--offset M] [--len N]        hexdump the user data of <id>, read-only
+    create <id> --size N                    create <id> and leave it behind";
+
+/// Bytes shown per line of `dump`.
+const DUMP_WIDTH: usize = 16;
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let json = match args.iter().position(|arg| arg == "--json") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    let output = match run(&args) {
+        Ok(output) => output,
+        Err(Error::Usage(msg)) => {
+            eprintln!("shmemctl: {msg}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+        Err(Error::Shmem(err)) => {
+            eprintln!("shmemctl: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if json {
+        println!("{}", output.json);
+    } else {
+        print!("{}", output.text);
+    }
+
+    ExitCode::SUCCESS
+}
+
+enum Error {
+    Usage(String),
+    Shmem(ShmemError),
+}
+
+impl From<ShmemError> for Error {
+    fn from(err: ShmemError) -> Self {
+        Error::Shmem(err)
+    }
+}
+
+/// Result of a subcommand in both output formats.
+struct Output {
+    text: String,
+    json: Value,
+}
+
+fn run(args: &[String]) -> Result<Output, Error> {
+    let (command, args) = args
+        .split_first()
+        .ok_or_else(|| Error::Usage("missing command".into()))?;
+    let mut args = Args::new(args);
+    let output = match command.as_str() {
+        "list" => {
+            let prefix = args.optional();
+            args.finish()?;
+            list(prefix.as_deref().unwrap_or(""))?
+        }
+        "stat" => {
+            let id = args.required("id")?;
+            args.finish()?;
+            stat(&id)?
+        }
+        "rm" => {
+            let id = args.required("id")?;
+            args.finish()?;
+            rm(&id)?
+        }
+        "dump" => {
+            let id = args.required("id")?;
+            let offset = args.flag("--offset")?.unwrap_or(0);
+            let len = args.flag("--len")?;
+            args.finish()?;
+            dump(&id, offset, len)?
+        }
+        "create" => {
+            let id = args.required("id")?;
+            let size = args
+                .flag("--size")?
+                .ok_or_else(|| Error::Usage("missing --size".into()))?;
+            args.finish()?;
+            create(&id, size)?
+        }
+        command => return Err(Error::Usage(format!("unknown command `{command}`"))),
+    };
+
+    Ok(output)
+}
+
+/// The arguments following the subcommand, consumed flags first.
+struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Takes the numeric value of `--name N`, if present.
+    fn flag(&mut self, name: &str) -> Result<Option<usize>, Error> {
+        let Some(i) = self.args.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        if i + 1 == self.args.len() {
+            return Err(Error::Usage(format!("missing value of {name}")));
+        }
+        let value = self.args.remove(i + 1);
+        self.args.remove(i);
+
+        parse_size(&value)
+            .map(Some)
+            .ok_or_else(|| Error::Usage(format!("invalid value of {name}: `{value}`")))
+    }
+
+    /// Takes the next positional argument, if any.
+    fn optional(&mut self) -> Option<String> {
+        let i = self.args.iter().position(|arg| !arg.starts_with("--"))?;
+        Some(self.args.remove(i))
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, Error> {
+        self.optional()
+            .ok_or_else(|| Error::Usage(format!("missing <{name}>")))
+    }
+
+    /// Fails on any argument left over.
+    fn finish(self) -> Result<(), Error> {
+        match self.args.first() {
+            Some(arg) => Err(Error::Usage(format!("unexpected argument `{arg}`"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal number.
+fn parse_size(value: &str) -> Option<usize> {
+    match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn list(prefix: &str) -> Result<Output, Error> {
+    let found = shmem::list(prefix)?;
+
+    let mut text = String::new();
+    for info in &found {
+        let _ = writeln!(
+            text,
+            "{:04o} {:>12} {} {}",
+            info.mode,
+            info.size,
+            format_time(info.mtime),
+            info.name
+        );
+    }
+    let json = found
+        .iter()
+        .map(|info| {
+            json!({
+                "name": info.name,
+                "size": info.size,
+                "mode": info.mode,
+                "mtime": unix_secs(info.mtime),
+            })
+        })
+        .collect();
+
+    Ok(Output { text, json })
+}
+
+fn stat(id: &str) -> Result<Output, Error> {
+    let stat = shmem::stat(id)?;
+    // the header is only known from the mapping, which fails for an empty object
+    let conf = shmem::Builder::new(id).with_size(1).open_readonly().ok();
+    let header = conf.as_ref().filter(|conf| conf.creator_pid().is_some());
+
+    let mut text = String::new();
+    let mut field = |label: &str, value: &dyn std::fmt::Display| {
+        let _ = writeln!(text, "{:<12}{value}", format!("{label}:"));
+    };
+    field("name", &id);
+    field("size", &stat.size);
+    field("mode", &format_args!("{:04o}", stat.mode));
+    field("uid", &stat.uid);
+    field("gid", &stat.gid);
+    let mut json = json!({
+        "name": id,
+        "size": stat.size,
+        "mode": stat.mode,
+        "uid": stat.uid,
+        "gid": stat.gid,
+        "header": null,
+    });
+    if let Some(conf) = header {
+        let creator = conf.creator_pid().unwrap_or_default();
+        let created = conf.created_at().unwrap_or(UNIX_EPOCH);
+        let attached = conf.attach_count().unwrap_or_default();
+        field("creator", &creator);
+        field("created", &format_time(created));
+        field("owner", &if conf.owner_alive() { "alive" } else { "dead" });
+        field("attached", &attached);
+        if let Some(generation) = conf.generation() {
+            field("generation", &generation);
+        }
+        if let Some(version) = conf.schema_version() {
+            field("schema", &version);
+        }
+        json["header"] = json!({
+            "creator_pid": creator,
+            "created_at": unix_secs(created),
+            "owner_alive": conf.owner_alive(),
+            "attached": attached,
+            "generation": conf.generation(),
+            "schema_version": conf.schema_version(),
+        });
+    }
+
+    Ok(Output { text, json })
+}
+
+fn rm(id: &str) -> Result<Output, Error> {
+    if !ShmemConf::unlink(id)? {
+        return Err(Error::Shmem(ShmemError::NotFound(
+            io::Error::from_raw_os_error(libc::ENOENT),
+        )));
+    }
+
+    Ok(Output {
+        text: format!("unlinked {id}\n"),
+        json: json!({ "name": id, "unlinked": true }),
+    })
+}
+
+fn dump(id: &str, offset: usize, len: Option<usize>) -> Result<Output, Error> {
+    let open = |size| shmem::Builder::new(id).with_size(size).open_readonly();
+    let conf = match len {
+        Some(len) => {
+            let size = offset
+                .checked_add(len)
+                .filter(|&size| size > 0)
+                .ok_or_else(|| Error::Usage("empty range".into()))?;
+            open(size)?
+        }
+        // the size of the user data is only known once the header is detected
+        None => match open(shmem::stat(id)?.size) {
+            Err(ShmemError::SizeMismatch { found, .. }) if found > 0 => open(found)?,
+            conf => conf?,
+        },
+    };
+    // the mapping is read-only, so it is read as a single chunk instead of boxed
+    let bytes = conf.chunks(conf.size().max(1)).next().unwrap_or_default();
+    let data = bytes
+        .get(offset..offset + len.unwrap_or(bytes.len().saturating_sub(offset)))
+        .ok_or(ShmemError::OutOfBoundsErr)?;
+
+    let mut text = String::new();
+    for (i, line) in data.chunks(DUMP_WIDTH).enumerate() {
+        let _ = write!(text, "{:08x}:", offset + i * DUMP_WIDTH);
+        for (j, byte) in line.iter().enumerate() {
+            if j % 2 == 0 {
+                text.push(' ');
+            }
+            let _ = write!(text, "{byte:02x}");
+        }
+        // pads a short last line, so its characters line up with the ones above
+        let width = |len: usize| 2 * len + len.div_ceil(2);
+        text.push_str(&" ".repeat(width(DUMP_WIDTH) - width(line.len()) + 2));
+        text.extend(line.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        text.push('\n');
+    }
+    let hex: String = data.iter().map(|byte| format!("{byte:02x}")).collect();
+    let json = json!({ "name": id, "offset": offset, "len": data.len(), "hex": hex });
+
+    Ok(Output { text, json })
+}
+
+fn create(id: &str, size: usize) -> Result<Output, Error> {
+    let mut conf = shmem::Builder::new(id).with_size(size).create_exclusive()?;
+    // the shared memory outlives the tool
+    conf.set_cleanup_policy(CleanupPolicy::NeverUnlink)?;
+
+    Ok(Output {
+        text: format!("created {id} ({size} bytes)\n"),
+        json: json!({ "name": id, "size": size, "created": true }),
+    })
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Formats `time` as an ISO 8601 date and time in UTC, e.g. `2024-03-01T12:00:00Z`.
+fn format_time(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    // civil date of the days since the epoch, see Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
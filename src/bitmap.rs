@@ -0,0 +1,203 @@
+use std::{
+    fmt, mem, slice,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{ShmemConf, ShmemError};
+
+/// A fixed set of `N` boolean flags shared between processes, packed into `AtomicU64` words at
+/// the start of the shared memory.
+///
+/// The all-zero bit pattern is a bitmap with every bit cleared, so a freshly created shared
+/// memory is a valid bitmap right away. Operations use `Ordering::Relaxed` unless they are
+/// given an explicit ordering.
+///
+/// # Panics
+///
+/// Every operation taking a `bit` panics if `bit` is not smaller than `N`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemBitmap,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_bitmap");
+///     let shared_mem = shmem::Builder::new("flink_test_bitmap")
+///         .with_size(ShmemBitmap::<100>::required_size())
+///         .open()?;
+///     let healthy = ShmemBitmap::<100>::new(shared_mem)?;
+///
+///     healthy.set(3);
+///     healthy.set(70);
+///     assert!(healthy.test(70));
+///     assert_eq!(healthy.count_set(), 2);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemBitmap<const N: usize> {
+    conf: ShmemConf,
+}
+
+// # Safety
+//
+// the words are only accessed through atomics.
+unsafe impl<const N: usize> Sync for ShmemBitmap<N> {}
+
+impl<const N: usize> ShmemBitmap<N> {
+    const WORDS: usize = N.div_ceil(64);
+
+    /// Returns the size of the shared memory required to hold `N` bits.
+    pub const fn required_size() -> usize {
+        Self::WORDS * mem::size_of::<AtomicU64>()
+    }
+
+    /// Wraps the shared memory as a bitmap, keeping its current bits.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than
+    /// `required_size()` and `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub fn new(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        if conf.size < Self::required_size() {
+            return Err(ShmemError::SizeMismatch {
+                expected: Self::required_size(),
+                found: conf.size,
+            });
+        }
+
+        Ok(Self { conf })
+    }
+
+    /// Sets `bit`.
+    pub fn set(&self, bit: usize) {
+        self.set_with_ordering(bit, Ordering::Relaxed)
+    }
+
+    /// Sets `bit` using `ordering`.
+    pub fn set_with_ordering(&self, bit: usize, ordering: Ordering) {
+        let (word, mask) = Self::locate(bit);
+        self.words()[word].fetch_or(mask, ordering);
+    }
+
+    /// Clears `bit`.
+    pub fn clear(&self, bit: usize) {
+        let (word, mask) = Self::locate(bit);
+        self.words()[word].fetch_and(!mask, Ordering::Relaxed);
+    }
+
+    /// Returns whether `bit` is set.
+    pub fn test(&self, bit: usize) -> bool {
+        self.test_with_ordering(bit, Ordering::Relaxed)
+    }
+
+    /// Returns whether `bit` is set, loading it using `ordering`.
+    pub fn test_with_ordering(&self, bit: usize, ordering: Ordering) -> bool {
+        let (word, mask) = Self::locate(bit);
+        self.words()[word].load(ordering) & mask != 0
+    }
+
+    /// Sets every bit.
+    ///
+    /// The words are updated one at a time, so a concurrent reader may observe some of them
+    /// updated before others.
+    pub fn set_all(&self) {
+        for (i, word) in self.words().iter().enumerate() {
+            word.store(Self::word_mask(i), Ordering::Relaxed);
+        }
+    }
+
+    /// Clears every bit, with the same caveat as `set_all`.
+    pub fn clear_all(&self) {
+        for word in self.words() {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_set(&self) -> usize {
+        self.words()
+            .iter()
+            .enumerate()
+            .map(|(i, word)| (word.load(Ordering::Relaxed) & Self::word_mask(i)).count_ones())
+            .sum::<u32>() as usize
+    }
+
+    fn locate(bit: usize) -> (usize, u64) {
+        assert!(
+            bit < N,
+            "bit {bit} is out of range for a bitmap of {N} bits"
+        );
+        (bit / 64, 1 << (bit % 64))
+    }
+
+    /// Returns the mask of the bits of the `i`-th word belonging to the bitmap, which excludes
+    /// the padding bits of the last word.
+    fn word_mask(i: usize) -> u64 {
+        match N - i * 64 {
+            bits if bits >= 64 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    fn words(&self) -> &[AtomicU64] {
+        // # Safety
+        //
+        // the shared memory is page aligned and holds at least `WORDS` words, and every bit
+        // pattern is a valid `AtomicU64`
+        unsafe { slice::from_raw_parts(self.conf.addr.as_ptr() as *const AtomicU64, Self::WORDS) }
+    }
+}
+
+impl<const N: usize> fmt::Debug for ShmemBitmap<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries((0..N).filter(|bit| self.test(*bit)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    fn open_bitmap<const N: usize>(id: &str) -> ShmemBitmap<N> {
+        let shmconf = Builder::new(id)
+            .with_size(ShmemBitmap::<N>::required_size())
+            .open()
+            .unwrap();
+        ShmemBitmap::new(shmconf).unwrap()
+    }
+
+    #[test]
+    fn set_clear() {
+        let tmp = TempShmem::new("test-shmem-bitmap");
+        let bitmap = open_bitmap::<100>(tmp.id());
+        let attached = open_bitmap::<100>(tmp.id());
+
+        bitmap.set(0);
+        bitmap.set(64);
+        bitmap.set_with_ordering(99, Ordering::Release);
+        assert!(attached.test(64));
+        assert!(attached.test_with_ordering(99, Ordering::Acquire));
+        assert!(!attached.test(1));
+        assert_eq!(attached.count_set(), 3);
+        assert_eq!(format!("{attached:?}"), "{0, 64, 99}");
+
+        bitmap.clear(64);
+        assert_eq!(attached.count_set(), 2);
+
+        // the padding bits of the last word are never set
+        bitmap.set_all();
+        assert_eq!(attached.count_set(), 100);
+        bitmap.clear_all();
+        assert_eq!(attached.count_set(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn out_of_range() {
+        let tmp = TempShmem::new("test-shmem-bitmap-out-of-range");
+        open_bitmap::<64>(tmp.id()).set(64);
+    }
+}
@@ -0,0 +1,235 @@
+use std::{fmt, marker::PhantomData, mem, sync::Arc};
+
+use crate::{
+    event::{EventFd, EventHeader},
+    size_add, Builder, ShmemError, ShmemQueue,
+};
+
+/// A blocking channel between processes, consisting of a `ShmemQueue` and an eventfd-backed
+/// notifier living in a single shared memory.
+///
+/// The shared memory starts with the header of the notifier followed by the queue. Every sent
+/// element signals the notifier once, which wakes up a receiver blocked in `recv`.
+///
+/// Like `ShmemEvent`, the notifier can only be attached to within the creating process while
+/// the creating handles are alive. Children forked from the creator use the handles they inherit
+/// instead.
+///
+/// Only available on Linux.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{ShmemChannel,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_channel");
+///     let (sender, receiver) = ShmemChannel::<u32>::create("flink_test_channel", 8)?;
+///
+///     let handle = std::thread::spawn(move || receiver.recv());
+///     sender.send(42)?;
+///     assert_eq!(handle.join().unwrap(), 42);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemChannel<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> ShmemChannel<T> {
+    /// Returns the size of the shared memory required to hold a channel of `capacity` elements.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size(capacity: usize) -> Result<usize, ShmemError> {
+        size_add(
+            Self::queue_offset(),
+            ShmemQueue::<T>::required_size(capacity)?,
+        )
+    }
+
+    /// Creates the shared memory named `id` and initializes an empty channel of `capacity`
+    /// elements in it.
+    ///
+    /// Returns `ShmemError::AlreadyExists` if a shared memory named `id` is already present. The
+    /// returned handles own the shared memory, it is cleaned up once both of them are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn create(
+        id: &str,
+        capacity: usize,
+    ) -> Result<(ShmemSender<T>, ShmemReceiver<T>), ShmemError> {
+        assert!(capacity > 0, "capacity of the channel must not be zero");
+        let conf = Builder::new(id)
+            .with_size(Self::required_size(capacity)?)
+            .create_exclusive()?;
+
+        let event = EventFd::create(unsafe { &*(conf.addr.as_ptr() as *const EventHeader) })?;
+        let queue = ShmemQueue::create_at(conf, Self::queue_offset(), capacity, false)?;
+
+        Ok(Self::split(queue, event))
+    }
+
+    /// Attaches to the channel of `capacity` elements previously created via `create` in the
+    /// shared memory named `id`.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the channel is not initialized yet or its creating
+    /// handles are dropped and `ShmemError::Unsupported` if the calling process is not the
+    /// creator of the channel.
+    ///
+    /// # Safety
+    ///
+    /// The channel must have been created with the same `T`, and `T` must be valid to be moved
+    /// between processes, i.e. it must not contain pointers to process local memory.
+    pub unsafe fn attach(
+        id: &str,
+        capacity: usize,
+    ) -> Result<(ShmemSender<T>, ShmemReceiver<T>), ShmemError> {
+        let conf = Builder::new(id)
+            .with_size(Self::required_size(capacity)?)
+            .open_existing()?;
+
+        let event = EventFd::attach(&*(conf.addr.as_ptr() as *const EventHeader))?;
+        let queue = ShmemQueue::attach_at(conf, Self::queue_offset())?;
+
+        Ok(Self::split(queue, event))
+    }
+
+    fn split(queue: ShmemQueue<T>, event: EventFd) -> (ShmemSender<T>, ShmemReceiver<T>) {
+        let inner = Arc::new(Inner { queue, event });
+
+        (
+            ShmemSender {
+                inner: inner.clone(),
+            },
+            ShmemReceiver { inner },
+        )
+    }
+
+    fn queue_offset() -> usize {
+        let align = ShmemQueue::<T>::align();
+        mem::size_of::<EventHeader>().div_ceil(align) * align
+    }
+}
+
+struct Inner<T> {
+    queue: ShmemQueue<T>,
+    event: EventFd,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let header = unsafe { &*(self.queue.conf().addr.as_ptr() as *const EventHeader) };
+        self.event.retire(header);
+    }
+}
+
+/// The sending half of a `ShmemChannel`, which can be cloned to send from multiple threads.
+pub struct ShmemSender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> ShmemSender<T> {
+    /// Sends `val` to the receiving side of the channel.
+    ///
+    /// If the channel is full, the current thread yields until a receiver makes room for the
+    /// element.
+    pub fn send(&self, val: T) -> Result<(), ShmemError> {
+        let mut val = val;
+        while let Err(v) = self.inner.queue.push(val) {
+            val = v;
+            std::thread::yield_now();
+        }
+
+        self.inner.event.signal()
+    }
+}
+
+impl<T> Clone for ShmemSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShmemSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemSender")
+            .field("id", &self.inner.queue.conf().id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a `ShmemChannel`.
+pub struct ShmemReceiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> ShmemReceiver<T> {
+    /// Receives the next element, blocking until one is sent if the channel is empty.
+    pub fn recv(&self) -> T {
+        loop {
+            if let Some(val) = self.inner.queue.pop() {
+                return val;
+            }
+            // every send signals once after pushing, so an element pushed after the failed pop
+            // above wakes us up
+            self.inner.event.wait();
+        }
+    }
+
+    /// Receives the next element if the channel is not empty, without blocking.
+    pub fn try_recv(&self) -> Option<T> {
+        self.inner.queue.pop()
+    }
+}
+
+impl<T> fmt::Debug for ShmemReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemReceiver")
+            .field("id", &self.inner.queue.conf().id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn send_recv() {
+        let tmp = TempShmem::new("test-shmem-channel");
+        const ITEMS: u64 = 10_000;
+
+        let (sender, receiver) = ShmemChannel::<u64>::create(tmp.id(), 4).unwrap();
+        assert_eq!(receiver.try_recv(), None);
+
+        std::thread::scope(|s| {
+            for _ in 0..2 {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for i in 1..=ITEMS {
+                        sender.send(i).unwrap();
+                    }
+                });
+            }
+
+            let sum: u64 = (0..2 * ITEMS).map(|_| receiver.recv()).sum();
+            assert_eq!(sum, ITEMS * (ITEMS + 1));
+        });
+        assert_eq!(receiver.try_recv(), None);
+    }
+
+    #[test]
+    fn attach() {
+        let tmp = TempShmem::new("test-shmem-channel-attach");
+        let (sender, _receiver) = ShmemChannel::<u64>::create(tmp.id(), 4).unwrap();
+        let (_, attached) = unsafe { ShmemChannel::<u64>::attach(tmp.id(), 4) }.unwrap();
+
+        sender.send(1).unwrap();
+        assert_eq!(attached.recv(), 1);
+    }
+}
@@ -0,0 +1,153 @@
+//! Iterating over the bytes of a shared memory in fixed-size chunks, e.g. to hash or compress
+//! them with the usual iterator adaptors.
+use std::{iter::FusedIterator, slice};
+
+use crate::ShmemConf;
+
+/// Chunk size of the iterator returned by `<&ShmemConf>::into_iter`.
+const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// An iterator over the bytes of a shared memory in chunks of a fixed size, created via
+/// `ShmemConf::chunks` or by iterating over a `&ShmemConf`.
+///
+/// Every chunk holds the chunk size in bytes, except the last one, which holds the rest of the
+/// shared memory. Only the `size()` bytes of the shared memory are covered, not the rest of the
+/// last page. Other handles may write the bytes concurrently, the same as for
+/// `ShmemConf::boxed_bytes`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_chunks");
+///     let shared_mem = shmem::Builder::new("flink_test_chunks")
+///         .with_size(10_000)
+///         .open()?;
+///
+///     let sum = shared_mem
+///         .into_iter()
+///         .fold(0u64, |sum, chunk| chunk.iter().fold(sum, |sum, &byte| sum + byte as u64));
+///     assert_eq!(sum, 0);
+///     let lens: Vec<_> = shared_mem.chunks(4096).map(<[u8]>::len).collect();
+///     assert_eq!(lens, [4096, 4096, 1808]);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemChunkIter<'a> {
+    conf: &'a ShmemConf,
+    offset: usize,
+    chunk_size: usize,
+}
+
+impl<'a> ShmemChunkIter<'a> {
+    fn new(conf: &'a ShmemConf, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        assert!(
+            conf.check_readable().is_ok(),
+            "shared memory is not readable"
+        );
+        Self {
+            conf,
+            offset: 0,
+            chunk_size,
+        }
+    }
+}
+
+impl<'a> Iterator for ShmemChunkIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.conf.size() - self.offset;
+        if remaining == 0 {
+            return None;
+        }
+        let len = remaining.min(self.chunk_size);
+        // # Safety
+        //
+        // the chunk lies within the `size()` bytes of the mapping, which outlives the borrow of
+        // the `ShmemConf`, and every bit pattern is a valid `u8`
+        let chunk = unsafe { slice::from_raw_parts(self.conf.as_ptr().add(self.offset), len) };
+        self.offset += len;
+
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.conf.size() - self.offset).div_ceil(self.chunk_size);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ShmemChunkIter<'_> {}
+
+impl FusedIterator for ShmemChunkIter<'_> {}
+
+impl<'a> IntoIterator for &'a ShmemConf {
+    type Item = &'a [u8];
+    type IntoIter = ShmemChunkIter<'a>;
+
+    /// Iterates over the shared memory in chunks of 4096 bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared memory is mapped with `Prot::None`.
+    fn into_iter(self) -> Self::IntoIter {
+        ShmemChunkIter::new(self, DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl ShmemConf {
+    /// Returns an iterator over the shared memory in chunks of `chunk_size` bytes. See
+    /// `ShmemChunkIter`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero or the shared memory is mapped with `Prot::None`.
+    pub fn chunks(&self, chunk_size: usize) -> ShmemChunkIter<'_> {
+        ShmemChunkIter::new(self, chunk_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn chunks() {
+        let tmp = TempShmem::new("test-shmem-chunks");
+        let size = 2 * DEFAULT_CHUNK_SIZE + 5;
+        let shmconf = tmp.builder().with_size(size).open().unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let shmconf = &bytes.conf;
+
+        let lens: Vec<_> = shmconf.into_iter().map(<[u8]>::len).collect();
+        assert_eq!(lens, [DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_SIZE, 5]);
+        let mut iter = shmconf.chunks(3);
+        assert_eq!(iter.len(), size.div_ceil(3));
+        assert_eq!(iter.next(), Some(&[0, 1, 2][..]));
+        assert_eq!(iter.len(), size.div_ceil(3) - 1);
+
+        // the chunks cover every byte exactly once, in order
+        let joined: Vec<u8> = shmconf.chunks(1000).flatten().copied().collect();
+        assert_eq!(joined, &bytes[..]);
+        let mut iter = shmconf.chunks(size);
+        assert_eq!(iter.next().map(<[u8]>::len), Some(size));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn zero_chunk_size() {
+        let tmp = TempShmem::new("test-shmem-chunks-zero");
+        let shmconf = tmp.builder().with_size(8).open().unwrap();
+        shmconf.chunks(0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Copying arbitrary serde types in and out of shared memory via `bincode`, enabled by the
+//! `bincode` feature.
+use std::slice;
+
+use bincode::error::EncodeError;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{ShmemConf, ShmemError};
+
+/// Size of the length prefix in front of the encoded value.
+const LEN_PREFIX: usize = 4;
+
+impl ShmemConf {
+    /// Encodes `val` via `bincode` into the shared memory, e.g. to share types without a
+    /// `#[repr(C)]` layout like `Vec<String>` at the cost of a copy.
+    ///
+    /// The encoded length is stored as a 4 byte little endian prefix at offset 0, followed by
+    /// the encoded value. Returns the total number of bytes written, including the prefix.
+    ///
+    /// Nothing synchronizes the copy with other processes, so readers have to be kept out
+    /// until it is complete, e.g. via a `ShmemFlag`.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the encoded value does not fit,
+    /// `ShmemError::EncodeFailed` if `val` can not be encoded and
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable. The bytes after the
+    /// prefix may be partially overwritten on failure.
+    ///
+    /// Only available with the `bincode` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_encode");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_encode")
+    ///         .with_size(64)
+    ///         .open()?;
+    ///
+    ///     let names = vec!["alice".to_string(), "bob".to_string()];
+    ///     shared_mem.encode(&names)?;
+    ///     assert_eq!(shared_mem.decode::<Vec<String>>()?, names);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn encode<T: Serialize>(&mut self, val: &T) -> Result<usize, ShmemError> {
+        self.check_writable()?;
+        let size = self.size;
+        let bytes = unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), size) };
+        let Some((prefix, body)) = bytes.split_at_mut_checked(LEN_PREFIX) else {
+            return Err(ShmemError::SizeMismatch {
+                expected: LEN_PREFIX,
+                found: size,
+            });
+        };
+
+        let len = match bincode::serde::encode_into_slice(val, body, config()) {
+            Ok(len) => len,
+            Err(EncodeError::UnexpectedEnd) => {
+                // only pay for encoding into a vector when reporting the required size
+                let body = bincode::serde::encode_to_vec(val, config())
+                    .map_err(|err| ShmemError::EncodeFailed(err.to_string()))?;
+                return Err(ShmemError::SizeMismatch {
+                    expected: LEN_PREFIX + body.len(),
+                    found: size,
+                });
+            }
+            Err(err) => return Err(ShmemError::EncodeFailed(err.to_string())),
+        };
+        let encoded_len = u32::try_from(len)
+            .map_err(|_| ShmemError::EncodeFailed(format!("{len} bytes exceed the prefix")))?;
+        prefix.copy_from_slice(&encoded_len.to_le_bytes());
+
+        Ok(LEN_PREFIX + len)
+    }
+
+    /// Decodes a value previously written via `encode`.
+    ///
+    /// Returns `ShmemError::DecodeFailed` if the length prefix exceeds the shared memory or the
+    /// bytes do not decode to a `T`, `ShmemError::SizeMismatch` if the shared memory is too
+    /// small to hold the prefix and `ShmemError::ProtectionViolation` unless the mapping is
+    /// readable.
+    ///
+    /// Only available with the `bincode` feature.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, ShmemError> {
+        self.check_readable()?;
+        let bytes = unsafe { slice::from_raw_parts(self.as_ptr(), self.size) };
+        let Some((prefix, body)) = bytes.split_first_chunk::<LEN_PREFIX>() else {
+            return Err(ShmemError::SizeMismatch {
+                expected: LEN_PREFIX,
+                found: self.size,
+            });
+        };
+        let len = u32::from_le_bytes(*prefix) as usize;
+        let Some(body) = body.get(..len) else {
+            return Err(ShmemError::DecodeFailed(format!(
+                "encoded length of {len} bytes exceeds the shared memory"
+            )));
+        };
+
+        bincode::serde::decode_from_slice(body, config())
+            .map(|(val, _)| val)
+            .map_err(|err| ShmemError::DecodeFailed(err.to_string()))
+    }
+}
+
+fn config() -> bincode::config::Configuration {
+    bincode::config::standard()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{ShmemError, TempShmem};
+
+    #[test]
+    fn encode_decode() {
+        let tmp = TempShmem::new("test-shmem-codec-encode-decode");
+        let mut shmconf = tmp.builder().with_size(128).open().unwrap();
+
+        let val: HashMap<String, Vec<u32>> =
+            [("a".to_string(), vec![1, 2]), ("b".to_string(), vec![])].into();
+        let written = shmconf.encode(&val).unwrap();
+        assert!(written > 4 && written <= 128);
+        assert_eq!(shmconf.decode::<HashMap<String, Vec<u32>>>().unwrap(), val);
+
+        let err = shmconf.encode(&vec![0u8; 200]).unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { found: 128, .. }));
+
+        // a corrupted length prefix is rejected
+        unsafe { shmconf.as_mut_ptr().cast::<u32>().write_unaligned(u32::MAX) };
+        let err = shmconf.decode::<Vec<u8>>().unwrap_err();
+        assert!(matches!(err, ShmemError::DecodeFailed(_)));
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt, mem,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use crate::{ShmemBox, ShmemConf, ShmemError};
+
+/// A counter shared between processes, backed by a single `AtomicI64` at the start of the
+/// shared memory.
+///
+/// The all-zero bit pattern is a counter at zero, so a freshly created shared memory is a valid
+/// counter right away. `increment`, `decrement` and `add` use `Ordering::Relaxed`, since counters
+/// are usually not used to synchronize other memory, and return the previous value.
+///
+/// # Examples
+/// ```
+/// use std::mem;
+/// use std::sync::atomic::{AtomicI64, Ordering};
+/// use shmem_bind::{self as shmem,ShmemCounter,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_counter");
+///     let shared_mem = shmem::Builder::new("flink_test_counter")
+///         .with_size(mem::size_of::<AtomicI64>())
+///         .open()?;
+///     let counter = ShmemCounter::new(shared_mem)?;
+///
+///     counter.increment();
+///     counter.add(5);
+///     counter.decrement();
+///     assert_eq!(counter.load(Ordering::Relaxed), 5);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemCounter {
+    inner: ShmemBox<AtomicI64>,
+}
+
+impl ShmemCounter {
+    /// Wraps the shared memory as a counter, keeping its current value.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than an `AtomicI64`
+    /// and `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub fn new(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        if conf.size < mem::size_of::<AtomicI64>() {
+            return Err(ShmemError::SizeMismatch {
+                expected: mem::size_of::<AtomicI64>(),
+                found: conf.size,
+            });
+        }
+
+        // # Safety
+        //
+        // the shared memory is large enough and page aligned, and every bit pattern is a valid
+        // `AtomicI64`
+        Ok(Self {
+            inner: unsafe { conf.boxed::<AtomicI64>() },
+        })
+    }
+
+    /// Adds one to the counter, returning the previous value.
+    pub fn increment(&self) -> i64 {
+        self.add(1)
+    }
+
+    /// Subtracts one from the counter, returning the previous value.
+    pub fn decrement(&self) -> i64 {
+        self.add(-1)
+    }
+
+    /// Adds `n` to the counter, wrapping around on overflow, returning the previous value.
+    pub fn add(&self, n: i64) -> i64 {
+        self.inner.fetch_add(n, Ordering::Relaxed)
+    }
+
+    /// Loads the current value of the counter.
+    pub fn load(&self, ordering: Ordering) -> i64 {
+        self.inner.load(ordering)
+    }
+
+    /// Stores `val` into the counter.
+    pub fn store(&self, val: i64, ordering: Ordering) {
+        self.inner.store(val, ordering)
+    }
+}
+
+impl fmt::Debug for ShmemCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShmemCounter")
+            .field(&self.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    fn open_counter(id: &str) -> ShmemCounter {
+        let shmconf = Builder::new(id)
+            .with_size(mem::size_of::<AtomicI64>())
+            .open()
+            .unwrap();
+        ShmemCounter::new(shmconf).unwrap()
+    }
+
+    #[test]
+    fn shared_between_handles() {
+        let tmp = TempShmem::new("test-shmem-counter");
+        let counter = open_counter(tmp.id());
+        let attached = open_counter(tmp.id());
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        counter.increment();
+                        attached.add(2);
+                        attached.decrement();
+                    }
+                });
+            }
+        });
+        assert_eq!(attached.load(Ordering::Relaxed), 8000);
+
+        counter.store(-3, Ordering::Relaxed);
+        assert_eq!(format!("{attached:?}"), "ShmemCounter(-3)");
+    }
+
+    #[test]
+    fn too_small() {
+        let tmp = TempShmem::new("test-shmem-counter-too-small");
+        let shmconf = tmp.builder().with_size(4).open().unwrap();
+        let err = ShmemCounter::new(shmconf).unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { .. }));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    slice,
+};
+
+use crate::{ShmemBox, ShmemConf};
+
+/// A sequential reader and writer over the bytes of a shared memory, like `std::io::Cursor`.
+///
+/// The cursor tracks a position within the shared memory and never reads or writes past its
+/// end: a read at the end returns 0 bytes and a write at the end writes 0 bytes, so
+/// `read_exact` fails with `io::ErrorKind::UnexpectedEof` and `write_all` with
+/// `io::ErrorKind::WriteZero` once the shared memory is exhausted. Seeking past the end is
+/// allowed, like for `std::io::Cursor`.
+///
+/// The cursor mutably borrows the `ShmemConf` or `ShmemBox<[u8]>` it is created from via
+/// `ShmemConf::cursor` or `ShmemBox::cursor`, so the mapping outlives it.
+///
+/// # Examples
+/// ```
+/// use std::io::{Read,Seek,SeekFrom,Write};
+/// use shmem_bind as shmem;
+///
+/// fn main() -> Result<(),Box<dyn std::error::Error>>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_cursor");
+///     let mut shared_mem = shmem::Builder::new("flink_test_cursor")
+///         .with_size(16)
+///         .open()?;
+///     let mut cursor = shared_mem.cursor();
+///
+///     cursor.write_all(&7u32.to_le_bytes())?;
+///     cursor.write_all(b"hello")?;
+///
+///     cursor.seek(SeekFrom::Start(4))?;
+///     let mut greeting = [0; 5];
+///     cursor.read_exact(&mut greeting)?;
+///     assert_eq!(&greeting, b"hello");
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemCursor<'a> {
+    bytes: &'a mut [u8],
+    pos: u64,
+}
+
+impl<'a> ShmemCursor<'a> {
+    fn new(bytes: &'a mut [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Returns the current position of the cursor.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Sets the position of the cursor, which may lie past the end.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// Returns the bytes between the position and the end of the shared memory.
+    fn remaining(&mut self) -> &mut [u8] {
+        let start =
+            usize::try_from(self.pos).map_or(self.bytes.len(), |pos| pos.min(self.bytes.len()));
+        &mut self.bytes[start..]
+    }
+}
+
+impl Read for ShmemCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.remaining();
+        let len = remaining.len().min(buf.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl Write for ShmemCursor<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let remaining = self.remaining();
+        let len = remaining.len().min(buf.len());
+        remaining[..len].copy_from_slice(&buf[..len]);
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ShmemCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => {
+                self.pos = pos;
+                return Ok(pos);
+            }
+            SeekFrom::End(offset) => (self.bytes.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl ShmemConf {
+    /// Returns a cursor reading and writing the shared memory sequentially from its start. See
+    /// `ShmemCursor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless the shared memory is mapped with `Prot::ReadWrite`.
+    pub fn cursor(&mut self) -> ShmemCursor<'_> {
+        assert!(
+            self.check_writable().is_ok(),
+            "shared memory is not writable"
+        );
+        let size = self.size;
+        ShmemCursor::new(unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), size) })
+    }
+}
+
+impl ShmemBox<[u8]> {
+    /// Returns a cursor reading and writing the boxed bytes sequentially from their start. See
+    /// `ShmemCursor`.
+    pub fn cursor(&mut self) -> ShmemCursor<'_> {
+        ShmemCursor::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    fn write_varint(w: &mut impl Write, mut val: u64) -> io::Result<()> {
+        loop {
+            let byte = (val & 0x7f) as u8;
+            val >>= 7;
+            if val == 0 {
+                return w.write_all(&[byte]);
+            }
+            w.write_all(&[byte | 0x80])?;
+        }
+    }
+
+    fn read_varint(r: &mut impl Read) -> io::Result<u64> {
+        let mut val = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0];
+            r.read_exact(&mut byte)?;
+            val |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+
+        Ok(val)
+    }
+
+    #[test]
+    fn varints() {
+        let tmp = TempShmem::new("test-shmem-cursor-varints");
+        let mut shmconf = tmp.builder().with_size(32).open().unwrap();
+        let values = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+
+        let mut cursor = shmconf.cursor();
+        for val in values {
+            write_varint(&mut cursor, val).unwrap();
+        }
+        let written = cursor.position();
+
+        cursor.rewind().unwrap();
+        for val in values {
+            assert_eq!(read_varint(&mut cursor).unwrap(), val);
+        }
+        assert_eq!(cursor.position(), written);
+    }
+
+    #[test]
+    fn bounds() {
+        let tmp = TempShmem::new("test-shmem-cursor-bounds");
+        let shmconf = tmp.builder().with_size(8).open().unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+        let mut cursor = bytes.cursor();
+
+        let err = cursor.write_all(&[1; 9]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(cursor.position(), 8);
+
+        cursor.seek(SeekFrom::End(-2)).unwrap();
+        let mut buf = [0; 4];
+        let err = cursor.read_exact(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // seeking past the end is fine, reading and writing there is not
+        assert_eq!(cursor.seek(SeekFrom::Current(10)).unwrap(), 18);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        assert_eq!(cursor.write(&buf).unwrap(), 0);
+        let err = cursor.seek(SeekFrom::Current(-20)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
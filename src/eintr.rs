@@ -0,0 +1,22 @@
+//! Internal helper retrying syscalls interrupted by a signal.
+
+/// Evaluates the syscall `$call` again for as long as it fails with `EINTR`, yielding the
+/// result of the first attempt that was not interrupted.
+///
+/// The syscall must signal failure by returning `-1` and setting `errno`.
+///
+/// `close` must never be wrapped: on Linux the file descriptor is released even if `close`
+/// fails with `EINTR`, so retrying could close a file descriptor reused by another thread in
+/// the meantime.
+macro_rules! eintr_retry {
+    ($call:expr) => {
+        loop {
+            let res = $call;
+            if res != -1 || std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+                break res;
+            }
+        }
+    };
+}
+
+pub(crate) use eintr_retry;
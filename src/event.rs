@@ -0,0 +1,358 @@
+use std::{
+    fmt, io, mem,
+    os::fd::{AsRawFd, RawFd},
+    sync::atomic::{AtomicI32, AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{ShmemConf, ShmemError};
+
+/// Marks a header initialized by `EventFd::create`.
+const EVENT_MAGIC: u32 = 0x5348_4556;
+
+/// Header of the event, placed at the start of the shared memory.
+#[repr(C)]
+pub(crate) struct EventHeader {
+    magic: AtomicU32,
+    /// The eventfd backing the event, only meaningful within the process identified by `pid`.
+    fd: AtomicI32,
+    pid: AtomicI32,
+}
+
+/// A lightweight signal living in shared memory, backed by a Linux `eventfd`.
+///
+/// The eventfd is created in semaphore mode, so every `signal` lets exactly one `wait` return.
+/// Signals are counted, a waiter arriving after the signal returns right away.
+///
+/// The file descriptor is recorded in the header of the shared memory. Since file descriptors
+/// are local to a process, `attach` only succeeds in the creating process, while the creating
+/// handle is alive. Every attached handle duplicates the file descriptor, so it stays usable
+/// once the creating handle is dropped. Children forked from the creator use the handles they
+/// inherit instead.
+///
+/// The eventfd is non-blocking, so it can be registered with `epoll` or tokio's `AsyncFd` through
+/// `as_raw_fd`.
+///
+/// Only available on Linux.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use shmem_bind::{self as shmem,ShmemError,ShmemEvent};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_event");
+///     let shared_mem = shmem::Builder::new("flink_test_event")
+///         .with_size(ShmemEvent::required_size())
+///         .open()?;
+///     let event = ShmemEvent::create(shared_mem)?;
+///
+///     assert!(!event.try_wait());
+///     event.signal()?;
+///     assert!(event.wait_timeout(Duration::from_millis(10)));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemEvent {
+    conf: ShmemConf,
+    event: EventFd,
+}
+
+impl Drop for ShmemEvent {
+    fn drop(&mut self) {
+        self.event.retire(unsafe { Self::header(&self.conf) });
+    }
+}
+
+// # Safety
+//
+// the header is only accessed through atomics and the eventfd itself may be used from any
+// thread.
+unsafe impl Sync for ShmemEvent {}
+
+impl ShmemEvent {
+    /// Returns the size of the shared memory required to hold an event.
+    pub const fn required_size() -> usize {
+        mem::size_of::<EventHeader>()
+    }
+
+    /// Creates a new eventfd and records it in the header of the shared memory.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than `required_size()`,
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable and
+    /// `ShmemError::IoError` if the eventfd can not be created.
+    pub fn create(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        Self::check_size(&conf)?;
+        let event = EventFd::create(unsafe { Self::header(&conf) })?;
+
+        Ok(Self { conf, event })
+    }
+
+    /// Attaches to an event previously created via `create` in the shared memory.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the event is not created yet or its creating
+    /// handle is dropped, `ShmemError::Unsupported` if the calling process is not the creator of
+    /// the event, in which case the recorded file descriptor is meaningless,
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable and
+    /// `ShmemError::IoError` if the file descriptor can not be duplicated.
+    pub fn attach(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        Self::check_size(&conf)?;
+        let event = EventFd::attach(unsafe { Self::header(&conf) })?;
+
+        Ok(Self { conf, event })
+    }
+
+    /// Signals the event, letting one current or future waiter return.
+    pub fn signal(&self) -> Result<(), ShmemError> {
+        self.event.signal()
+    }
+
+    /// Consumes one signal if the event is signaled, without blocking.
+    pub fn try_wait(&self) -> bool {
+        self.event.try_wait()
+    }
+
+    /// Blocks until the event is signaled and consumes the signal.
+    pub fn wait(&self) {
+        self.event.wait()
+    }
+
+    /// Blocks until the event is signaled or `dur` has elapsed, consuming the signal.
+    ///
+    /// Returns false if the timeout elapsed before the event was signaled.
+    pub fn wait_timeout(&self, dur: Duration) -> bool {
+        self.event.wait_timeout(dur)
+    }
+
+    fn check_size(conf: &ShmemConf) -> Result<(), ShmemError> {
+        if conf.size < Self::required_size() {
+            return Err(ShmemError::SizeMismatch {
+                expected: Self::required_size(),
+                found: conf.size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// The shared memory must be at least `required_size()` bytes large.
+    unsafe fn header(conf: &ShmemConf) -> &EventHeader {
+        &*(conf.addr.as_ptr() as *const EventHeader)
+    }
+}
+
+impl AsRawFd for ShmemEvent {
+    /// Returns the eventfd backing the event.
+    ///
+    /// Readability of the file descriptor indicates a pending signal, which still has to be
+    /// consumed via `try_wait`.
+    fn as_raw_fd(&self) -> RawFd {
+        self.event.fd
+    }
+}
+
+impl fmt::Debug for ShmemEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemEvent")
+            .field("id", &self.conf.id)
+            .field("fd", &self.event.fd)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The eventfd recorded in an `EventHeader`, shared by `ShmemEvent` and the notifier embedded in
+/// `ShmemChannel`.
+///
+/// Every handle owns its file descriptor, attached handles a duplicate of the recorded one.
+pub(crate) struct EventFd {
+    fd: RawFd,
+    /// True if this handle created the eventfd recorded in the header.
+    created: bool,
+}
+
+impl EventFd {
+    /// Creates a new eventfd and records it in `header`.
+    pub(crate) fn create(header: &EventHeader) -> Result<Self, ShmemError> {
+        let fd = unsafe {
+            libc::eventfd(
+                0,
+                libc::EFD_SEMAPHORE | libc::EFD_NONBLOCK | libc::EFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(ShmemError::IoError(io::Error::last_os_error()));
+        }
+
+        header.fd.store(fd, Ordering::Relaxed);
+        header
+            .pid
+            .store(unsafe { libc::getpid() }, Ordering::Relaxed);
+        // publish the initialized header to attaching handles
+        header.magic.store(EVENT_MAGIC, Ordering::Release);
+
+        Ok(Self { fd, created: true })
+    }
+
+    /// Attaches to the eventfd recorded in `header`.
+    pub(crate) fn attach(header: &EventHeader) -> Result<Self, ShmemError> {
+        if header.magic.load(Ordering::Acquire) != EVENT_MAGIC {
+            return Err(ShmemError::NotInitialized);
+        }
+        if header.pid.load(Ordering::Relaxed) != unsafe { libc::getpid() } {
+            return Err(ShmemError::Unsupported);
+        }
+        // the creating handle may close the recorded file descriptor at any time
+        let fd =
+            unsafe { libc::fcntl(header.fd.load(Ordering::Relaxed), libc::F_DUPFD_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(ShmemError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(Self { fd, created: false })
+    }
+
+    /// Marks `header` as not initialized if this handle created the recorded eventfd, which is
+    /// closed once the handle is dropped, so nobody attaches to a reused file descriptor.
+    pub(crate) fn retire(&self, header: &EventHeader) {
+        if self.created && header.pid.load(Ordering::Relaxed) == unsafe { libc::getpid() } {
+            header.magic.store(0, Ordering::Release);
+        }
+    }
+
+    pub(crate) fn signal(&self) -> Result<(), ShmemError> {
+        let val: u64 = 1;
+        loop {
+            let res = unsafe { libc::write(self.fd, &val as *const u64 as *const libc::c_void, 8) };
+            if res == 8 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(ShmemError::IoError(err));
+            }
+        }
+    }
+
+    pub(crate) fn try_wait(&self) -> bool {
+        let mut val: u64 = 0;
+        loop {
+            let res = unsafe { libc::read(self.fd, &mut val as *mut u64 as *mut libc::c_void, 8) };
+            if res == 8 {
+                return true;
+            }
+            if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                return false;
+            }
+        }
+    }
+
+    pub(crate) fn wait(&self) {
+        while !self.try_wait() {
+            self.poll(-1);
+        }
+    }
+
+    pub(crate) fn wait_timeout(&self, dur: Duration) -> bool {
+        let deadline = Instant::now() + dur;
+        loop {
+            if self.try_wait() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            // round up, so the loop does not spin for the last partial millisecond
+            let millis = remaining.as_nanos().div_ceil(1_000_000);
+            self.poll(millis.min(libc::c_int::MAX as u128) as libc::c_int);
+        }
+    }
+
+    /// Blocks until the eventfd is readable or `timeout` milliseconds have elapsed.
+    ///
+    /// Another waiter may consume the signal in between, so readiness is only a hint.
+    fn poll(&self, timeout: libc::c_int) {
+        let mut pfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pfd, 1, timeout) };
+    }
+}
+
+impl Drop for EventFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    fn open_conf(id: &str) -> ShmemConf {
+        Builder::new(id)
+            .with_size(ShmemEvent::required_size())
+            .open()
+            .unwrap()
+    }
+
+    #[test]
+    fn signal_wait() {
+        let tmp = TempShmem::new("test-shmem-event-signal-wait");
+        let event = ShmemEvent::create(open_conf(tmp.id())).unwrap();
+        assert!(!event.try_wait());
+
+        // signals are counted
+        event.signal().unwrap();
+        event.signal().unwrap();
+        assert!(event.try_wait());
+        assert!(event.try_wait());
+        assert!(!event.try_wait());
+
+        assert!(!event.wait_timeout(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn attach() {
+        let tmp = TempShmem::new("test-shmem-event-attach");
+        let event = ShmemEvent::create(open_conf(tmp.id())).unwrap();
+        let attached = ShmemEvent::attach(open_conf(tmp.id())).unwrap();
+
+        std::thread::scope(|s| {
+            s.spawn(|| attached.wait());
+            std::thread::sleep(Duration::from_millis(20));
+            event.signal().unwrap();
+        });
+        assert!(!event.try_wait());
+    }
+
+    #[test]
+    fn attach_after_creator() {
+        let tmp = TempShmem::new("test-shmem-event-attach-after-creator");
+        let event = ShmemEvent::create(open_conf(tmp.id())).unwrap();
+        let attached = ShmemEvent::attach(open_conf(tmp.id())).unwrap();
+        assert_ne!(attached.as_raw_fd(), event.as_raw_fd());
+
+        // the attached handle owns a duplicate of the eventfd
+        drop(event);
+        attached.signal().unwrap();
+        assert!(attached.try_wait());
+        let err = ShmemEvent::attach(open_conf(tmp.id())).unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+    }
+
+    #[test]
+    fn attach_uninitialized() {
+        let tmp = TempShmem::new("test-shmem-event-attach-uninitialized");
+        let err = ShmemEvent::attach(open_conf(tmp.id())).unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+    }
+}
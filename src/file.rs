@@ -0,0 +1,171 @@
+//! Conversions of already opened files, e.g. from `OpenOptions` or `memfd_create`, into
+//! mappings.
+use std::{
+    fs::File,
+    os::fd::{AsRawFd, IntoRawFd},
+};
+
+use crate::{active, fstat, map_shared, validate_size, Options, Prot, ShmemConf, ShmemError};
+
+/// Maps `file` with `size` bytes, or its whole length if `size` is `None`.
+fn from_file(file: File, size: Option<i64>) -> Result<ShmemConf, ShmemError> {
+    let stat = fstat(file.as_raw_fd())?;
+    let size = validate_size(
+        size.map_or(stat.st_size as i128, i128::from),
+        &Options::default(),
+    )?;
+    // accessing a file mapping past the end of the file raises `SIGBUS`, other files like
+    // devices do not report a size
+    let found = stat.st_size as usize;
+    if stat.st_mode & libc::S_IFMT == libc::S_IFREG && found < size {
+        return Err(ShmemError::SizeMismatch {
+            expected: size,
+            found,
+        });
+    }
+
+    // a file opened read-only can only be mapped read-only
+    let flags = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFL) };
+    if flags < 0 {
+        return Err(ShmemError::IoError(std::io::Error::last_os_error()));
+    }
+    let prot = match flags & libc::O_ACCMODE {
+        libc::O_RDONLY => Prot::Read,
+        _ => Prot::ReadWrite,
+    };
+    let options = Options {
+        prot,
+        ..Options::default()
+    };
+    let addr = map_shared(file.as_raw_fd(), size, &options)?;
+
+    let conf = ShmemConf {
+        id: String::new(),
+        is_owner: false,
+        fd: file.into_raw_fd(),
+        addr,
+        size,
+        options,
+        unmapped: Vec::new(),
+    };
+    active::register(&conf);
+
+    Ok(conf)
+}
+
+/// Maps the whole file, as reported by `fstat`.
+///
+/// The `ShmemConf` takes over the file descriptor and closes it when dropped, no `shm_open` is
+/// involved. Since the file has no `flink_id`, the `ShmemConf` has an empty one and is never
+/// unlinked, not even after `ShmemBox::own`. A file opened read-only is mapped with
+/// `Prot::Read`.
+///
+/// Returns `ShmemError::InvalidSize` if the file is empty and `ShmemError::MapFailedErr` if it
+/// can not be mapped, e.g. because it is not a regular file or shared memory.
+///
+/// # Examples
+/// ```
+/// use std::{fs::OpenOptions,io::Write};
+/// use shmem_bind::{ShmemConf,ShmemError};
+///
+/// fn main() -> Result<(),Box<dyn std::error::Error>>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_try_from_file");
+///     let path = std::env::temp_dir().join("flink_test_try_from_file");
+///     let mut file = OpenOptions::new().read(true).write(true).create(true).open(&path)?;
+///     file.write_all(b"hello")?;
+///
+///     let shared_mem = ShmemConf::try_from(file)?;
+///     let bytes = shared_mem.boxed_bytes();
+///     assert_eq!(&*bytes, b"hello");
+///     std::fs::remove_file(path)?;
+///
+///     Ok(())
+/// }
+/// ```
+impl TryFrom<File> for ShmemConf {
+    type Error = ShmemError;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        from_file(file, None)
+    }
+}
+
+/// Maps the first `size` bytes of the file, for files whose size `fstat` does not report.
+///
+/// Same as `TryFrom<File>`, but returns `ShmemError::SizeMismatch` if a regular file is shorter
+/// than `size`.
+impl TryFrom<(File, i64)> for ShmemConf {
+    type Error = ShmemError;
+
+    fn try_from((file, size): (File, i64)) -> Result<Self, Self::Error> {
+        from_file(file, Some(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::PathBuf,
+    };
+
+    use super::*;
+    use crate::{RequestedSize, TempShmem};
+
+    fn temp_file(name: &str, contents: &[u8]) -> (PathBuf, File) {
+        let path = std::env::temp_dir().join(format!("{name}.{}", std::process::id()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(contents).unwrap();
+        (path, file)
+    }
+
+    #[test]
+    fn map_file() {
+        let tmp = TempShmem::new("test-shmem-file-map-file");
+        let tmp2 = TempShmem::new("test-shmem-file-empty");
+        let (path, file) = temp_file(tmp.id(), b"hello world");
+        let shmconf = ShmemConf::try_from(file).unwrap();
+        assert!(!shmconf.is_owner);
+        assert_eq!(shmconf.size(), 11);
+
+        let mut bytes = shmconf.boxed_bytes();
+        assert_eq!(&*bytes, b"hello world");
+        bytes[..5].copy_from_slice(b"HELLO");
+        bytes.flush().unwrap();
+        drop(bytes);
+        assert_eq!(fs::read(&path).unwrap(), b"HELLO world");
+
+        // a prefix of the file, a read-only file and bounds
+        let shmconf = ShmemConf::try_from((File::open(&path).unwrap(), 5)).unwrap();
+        assert_eq!(shmconf.protection(), Prot::Read);
+        assert_eq!(shmconf.chunks(5).next(), Some(&b"HELLO"[..]));
+        assert!(matches!(
+            shmconf.boxed_safe::<[u8; 5]>(),
+            Err(ShmemError::ProtectionViolation)
+        ));
+        let err = ShmemConf::try_from((File::open(&path).unwrap(), 12)).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::SizeMismatch {
+                expected: 12,
+                found: 11
+            }
+        ));
+        fs::remove_file(&path).unwrap();
+
+        let (path, file) = temp_file(tmp2.id(), b"");
+        let err = ShmemConf::try_from(file).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Bytes(0))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    fmt, hint, mem,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{ShmemBox, ShmemConf, ShmemError};
+
+/// A boolean flag shared between processes, backed by a single `AtomicBool` at the start of the
+/// shared memory.
+///
+/// The all-zero bit pattern is a cleared flag, so a freshly created shared memory is a valid
+/// flag right away. Setting the flag releases every write made before it to a process observing
+/// the flag via `is_set` or `wait_until_set`, which makes it suitable for one-shot readiness
+/// signals.
+///
+/// # Examples
+/// ```
+/// use std::mem;
+/// use std::sync::atomic::AtomicBool;
+/// use std::time::Duration;
+/// use shmem_bind::{self as shmem,ShmemError,ShmemFlag};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_flag");
+///     let shared_mem = shmem::Builder::new("flink_test_flag")
+///         .with_size(mem::size_of::<AtomicBool>())
+///         .open()?;
+///     let ready = ShmemFlag::new(shared_mem)?;
+///
+///     assert!(!ready.wait_until_set(Some(Duration::from_millis(1))));
+///     ready.set();
+///     assert!(ready.wait_until_set(None));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemFlag {
+    inner: ShmemBox<AtomicBool>,
+}
+
+impl ShmemFlag {
+    /// Wraps the shared memory as a flag, keeping its current state.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than an `AtomicBool`
+    /// and `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub fn new(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        if conf.size < mem::size_of::<AtomicBool>() {
+            return Err(ShmemError::SizeMismatch {
+                expected: mem::size_of::<AtomicBool>(),
+                found: conf.size,
+            });
+        }
+
+        // # Safety
+        //
+        // the shared memory is large enough, and only zero and one are ever written to it
+        Ok(Self {
+            inner: unsafe { conf.boxed::<AtomicBool>() },
+        })
+    }
+
+    /// Sets the flag.
+    pub fn set(&self) {
+        self.inner.store(true, Ordering::Release);
+    }
+
+    /// Clears the flag.
+    pub fn clear(&self) {
+        self.inner.store(false, Ordering::Release);
+    }
+
+    /// Returns whether the flag is set.
+    pub fn is_set(&self) -> bool {
+        self.inner.load(Ordering::Acquire)
+    }
+
+    /// Spins until the flag is set or `timeout` has elapsed, waiting forever if `timeout` is
+    /// `None`.
+    ///
+    /// Returns false if the timeout elapsed before the flag was set. Spinning burns a core, so
+    /// this is only meant for flags expected to be set shortly, use `ShmemEvent` otherwise.
+    pub fn wait_until_set(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if self.is_set() {
+                return true;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            hint::spin_loop();
+        }
+    }
+}
+
+impl fmt::Debug for ShmemFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShmemFlag").field(&self.is_set()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    fn open_flag(id: &str) -> ShmemFlag {
+        let shmconf = Builder::new(id)
+            .with_size(mem::size_of::<AtomicBool>())
+            .open()
+            .unwrap();
+        ShmemFlag::new(shmconf).unwrap()
+    }
+
+    #[test]
+    fn set_and_wait() {
+        let tmp = TempShmem::new("test-shmem-flag");
+        let flag = open_flag(tmp.id());
+        let attached = open_flag(tmp.id());
+        assert!(!attached.is_set());
+
+        std::thread::scope(|s| {
+            let waiter = s.spawn(|| attached.wait_until_set(Some(Duration::from_secs(10))));
+            std::thread::sleep(Duration::from_millis(10));
+            flag.set();
+            assert!(waiter.join().unwrap());
+        });
+
+        flag.clear();
+        assert!(!attached.wait_until_set(Some(Duration::from_millis(10))));
+        assert_eq!(format!("{attached:?}"), "ShmemFlag(false)");
+    }
+}
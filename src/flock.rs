@@ -0,0 +1,87 @@
+//! Advisory `flock` locks telling the last handle of a shared memory apart, see
+//! `CleanupPolicy::UnlinkIfLast`.
+use std::{ffi::CStr, io};
+
+use crate::{
+    backend::{Platform, ShmBackend},
+    eintr::eintr_retry,
+    fstat, ShmemError,
+};
+
+/// Takes a shared lock on `fd`, waiting for a handle holding the exclusive lock while it drains
+/// the shared memory, then checks that `name` still refers to the shared memory behind `fd`.
+///
+/// Returns false if the shared memory was unlinked in between, e.g. by the last handle of
+/// another process leaving right after `fd` was opened, in which case the caller opens the
+/// name again. `fd` stays with the caller either way.
+pub(crate) fn lock_shared(fd: i32, name: &CStr) -> Result<bool, ShmemError> {
+    lock(fd)?;
+
+    // names are advisory on Android, where nothing is ever unlinked
+    if cfg!(target_os = "android") {
+        return Ok(true);
+    }
+    let named = match Platform::open(name) {
+        Ok(named) => named,
+        Err(ShmemError::NotFound(_)) => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    let same = same_object(named, fd);
+    unsafe { libc::close(named) };
+
+    same
+}
+
+/// Opens a new file description of the shared memory behind `fd` via its `name` and takes a
+/// shared lock on it, for handles which have to hold a lock of their own.
+///
+/// Returns `ShmemError::IdMismatch` if `name` refers to another shared memory by now and
+/// `ShmemError::NotFound` if it is unlinked. On Android, where names are advisory, `fd` is
+/// duplicated instead.
+pub(crate) fn reopen(fd: i32, name: &CStr) -> Result<i32, ShmemError> {
+    let reopened = if cfg!(target_os = "android") {
+        unsafe { libc::dup(fd) }
+    } else {
+        Platform::open(name)?
+    };
+    if reopened < 0 {
+        return Err(ShmemError::DuplicateFailedErr);
+    }
+
+    let locked = match same_object(reopened, fd) {
+        Ok(true) => lock(reopened).map(|()| reopened),
+        Ok(false) => Err(ShmemError::IdMismatch),
+        Err(err) => Err(err),
+    };
+    if locked.is_err() {
+        unsafe { libc::close(reopened) };
+    }
+
+    locked
+}
+
+/// Takes a shared lock on `fd` without checking what the name refers to, for shared memories
+/// which were just created or are already mapped.
+pub(crate) fn lock(fd: i32) -> Result<(), ShmemError> {
+    if eintr_retry!(unsafe { libc::flock(fd, libc::LOCK_SH) }) != 0 {
+        return Err(ShmemError::IoError(io::Error::last_os_error()));
+    }
+
+    Ok(())
+}
+
+/// Tries to convert the shared lock on `fd` to an exclusive one without blocking, which only
+/// succeeds if no other file description of the shared memory is locked.
+///
+/// The conversion is not atomic: the shared lock is released first, so it is lost if another
+/// handle still holds a lock. This is only meant for handles about to close `fd`.
+pub(crate) fn try_lock_exclusive(fd: i32) -> bool {
+    eintr_retry!(unsafe { libc::flock(fd, libc::LOCK_EX | libc::LOCK_NB) }) == 0
+}
+
+/// Returns whether both file descriptors refer to the same shared memory object.
+fn same_object(fd: i32, other: i32) -> Result<bool, ShmemError> {
+    let (stat, other) = (fstat(fd)?, fstat(other)?);
+
+    Ok(stat.st_dev == other.st_dev && stat.st_ino == other.st_ino)
+}
@@ -0,0 +1,156 @@
+//! Detecting that the name of a shared memory now refers to a newer shared memory.
+use crate::{
+    fstat, storage_id, BuilderWithSize, CleanupPolicy, Options, Prot, SegmentHeader, ShmemConf,
+    ShmemError, StaleHandling,
+};
+
+/// Returns the generation recorded by the shared memory currently named `id`, if it has a
+/// header, without counting as attached to it.
+pub(crate) fn peek(id: &str, options: &Options) -> Option<u64> {
+    if !options.track_owner {
+        return None;
+    }
+    let conf = BuilderWithSize {
+        id: id.to_string(),
+        size: 1,
+        options: Options {
+            prot: Prot::Read,
+            align: options.align,
+            track_owner: true,
+            file_backed: options.file_backed,
+            cleanup: CleanupPolicy::NeverUnlink,
+            stale: StaleHandling::Attach,
+            ..Options::default()
+        },
+    }
+    .open_existing()
+    .ok()?;
+
+    conf.generation()
+}
+
+impl ShmemConf {
+    /// Returns how many times a shared memory was created under the `flink_id`, as known to the
+    /// creator of this one.
+    ///
+    /// A shared memory created from scratch is the first generation, one created via
+    /// `BuilderWithSize::recreate` or `Builder::reclaim_stale` replaces the previous one and
+    /// records its generation plus one. Returns `None` unless the owner is tracked via
+    /// `Builder::track_owner` or if the header is not initialized by its creator yet.
+    pub fn generation(&self) -> Option<u64> {
+        self.header().and_then(SegmentHeader::generation)
+    }
+
+    /// Returns whether the `flink_id` still refers to the shared memory mapped by this handle.
+    ///
+    /// Returns false once the shared memory was unlinked, e.g. because its producer restarted
+    /// and created it anew, in which case this handle keeps reading the pages of the orphaned
+    /// shared memory. The check compares the identity of the object behind the name with the
+    /// mapped one via `fstat` and works without `Builder::track_owner`. Always returns false
+    /// on Android, where shared memories have no names.
+    ///
+    /// Returns any error of opening the shared memory but `ShmemError::NotFound`, and
+    /// `ShmemError::Unsupported` for an ephemeral shared memory, which has no `flink_id`
+    /// referring to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_is_current");
+    ///     let shared_mem = shmem::Builder::new("flink_test_is_current")
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     assert!(shared_mem.is_current()?);
+    ///
+    ///     ShmemConf::unlink("flink_test_is_current")?;
+    ///     assert!(!shared_mem.is_current()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn is_current(&self) -> Result<bool, ShmemError> {
+        if self.options.ephemeral {
+            return Err(ShmemError::Unsupported);
+        }
+        let fd = match self.options.open_object(&storage_id(&self.id)?) {
+            Ok(fd) => fd,
+            Err(ShmemError::NotFound(_)) => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let named = fstat(fd);
+        unsafe { libc::close(fd) };
+        let (named, mapped) = (named?, fstat(self.fd)?);
+
+        Ok(named.st_dev == mapped.st_dev && named.st_ino == mapped.st_ino)
+    }
+
+    /// Replaces the mapping by one of the shared memory the `flink_id` refers to now, unless it
+    /// is still current according to `is_current`.
+    ///
+    /// The new mapping has the size and options of the old one and is never the owner, the
+    /// orphaned shared memory is unmapped without being unlinked. Pointers into the old mapping
+    /// dangle afterwards.
+    ///
+    /// Returns whether the mapping was replaced, and the errors of `is_current` and
+    /// `BuilderWithSize::open_existing`, e.g. `ShmemError::NotFound` if the shared memory was
+    /// unlinked without being created anew. The old mapping is kept on errors.
+    pub fn reattach_if_stale(&mut self) -> Result<bool, ShmemError> {
+        if self.is_current()? {
+            return Ok(false);
+        }
+
+        let fresh = BuilderWithSize {
+            id: self.id.clone(),
+            size: self.size as i128,
+            options: self.options,
+        }
+        .open_existing()?;
+        let mut orphaned = std::mem::replace(self, fresh);
+        // the name belongs to the new shared memory now
+        orphaned.is_owner = false;
+        orphaned.options.cleanup = CleanupPolicy::NeverUnlink;
+        drop(orphaned);
+
+        Ok(true)
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use crate::{Builder, ShmemConf, TempShmem};
+
+    #[test]
+    fn recreated_segment() {
+        let tmp = TempShmem::new("test-shmem-generation-recreated");
+        let id = tmp.id();
+        let open = || Builder::new(id).track_owner(true).with_size(8);
+
+        let mut consumer = open().open().unwrap();
+        unsafe { *consumer.as_mut_ptr() = 1 };
+        assert_eq!(consumer.generation(), Some(1));
+        assert!(consumer.is_current().unwrap());
+        assert!(!consumer.reattach_if_stale().unwrap());
+
+        // the producer restarts
+        let mut producer = open().recreate().unwrap();
+        unsafe { *producer.as_mut_ptr() = 2 };
+        assert_eq!(producer.generation(), Some(2));
+        assert!(!consumer.is_current().unwrap());
+        assert_eq!(unsafe { *consumer.as_ptr() }, 1);
+
+        assert!(consumer.reattach_if_stale().unwrap());
+        assert!(!consumer.is_owner);
+        assert!(consumer.is_current().unwrap());
+        assert_eq!(consumer.generation(), Some(2));
+        assert_eq!(unsafe { *consumer.as_ptr() }, 2);
+        assert_eq!(producer.attach_count(), Some(2));
+
+        // gone without a successor
+        drop(producer);
+        assert!(!consumer.is_current().unwrap());
+        assert!(consumer.reattach_if_stale().is_err());
+        assert!(!ShmemConf::unlink(id).unwrap());
+    }
+}
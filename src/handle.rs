@@ -0,0 +1,257 @@
+//! Portable descriptor strings describing how to attach to a shared memory.
+use std::fmt::Write;
+
+use crate::{Builder, BuilderWithSize, Prot, ShmemConf, ShmemError};
+
+/// Prefix and version of the format, bumped whenever the fields change.
+const HANDLE_PREFIX: &str = "shmem1";
+
+impl ShmemConf {
+    /// Encodes everything needed to attach to the shared memory into a single string, e.g. to
+    /// pass it to a worker spawned via `Command` as an argument or environment variable.
+    ///
+    /// The string records the `flink_id`, the size, the protection and the options changing the
+    /// layout of the mapping, i.e. `Builder::track_owner`, `Builder::with_align`,
+    /// `Builder::mirrored`, whether the mapping is copy-on-write and whether the shared memory
+    /// is backed by a file via `Builder::with_file_path`, in which case the `flink_id` is the
+    /// path. It consists of printable ASCII characters without whitespace only. Use
+    /// `from_handle_string` to attach.
+    ///
+    /// Shared memories without a `flink_id`, i.e. files mapped via `TryFrom<File>` and anonymous
+    /// shared memories, are encoded with an empty one. Such a handle can not be attached to by
+    /// name, only together with the file descriptor as done by `send_via_unix_socket`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_handle_string");
+    ///     let shared_mem = shmem::Builder::new("flink_test_handle_string")
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     let handle = shared_mem.to_handle_string();
+    ///
+    ///     // e.g. in another process
+    ///     let attached = ShmemConf::from_handle_string(&handle)?;
+    ///     assert_eq!(attached.size(), 4);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn to_handle_string(&self) -> String {
+        // the id is hex encoded, so it can contain any character including the separator
+        let mut handle = format!("{HANDLE_PREFIX}:");
+        for byte in self.id.bytes() {
+            let _ = write!(handle, "{byte:02x}");
+        }
+        let _ = write!(handle, ":{}:{}:", self.size, self.options.align);
+        handle.push(match self.options.prot {
+            Prot::None => 'n',
+            Prot::Read => 'r',
+            Prot::ReadWrite => 'w',
+        });
+        for (flag, set) in [
+            ('t', self.options.track_owner),
+            ('m', self.options.mirrored),
+            ('c', self.options.copy_on_write),
+            ('f', self.options.file_backed),
+        ] {
+            if set {
+                handle.push(flag);
+            }
+        }
+
+        handle
+    }
+
+    /// Attaches to the shared memory described by a string created via `to_handle_string`.
+    ///
+    /// The shared memory is opened via `BuilderWithSize::open_existing`, or
+    /// `BuilderWithSize::open_cow` if the handle was created from a copy-on-write mapping, so
+    /// the returned `ShmemConf` is never the owner.
+    ///
+    /// Returns `ShmemError::InvalidHandle` if `handle` is malformed, `ShmemError::InvalidIdErr`
+    /// if it records no `flink_id` and any error of `open_existing` otherwise.
+    pub fn from_handle_string(handle: &str) -> Result<ShmemConf, ShmemError> {
+        let builder = parse_handle_string(handle)?;
+        if builder.id.is_empty() {
+            return Err(ShmemError::InvalidIdErr);
+        }
+        if builder.options.copy_on_write {
+            builder.open_cow()
+        } else {
+            builder.open_existing()
+        }
+    }
+}
+
+/// Decodes a string created via `ShmemConf::to_handle_string` into a builder with the recorded
+/// `flink_id`, size and options.
+pub(crate) fn parse_handle_string(handle: &str) -> Result<BuilderWithSize, ShmemError> {
+    let mut fields = handle.split(':');
+    let mut field = || fields.next().ok_or(ShmemError::InvalidHandle);
+    if field()? != HANDLE_PREFIX {
+        return Err(ShmemError::InvalidHandle);
+    }
+    let id = decode_hex(field()?).ok_or(ShmemError::InvalidHandle)?;
+    let size: usize = field()?.parse().map_err(|_| ShmemError::InvalidHandle)?;
+    let align: usize = field()?.parse().map_err(|_| ShmemError::InvalidHandle)?;
+    if !align.is_power_of_two() {
+        return Err(ShmemError::InvalidHandle);
+    }
+    let flags = field()?;
+    if fields.next().is_some() {
+        return Err(ShmemError::InvalidHandle);
+    }
+
+    let mut flags = flags.chars();
+    let prot = match flags.next() {
+        Some('n') => Prot::None,
+        Some('r') => Prot::Read,
+        Some('w') => Prot::ReadWrite,
+        _ => return Err(ShmemError::InvalidHandle),
+    };
+    // flags are written in a fixed order, which also rules out duplicates
+    let mut remaining = flags.as_str();
+    let mut take = |flag| match remaining.strip_prefix(flag) {
+        Some(rest) => {
+            remaining = rest;
+            true
+        }
+        None => false,
+    };
+    let (track_owner, mirrored, copy_on_write, file_backed) =
+        (take('t'), take('m'), take('c'), take('f'));
+    if !remaining.is_empty() {
+        return Err(ShmemError::InvalidHandle);
+    }
+
+    let mut builder = Builder::new(&id)
+        .track_owner(track_owner)
+        .with_align(align)
+        .mirrored(mirrored)
+        .with_size(size)
+        .with_protection(prot);
+    builder.options.copy_on_write = copy_on_write;
+    builder.options.file_backed = file_backed;
+
+    Ok(builder)
+}
+
+/// Decodes a string of lowercase hex digit pairs into the UTF-8 string they encode, possibly an
+/// empty one.
+fn decode_hex(hex: &str) -> Option<String> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = hex
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digit = |d: u8| match d {
+                b'0'..=b'9' => Some(d - b'0'),
+                b'a'..=b'f' => Some(d - b'a' + 10),
+                _ => None,
+            };
+            Some(digit(pair[0])? << 4 | digit(pair[1])?)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn round_trip() {
+        let tmp = TempShmem::new("test-shmem-handle:round-trip");
+        let shmconf = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let mut owner = unsafe { shmconf.boxed::<u64>() };
+        *owner = 7;
+
+        let handle = owner.conf.to_handle_string();
+        assert!(handle.starts_with("shmem1:"));
+        assert!(handle.ends_with(":8:1:wt"));
+
+        let attached = ShmemConf::from_handle_string(&handle).unwrap();
+        assert!(!attached.is_owner);
+        assert_eq!(attached.to_handle_string(), handle);
+        assert_eq!(*unsafe { attached.boxed::<u64>() }, 7);
+
+        let read_only = owner.conf.try_clone_with(Prot::Read).unwrap();
+        assert!(read_only.to_handle_string().ends_with(":8:1:rt"));
+    }
+
+    #[test]
+    fn file_backed() {
+        let tmp = TempShmem::new("test-shmem-handle-file-backed");
+        let path = std::env::temp_dir().join(tmp.id());
+        let shmconf = Builder::with_file_path(&path)
+            .unwrap()
+            .unlink_on_drop(true)
+            .with_size(8)
+            .open()
+            .unwrap();
+        *unsafe { shmconf.clone_handle().unwrap().boxed::<u64>() } = 7;
+
+        let handle = shmconf.to_handle_string();
+        assert!(handle.ends_with(":8:1:wf"));
+        let attached = ShmemConf::from_handle_string(&handle).unwrap();
+        assert!(attached.is_file_backed());
+        assert_eq!(*unsafe { attached.boxed::<u64>() }, 7);
+
+        drop(shmconf);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn malformed() {
+        for handle in [
+            "",
+            "shmem1",
+            "shmem2:61:8:1:w",
+            "shmem1:6:8:1:w",
+            "shmem1:6G:8:1:w",
+            "shmem1:61:-8:1:w",
+            "shmem1:61:8:3:w",
+            "shmem1:61:8:1:",
+            "shmem1:61:8:1:x",
+            "shmem1:61:8:1:wtt",
+            "shmem1:61:8:1:wct",
+            "shmem1:61:8:1:wfc",
+            "shmem1:61:8:1:w:",
+        ] {
+            let err = ShmemConf::from_handle_string(handle).unwrap_err();
+            assert!(matches!(err, ShmemError::InvalidHandle), "{handle:?}");
+        }
+    }
+
+    #[test]
+    fn without_id() {
+        use std::{fs::OpenOptions, io::Write};
+
+        let tmp = TempShmem::new("test-shmem-handle-without-id");
+        let path = std::env::temp_dir().join(tmp.id());
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(&[0; 8]).unwrap();
+        let shmconf = ShmemConf::try_from(file).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // the handle is well-formed, but there is no name to open
+        let handle = shmconf.to_handle_string();
+        assert!(handle.starts_with("shmem1::8:"));
+        assert_eq!(parse_handle_string(&handle).unwrap().id, "");
+        let err = ShmemConf::from_handle_string(&handle).unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidIdErr));
+    }
+}
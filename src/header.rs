@@ -0,0 +1,271 @@
+//! Header placed at the start of segments opened with `Builder::track_owner`.
+
+use std::{
+    sync::atomic::{AtomicI32, AtomicU32, AtomicU64, AtomicU8, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Marks a header initialized by the creator of the shared memory.
+const HEADER_MAGIC: u32 = 0x5348_4844;
+
+/// Returns whether the shared memory object behind `fd` starts with an initialized header,
+/// peeking at its first page without counting as attached.
+pub(crate) fn present(fd: i32) -> bool {
+    // a header is followed by at least a byte of user data, and the page must not extend
+    // past the end of the object entirely
+    if crate::object_size(fd).is_ok_and(|size| size <= crate::page_size()) {
+        return false;
+    }
+    let page = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            crate::page_size(),
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            fd,
+            0,
+        )
+    };
+    if page == libc::MAP_FAILED {
+        return false;
+    }
+    let header = unsafe { &*(page as *const SegmentHeader) };
+    let present = header.magic.load(Ordering::Acquire) == HEADER_MAGIC;
+    unsafe { libc::munmap(page, crate::page_size()) };
+
+    present
+}
+
+/// Bookkeeping of the segment, stored in front of the user data.
+///
+/// The header occupies at least a page, so the user data keeps the alignment of the mapping.
+#[repr(C)]
+pub(crate) struct SegmentHeader {
+    magic: AtomicU32,
+    /// The process responsible for cleaning up the shared memory, zero while the creator is
+    /// still initializing the header and after the owner released the responsibility.
+    owner_pid: AtomicI32,
+    /// Number of handles currently mapping the shared memory.
+    attached: AtomicU32,
+    /// The process which created the shared memory, unlike `owner_pid` never replaced.
+    creator_pid: AtomicI32,
+    /// Creation time of the shared memory, in nanoseconds since the Unix epoch.
+    created_at: AtomicU64,
+    /// Number of times the shared memory was created under its name, as far as its creator
+    /// knew.
+    generation: AtomicU64,
+    /// Application defined version of the user data, zero if the creator set none.
+    schema_version: AtomicU32,
+    /// Progress of initializing the user data via `ShmemConf::boxed_with`, one of the
+    /// `INIT_*` states or zero before anyone started.
+    init_state: AtomicU8,
+    /// The process initializing the user data, zero before anyone started.
+    init_pid: AtomicI32,
+}
+
+/// The process recorded in `init_pid` is initializing the user data.
+pub(crate) const INIT_INITIALIZING: u8 = 1;
+/// The user data is initialized and published.
+pub(crate) const INIT_READY: u8 = 2;
+
+impl SegmentHeader {
+    /// Records the current process as the creator and owner of the `generation`-th shared
+    /// memory under its name, holding data of `schema_version`, and publishes the header.
+    pub(crate) fn init(&self, generation: u64, schema_version: u32) {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        self.creator_pid.store(current_pid(), Ordering::Relaxed);
+        self.created_at.store(created_at, Ordering::Relaxed);
+        self.generation.store(generation, Ordering::Relaxed);
+        self.schema_version.store(schema_version, Ordering::Relaxed);
+        self.owner_pid.store(current_pid(), Ordering::Relaxed);
+        self.magic.store(HEADER_MAGIC, Ordering::Release);
+    }
+
+    /// Returns the process which created the shared memory, or `None` if the header is not
+    /// initialized yet.
+    pub(crate) fn creator_pid(&self) -> Option<i32> {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return None;
+        }
+        Some(self.creator_pid.load(Ordering::Relaxed))
+    }
+
+    /// Returns when the shared memory was created, or `None` if the header is not initialized
+    /// yet.
+    pub(crate) fn created_at(&self) -> Option<SystemTime> {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return None;
+        }
+        Some(UNIX_EPOCH + Duration::from_nanos(self.created_at.load(Ordering::Relaxed)))
+    }
+
+    /// Returns the generation of the shared memory, or `None` if the header is not initialized
+    /// yet.
+    pub(crate) fn generation(&self) -> Option<u64> {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return None;
+        }
+        Some(self.generation.load(Ordering::Relaxed))
+    }
+
+    /// Returns the version of the user data, or `None` if the header is not initialized yet.
+    pub(crate) fn schema_version(&self) -> Option<u32> {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return None;
+        }
+        Some(self.schema_version.load(Ordering::Acquire))
+    }
+
+    /// Replaces the version of the user data with `new` if it is still `old`.
+    ///
+    /// Returns false if another process replaced `old` first.
+    pub(crate) fn migrate_schema(&self, old: u32, new: u32) -> bool {
+        self.schema_version
+            .compare_exchange(old, new, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Returns the progress of initializing the user data and the process initializing it.
+    pub(crate) fn init_state(&self) -> (u8, i32) {
+        let state = self.init_state.load(Ordering::Acquire);
+        (state, self.init_pid.load(Ordering::Relaxed))
+    }
+
+    /// Records the current process as initializing the user data.
+    pub(crate) fn begin_init(&self) {
+        self.init_pid.store(current_pid(), Ordering::Relaxed);
+        self.init_state.store(INIT_INITIALIZING, Ordering::Release);
+    }
+
+    /// Publishes the user data written since `begin_init`.
+    pub(crate) fn finish_init(&self) {
+        self.init_state.store(INIT_READY, Ordering::Release);
+    }
+
+    /// Returns the recorded owner if it is not running anymore.
+    pub(crate) fn dead_owner(&self) -> Option<i32> {
+        self.owner_pid().filter(|pid| !pid_alive(*pid))
+    }
+
+    /// Returns the recorded owner, or `None` if the header is not initialized yet.
+    pub(crate) fn owner_pid(&self) -> Option<i32> {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return None;
+        }
+        match self.owner_pid.load(Ordering::Acquire) {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// Records the current process as the owner.
+    pub(crate) fn set_owner(&self) {
+        self.owner_pid.store(current_pid(), Ordering::Release);
+    }
+
+    /// Records the current process as the owner if `dead` is still the recorded owner.
+    ///
+    /// Returns false if another process replaced `dead` first.
+    pub(crate) fn replace_owner(&self, dead: i32) -> bool {
+        self.owner_pid
+            .compare_exchange(dead, current_pid(), Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Counts a new handle mapping the shared memory.
+    pub(crate) fn attach(&self) {
+        self.attached.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Stops counting a handle, returning whether it was the last one attached.
+    pub(crate) fn detach(&self) -> bool {
+        self.attached
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok_and(|n| n == 1)
+    }
+
+    /// Returns the number of handles currently mapping the shared memory.
+    pub(crate) fn attached(&self) -> u32 {
+        self.attached.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the current process is the recorded owner.
+    pub(crate) fn held_by_current(&self) -> bool {
+        self.owner_pid.load(Ordering::Acquire) == current_pid()
+    }
+
+    /// Records the current process as the owner if nobody or a dead process is recorded.
+    pub(crate) fn claim(&self) -> Claim {
+        if self.magic.load(Ordering::Acquire) != HEADER_MAGIC {
+            return Claim::NotInitialized;
+        }
+        let pid = current_pid();
+        let mut owner = self.owner_pid.load(Ordering::Acquire);
+        loop {
+            if owner == pid {
+                return Claim::AlreadyHeld;
+            }
+            if owner != 0 && pid_alive(owner) {
+                return Claim::HeldByOther;
+            }
+            match self
+                .owner_pid
+                .compare_exchange(owner, pid, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Claim::Claimed,
+                // another process claimed or released in between, judge the new owner
+                Err(current) => owner = current,
+            }
+        }
+    }
+
+    /// Clears the recorded owner if it is the current process.
+    pub(crate) fn release(&self) {
+        let _ =
+            self.owner_pid
+                .compare_exchange(current_pid(), 0, Ordering::AcqRel, Ordering::Relaxed);
+    }
+}
+
+/// Outcome of `SegmentHeader::claim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Claim {
+    /// The current process replaced nobody or a dead owner.
+    Claimed,
+    /// The current process already was the recorded owner.
+    AlreadyHeld,
+    /// A live process other than the current one is the recorded owner.
+    HeldByOther,
+    /// The creator did not initialize the header yet.
+    NotInitialized,
+}
+
+fn current_pid() -> i32 {
+    unsafe { libc::getpid() }
+}
+
+/// Returns whether the process `pid` is still running.
+///
+/// Zombies count as dead, since they never run `Drop` anymore.
+#[cfg(target_os = "linux")]
+pub(crate) fn pid_alive(pid: i32) -> bool {
+    let Ok(status) = std::fs::read_to_string(format!("/proc/{pid}/status")) else {
+        return false;
+    };
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("State:"))
+        .and_then(|state| state.trim_start().chars().next())
+        .is_none_or(|state| !matches!(state, 'Z' | 'X'))
+}
+
+/// Returns whether the process `pid` is still running.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pid_alive(pid: i32) -> bool {
+    // `EPERM` means the process exists but belongs to another user
+    let res = unsafe { libc::kill(pid, 0) };
+    res == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
@@ -0,0 +1,239 @@
+//! Tracking whether the creator finished initializing the user data, see
+//! `ShmemConf::boxed_with`.
+use std::{
+    mem, ptr, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    header::{pid_alive, INIT_INITIALIZING, INIT_READY},
+    ShmemBox, ShmemConf, ShmemError,
+};
+
+/// Delay before the first poll of `wait_until_ready`, doubled after every attempt up to
+/// `MAX_BACKOFF`.
+const MIN_BACKOFF: Duration = Duration::from_micros(50);
+const MAX_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Progress of initializing the user data of a shared memory, as returned by
+/// `ShmemConf::init_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InitState {
+    /// Nobody started initializing the user data via `ShmemConf::boxed_with` yet.
+    Uninitialized,
+    /// The process `pid` is initializing the user data, or died while doing so if it is not
+    /// running anymore.
+    Initializing { pid: i32 },
+    /// The user data is initialized.
+    Ready,
+}
+
+impl ShmemConf {
+    /// Initializes the shared memory with the value returned by `init` and converts it to a
+    /// boxed pointer, recording the progress in the header so other handles can tell when the
+    /// value is ready via `init_state`, `wait_until_ready` or `boxed_ready`.
+    ///
+    /// The header marks the value as being initialized by the current process before `init` is
+    /// called and as ready once the value is written, which releases every write made by
+    /// `init` to the handles observing it. Any value already present is overwritten without
+    /// being dropped.
+    ///
+    /// Returns `ShmemError::NotOwner` unless this `ShmemConf` is the owner,
+    /// `ShmemError::Unsupported` unless the shared memory is opened with `Builder::track_owner`,
+    /// `ShmemError::OutOfBoundsErr` if the shared memory is smaller than a `T`,
+    /// `ShmemError::Misaligned` if the mapping is not aligned for it and
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use shmem_bind::{self as shmem,InitState,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_with");
+    ///     let open = || {
+    ///         shmem::Builder::new("flink_test_boxed_with")
+    ///             .track_owner(true)
+    ///             .with_size(8)
+    ///             .open()
+    ///     };
+    ///     let owner = open()?;
+    ///     assert_eq!(owner.init_state()?, InitState::Uninitialized);
+    ///     let owner = owner.boxed_with(|| 42_u64)?;
+    ///
+    ///     // e.g. in another process
+    ///     let attached = open()?;
+    ///     attached.wait_until_ready(Some(Duration::from_secs(1)))?;
+    ///     assert_eq!(*unsafe { attached.boxed_ready::<u64>()? }, 42);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn boxed_with<T>(self, init: impl FnOnce() -> T) -> Result<ShmemBox<T>, ShmemError> {
+        if !self.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        self.check_writable()?;
+        let header = self.header().ok_or(ShmemError::Unsupported)?;
+        if mem::size_of::<T>() > self.size {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+        if !self.addr.as_ptr().cast::<T>().is_aligned() {
+            return Err(ShmemError::Misaligned);
+        }
+
+        header.begin_init();
+        let val = init();
+        unsafe { ptr::write(self.addr.as_ptr().cast::<T>(), val) };
+        header.finish_init();
+
+        // # Safety
+        //
+        // the value is initialized and aligned, and fits in the shared memory
+        Ok(unsafe { self.boxed::<T>() })
+    }
+
+    /// Returns the progress of initializing the user data via `boxed_with`.
+    ///
+    /// Returns `ShmemError::Unsupported` unless the shared memory is opened with
+    /// `Builder::track_owner`.
+    pub fn init_state(&self) -> Result<InitState, ShmemError> {
+        let header = self.header().ok_or(ShmemError::Unsupported)?;
+        Ok(match header.init_state() {
+            (INIT_READY, _) => InitState::Ready,
+            (INIT_INITIALIZING, pid) => InitState::Initializing { pid },
+            _ => InitState::Uninitialized,
+        })
+    }
+
+    /// Blocks until the user data is initialized via `boxed_with` or `timeout` has elapsed,
+    /// waiting forever if `timeout` is `None`.
+    ///
+    /// The state is polled with a backoff of up to 10ms between attempts, so this replaces the
+    /// sleep loops otherwise needed to wait for the creator.
+    ///
+    /// Returns `ShmemError::InitAborted` carrying the pid of the initializing process if it
+    /// died before the value was ready, `ShmemError::Timeout` if the value was not ready within
+    /// `timeout` and `ShmemError::Unsupported` unless the shared memory is opened with
+    /// `Builder::track_owner`.
+    pub fn wait_until_ready(&self, timeout: Option<Duration>) -> Result<(), ShmemError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            self.check_ready()?;
+            if self.init_state()? == InitState::Ready {
+                return Ok(());
+            }
+
+            let remaining = deadline.map_or(MAX_BACKOFF, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return Err(ShmemError::Timeout);
+            }
+            thread::sleep(backoff.min(remaining));
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer after checking that the value was
+    /// initialized via `boxed_with`, instead of handing out a possibly uninitialized `T`.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the value is not ready yet,
+    /// `ShmemError::InitAborted` if the initializing process died before it was ready and
+    /// `ShmemError::Unsupported` unless the shared memory is opened with
+    /// `Builder::track_owner`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`, the state does not guarantee that the value was initialized as a `T`.
+    pub unsafe fn boxed_ready<T>(self) -> Result<ShmemBox<T>, ShmemError> {
+        self.check_ready()?;
+        if self.init_state()? != InitState::Ready {
+            return Err(ShmemError::NotInitialized);
+        }
+
+        self.boxed_at::<T>(0)
+    }
+
+    /// Fails with `ShmemError::InitAborted` if the process initializing the value died.
+    fn check_ready(&self) -> Result<(), ShmemError> {
+        match self.init_state()? {
+            InitState::Initializing { pid } if !pid_alive(pid) => {
+                Err(ShmemError::InitAborted { pid })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn ready() {
+        let tmp = TempShmem::new("test-shmem-init-ready");
+        let tmp2 = TempShmem::new("test-shmem-init-untracked");
+        let open = || tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let owner = open();
+        let attached = open();
+        assert_eq!(attached.init_state().unwrap(), InitState::Uninitialized);
+        let err = attached
+            .wait_until_ready(Some(Duration::from_millis(5)))
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Timeout));
+        let err = unsafe { open().boxed_ready::<u64>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+        assert!(matches!(
+            open().boxed_with(|| 0_u64),
+            Err(ShmemError::NotOwner)
+        ));
+
+        thread::scope(|s| {
+            let waiter = s.spawn(|| attached.wait_until_ready(None));
+            let owner = owner
+                .boxed_with(|| {
+                    thread::sleep(Duration::from_millis(20));
+                    7_u64
+                })
+                .unwrap();
+            waiter.join().unwrap().unwrap();
+            assert_eq!(*owner, 7);
+        });
+        assert_eq!(attached.init_state().unwrap(), InitState::Ready);
+        assert_eq!(*unsafe { attached.boxed_ready::<u64>() }.unwrap(), 7);
+
+        let untracked = tmp2.builder().with_size(8).open().unwrap();
+        assert!(matches!(
+            untracked.init_state(),
+            Err(ShmemError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn crashed_initializer() {
+        let tmp = TempShmem::new("test-shmem-init-crashed");
+        let owner = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let attached = owner.clone_handle().unwrap();
+
+        // the child dies halfway through initializing the shared memory
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let _ = owner.boxed_with(|| -> u64 { unsafe { libc::_exit(0) } });
+            unsafe { libc::_exit(1) };
+        }
+        unsafe { libc::waitpid(pid, ptr::null_mut(), 0) };
+
+        assert_eq!(
+            attached.init_state().unwrap(),
+            InitState::Initializing { pid }
+        );
+        let err = attached.wait_until_ready(None).unwrap_err();
+        assert!(matches!(err, ShmemError::InitAborted { pid: found } if found == pid));
+        let err = unsafe { attached.boxed_ready::<u64>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::InitAborted { .. }));
+    }
+}
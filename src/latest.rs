@@ -0,0 +1,300 @@
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{self, AtomicU32, AtomicU64, Ordering},
+};
+
+use crate::{size_add, size_mul, Builder, ShmemConf, ShmemError};
+
+/// Marks a header initialized by `ShmemLatest::create`.
+const LATEST_MAGIC: u32 = 0x5348_4c54;
+
+/// Number of slots, one holding the latest value, one being written and one still being read.
+const SLOTS: u64 = 3;
+
+/// Header of the triple buffer, placed at the start of the shared memory.
+#[repr(C)]
+struct LatestHeader {
+    magic: AtomicU32,
+    /// Number of values published so far, the latest one is in slot `version % SLOTS`.
+    version: AtomicU64,
+}
+
+#[repr(C)]
+struct Slot<T> {
+    /// Sequence lock of the slot. Odd while the publisher writes version `(seq + 1) / 2`, and
+    /// `2 * version` once the value of `version` is complete.
+    seq: AtomicU64,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A triple buffer in shared memory through which a single publisher hands the latest value to
+/// any number of subscribers.
+///
+/// Unlike `ShmemTopic`, subscribers do not receive every value but only the most recently
+/// published one. The publisher writes each value into a slot no subscriber is directed to and
+/// then atomically flips the index of the latest slot, so it never waits for subscribers.
+/// Subscribers never block the publisher nor each other, a subscriber only retries its copy if
+/// the publisher publishes three further values while it is copying. Since a value may be
+/// overwritten while it is copied, values are restricted to `Copy` types.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{ShmemError,ShmemLatest};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_latest");
+///     let mut publisher = ShmemLatest::<[f64; 3]>::create("flink_test_latest")?;
+///     let subscriber = unsafe { ShmemLatest::<[f64; 3]>::subscribe("flink_test_latest")? };
+///     assert_eq!(subscriber.latest(), None);
+///
+///     publisher.publish([1.0, 2.0, 3.0]);
+///     publisher.publish([4.0, 5.0, 6.0]);
+///     assert_eq!(subscriber.latest(), Some([4.0, 5.0, 6.0]));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemLatest<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> ShmemLatest<T> {
+    /// Returns the size of the shared memory required to hold the three slots and the index.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size() -> Result<usize, ShmemError> {
+        size_add(
+            slots_offset::<T>(),
+            size_mul(SLOTS as usize, mem::size_of::<Slot<T>>())?,
+        )
+    }
+
+    /// Creates the shared memory named `id` and initializes an empty triple buffer in it.
+    ///
+    /// Returns `ShmemError::AlreadyExists` if a shared memory named `id` is already present. The
+    /// publisher owns the shared memory, it is cleaned up once the publisher is dropped.
+    pub fn create(id: &str) -> Result<ShmemLatestPublisher<T>, ShmemError> {
+        let conf = Builder::new(id)
+            .with_size(Self::required_size()?)
+            .create_exclusive()?;
+
+        let publisher = ShmemLatestPublisher {
+            conf,
+            _marker: PhantomData,
+        };
+        let header = header(&publisher.conf);
+        header.version.store(0, Ordering::Relaxed);
+        for i in 0..SLOTS {
+            slot::<T>(&publisher.conf, i)
+                .seq
+                .store(0, Ordering::Relaxed);
+        }
+        // publish the initialized header to subscribers
+        header.magic.store(LATEST_MAGIC, Ordering::Release);
+
+        Ok(publisher)
+    }
+
+    /// Subscribes to the triple buffer previously created via `create` in the shared memory
+    /// named `id`.
+    ///
+    /// Returns `ShmemError::NotFound` if the triple buffer does not exist and
+    /// `ShmemError::NotInitialized` if it is not initialized yet.
+    ///
+    /// # Safety
+    ///
+    /// The triple buffer must have been created with the same `T`, and `T` must be valid to be
+    /// moved between processes, i.e. it must not contain pointers to process local memory.
+    pub unsafe fn subscribe(id: &str) -> Result<ShmemLatestSubscriber<T>, ShmemError> {
+        let conf = Builder::new(id)
+            .with_size(Self::required_size()?)
+            .open_existing()?;
+        if header(&conf).magic.load(Ordering::Acquire) != LATEST_MAGIC {
+            return Err(ShmemError::NotInitialized);
+        }
+
+        Ok(ShmemLatestSubscriber {
+            conf,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// The publishing half of a `ShmemLatest`.
+pub struct ShmemLatestPublisher<T> {
+    conf: ShmemConf,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// the publisher is the only writer of the triple buffer and values are `Copy`, i.e. they carry
+// no ownership to be transferred between threads.
+unsafe impl<T: Copy + Send> Send for ShmemLatestPublisher<T> {}
+
+impl<T: Copy> ShmemLatestPublisher<T> {
+    /// Publishes `val` as the latest value, replacing the previous one.
+    ///
+    /// Never waits for subscribers.
+    pub fn publish(&mut self, val: T) {
+        let header = header(&self.conf);
+        let version = header.version.load(Ordering::Relaxed) + 1;
+        // never the slot of the latest value, which subscribers are directed to
+        let slot = slot::<T>(&self.conf, version % SLOTS);
+
+        slot.seq.store(2 * version - 1, Ordering::Relaxed);
+        // pairs with the fence in `ShmemLatestSubscriber::latest`, a subscriber seeing any part
+        // of the new value sees the odd sequence number as well
+        atomic::fence(Ordering::Release);
+        unsafe { ptr::write_volatile(slot.val.get(), MaybeUninit::new(val)) };
+        slot.seq.store(2 * version, Ordering::Release);
+
+        header.version.store(version, Ordering::Release);
+    }
+
+    /// Returns the number of values published so far.
+    pub fn version(&self) -> u64 {
+        header(&self.conf).version.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> fmt::Debug for ShmemLatestPublisher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemLatestPublisher")
+            .field("id", &self.conf.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A subscribing half of a `ShmemLatest`.
+pub struct ShmemLatestSubscriber<T> {
+    conf: ShmemConf,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// subscribers only read the triple buffer and values are `Copy`.
+unsafe impl<T: Copy + Send> Send for ShmemLatestSubscriber<T> {}
+
+impl<T: Copy> ShmemLatestSubscriber<T> {
+    /// Returns the latest published value, or `None` if nothing was published yet.
+    ///
+    /// Values returned by consecutive calls are never older than the ones returned before.
+    pub fn latest(&self) -> Option<T> {
+        self.latest_with_version().map(|(_, val)| val)
+    }
+
+    /// Returns the latest published value together with its version, i.e. the number of
+    /// values published up to and including it.
+    pub fn latest_with_version(&self) -> Option<(u64, T)> {
+        let header = header(&self.conf);
+        loop {
+            let version = header.version.load(Ordering::Acquire);
+            if version == 0 {
+                return None;
+            }
+
+            let slot = slot::<T>(&self.conf, version % SLOTS);
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq != 2 * version {
+                // the slot is being reused for a newer value, which is to be published soon
+                continue;
+            }
+            let val = unsafe { ptr::read_volatile(slot.val.get()) };
+            // pairs with the fence in `ShmemLatestPublisher::publish`
+            atomic::fence(Ordering::Acquire);
+            if slot.seq.load(Ordering::Relaxed) != seq {
+                continue;
+            }
+
+            // # Safety
+            //
+            // the sequence number did not change while copying, so the value is complete
+            return Some((version, unsafe { val.assume_init() }));
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShmemLatestSubscriber<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemLatestSubscriber")
+            .field("id", &self.conf.id)
+            .finish_non_exhaustive()
+    }
+}
+
+fn slots_offset<T>() -> usize {
+    let align = mem::align_of::<Slot<T>>();
+    mem::size_of::<LatestHeader>().div_ceil(align) * align
+}
+
+fn header(conf: &ShmemConf) -> &LatestHeader {
+    unsafe { &*(conf.addr.as_ptr() as *const LatestHeader) }
+}
+
+fn slot<T>(conf: &ShmemConf, index: u64) -> &Slot<T> {
+    unsafe {
+        &*(conf.addr.as_ptr() as *const u8)
+            .add(slots_offset::<T>())
+            .cast::<Slot<T>>()
+            .add(index as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn latest_value() {
+        let tmp = TempShmem::new("test-shmem-latest");
+        let mut publisher = ShmemLatest::<u64>::create(tmp.id()).unwrap();
+        let subscriber = unsafe { ShmemLatest::<u64>::subscribe(tmp.id()) }.unwrap();
+        assert_eq!(subscriber.latest(), None);
+
+        for i in 1..=5 {
+            publisher.publish(i * 10);
+        }
+        assert_eq!(publisher.version(), 5);
+        assert_eq!(subscriber.latest_with_version(), Some((5, 50)));
+        assert_eq!(subscriber.latest(), Some(50));
+
+        let err = ShmemLatest::<u64>::create(tmp.id()).unwrap_err();
+        assert!(matches!(err, ShmemError::AlreadyExists(_)));
+    }
+
+    #[test]
+    fn concurrent_publish() {
+        let tmp = TempShmem::new("test-shmem-latest-concurrent");
+        #[derive(Clone, Copy)]
+        struct Pair(u64, [u64; 15]);
+
+        let mut publisher = ShmemLatest::<Pair>::create(tmp.id()).unwrap();
+        let subscriber = unsafe { ShmemLatest::<Pair>::subscribe(tmp.id()) }.unwrap();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 1..=200_000 {
+                    publisher.publish(Pair(i, [i; 15]));
+                }
+            });
+
+            let mut last = 0;
+            while last != 200_000 {
+                if let Some((version, Pair(a, rest))) = subscriber.latest_with_version() {
+                    // torn values are never returned
+                    assert_eq!(version, a);
+                    assert_eq!(rest, [a; 15]);
+                    assert!(a >= last);
+                    last = a;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,209 @@
+use std::{alloc::Layout, mem};
+
+use crate::{size_add, size_mul, RequestedSize, ShmemError};
+
+/// Computes the offsets of several values carved out of a single shared memory, inserting the
+/// padding required by the alignment of each value.
+///
+/// The offsets are relative to the start of the shared memory, which is page aligned. Values
+/// aligned to more than a page require the shared memory to be mapped via `Builder::with_align`
+/// with at least `align()`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,SegmentLayout,ShmemError};
+///
+/// #[repr(C, align(64))]
+/// struct CacheLine([u8; 64]);
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_segment_layout");
+///     let mut layout = SegmentLayout::new();
+///     let header = layout.push::<[u8; 5]>()?;
+///     let line = layout.push::<CacheLine>()?;
+///     assert_eq!((header, line), (0, 64));
+///
+///     let shared_mem = shmem::Builder::new("flink_test_segment_layout")
+///         .with_align(layout.align())
+///         .with_size(layout.size())
+///         .open()?;
+///     let line = unsafe { shared_mem.boxed_at::<CacheLine>(line)? };
+///     assert_eq!(line.0, [0; 64]);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentLayout {
+    size: usize,
+    align: usize,
+    offsets: Vec<usize>,
+}
+
+impl SegmentLayout {
+    /// Creates an empty layout.
+    pub fn new() -> Self {
+        Self {
+            size: 0,
+            align: 1,
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Appends a `T` to the layout, returning its offset.
+    pub fn push<T>(&mut self) -> Result<usize, ShmemError> {
+        self.push_layout(Layout::new::<T>())
+    }
+
+    /// Appends `len` consecutive `T`s to the layout, returning the offset of the first one.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the values take up more than `isize::MAX` bytes.
+    pub fn push_array<T>(&mut self, len: usize) -> Result<usize, ShmemError> {
+        // `Layout::array` also rejects sizes above `isize::MAX`
+        let size = size_mul(len, mem::size_of::<T>())?;
+        let layout = Layout::array::<T>(len)
+            .map_err(|_| ShmemError::InvalidSize(RequestedSize::Bytes(size)))?;
+        self.push_layout(layout)
+    }
+
+    /// Appends a value described by `layout`, returning its offset.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size of the layout overflows `usize`, in which
+    /// case the layout is left unchanged.
+    pub fn push_layout(&mut self, layout: Layout) -> Result<usize, ShmemError> {
+        let offset = self
+            .size
+            .checked_next_multiple_of(layout.align())
+            .ok_or_else(|| {
+                let align = layout.align() as u128;
+                let offset = (self.size as u128).div_ceil(align) * align;
+                ShmemError::InvalidSize(RequestedSize::Overflow(offset + layout.size() as u128))
+            })?;
+        self.size = size_add(offset, layout.size())?;
+        self.align = self.align.max(layout.align());
+        self.offsets.push(offset);
+
+        Ok(offset)
+    }
+
+    /// Returns the offsets of the values in the order they were pushed.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Returns the size of the shared memory required to hold every value of the layout.
+    pub fn size(&self) -> usize {
+        // an empty shared memory can not be mapped
+        self.size.max(mem::size_of::<u8>())
+    }
+
+    /// Returns the largest alignment of the values of the layout.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+}
+
+impl Default for SegmentLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asserts at compile time that a type has the expected size and alignment.
+///
+/// `assert_shm_layout!(Type, size, align)` expands to two `const _` assertions, so a change to
+/// the layout of a type shared between processes, e.g. a field added to a `#[repr(C)]` struct,
+/// fails the build instead of leaving the segment passed to `Builder::with_size` too small.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::assert_shm_layout;
+///
+/// #[repr(C)]
+/// struct Header {
+///     version: u32,
+///     len: u64,
+/// }
+///
+/// assert_shm_layout!(Header, 16, 8);
+/// ```
+///
+/// A mismatch fails to compile:
+/// ```compile_fail
+/// use shmem_bind::assert_shm_layout;
+///
+/// #[repr(C)]
+/// struct Header {
+///     version: u32,
+///     len: u64,
+/// }
+///
+/// assert_shm_layout!(Header, 12, 8);
+/// ```
+#[macro_export]
+macro_rules! assert_shm_layout {
+    ($ty:ty, $size:expr, $align:expr $(,)?) => {
+        const _: () = ::std::assert!(
+            ::std::mem::size_of::<$ty>() == $size,
+            ::std::concat!("unexpected size of `", ::std::stringify!($ty), "`"),
+        );
+        const _: () = ::std::assert!(
+            ::std::mem::align_of::<$ty>() == $align,
+            ::std::concat!("unexpected alignment of `", ::std::stringify!($ty), "`"),
+        );
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(64))]
+    struct CacheLine([u8; 64]);
+
+    assert_shm_layout!(CacheLine, 64, 64);
+    assert_shm_layout!([CacheLine; 2], 128, 64);
+
+    #[test]
+    fn padding() {
+        let mut layout = SegmentLayout::new();
+        assert_eq!(layout.push::<[u8; 5]>().unwrap(), 0);
+        assert_eq!(layout.push::<CacheLine>().unwrap(), 64);
+        assert_eq!(layout.push::<u32>().unwrap(), 128);
+        assert_eq!(layout.push_array::<u64>(3).unwrap(), 136);
+
+        assert_eq!(layout.offsets(), &[0, 64, 128, 136]);
+        assert_eq!(layout.size(), 160);
+        assert_eq!(layout.align(), 64);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut layout = SegmentLayout::new();
+        let err = layout.push_array::<u64>(usize::MAX).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Overflow(size)) if size == usize::MAX as u128 * 8
+        ));
+        let err = layout.push_array::<[u8; 1 << 60]>(16).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Overflow(size)) if size == 1 << 64
+        ));
+        // fits `usize` but not `isize`
+        let err = layout.push_array::<u8>(usize::MAX).unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidSize(_)));
+
+        let half = isize::MAX as usize;
+        layout.push_array::<u8>(half).unwrap();
+        layout.push_array::<u8>(half).unwrap();
+        let err = layout.push_array::<u8>(half).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Overflow(size)) if size == 3 * half as u128
+        ));
+        // the layout is left unchanged
+        assert_eq!(layout.size(), 2 * half);
+        assert_eq!(layout.offsets().len(), 2);
+    }
+}
@@ -1,417 +1,7645 @@
 use std::{
+    any,
     error::Error,
+    ffi::{CStr, CString},
     fmt::Display,
-    ops::{Deref, DerefMut},
+    hash::{DefaultHasher, Hash, Hasher},
+    io, mem,
+    ops::{Deref, DerefMut, Range},
+    path::Path,
+    pin::Pin,
     ptr::{self, drop_in_place, NonNull},
+    slice,
+    sync::atomic::{self, Ordering},
+    time::SystemTime,
 };
 
+use backend::{FilePath, Platform, ShmBackend};
+use header::{Claim, SegmentHeader};
+
 use libc::{
-    c_char, c_void, close, ftruncate, mmap, munmap, shm_open, shm_unlink, MAP_SHARED, O_CREAT,
-    O_RDWR, PROT_WRITE, S_IRUSR, S_IWUSR,
+    c_int, c_void, close, dup, mmap, mprotect, msync, munmap, sysconf, _SC_PAGESIZE, MAP_FAILED,
+    MAP_PRIVATE, MAP_SHARED, MS_ASYNC, MS_SYNC, PROT_NONE, PROT_READ, PROT_WRITE,
+};
+
+mod active;
+mod backend;
+mod backing;
+mod bitmap;
+#[cfg(target_os = "linux")]
+mod channel;
+mod chunks;
+#[cfg(feature = "bincode")]
+mod codec;
+mod counter;
+mod cursor;
+mod eintr;
+#[cfg(target_os = "linux")]
+mod event;
+mod file;
+mod flag;
+mod flock;
+mod generation;
+mod handle;
+mod header;
+mod init;
+mod latest;
+mod layout;
+mod list;
+mod log;
+#[cfg(all(feature = "numa", target_os = "linux"))]
+mod numa;
+mod queue;
+mod refcount;
+mod registry;
+mod rwlock;
+mod safe;
+mod schema;
+#[cfg(not(target_os = "android"))]
+mod semaphore;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod socket;
+mod spinlock;
+mod stale;
+mod stat;
+mod stats;
+mod temp;
+mod topic;
+mod trace;
+mod version;
+mod wait;
+
+pub use active::{list_active_segments, SegmentSummary};
+pub use backing::BackingKind;
+pub use bitmap::ShmemBitmap;
+#[cfg(target_os = "linux")]
+pub use channel::{ShmemChannel, ShmemReceiver, ShmemSender};
+pub use chunks::ShmemChunkIter;
+pub use counter::ShmemCounter;
+pub use cursor::ShmemCursor;
+#[cfg(target_os = "linux")]
+pub use event::ShmemEvent;
+pub use flag::ShmemFlag;
+pub use init::InitState;
+pub use latest::{ShmemLatest, ShmemLatestPublisher, ShmemLatestSubscriber};
+pub use layout::SegmentLayout;
+pub use list::{list, unlink_all, ShmemInfo};
+pub use log::ShmemLog;
+pub use queue::ShmemQueue;
+pub use registry::SharedShmem;
+pub use rwlock::{
+    ShmemLockResult, ShmemPoisonError, ShmemRwLock, ShmemRwLockReadGuard, ShmemRwLockWriteGuard,
 };
+pub use safe::ShmemSafe;
+pub use schema::{schema_hash, LayoutFingerprint, ShmemSchema};
+#[cfg(not(target_os = "android"))]
+pub use semaphore::ShmemSemaphore;
+#[cfg(feature = "derive")]
+pub use shmem_bind_derive::{ShmemSafe, ShmemSchema};
+pub use spinlock::{ShmemSpinLock, ShmemSpinLockGuard};
+pub use stale::enable_stale_cleanup_daemon;
+pub use stat::{exists, stat, ShmemStat};
+pub use stats::ShmemStats;
+pub use temp::TempShmem;
+pub use topic::{ShmemPublisher, ShmemSubscriber, ShmemTopic};
+pub use version::MigrationDecision;
+pub use wait::wait_for;
 
 pub struct Builder {
     id: String,
+    options: Options,
 }
 
 impl Builder {
+    /// Starts building the shared memory named `id`.
+    ///
+    /// On Android, shared memories are anonymous ashmem regions and `id` is advisory only: every
+    /// `open` creates a new region and other processes get access by receiving its file
+    /// descriptor rather than by opening the same `id`.
     pub fn new(id: &str) -> Self {
         Self {
             id: String::from(id),
+            options: Options::default(),
         }
     }
 
-    pub fn with_size(self, size: i64) -> BuilderWithSize {
-        BuilderWithSize { id: self.id, size }
+    /// Starts building a shared memory named `prefix` followed by the pid of the current
+    /// process and a random suffix, so it neither collides with shared memories of concurrent
+    /// processes nor with ones leaked by earlier processes, e.g. in tests.
+    ///
+    /// Use `id` to pass the resulting name on to other processes. See `TempShmem` for a name
+    /// which is unlinked once it is not needed anymore.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_new_unique");
+    /// #     let _tmp2 = shmem_bind::TempShmem::with_id("flink_test_new_unique-");
+    ///     let builder = shmem::Builder::new_unique("flink_test_new_unique");
+    ///     assert!(builder.id().starts_with("flink_test_new_unique-"));
+    ///     let shared_mem = builder.with_size(4).open()?;
+    ///     let attached = shmem::Builder::new(shared_mem.id()).with_size(4).open()?;
+    ///     assert!(!attached.is_owner());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new_unique(prefix: &str) -> Self {
+        Self::new(&temp::unique_id(prefix))
     }
-}
 
-pub struct BuilderWithSize {
-    id: String,
-    size: i64,
-}
-impl BuilderWithSize {
-    /// Ensures a shared memory using the specified `size` and `flink_id` and mapping it to the
-    /// virtual address of the process memory.
+    /// Starts building an ephemeral shared memory, which is unlinked right after it is created
+    /// and is thereby only reachable through its file descriptor, e.g. to share it with child
+    /// processes inheriting the file descriptor or receiving it over a Unix domain socket.
     ///
-    /// In case of success, a `ShmemConf` is returned, representing the configuration of the
-    /// allocated shared memory.
+    /// The shared memory gets a unique `flink_id` as of `new_unique`, which only exists between
+    /// the calls to `shm_open` and `shm_unlink` within `BuilderWithSize::open`. Since nothing is
+    /// left to unlink, dropping the `ShmemConf` only unmaps the shared memory and closes the file
+    /// descriptor, and the shared memory is gone once the last process does so, even if the
+    /// creator crashes.
     ///
-    /// If the shared memory with the given `flink_id` is not present on the system, the call to
-    /// `open` would create a new shared memory and claims its ownership which is later used for
-    /// cleanup of the shared memory.
+    /// Opening an ephemeral shared memory always creates it, `BuilderWithSize::open_existing`
+    /// and `BuilderWithSize::open_cow` fail with `ShmemError::Unsupported` as do the
+    /// `ShmemConf` methods looking up the `flink_id`, see `ShmemConf::is_ephemeral`.
     ///
     /// # Examples
     /// ```
-    /// use std::mem;
-    /// use shmem_bind::{self as shmem,ShmemError};
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
     ///
     /// fn main() -> Result<(),ShmemError>{
-    ///     // shared_mem is the owner
-    ///     let shared_mem = shmem::Builder::new("flink_test")
-    ///         .with_size(mem::size_of::<i32>() as i64)
-    ///         .open()?;
-    ///     {
-    ///         // shared_mem_barrow is not the owner
-    ///         let shared_mem_barrow = shmem::Builder::new("flink_test")
-    ///             .with_size(mem::size_of::<i32>() as i64)
-    ///             .open()?;
+    ///     let shared_mem = shmem::Builder::ephemeral().with_size(8).open()?;
+    ///     assert!(shared_mem.is_ephemeral());
+    ///     assert!(!shmem::exists(shared_mem.id())?);
+    ///     let mut counter = unsafe { shared_mem.clone_handle()?.boxed::<u64>() };
+    ///     *counter = 1;
+    ///
+    ///     // e.g. after passing the file descriptor to a child process
+    ///     let size = shared_mem.size();
+    ///     let fd = shared_mem.into_fd_keep_unmapped();
+    ///     let mapped = unsafe { ShmemConf::map_only("ephemeral", fd, size)?.boxed::<u64>() };
+    ///     assert_eq!(*mapped, 1);
     ///
-    ///         // shared_mem_barrow goes out of scope, the shared memory is unmapped from virtual
-    ///         // memory of the process.
-    ///     }
-    ///     // shared_mem goes out of scope, the shared memory is unmapped from virtual memory of
-    ///     // the process. after that, the shared memory is unlinked from the system.
     ///     Ok(())
     /// }
-    ///```
-    pub fn open(self) -> Result<ShmemConf, ShmemError> {
-        let (fd, is_owner) = unsafe {
-            let storage_id: *const c_char = self.id.as_bytes().as_ptr() as *const c_char;
-
-            // open the existing shared memory if exists
-            let fd = shm_open(storage_id, O_RDWR, S_IRUSR | S_IWUSR);
-
-            // shared memory didn't exist
-            if fd < 0 {
-                // create the shared memory
-                let fd = shm_open(storage_id, O_RDWR | O_CREAT, S_IRUSR | S_IWUSR);
-                if fd < 0 {
-                    return Err(ShmemError::CreateFailedErr);
-                }
-
-                // allocate the shared memory with required size
-                let res = ftruncate(fd, self.size);
-                if res < 0 {
-                    return Err(ShmemError::AllocationFailedErr);
-                }
-
-                (fd, true)
-            } else {
-                (fd, false)
-            }
-        };
+    /// ```
+    pub fn ephemeral() -> Self {
+        let mut builder = Self::new_unique("shmem-ephemeral");
+        builder.options.ephemeral = true;
+        builder
+    }
 
-        let null = ptr::null_mut();
-        let addr = unsafe { mmap(null, self.size as usize, PROT_WRITE, MAP_SHARED, fd, 0) };
+    /// Starts building a shared memory backed by the regular file at `path` rather than by a
+    /// POSIX shared memory object, e.g. for state which persists across process restarts and
+    /// reboots or which is inspected with the usual file tools.
+    ///
+    /// The file is opened via `open` with `O_CREAT` and mapped like any other shared memory,
+    /// `id` returns `path`. Nothing else changes, except that dropping the owner leaves the
+    /// file behind unless `unlink_on_drop` is set, regardless of the `CleanupPolicy`. Pages
+    /// written through the mapping reach the file asynchronously, see `ShmemConf::flush` for
+    /// waiting for them. `BuilderWithSize::recreate` replaces the file, while the functions
+    /// taking a `flink_id`, such as `exists` and `ShmemConf::unlink`, refer to POSIX shared
+    /// memory objects only.
+    ///
+    /// Returns `ShmemError::InvalidIdErr` if `path` is not valid UTF-8 or contains a nul byte,
+    /// as the path is kept as the `flink_id`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    ///     let path = std::env::temp_dir().join(format!("flink_test_file.{}", std::process::id()));
+    ///     let shared_mem = shmem::Builder::with_file_path(&path)?.with_size(8).open()?;
+    ///     assert!(shared_mem.is_file_backed());
+    ///     *unsafe { shared_mem.boxed::<u64>() } = 7;
+    ///
+    ///     // e.g. after a restart
+    ///     let reopened = shmem::Builder::with_file_path(&path)?
+    ///         .unlink_on_drop(true)
+    ///         .with_size(8)
+    ///         .open_existing()?;
+    ///     assert_eq!(*unsafe { reopened.boxed::<u64>() }, 7);
+    ///     assert!(path.exists());
+    ///
+    ///     std::fs::remove_file(&path).map_err(ShmemError::IoError)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_file_path(path: impl AsRef<Path>) -> Result<Self, ShmemError> {
+        let path = path.as_ref().to_str().ok_or(ShmemError::InvalidIdErr)?;
+        if path.contains('\0') {
+            return Err(ShmemError::InvalidIdErr);
+        }
+        let mut builder = Self::new(path);
+        builder.options.file_backed = true;
 
-        Ok(ShmemConf {
-            id: self.id,
-            is_owner,
-            fd,
-            addr: NonNull::new(addr as *mut _).ok_or(ShmemError::NullPointerErr)?,
-            size: self.size,
-        })
+        Ok(builder)
     }
-}
 
-/// A representation of a ***mapped*** shared memory.
-#[derive(Debug)]
-pub struct ShmemConf {
-    /// `flink_id` of the shared memory to be created on the system
-    id: String,
-    /// Wether or not this `ShmemConf` is the owner of the shared memory.
-    /// This field is set to true when the shared memory is created by this `ShmemConf`
-    is_owner: bool,
-    /// File descriptor of the allocated shared memory 
-    fd: i32,
-    /// Pointer to the shared memory
-    addr: NonNull<()>,
-    /// Size of the allocation
-    size: i64,
-}
+    /// Returns the name of the shared memory to be built.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
 
-impl ShmemConf {
-    /// Converts `ShmemConf`'s raw pointer to a boxed pointer of type `T`.
+    /// Maps the shared memory with `MAP_NORESERVE`, so no swap space is reserved for the
+    /// mapping up front.
     ///
-    /// # Safety
+    /// This is useful for very large and sparse shared memories of which only a small portion is
+    /// ever touched. The mapped size is still the full requested size. If the system runs out of
+    /// memory when a page is touched later, the process receives a `SIGBUS` instead of failing
+    /// the mapping up front. Use `ShmemConf::resident_pages` to monitor how much of the shared memory
+    /// is actually committed.
     ///
-    /// This function is unsafe because there is no guarantee that the referred T is initialized.
-    /// The caller must ensure that the value behind the pointer is initialized before use.
+    /// This option is Linux specific and is a no-op on other platforms.
     ///
     /// # Examples
     /// ```
-    /// use std::mem;
     /// use shmem_bind::{self as shmem,ShmemError};
     ///
-    /// type NotZeroI32 = i32;
-    ///
     /// fn main() -> Result<(),ShmemError>{
-    ///     let shared_mem = shmem::Builder::new("flink_test_boxed")
-    ///         .with_size(mem::size_of::<NotZeroI32>() as i64)
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_no_reserve");
+    ///     // a 16GiB shared memory of which only a single page is touched
+    ///     let shared_mem = shmem::Builder::new("flink_test_no_reserve")
+    ///         .no_reserve(true)
+    ///         .with_size(16 << 30)
     ///         .open()?;
     ///
-    ///     let boxed_val = unsafe {
-    ///         // the allocated shared_memory is not initialized and thus, not guaranteed to be a
-    ///         // valid `NotZeroI32`
-    ///         let mut boxed_val = shared_mem.boxed::<NotZeroI32>();
-    ///         // manually initialize the value in the unsafe block
-    ///         *boxed_val = 5;
-    ///         boxed_val
-    ///     };
+    ///     let mut bytes = shared_mem.boxed_bytes();
+    ///     bytes[0] = 1;
     ///
-    ///     assert_eq!(*boxed_val, 5);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn no_reserve(mut self, no_reserve: bool) -> Self {
+        self.options.no_reserve = no_reserve;
+        self
+    }
+
+    /// Binds the shared memory to the NUMA `node` right after it is mapped, so the pages are
+    /// allocated on that node when they are touched for the first time.
     ///
-    ///     let shared_mem = shmem::Builder::new("flink_test_boxed")
-    ///         .with_size(mem::size_of::<NotZeroI32>() as i64)
-    ///         .open()?;
+    /// Opening the shared memory fails with `ShmemError::NumaFailed` if the binding fails, e.g.
+    /// because NUMA is not available on the system. Call `ShmemConf::bind_numa_node` after
+    /// opening instead if placement is only a best-effort optimization.
     ///
-    ///     let mut boxed_barrow_val = unsafe { shared_mem.boxed::<NotZeroI32>() };
+    /// Only available on Linux with the `numa` feature.
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    pub fn with_numa_node(mut self, node: u32) -> Self {
+        self.options.numa_node = Some(node);
+        self
+    }
+
+    /// Excludes the shared memory from core dumps of the process right after it is mapped. See
+    /// `ShmemConf::exclude_from_core_dumps`.
     ///
-    ///     assert_eq!(*boxed_barrow_val, 5);
+    /// Opening the shared memory fails with `ShmemError::Unsupported` on platforms other than
+    /// Linux.
+    pub fn dont_dump(mut self, dont_dump: bool) -> Self {
+        self.options.dont_dump = dont_dump;
+        self
+    }
+
+    /// Keeps the shared memory out of processes forked from the current process right after it
+    /// is mapped. See `ShmemConf::dont_fork`.
+    ///
+    /// Opening the shared memory fails with `ShmemError::Unsupported` on platforms other than
+    /// Linux.
+    pub fn dont_fork(mut self, dont_fork: bool) -> Self {
+        self.options.dont_fork = dont_fork;
+        self
+    }
+
+    /// Names an anonymous shared memory mapped via `BuilderWithSize::allow_anonymous_fallback`
+    /// after the `flink_id` right after it is mapped, see `ShmemConf::set_debug_name`.
+    ///
+    /// Mappings of named shared memories are listed under their path anyway and are left
+    /// alone, as are anonymous ones if the kernel does not support naming them.
+    pub fn debug_name(mut self, debug_name: bool) -> Self {
+        self.options.debug_name = debug_name;
+        self
+    }
+
+    /// Overwrites the shared memory with zeros when the owning handle is dropped, right before
+    /// it is unmapped, e.g. for shared memories holding keys or tokens.
+    ///
+    /// The zeros are written via volatile writes the compiler can not elide, after the boxed
+    /// value, if any, is dropped. The pages of an unlinked shared memory are freed without
+    /// being cleared, on `tmpfs` they are only zeroed once they are handed out again, so this
+    /// keeps plaintext out of the freed pages. Handles still attached to the shared memory read
+    /// zeros afterwards. It does not help against peers which copied the data already. Only the
+    /// handle responsible for cleaning up wipes the shared memory, as told by
+    /// `ShmemConf::is_owner` and the header of `track_owner`, regardless of the `CleanupPolicy`.
+    /// See `ShmemBox::wipe` for wiping a value while the shared memory is in use.
+    pub fn zero_on_drop(mut self, zero_on_drop: bool) -> Self {
+        self.options.zero_on_drop = zero_on_drop;
+        self
+    }
+
+    /// Removes the file of a shared memory built via `with_file_path` when the owning handle is
+    /// dropped, as told by `ShmemConf::is_owner` and the header of `track_owner`. Has no effect
+    /// on POSIX shared memories, which follow the `CleanupPolicy`.
+    pub fn unlink_on_drop(mut self, unlink_on_drop: bool) -> Self {
+        self.options.unlink_on_drop = unlink_on_drop;
+        self
+    }
+
+    /// Records the process owning the shared memory in a header in front of the user data, so
+    /// other processes can detect a crashed owner via `ShmemConf::owner_alive` and take over
+    /// via `BuilderWithSize::open_stealing_if_dead`.
+    ///
+    /// The header occupies the first page of the shared memory object, or `with_align` bytes if
+    /// that is larger. It is invisible through `ShmemConf`, which still starts at the user data,
+    /// but every process opening the shared memory has to enable the option alike.
+    pub fn track_owner(mut self, track_owner: bool) -> Self {
+        self.options.track_owner = track_owner;
+        self
+    }
+
+    /// Chooses which handles unlink the shared memory when they are dropped, by default the
+    /// owner. See `CleanupPolicy`.
+    ///
+    /// `CleanupPolicy::UnlinkWhenLastDetaches` enables `track_owner` as well, so it must not
+    /// be disabled afterwards.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,CleanupPolicy,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_cleanup_policy");
+    ///     let open = || {
+    ///         shmem::Builder::new("flink_test_cleanup_policy")
+    ///             .cleanup_policy(CleanupPolicy::UnlinkWhenLastDetaches)
+    ///             .with_size(4)
+    ///             .open()
+    ///     };
+    ///     let creator = open()?;
+    ///     let consumer = open()?;
+    ///
+    ///     // the creator leaves first, the consumer still finds the shared memory by name
+    ///     drop(creator);
+    ///     assert!(shmem::exists("flink_test_cleanup_policy")?);
+    ///
+    ///     drop(consumer);
+    ///     assert!(!shmem::exists("flink_test_cleanup_policy")?);
     ///
-    ///     // changes to boxed_barrow_val would reflect to boxed_val as well since they both point
-    ///     // to the same location.
-    ///     *boxed_barrow_val = 3;
-    ///     assert_eq!(*boxed_val, 3);
-    ///     
     ///     Ok(())
     /// }
-    ///
     /// ```
-    pub unsafe fn boxed<T>(self) -> ShmemBox<T> {
-        ShmemBox {
-            ptr: self.addr.cast(),
-            conf: self,
+    pub fn cleanup_policy(mut self, policy: CleanupPolicy) -> Self {
+        if policy == CleanupPolicy::UnlinkWhenLastDetaches {
+            self.options.track_owner = true;
         }
+        self.options.cleanup = policy;
+        self
     }
-}
 
-/// # Safety
-///
-/// Shared memory is shared between processes.
-/// If it can withstand multiple processes mutating it, it can sure handle a thread or two!
-unsafe impl<T: Sync> Sync for ShmemBox<T> {}
-unsafe impl<T: Send> Send for ShmemBox<T> {}
-
-/// A safe and typed wrapper for shared memory
-///
-/// `ShmemBox<T>` wraps the underlying pointer to the shared memory and implements `Deref` and
-/// `DerefMut` for T
-///
-/// When ShmemBox<T> goes out of scope, the cleanup process of the shared memory is done.
-#[derive(Debug)]
-pub struct ShmemBox<T> {
-    ptr: NonNull<T>,
-    conf: ShmemConf,
-}
+    /// Makes `open` fail with `ShmemError::StaleSegment` instead of attaching to an existing
+    /// shared memory whose owner is dead, e.g. one left half-initialized by a crashed process.
+    ///
+    /// The owner is judged via `ShmemConf::owner_alive`. This enables `track_owner` as well, so
+    /// it must not be disabled afterwards. A shared memory whose owner released it, e.g. via
+    /// `ShmemBox::leak`, or whose header is not initialized yet is never stale.
+    pub fn detect_stale(mut self, detect_stale: bool) -> Self {
+        self.options.stale = match (detect_stale, self.options.stale) {
+            (true, StaleHandling::Attach) => StaleHandling::Reject,
+            (true, stale) => stale,
+            (false, _) => StaleHandling::Attach,
+        };
+        self.options.track_owner |= detect_stale;
+        self
+    }
 
-impl<T> ShmemBox<T> {
-    /// Owns the shared memory. this would result in shared memory cleanup when this pointer goes
-    /// out of scope.
+    /// Same as `detect_stale`, but makes `open` unlink a stale shared memory and create a fresh
+    /// one in its place instead of failing, so the returned `ShmemConf` is the owner.
     ///
-    /// # Examples
+    /// If several processes find the same stale shared memory, exactly one of them unlinks and
+    /// recreates it, the others open the shared memory anew. One reopening before the unlink
+    /// still attaches to the stale shared memory, which is detached from the name afterwards.
     ///
+    /// # Examples
     /// ```
-    /// use std::mem;
-    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    /// use shmem_bind::{self as shmem,ShmemError};
     ///
     /// fn main() -> Result<(),ShmemError>{
-    ///     // shared memory is created. `shared_mem` owns the shared memory
-    ///     let shared_mem = shmem::Builder::new("flink_test_own")
-    ///         .with_size(mem::size_of::<i32>() as i64)
-    ///         .open()?;
-    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
-    ///     
-    ///     // leaking the shared memory to prevent `shared_mem` from cleaning it up.
-    ///     ShmemBox::leak(boxed_val);
-    ///     
-    ///     // shared memory is already present on the machine. `shared_mem` does not own the
-    ///     // shared memory.
-    ///     let shared_mem = shmem::Builder::new("flink_test_own")
-    ///         .with_size(mem::size_of::<i32>() as i64)
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_reclaim_stale");
+    ///     let shared_mem = shmem::Builder::new("flink_test_reclaim_stale")
+    ///         .reclaim_stale(true)
+    ///         .with_size(4)
     ///         .open()?;
-    ///     let boxed_val = unsafe { shared_mem.boxed::<i32>() };
     ///
-    ///     // own the shared memory to ensure it's cleanup when the shared_mem goes out of scope.
-    ///     let boxed_val = ShmemBox::own(boxed_val);
+    ///     // nobody owned the shared memory before, so it is created
+    ///     assert_eq!(shared_mem.creator_pid(), Some(std::process::id() as i32));
+    ///     assert!(shared_mem.owner_alive());
     ///
-    ///     // boxed_val goes out of scope, the shared memory is cleaned up
     ///     Ok(())
     /// }
-    ///
     /// ```
-    pub fn own(mut shmem_box: Self) -> Self {
-        shmem_box.conf.is_owner = true;
-
-        shmem_box
+    pub fn reclaim_stale(mut self, reclaim_stale: bool) -> Self {
+        self.options.stale = match reclaim_stale {
+            true => StaleHandling::Reclaim,
+            false => StaleHandling::Attach,
+        };
+        self.options.track_owner |= reclaim_stale;
+        self
     }
 
-    /// Leaks the shared memory and prevents the cleanup if the ShmemBox is the owner of the shared
-    /// memory.
-    /// This function is useful when you want to create a shared memory which lasts longer than the
-    /// process creating it.
+    /// Maps the shared memory twice, back to back, so any window of up to `size` bytes starting
+    /// within the shared memory is contiguous in the virtual address space. See
+    /// `ShmemConf::as_mirrored_slice`.
     ///
-    /// # Examples
+    /// This is the classic trick to implement ring buffers without handling the wrap around.
+    /// Opening the shared memory fails with `ShmemError::InvalidSize` if the size is not a
+    /// multiple of the page size and with `ShmemError::Unsupported` if combined with
+    /// `track_owner`. Growing, shrinking and partially unmapping a mirrored shared memory is not
+    /// supported, and the remaining page granular operations only act on the first half.
     ///
+    /// # Examples
     /// ```
-    /// use std::mem;
-    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    /// use shmem_bind::{self as shmem,ShmemError};
     ///
     /// fn main() -> Result<(),ShmemError>{
-    ///     // shared memory is created. `shared_mem` owns the shared memory
-    ///     let shared_mem = shmem::Builder::new("flink_test_leak")
-    ///         .with_size(mem::size_of::<i32>() as i64)
-    ///         .open()?;
-    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
-    ///     
-    ///     // leaking the shared memory to prevent `shared_mem` from cleaning it up.
-    ///     ShmemBox::leak(boxed_val);
-    ///     
-    ///     // shared memory is already present on the machine. `shared_mem` does not own the
-    ///     // shared memory.
-    ///     let shared_mem = shmem::Builder::new("flink_test_leak")
-    ///         .with_size(mem::size_of::<i32>() as i64)
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_mirrored");
+    ///     let size = shmem::page_size();
+    ///     let shared_mem = shmem::Builder::new("flink_test_mirrored")
+    ///         .mirrored(true)
+    ///         .with_size(size)
     ///         .open()?;
-    ///     let boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///     {
+    ///         let ring = unsafe { shared_mem.as_mirrored_slice() };
+    ///         ring[size - 2..size + 2].copy_from_slice(b"wrap");
+    ///     }
     ///
-    ///     // own the shared memory to ensure it's cleanup when the shared_mem goes out of scope.
-    ///     let boxed_val = ShmemBox::own(boxed_val);
+    ///     // the part of the write past the end landed at the start
+    ///     assert_eq!(unsafe { *shared_mem.as_ptr() }, b'a');
     ///
-    ///     // boxed_val goes out of scope, the shared memory is cleaned up
     ///     Ok(())
     /// }
-    ///
     /// ```
-    pub fn leak(mut shmem_box: Self) {
-        // disabling cleanup for shared memory
-        shmem_box.conf.is_owner = false;
+    pub fn mirrored(mut self, mirrored: bool) -> Self {
+        self.options.mirrored = mirrored;
+        self
     }
-}
 
-impl<T> Drop for ShmemBox<T> {
-    fn drop(&mut self) {
-        if self.conf.is_owner {
-            // # Safety
-            //
-            // if current process is the owner of the shared_memory,i.e. creator of the shared
-            // memory, then it should clean up after, that is, it should drop the inner T
-            unsafe { drop_in_place(self.ptr.as_mut()) };
-        }
+    /// Maps the shared memory at a start address aligned to `align`.
+    ///
+    /// Mappings are always page aligned. Larger alignments, e.g. 2MiB for huge pages, are
+    /// implemented by reserving `align` extra bytes of address space and mapping the shared
+    /// memory at the aligned address within the reservation, the excess is released right away.
+    /// The alignment only applies to the mapping of the current process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn with_align(mut self, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        self.options.align = align;
+        self
     }
-}
-impl Drop for ShmemConf {
-    fn drop(&mut self) {
-        // # Safety
-        //
-        // if current process is the owner of the shared_memory,i.e. creator of the shared
-        // memory, then it should clean up after.
-        // the procedure is as follow:
-        // 1. unmap the shared memory from processes virtual address space.
-        // 2. unlink the shared memory completely from the os if self is the owner
-        // 3. close the file descriptor of the shared memory
-        if unsafe { munmap(self.addr.as_ptr() as *mut c_void, self.size as usize) } != 0 {
-            panic!("failed to unmap shared memory from the virtual memory space")
-        }
-
-        if self.is_owner {
-            let storage_id: *const c_char = self.id.as_bytes().as_ptr() as *const c_char;
-            if unsafe { shm_unlink(storage_id) } != 0 {
-                panic!("failed to reclaim shared memory")
-            }
-        }
 
-        if unsafe { close(self.fd) } != 0 {
-            panic!("failed to close shared memory file descriptor")
+    /// Sets the size of the shared memory in bytes.
+    ///
+    /// Opening the shared memory fails with `ShmemError::InvalidSize` if `size` is zero or does
+    /// not fit in `off_t`.
+    pub fn with_size(self, size: usize) -> BuilderWithSize {
+        BuilderWithSize {
+            id: self.id,
+            size: size as i128,
+            options: self.options,
         }
     }
+
+    /// Same as `with_size`, taking the size as an `i64` like previous releases did.
+    ///
+    /// Opening the shared memory fails with `ShmemError::InvalidSize` if `size` is not positive.
+    #[deprecated(since = "0.1.4", note = "use `with_size`, which takes a `usize`")]
+    pub fn with_size_i64(self, size: i64) -> BuilderWithSize {
+        BuilderWithSize {
+            id: self.id,
+            size: size as i128,
+            options: self.options,
+        }
+    }
+
+    /// Same as `with_size`, but rounds `size` up to the next multiple of the page size, so the
+    /// whole mapping is usable and page granular operations cover it exactly.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_with_size_rounded_to_page");
+    ///     let shared_mem = shmem::Builder::new("flink_test_with_size_rounded_to_page")
+    ///         .with_size_rounded_to_page(12)
+    ///         .open()?;
+    ///     assert_eq!(shared_mem.size(), shmem::page_size());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_size_rounded_to_page(self, size: usize) -> BuilderWithSize {
+        // if rounding overflows, the size is invalid either way and kept for the error
+        let size = size.checked_next_multiple_of(page_size()).unwrap_or(size);
+        self.with_size(size)
+    }
+
+    /// Opens an existing shared memory like `BuilderWithSize::open_existing`, with the size it
+    /// has rather than a size given via `with_size`, e.g. for attaching to shared memories of
+    /// which only the `flink_id` is known.
+    ///
+    /// The size is taken from `fstat` of the shared memory object, less the header of
+    /// `track_owner` if enabled. The shared memory is never created.
+    ///
+    /// Returns `ShmemError::NotFound` if no shared memory with the given `flink_id` is present
+    /// on the system and `ShmemError::InvalidSize` if it is empty, e.g. as its creator has not
+    /// sized it yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_inferred");
+    ///     let shared_mem = shmem::Builder::new("flink_test_inferred")
+    ///         .with_size(1234)
+    ///         .open()?;
+    ///
+    ///     let attached = shmem::Builder::new("flink_test_inferred").open_existing_inferred()?;
+    ///     assert_eq!(attached.size(), 1234);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_existing_inferred(self) -> Result<ShmemConf, ShmemError> {
+        if self.options.ephemeral {
+            return Err(ShmemError::Unsupported);
+        }
+        let fd = self.options.open_existing_object(&storage_id(&self.id)?)?;
+        let size = match object_size(fd) {
+            Ok(object_size) => object_size.saturating_sub(self.options.header_len()),
+            Err(err) => {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        };
+
+        self.with_size(size).open_fd(fd)
+    }
+}
+
+/// Options shared between `Builder` and `BuilderWithSize` which control how the shared memory is
+/// mapped.
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    prot: Prot,
+    no_reserve: bool,
+    dont_dump: bool,
+    dont_fork: bool,
+    debug_name: bool,
+    /// Alignment of the start address of the mapping, if larger than a page
+    align: usize,
+    /// Whether the shared memory starts with a `SegmentHeader`
+    track_owner: bool,
+    /// Whether the shared memory is mapped twice, back to back
+    mirrored: bool,
+    /// Whether the shared memory is mapped with `MAP_PRIVATE`, see `BuilderWithSize::open_cow`
+    copy_on_write: bool,
+    /// Which handles unlink the shared memory when dropped
+    cleanup: CleanupPolicy,
+    /// What `open` does with a shared memory whose recorded owner is dead
+    stale: StaleHandling,
+    /// Permission bits of shared memories created by `open`
+    mode: u32,
+    /// Whether `open` fails unless the shared memory lives in memory
+    require_tmpfs: bool,
+    /// Version of the user data stamped into the header, zero for none
+    schema_version: u32,
+    /// Decides what to do with a shared memory of another version
+    on_version_mismatch: Option<fn(u32, u32) -> MigrationDecision>,
+    /// Address the user data is mapped at, see `BuilderWithSize::map_at`, zero for any
+    map_at: usize,
+    /// Whether the shared memory is unlinked right after it is created, see
+    /// `Builder::ephemeral`
+    ephemeral: bool,
+    /// Whether `open` maps anonymous shared memory if `shm_open` is not permitted, see
+    /// `BuilderWithSize::allow_anonymous_fallback`
+    anonymous_fallback: bool,
+    /// Whether the owner overwrites the shared memory with zeros when dropped
+    zero_on_drop: bool,
+    /// Whether the `flink_id` is the path of a regular file, see `Builder::with_file_path`
+    file_backed: bool,
+    /// Whether the owner of a file backed shared memory removes the file when dropped
+    unlink_on_drop: bool,
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    numa_node: Option<u32>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            prot: Prot::ReadWrite,
+            no_reserve: false,
+            dont_dump: false,
+            dont_fork: false,
+            debug_name: false,
+            align: 1,
+            track_owner: false,
+            mirrored: false,
+            copy_on_write: false,
+            cleanup: CleanupPolicy::OwnerUnlinks,
+            stale: StaleHandling::Attach,
+            mode: 0o600,
+            require_tmpfs: false,
+            schema_version: 0,
+            on_version_mismatch: None,
+            map_at: 0,
+            ephemeral: false,
+            anonymous_fallback: false,
+            zero_on_drop: false,
+            file_backed: false,
+            unlink_on_drop: false,
+            #[cfg(all(feature = "numa", target_os = "linux"))]
+            numa_node: None,
+        }
+    }
+}
+
+/// What `BuilderWithSize::open` does with a shared memory whose recorded owner is dead, see
+/// `Builder::detect_stale` and `Builder::reclaim_stale`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleHandling {
+    Attach,
+    Reject,
+    Reclaim,
+}
+
+impl Options {
+    /// Flags passed to `mmap`.
+    fn map_flags(&self) -> c_int {
+        let mut flags = if self.copy_on_write {
+            MAP_PRIVATE
+        } else {
+            MAP_SHARED
+        };
+        if self.no_reserve {
+            flags |= MAP_NORESERVE;
+        }
+
+        flags
+    }
+
+    /// Returns whether handles hold the `flock` lock of `CleanupPolicy::UnlinkIfLast`, which
+    /// file backed shared memories do without as they are only unlinked via `unlink_on_drop`.
+    fn takes_flock(&self) -> bool {
+        self.cleanup == CleanupPolicy::UnlinkIfLast && !self.file_backed
+    }
+
+    /// Opens the existing object named `name` as `open_object` does and takes the lock of
+    /// `CleanupPolicy::UnlinkIfLast` if needed, opening the name again if the object is unlinked
+    /// in between.
+    fn open_existing_object(&self, name: &CStr) -> Result<c_int, ShmemError> {
+        loop {
+            let fd = self.open_object(name)?;
+            if !self.takes_flock() {
+                return Ok(fd);
+            }
+            match flock::lock_shared(fd, name) {
+                Ok(true) => return Ok(fd),
+                Ok(false) => unsafe { close(fd) },
+                Err(err) => {
+                    unsafe { close(fd) };
+                    return Err(err);
+                }
+            };
+        }
+    }
+
+    /// Opens the existing object named `name` in the backend holding the shared memory.
+    fn open_object(&self, name: &CStr) -> Result<c_int, ShmemError> {
+        match self.file_backed {
+            true => FilePath::open(name),
+            false => Platform::open(name),
+        }
+    }
+
+    /// Creates the object named `name` of `len` bytes in the backend holding the shared
+    /// memory, with the permission bits of `with_permissions`.
+    fn create_object(
+        &self,
+        name: &CStr,
+        len: libc::off_t,
+        exclusive: bool,
+    ) -> Result<c_int, ShmemError> {
+        match self.file_backed {
+            true => FilePath::create(name, len, self.mode, exclusive),
+            false => Platform::create(name, len, self.mode, exclusive),
+        }
+    }
+
+    fn resize_object(&self, fd: c_int, len: libc::off_t) -> io::Result<()> {
+        match self.file_backed {
+            true => FilePath::resize(fd, len),
+            false => Platform::resize(fd, len),
+        }
+    }
+
+    fn unlink_object(&self, name: &CStr) -> io::Result<()> {
+        match self.file_backed {
+            true => FilePath::unlink(name),
+            false => Platform::unlink(name),
+        }
+    }
+
+    /// Unlinks the object named `id` as `ShmemConf::unlink` does, in the backend holding the
+    /// shared memory.
+    fn unlink_id(&self, id: &str) -> Result<bool, ShmemError> {
+        match self.unlink_object(&storage_id(id)?) {
+            Ok(()) => {
+                trace::info!(name = %id, pid = std::process::id(), "unlinked shared memory");
+                Ok(true)
+            }
+            Err(err) if err.raw_os_error() == Some(libc::ENOENT) => Ok(false),
+            Err(err) => Err(ShmemError::IoError(err)),
+        }
+    }
+
+    /// Size of the region reserved for the `SegmentHeader` in front of the user data, which
+    /// keeps the user data aligned like the mapping.
+    fn header_len(&self) -> usize {
+        if self.track_owner {
+            self.align.max(page_size())
+        } else {
+            0
+        }
+    }
+
+    /// Returns whether a handle with these options is laid out and cleaned up like one with the
+    /// default options, which is all `ShmemBox::from_raw_parts` can reconstitute.
+    fn is_plain(&self) -> bool {
+        self.prot == Prot::ReadWrite
+            && !self.track_owner
+            && !self.mirrored
+            && !self.copy_on_write
+            && self.cleanup == CleanupPolicy::OwnerUnlinks
+            && !self.ephemeral
+            && !self.zero_on_drop
+            && !self.file_backed
+    }
+}
+
+#[cfg(target_os = "linux")]
+const MAP_NORESERVE: c_int = libc::MAP_NORESERVE;
+#[cfg(not(target_os = "linux"))]
+const MAP_NORESERVE: c_int = 0;
+
+#[cfg(target_os = "linux")]
+const MAP_FIXED_NOREPLACE: c_int = libc::MAP_FIXED_NOREPLACE;
+#[cfg(not(target_os = "linux"))]
+const MAP_FIXED_NOREPLACE: c_int = 0;
+
+/// Memory protection of a mapped shared memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prot {
+    /// The memory can not be accessed at all.
+    None,
+    /// The memory can only be read.
+    Read,
+    /// The memory can be read and written.
+    ReadWrite,
+}
+
+impl Prot {
+    fn as_raw(self) -> c_int {
+        match self {
+            Prot::None => PROT_NONE,
+            Prot::Read => PROT_READ,
+            Prot::ReadWrite => PROT_READ | PROT_WRITE,
+        }
+    }
+}
+
+/// Access pattern advice for a mapped shared memory, passed to `madvise`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    /// `MADV_SEQUENTIAL`, pages are accessed in order, so aggressive readahead pays off.
+    Sequential,
+    /// `MADV_RANDOM`, pages are accessed in random order, so readahead is wasted.
+    Random,
+    /// `MADV_WILLNEED`, the pages are accessed soon.
+    WillNeed,
+    /// `MADV_DONTNEED`, the pages are not accessed soon. The contents of the shared memory are
+    /// kept, only the page tables of this mapping are released.
+    DontNeed,
+    /// `MADV_HUGEPAGE`, back the mapping with transparent huge pages where possible. Linux only.
+    HugePage,
+}
+
+impl Advice {
+    fn as_raw(self) -> Option<c_int> {
+        match self {
+            Advice::Sequential => Some(libc::MADV_SEQUENTIAL),
+            Advice::Random => Some(libc::MADV_RANDOM),
+            Advice::WillNeed => Some(libc::MADV_WILLNEED),
+            Advice::DontNeed => Some(libc::MADV_DONTNEED),
+            #[cfg(target_os = "linux")]
+            Advice::HugePage => Some(libc::MADV_HUGEPAGE),
+            #[cfg(not(target_os = "linux"))]
+            Advice::HugePage => None,
+        }
+    }
+}
+
+/// Which handles unlink the shared memory when they are dropped, see `Builder::cleanup_policy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CleanupPolicy {
+    /// The owner unlinks the shared memory, i.e. the handle which created it or took over via
+    /// `ShmemBox::own` and friends.
+    #[default]
+    OwnerUnlinks,
+    /// Nobody unlinks the shared memory, it outlives every process using it until it is
+    /// unlinked explicitly, e.g. via `ShmemConf::unlink` by a supervisor.
+    NeverUnlink,
+    /// Every handle unlinks the shared memory, the first one dropped removes the name and the
+    /// others find it gone. Boxed values are still only dropped by the owner.
+    AlwaysUnlink,
+    /// The last handle mapping the shared memory unlinks it, in whichever process that is.
+    ///
+    /// Handles are counted in the header of `Builder::track_owner`, which this policy
+    /// therefore enables. Every process opening the shared memory has to enable the header
+    /// alike, but may use any policy, only handles with this policy unlink once the count
+    /// drops to zero. Handles mapping the shared memory read-only or copy-on-write are not
+    /// counted and never unlink it. Handles of crashed processes are never uncounted, so the
+    /// shared memory of a process which crashed is not unlinked by the others.
+    UnlinkWhenLastDetaches,
+    /// The last handle mapping the shared memory unlinks it, telling so via advisory `flock`
+    /// locks instead of a count in the shared memory.
+    ///
+    /// Every handle takes a shared lock on its file descriptor when it is opened. When dropped,
+    /// it tries to convert it to an exclusive lock without blocking, which only succeeds if no
+    /// other handle holds a lock, and unlinks the shared memory if so. Unlike
+    /// `UnlinkWhenLastDetaches`, no header is needed and the kernel releases the locks of
+    /// crashed processes, so they never keep the shared memory alive. Boxed values are still
+    /// only dropped by the owner.
+    ///
+    /// Locks are held per open file description, so only handles opened with this policy are
+    /// accounted for, every process should use it alike. Clones made via
+    /// `ShmemConf::try_clone` open the name again to hold a lock of their own. A child forked
+    /// from the process, however, shares the locks of the handles it inherits, it must open the
+    /// shared memory itself instead of dropping inherited handles.
+    ///
+    /// The locking is racy by nature, the races are resolved as follows:
+    /// - A process may open the name right before the last handle unlinks it. Its shared lock
+    ///   waits for the exclusive one to be released, then `open` notices that the name no
+    ///   longer refers to the shared memory and opens it again, creating a fresh one, whereas
+    ///   `open_existing` fails with `ShmemError::NotFound`.
+    /// - Handles leaving at the same time never both unlink the shared memory, the exclusive
+    ///   lock goes to at most one of them, and to exactly one if they are the last ones. A
+    ///   failed conversion releases the shared lock, which is harmless since the handle is
+    ///   about to close its file descriptor anyway.
+    /// - A handle opened and dropped right between the creation of the shared memory via
+    ///   `create_exclusive` and its creator taking the lock unlinks the name. The creator keeps
+    ///   its mapping, like after `ShmemConf::unlink`. `open` detects this and starts over.
+    UnlinkIfLast,
+}
+
+/// How `ShmemConf::shrink` treats the shared memory object behind the mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShrinkPolicy {
+    /// Only the mapping of the current handle shrinks, the shared memory object keeps its size.
+    /// This is always safe, other mappings are unaffected.
+    RemapOnly,
+    /// The shared memory object is truncated to the new size as well, returning the memory to
+    /// the system.
+    ///
+    /// Every other mapping of the shared memory, in this or any other process, keeps its larger
+    /// size and raises `SIGBUS` when accessed past the new end. The caller has to make sure no
+    /// one accesses that part anymore, e.g. by having every other process shrink first.
+    TruncateFile,
+}
+
+/// Residency of the pages of a mapped shared memory, as reported by `mincore`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResidencyStats {
+    /// Number of pages covered by the query.
+    pub total_pages: usize,
+    /// Number of those pages currently resident in memory.
+    pub resident_pages: usize,
+    /// One bit per page, least significant bit first, set if the page is resident. Only present
+    /// if requested.
+    pub bitmap: Option<Vec<u8>>,
+}
+
+impl ResidencyStats {
+    /// Returns whether the `page`-th page of the query is resident, or `None` if no bitmap was
+    /// requested or `page` is out of range.
+    pub fn is_resident(&self, page: usize) -> Option<bool> {
+        if page >= self.total_pages {
+            return None;
+        }
+        let bitmap = self.bitmap.as_ref()?;
+
+        Some(bitmap[page / 8] & (1 << (page % 8)) != 0)
+    }
+}
+
+/// Maximum length of a mapping name including the terminating nul, `ANON_VMA_NAME_MAX_LEN`.
+const DEBUG_NAME_MAX_LEN: usize = 80;
+
+/// Length of the type hash written by `ShmemConf::boxed_checked`.
+const TYPE_HASH_LEN: usize = mem::size_of::<u64>();
+
+/// Number of times `wait_until_sized` checks the size of a shared memory, 100µs apart.
+const SIZE_WAIT_ATTEMPTS: usize = 1000;
+
+/// Number of pages queried by a single `mincore` call, bounding the temporary vector.
+const MINCORE_CHUNK_PAGES: usize = 64 * 1024;
+
+/// Outcome of `BuilderWithSize::open_with_status`.
+#[derive(Debug)]
+pub enum Opened {
+    /// The shared memory was created by this call, the `ShmemConf` is its owner.
+    Created(ShmemConf),
+    /// The shared memory existed already, e.g. created by another process.
+    Attached(ShmemConf),
+}
+
+impl Opened {
+    /// Returns true if the shared memory was created by this call.
+    pub fn is_created(&self) -> bool {
+        matches!(self, Opened::Created(_))
+    }
+
+    /// Returns the `ShmemConf`, either way.
+    pub fn into_inner(self) -> ShmemConf {
+        match self {
+            Opened::Created(conf) | Opened::Attached(conf) => conf,
+        }
+    }
+}
+
+pub struct BuilderWithSize {
+    id: String,
+    /// Requested size, validated via `validate_size` when the shared memory is opened. Wide
+    /// enough to carry both `usize` sizes and the negative sizes of the deprecated
+    /// `with_size_i64`.
+    size: i128,
+    options: Options,
 }
+impl BuilderWithSize {
+    /// Sets the protection the shared memory is mapped with. Defaults to `Prot::ReadWrite`.
+    ///
+    /// The safe accessors of `ShmemConf`, e.g. `boxed_safe` or `write_at`, check the protection
+    /// and fail with `ShmemError::ProtectionViolation` instead of faulting if it does not permit
+    /// the access. The protection can later be changed via `ShmemConf::protect`.
+    pub fn with_protection(mut self, prot: Prot) -> Self {
+        self.options.prot = prot;
+        self
+    }
+
+    /// Maps the shared memory with `MAP_NORESERVE`. See `Builder::no_reserve`.
+    pub fn no_reserve(mut self, no_reserve: bool) -> Self {
+        self.options.no_reserve = no_reserve;
+        self
+    }
+
+    /// Sets the permission bits the shared memory is created with, e.g. `0o660` to share it
+    /// with processes of other users in the same group. Defaults to `0o600`, which only grants
+    /// access to the current user.
+    ///
+    /// Only the permission bits of `mode` are used and, unlike `shm_open`, they are not
+    /// restricted by the umask of the process. Opening an existing shared memory keeps its
+    /// permissions. Ignored on Android, where shared memories have no permissions.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_with_permissions");
+    ///     let shared_mem = shmem::Builder::new("flink_test_with_permissions")
+    ///         .with_size(4)
+    ///         .with_permissions(0o660)
+    ///         .open()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_permissions(mut self, mode: u32) -> Self {
+        self.options.mode = mode & 0o777;
+        self
+    }
+
+    /// Maps the shared memory at the address `addr`, e.g. so that every process sees it at the
+    /// same address and raw pointers into it can be exchanged instead of offsets.
+    ///
+    /// `addr` becomes the address of the user data, i.e. what `ShmemConf::as_ptr` returns, so
+    /// with `Builder::track_owner` the header occupies the page in front of it. The shared memory
+    /// is mapped with `MAP_FIXED_NOREPLACE`, never with `MAP_FIXED`, which would silently
+    /// clobber whatever is mapped at `addr` already, e.g. the heap or another shared memory.
+    /// Platforms without `MAP_FIXED_NOREPLACE` pass `addr` as a hint and release the mapping if
+    /// it was placed elsewhere. Pick an address far from the heap and the stack of every process
+    /// involved, e.g. one found free in all of them.
+    ///
+    /// The mapping stays at `addr`: `ShmemConf::grow` only grows it in place on Linux and
+    /// returns `ShmemError::Unsupported` elsewhere, while `ShmemConf::try_clone` maps the clone
+    /// at an independent address.
+    ///
+    /// Opening the shared memory fails with `ShmemError::AddressUnavailable` carrying `EEXIST`
+    /// if any part of the range is in use, with `ShmemError::Misaligned` if `addr` is not aligned
+    /// to a page or to `Builder::with_align` and with `ShmemError::Unsupported` if combined with
+    /// `Builder::mirrored`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_map_at");
+    ///     let open = || shmem::Builder::new("flink_test_map_at").with_size(4096);
+    ///     // an address which was free a moment ago
+    ///     let addr = open().open()?.as_ptr() as *mut _;
+    ///
+    ///     let shared_mem = open().map_at(addr).open()?;
+    ///     assert_eq!(shared_mem.as_ptr() as *mut _, addr);
+    ///
+    ///     // the address is taken by `shared_mem` now
+    ///     let err = open().map_at(addr).open().unwrap_err();
+    ///     assert!(matches!(err, ShmemError::AddressUnavailable(_)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn map_at(mut self, addr: *mut c_void) -> Self {
+        self.options.map_at = addr as usize;
+        self
+    }
+
+    /// Lets `open` and `open_with_status` fall back to anonymous shared memory if `shm_open`
+    /// fails with `EACCES` or `ENOENT`, e.g. on hardened systems where `/dev/shm` is not mounted.
+    ///
+    /// The anonymous shared memory is mapped via `mmap` with `MAP_ANONYMOUS | MAP_SHARED`, so it
+    /// is shared with forked children but no other process can open it. The returned
+    /// `ShmemConf` is the owner, has an empty `flink_id` and no file descriptor, see
+    /// `ShmemConf::is_anonymous`, which is mostly useful for code consuming a `ShmemConf`, e.g.
+    /// in tests. Methods relying on the file descriptor, e.g. `ShmemConf::try_clone` and
+    /// `ShmemConf::grow`, fail on it. Mapping it fails with `ShmemError::Unsupported` if
+    /// combined with `Builder::mirrored`.
+    pub fn allow_anonymous_fallback(mut self) -> Self {
+        self.options.anonymous_fallback = true;
+        self
+    }
+
+    /// Ensures a shared memory using the specified `size` and `flink_id` and mapping it to the
+    /// virtual address of the process memory.
+    ///
+    /// In case of success, a `ShmemConf` is returned, representing the configuration of the
+    /// allocated shared memory.
+    ///
+    /// If the shared memory with the given `flink_id` is not present on the system, the call to
+    /// `open` would create a new shared memory and claims its ownership which is later used for
+    /// cleanup of the shared memory.
+    ///
+    /// # Security
+    ///
+    /// On Linux, shared memories are files in `/dev/shm`, where any local user may create
+    /// entries. Another user could place a symlink under the `flink_id` before the shared memory
+    /// is created, so that opening it resizes and maps a file of the current user instead, or a
+    /// FIFO which blocks readers. Names are therefore never followed if they are symlinks and
+    /// `open` fails with `ShmemError::SecurityViolation` unless the name refers to a regular
+    /// file on a memory backed file system. Users of the same shared memory can always read and
+    /// write it, choose permissions accordingly via `with_permissions`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test");
+    ///     // shared_mem is the owner
+    ///     let shared_mem = shmem::Builder::new("flink_test")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     {
+    ///         // shared_mem_barrow is not the owner
+    ///         let shared_mem_barrow = shmem::Builder::new("flink_test")
+    ///             .with_size(mem::size_of::<i32>())
+    ///             .open()?;
+    ///
+    ///         // shared_mem_barrow goes out of scope, the shared memory is unmapped from virtual
+    ///         // memory of the process.
+    ///     }
+    ///     // shared_mem goes out of scope, the shared memory is unmapped from virtual memory of
+    ///     // the process. after that, the shared memory is unlinked from the system.
+    ///     Ok(())
+    /// }
+    ///```
+    pub fn open(self) -> Result<ShmemConf, ShmemError> {
+        self.open_with_status().map(Opened::into_inner)
+    }
+
+    /// Same as `open`, but reports whether the shared memory was created or attached to, e.g.
+    /// to initialize it only if it was created.
+    ///
+    /// The shared memory counts as created only if the call to `shm_open` passing
+    /// `O_CREAT | O_EXCL` succeeded, so exactly one handle is created even if several processes
+    /// open the same `flink_id` concurrently.
+    ///
+    /// Attaching to an existing shared memory returns `ShmemError::NotInitialized` if its
+    /// creator did not size it in time, e.g. because it died halfway, and
+    /// `ShmemError::SizeMismatch` if it is smaller than `size`, instead of mapping past its end.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,Opened,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_with_status");
+    ///     let open = || shmem::Builder::new("flink_test_open_with_status").with_size(8).open_with_status();
+    ///
+    ///     let created = open()?;
+    ///     assert!(created.is_created());
+    ///     let Opened::Created(conf) = created else { unreachable!() };
+    ///     let mut counter = unsafe { conf.boxed::<u64>() };
+    ///     *counter = 1;
+    ///
+    ///     match open()? {
+    ///         Opened::Created(_) => unreachable!("the shared memory exists already"),
+    ///         Opened::Attached(conf) => assert_eq!(*unsafe { conf.boxed::<u64>() }, 1),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_with_status(self) -> Result<Opened, ShmemError> {
+        if self.options.ephemeral {
+            return self.create_exclusive().map(Opened::Created);
+        }
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "shmem_open",
+            name = %self.id,
+            size = self.size,
+            created = tracing::field::Empty,
+        )
+        .entered();
+
+        let storage_id = storage_id(&self.id)?;
+        let size = validate_size(self.size, &self.options)?;
+        let (fd, is_owner) = loop {
+            let (fd, is_owner) = match self.options.open_object(&storage_id) {
+                Ok(fd) => (fd, false),
+                // shared memory didn't exist, create it with the required size
+                Err(ShmemError::NotFound(_)) => match self.options.create_object(
+                    &storage_id,
+                    segment_len(size, &self.options),
+                    true,
+                ) {
+                    Ok(fd) => (fd, true),
+                    // another process created it in between, attach to it instead
+                    Err(ShmemError::AlreadyExists(_)) => {
+                        trace::debug!(name = %self.id, "lost the race creating the shared memory");
+                        continue;
+                    }
+                    Err(err) => return self.fall_back_to_anonymous(err, size),
+                },
+                Err(err) => return self.fall_back_to_anonymous(err, size),
+            };
+            if !self.options.takes_flock() {
+                break (fd, is_owner);
+            }
+            match flock::lock_shared(fd, &storage_id) {
+                Ok(true) => break (fd, is_owner),
+                // the last handle of another process unlinked the shared memory in between
+                Ok(false) => {
+                    trace::debug!(name = %self.id, "shared memory unlinked while locking it");
+                    unsafe { close(fd) };
+                }
+                Err(err) => {
+                    unsafe { close(fd) };
+                    return Err(err);
+                }
+            }
+        };
+        if !is_owner {
+            wait_until_sized(fd);
+            // accessing the mapping past the end of the shared memory object raises `SIGBUS`
+            let res = match object_size(fd) {
+                Ok(0) => Err(ShmemError::NotInitialized),
+                Ok(object_size) => {
+                    let found = object_size.saturating_sub(self.options.header_len());
+                    match found < size {
+                        true => Err(ShmemError::SizeMismatch {
+                            expected: size,
+                            found,
+                        }),
+                        false => Ok(()),
+                    }
+                }
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        span.record("created", is_owner);
+
+        let addr = match map_shared(fd, size, &self.options) {
+            Ok(addr) => addr,
+            Err(err) => {
+                unsafe { close(fd) };
+                if is_owner {
+                    let _ = self.options.unlink_object(&storage_id);
+                }
+                return Err(err);
+            }
+        };
+
+        let conf = ShmemConf {
+            id: self.id,
+            is_owner,
+            fd,
+            addr,
+            size,
+            options: self.options,
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+        conf.attach_header();
+        if is_owner {
+            conf.init_header(1);
+        } else {
+            let dead = conf.header().and_then(SegmentHeader::dead_owner);
+            match (conf.options.stale, dead) {
+                (StaleHandling::Attach, _) | (_, None) => {}
+                (StaleHandling::Reject, Some(pid)) => {
+                    return Err(ShmemError::StaleSegment { pid });
+                }
+                (StaleHandling::Reclaim, Some(pid)) => return conf.reclaim(pid),
+            }
+            conf.check_schema_version()?;
+            conf.watch_owner();
+        }
+        conf.apply_options()?;
+        conf.trace_opened();
+
+        Ok(match is_owner {
+            true => Opened::Created(conf),
+            false => Opened::Attached(conf),
+        })
+    }
+
+    /// Maps anonymous shared memory instead of failing with `err`, if allowed via
+    /// `allow_anonymous_fallback` and `shm_open` failed with `EACCES` or `ENOENT`.
+    fn fall_back_to_anonymous(self, err: ShmemError, size: usize) -> Result<Opened, ShmemError> {
+        let errno = match &err {
+            ShmemError::IoError(err)
+            | ShmemError::NotFound(err)
+            | ShmemError::CreateFailedErr(err) => err.raw_os_error(),
+            _ => None,
+        };
+        if !self.options.anonymous_fallback || !matches!(errno, Some(libc::EACCES | libc::ENOENT)) {
+            return Err(err);
+        }
+        trace::warn!(name = %self.id, error = %err, "falling back to anonymous shared memory");
+
+        let addr = map_shared(-1, size, &self.options)?;
+        let conf = ShmemConf {
+            id: String::new(),
+            is_owner: true,
+            fd: -1,
+            addr,
+            size,
+            options: self.options,
+            unmapped: Vec::new(),
+        };
+        conf.attach_header();
+        conf.init_header(1);
+        conf.apply_options()?;
+        if self.options.debug_name {
+            match conf.set_debug_name(&self.id) {
+                // a `flink_id` too long to be a name leaves the mapping unnamed
+                Ok(()) | Err(ShmemError::InvalidIdErr) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        conf.trace_opened();
+
+        Ok(Opened::Created(conf))
+    }
+
+    /// Opens an existing shared memory using the specified `size` and `flink_id` and maps it to
+    /// the virtual address of the process memory.
+    ///
+    /// Unlike `open`, the shared memory is never created. The returned `ShmemConf` is never the
+    /// owner.
+    ///
+    /// Returns `ShmemError::NotFound` if no shared memory with the given `flink_id` is present on
+    /// the system, `ShmemError::SizeMismatch` if the shared memory is smaller than `size` and
+    /// `ShmemError::SecurityViolation` if the name is taken by something else than a shared
+    /// memory, e.g. a symlink planted in `/dev/shm`.
+    pub fn open_existing(self) -> Result<ShmemConf, ShmemError> {
+        if self.options.ephemeral {
+            return Err(ShmemError::Unsupported);
+        }
+        let storage_id = storage_id(&self.id)?;
+        validate_size(self.size, &self.options)?;
+        let fd = self.options.open_existing_object(&storage_id)?;
+
+        self.open_fd(fd)
+    }
+
+    /// Maps the shared memory behind `fd` as `open_existing` does after opening it by name, the
+    /// returned `ShmemConf` takes `fd` over. `fd` is closed on errors.
+    pub(crate) fn open_fd(self, fd: i32) -> Result<ShmemConf, ShmemError> {
+        let size = match validate_size(self.size, &self.options) {
+            Ok(size) => size,
+            Err(err) => {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        };
+
+        // accessing the mapping past the end of the shared memory object raises `SIGBUS`, e.g.
+        // after its owner shrunk it via `ShrinkPolicy::TruncateFile`
+        let found = match object_size(fd) {
+            Ok(object_size) => object_size.saturating_sub(self.options.header_len()),
+            Err(err) => {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        };
+        if found < size {
+            unsafe { close(fd) };
+            return Err(ShmemError::SizeMismatch {
+                expected: size,
+                found,
+            });
+        }
+
+        let addr = match map_shared(fd, size, &self.options) {
+            Ok(addr) => addr,
+            Err(err) => {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        };
+
+        let conf = ShmemConf {
+            id: self.id,
+            is_owner: false,
+            fd,
+            addr,
+            size,
+            options: self.options,
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+        conf.attach_header();
+        conf.check_schema_version()?;
+        conf.watch_owner();
+        conf.apply_options()?;
+        conf.trace_opened();
+
+        Ok(conf)
+    }
+
+    /// Same as `open_existing`, but maps the shared memory copy-on-write via `MAP_PRIVATE`.
+    ///
+    /// The returned `ShmemConf` is never the owner and its writes, e.g. through a box obtained
+    /// via `boxed`, stay local to the process: the first write to a page copies it and neither
+    /// the shared memory nor any other mapping of it ever sees the change. Dropping it never
+    /// unlinks the shared memory, even after `ShmemBox::own`.
+    ///
+    /// Pages not written yet are still backed by the shared memory, so later writes by the
+    /// owner or any other process remain visible through them. Once a page is written locally,
+    /// it is a snapshot and stops reflecting later writes to the shared memory. Writes of other
+    /// processes therefore show up on some pages but not on others, depending on which pages
+    /// were already copied.
+    ///
+    /// Returns `ShmemError::Unsupported` if combined with `Builder::mirrored`, since the two
+    /// halves of a mirrored mapping would diverge once written.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_cow");
+    ///     let shared_mem = shmem::Builder::new("flink_test_open_cow")
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     let mut shared = unsafe { shared_mem.boxed::<u32>() };
+    ///     *shared = 1;
+    ///
+    ///     let private_mem = shmem::Builder::new("flink_test_open_cow")
+    ///         .with_size(4)
+    ///         .open_cow()?;
+    ///     assert!(!private_mem.is_shared());
+    ///     let mut private = unsafe { private_mem.boxed::<u32>() };
+    ///     assert_eq!(*private, 1);
+    ///
+    ///     *private = 2;
+    ///     assert_eq!(*shared, 1);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_cow(mut self) -> Result<ShmemConf, ShmemError> {
+        if self.options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+        self.options.copy_on_write = true;
+
+        self.open_existing()
+    }
+
+    /// Opens an existing shared memory read-only for inspecting it, e.g. from a debugging tool
+    /// which does not know how the shared memory was created.
+    ///
+    /// Same as `open_existing` with `Prot::Read`, except that the header of
+    /// `Builder::track_owner` is detected: a shared memory starting with an initialized header
+    /// is opened as if `track_owner` was enabled, so the `ShmemConf` starts at the user data and
+    /// reports the owner via e.g. `ShmemConf::creator_pid`. Pass `Builder::with_align` if its
+    /// creator did, the header is a page long otherwise. A read-only handle is never counted as
+    /// attached.
+    ///
+    /// Returns the same errors as `open_existing`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_readonly");
+    ///     let shared_mem = shmem::Builder::new("flink_test_open_readonly")
+    ///         .track_owner(true)
+    ///         .with_size(8)
+    ///         .open()?;
+    ///     let mut counter = unsafe { shared_mem.boxed::<u64>() };
+    ///     *counter = 7;
+    ///
+    ///     // e.g. in a tool knowing nothing but the name
+    ///     let inspected = shmem::Builder::new("flink_test_open_readonly")
+    ///         .with_size(8)
+    ///         .open_readonly()?;
+    ///     assert_eq!(inspected.creator_pid(), Some(std::process::id() as i32));
+    ///     assert_eq!(inspected.chunks(8).next(), Some(&7_u64.to_ne_bytes()[..]));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_readonly(mut self) -> Result<ShmemConf, ShmemError> {
+        if self.options.ephemeral {
+            return Err(ShmemError::Unsupported);
+        }
+        let fd = self.options.open_object(&storage_id(&self.id)?)?;
+        self.options.prot = Prot::Read;
+        if !self.options.track_owner && header::present(fd) {
+            self.options.track_owner = true;
+        }
+
+        self.open_fd(fd)
+    }
+
+    /// Asynchronous version of `open` for use within a tokio runtime.
+    ///
+    /// `shm_open`, `ftruncate` and `mmap` may block, so the call to `open` is moved to tokio's
+    /// blocking thread pool via `spawn_blocking` instead of stalling the executor thread.
+    ///
+    /// Returns `ShmemError::IoError` if the blocking task fails to complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_async");
+    ///     let shared_mem = shmem::Builder::new("flink_test_open_async")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open_async()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn open_async(self) -> Result<ShmemConf, ShmemError> {
+        tokio::task::spawn_blocking(move || self.open())
+            .await
+            .map_err(|err| ShmemError::IoError(err.into()))?
+    }
+
+    /// Creates a new shared memory using the specified `size` and `flink_id`, failing if a shared
+    /// memory with the same `flink_id` is already present on the system.
+    ///
+    /// Unlike `open`, the check and the creation are done atomically by passing
+    /// `O_CREAT | O_EXCL` to `shm_open`, which makes this suitable for leader election or for
+    /// making sure no stale data is reused. The returned `ShmemConf` is always the owner.
+    ///
+    /// Returns `ShmemError::AlreadyExists` carrying the `EEXIST` error if the `flink_id` is
+    /// taken.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_create_exclusive");
+    ///     let shared_mem = shmem::Builder::new("flink_test_create_exclusive")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .create_exclusive()?;
+    ///
+    ///     // the shared memory is already created by `shared_mem`
+    ///     let res = shmem::Builder::new("flink_test_create_exclusive")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .create_exclusive();
+    ///     assert!(matches!(res, Err(ShmemError::AlreadyExists(_))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create_exclusive(self) -> Result<ShmemConf, ShmemError> {
+        self.create_generation(1)
+    }
+
+    /// Same as `create_exclusive`, recording `generation` in the header if the owner is
+    /// tracked.
+    fn create_generation(self, generation: u64) -> Result<ShmemConf, ShmemError> {
+        let storage_id = storage_id(&self.id)?;
+        let size = validate_size(self.size, &self.options)?;
+        let fd = self
+            .options
+            .create_object(&storage_id, segment_len(size, &self.options), true)?;
+        if self.options.takes_flock() {
+            if let Err(err) = flock::lock(fd) {
+                unsafe { close(fd) };
+                let _ = self.options.unlink_object(&storage_id);
+                return Err(err);
+            }
+        }
+        // an ephemeral shared memory is only ever reachable through `fd`
+        if self.options.ephemeral {
+            if let Err(err) = self.options.unlink_object(&storage_id) {
+                unsafe { close(fd) };
+                return Err(ShmemError::IoError(err));
+            }
+        }
+
+        // from here on, the shared memory is ours and has to be cleaned up on failure
+        let addr = match map_shared(fd, size, &self.options) {
+            Ok(addr) => addr,
+            Err(err) => {
+                unsafe { close(fd) };
+                let _ = self.options.unlink_object(&storage_id);
+                return Err(err);
+            }
+        };
+
+        let conf = ShmemConf {
+            id: self.id,
+            is_owner: true,
+            fd,
+            addr,
+            size,
+            options: self.options,
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+        conf.attach_header();
+        conf.init_header(generation);
+        conf.apply_options()?;
+        conf.trace_opened();
+
+        Ok(conf)
+    }
+
+    /// Same as `create_exclusive`, but unlinks any shared memory present under the `flink_id`
+    /// first, e.g. one left behind by a crashed process, so the returned `ShmemConf` always
+    /// starts from a clean slate and is the owner.
+    ///
+    /// With `Builder::track_owner`, the new shared memory records the generation of the previous
+    /// one plus one, see `ShmemConf::generation`.
+    ///
+    /// Processes which still map the previous shared memory keep using it, detached from the
+    /// new one. Since shared memories are unlinked by name, a still running owner of the
+    /// previous shared memory unlinks the new one once it is dropped, so this is meant for
+    /// owners which are gone. Returns `ShmemError::AlreadyExists` if another process creates a
+    /// shared memory under the same `flink_id` between the unlink and the creation.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemBox,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_recreate");
+    ///     let open = || shmem::Builder::new("flink_test_recreate").with_size(4);
+    ///     let mut stale = unsafe { open().open()?.boxed::<u32>() };
+    ///     *stale = 7;
+    ///     ShmemBox::leak(stale);
+    ///
+    ///     let fresh = unsafe { open().recreate()?.boxed::<u32>() };
+    ///     assert_eq!(*fresh, 0);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn recreate(self) -> Result<ShmemConf, ShmemError> {
+        let previous = generation::peek(&self.id, &self.options);
+        if self.options.unlink_id(&self.id)? {
+            trace::debug!(name = %self.id, "unlinked the previous shared memory");
+        }
+
+        self.create_generation(previous.map_or(1, |generation| generation + 1))
+    }
+
+    /// Same as `open`, but takes over the ownership of an existing shared memory whose owner
+    /// is confirmed dead by `ShmemConf::owner_alive`, e.g. because it crashed before cleaning
+    /// up.
+    ///
+    /// The returned `ShmemConf` is then the owner and the current process is recorded as such,
+    /// so the shared memory is cleaned up when it goes out of scope. If several processes try to
+    /// steal the same shared memory, exactly one of them succeeds.
+    ///
+    /// Returns `ShmemError::Unsupported` if the owner is not tracked via `Builder::track_owner`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_stealing_if_dead");
+    ///     let shared_mem = shmem::Builder::new("flink_test_open_stealing_if_dead")
+    ///         .track_owner(true)
+    ///         .with_size(16)
+    ///         .open_stealing_if_dead()?;
+    ///
+    ///     // nobody owned the shared memory before, so it is created
+    ///     assert!(shared_mem.owner_alive());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_stealing_if_dead(self) -> Result<ShmemConf, ShmemError> {
+        if !self.options.track_owner {
+            return Err(ShmemError::Unsupported);
+        }
+
+        let mut conf = self.open()?;
+        if conf.is_owner {
+            return Ok(conf);
+        }
+        if let Some(pid) = conf.header().and_then(SegmentHeader::owner_pid) {
+            if !header::pid_alive(pid) && conf.header().is_some_and(|h| h.replace_owner(pid)) {
+                trace::debug!(name = %conf.id, dead_pid = pid, "took over the shared memory of a dead owner");
+                conf.is_owner = true;
+                conf.trace_ownership();
+            }
+        }
+
+        Ok(conf)
+    }
+}
+
+impl ShmemConf {
+    /// Unlinks the stale shared memory of the `dead` owner and creates it anew, for
+    /// `Builder::reclaim_stale`. The shared memory counts as created only if this handle
+    /// created the fresh one.
+    fn reclaim(self, dead: i32) -> Result<Opened, ShmemError> {
+        let id = self.id.clone();
+        let (size, options) = (self.size as i128, self.options);
+        let builder = || BuilderWithSize {
+            id: id.clone(),
+            size,
+            options,
+        };
+        // only the process replacing the dead owner reclaims, the handle is not the owner so
+        // dropping it leaves the shared memory alone
+        let won = self
+            .header()
+            .is_some_and(|header| header.replace_owner(dead));
+        let generation = self.generation().map_or(1, |generation| generation + 1);
+        // the stale mapping may occupy the address requested via `map_at`
+        drop(self);
+
+        match won {
+            true => {
+                trace::debug!(name = %id, dead_pid = dead, "reclaiming a stale shared memory");
+                options.unlink_id(&id)?;
+                match builder().create_generation(generation) {
+                    Ok(conf) => Ok(Opened::Created(conf)),
+                    // another process created the shared memory in between
+                    Err(ShmemError::AlreadyExists(_)) => builder().open_with_status(),
+                    Err(err) => Err(err),
+                }
+            }
+            false => builder().open_with_status(),
+        }
+    }
+
+    /// Returns the header in front of the user data, if the owner is tracked.
+    fn header(&self) -> Option<&SegmentHeader> {
+        if !self.options.track_owner {
+            return None;
+        }
+
+        // # Safety
+        //
+        // the header is mapped `header_len` bytes in front of the user data, which is at least
+        // a page and thus large enough
+        Some(unsafe { &*(self.base() as *const SegmentHeader) })
+    }
+
+    /// Returns the header if the handle is counted in it. Copy-on-write mappings are never
+    /// counted since their writes to the header would stay local, neither are mappings which
+    /// can not write the header.
+    fn counting_header(&self) -> Option<&SegmentHeader> {
+        self.header()
+            .filter(|_| self.is_shared() && self.options.prot == Prot::ReadWrite)
+    }
+
+    /// Counts the handle in the header, if it is counted.
+    fn attach_header(&self) {
+        if let Some(header) = self.counting_header() {
+            header.attach();
+        }
+    }
+
+    /// Stops counting the handle in the header, returning whether it was the last one
+    /// attached, or `None` if the handle is not counted.
+    fn detach_header(&self) -> Option<bool> {
+        Some(self.counting_header()?.detach())
+    }
+
+    /// Records the current process as the owner in the header, if the owner is tracked.
+    fn init_header(&self, generation: u64) {
+        if let Some(header) = self.header() {
+            header.init(generation, self.options.schema_version);
+        }
+    }
+
+    /// Lets `enable_stale_cleanup_daemon` reclaim the shared memory once its owner dies, if the
+    /// owner is tracked and the shared memory is not a file the user keeps.
+    fn watch_owner(&self) {
+        if self.options.track_owner && !self.options.file_backed {
+            stale::watch(self.fd, &self.id, self.options.align);
+        }
+    }
+
+    /// Returns the start of the mapping, which lies in front of the user data if the shared
+    /// memory has a header.
+    fn base(&self) -> *mut c_void {
+        unsafe {
+            self.addr
+                .as_ptr()
+                .cast::<u8>()
+                .sub(self.options.header_len()) as *mut c_void
+        }
+    }
+
+    /// Applies the options which take effect after the shared memory is mapped.
+    fn apply_options(&self) -> Result<(), ShmemError> {
+        self.check_backing()?;
+        #[cfg(all(feature = "numa", target_os = "linux"))]
+        if let Some(node) = self.options.numa_node {
+            self.bind_numa_node(node)?;
+        }
+        if self.options.dont_dump {
+            self.exclude_from_core_dumps()?;
+        }
+        if self.options.dont_fork {
+            self.dont_fork()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates the requested size of a shared memory, which must be non-zero and, together with
+/// the header in front of it, fit in the `off_t` taken by `ftruncate`. Mirrored shared memories
+/// additionally need a size which is a multiple of the page size, and no header.
+fn validate_size(size: i128, options: &Options) -> Result<usize, ShmemError> {
+    if size <= 0 || size + options.header_len() as i128 > libc::off_t::MAX as i128 {
+        return Err(ShmemError::InvalidSize(RequestedSize::from_i128(size)));
+    }
+    let size = usize::try_from(size)
+        .map_err(|_| ShmemError::InvalidSize(RequestedSize::from_i128(size)))?;
+    // the mapping is rounded up to whole pages, which must not wrap around either
+    if size.checked_next_multiple_of(page_size()).is_none() {
+        return Err(ShmemError::InvalidSize(RequestedSize::Bytes(size)));
+    }
+    if options.mirrored {
+        if !size.is_multiple_of(page_size()) {
+            return Err(ShmemError::InvalidSize(RequestedSize::Bytes(size)));
+        }
+        if options.track_owner {
+            return Err(ShmemError::Unsupported);
+        }
+    }
+
+    Ok(size)
+}
+
+/// Returns the size of `len` values of `elem_size` bytes each.
+///
+/// Returns `ShmemError::InvalidSize` carrying the exact size if it overflows `usize`, instead
+/// of wrapping around to a size too small for the values.
+fn size_mul(len: usize, elem_size: usize) -> Result<usize, ShmemError> {
+    len.checked_mul(elem_size)
+        .ok_or(ShmemError::InvalidSize(RequestedSize::Overflow(
+            len as u128 * elem_size as u128,
+        )))
+}
+
+/// Returns the sum of the sizes `a` and `b`, or `ShmemError::InvalidSize` if it overflows
+/// `usize`.
+fn size_add(a: usize, b: usize) -> Result<usize, ShmemError> {
+    a.checked_add(b)
+        .ok_or(ShmemError::InvalidSize(RequestedSize::Overflow(
+            a as u128 + b as u128,
+        )))
+}
+
+/// Converts the `flink_id` to the nul-terminated form expected by `shm_open` and `shm_unlink`.
+fn storage_id(id: &str) -> Result<CString, ShmemError> {
+    CString::new(id).map_err(|_| ShmemError::InvalidIdErr)
+}
+
+/// Returns the size of the shared memory object behind `fd`, including the header if any.
+fn object_size(fd: i32) -> Result<usize, ShmemError> {
+    Ok(fstat(fd)?.st_size as usize)
+}
+
+fn fstat(fd: i32) -> Result<libc::stat, ShmemError> {
+    let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+        return Err(ShmemError::IoError(io::Error::last_os_error()));
+    }
+
+    Ok(unsafe { stat.assume_init() })
+}
+
+/// Returns the size of the shared memory object holding `size` bytes of user data.
+fn segment_len(size: usize, options: &Options) -> libc::off_t {
+    (options.header_len() + size) as libc::off_t
+}
+
+/// Maps `size` bytes of user data of the shared memory behind `fd` into the virtual address space
+/// of the process, returning the address of the user data.
+///
+/// The header in front of the user data, if any, is mapped as well.
+fn map_shared(fd: i32, size: usize, options: &Options) -> Result<NonNull<()>, ShmemError> {
+    let header_len = options.header_len();
+    let size = header_len + size;
+    let prot = options.prot.as_raw();
+    let mut flags = options.map_flags();
+    // anonymous shared memory, see `BuilderWithSize::allow_anonymous_fallback`
+    if fd < 0 {
+        if options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+        flags |= libc::MAP_ANONYMOUS;
+    }
+    let at = options.map_at;
+    if at != 0 {
+        if options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+        if at < header_len || !(at - header_len).is_multiple_of(options.align.max(page_size())) {
+            return Err(ShmemError::Misaligned);
+        }
+    }
+    let addr = trace::syscall!("shmem_bind::mmap", [fd, size], {
+        if at != 0 {
+            unsafe { map_fixed(fd, size, prot, flags, (at - header_len) as *mut c_void) }
+        } else {
+            let addr = if options.mirrored {
+                unsafe { map_mirrored(fd, size, prot, flags, options.align) }
+            } else if options.align > page_size() {
+                unsafe { map_aligned(fd, size, prot, flags, options.align) }
+            } else {
+                unsafe { mmap(ptr::null_mut(), size, prot, flags, fd, 0) }
+            };
+            if addr == MAP_FAILED {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(addr)
+            }
+        }
+    });
+    let addr = match addr {
+        Ok(addr) => addr,
+        Err(err) => {
+            trace::debug!(fd, size, error = %err, "mmap failed");
+            return Err(match err.raw_os_error() {
+                Some(libc::EEXIST) if at != 0 => ShmemError::AddressUnavailable(err),
+                _ => ShmemError::MapFailedErr,
+            });
+        }
+    };
+    trace::debug!(fd, size, ?addr, "mapped shared memory");
+
+    NonNull::new(unsafe { addr.cast::<u8>().add(header_len) } as *mut _)
+        .ok_or(ShmemError::NullPointerErr)
+}
+
+/// Overwrites `len` bytes at `ptr` with zeros via volatile writes, which the compiler can not
+/// elide even if the memory is never read again, e.g. since it is about to be unmapped.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` bytes.
+unsafe fn zero_volatile(ptr: *mut u8, len: usize) {
+    let word = mem::size_of::<usize>();
+    let head = ptr.align_offset(word).min(len);
+    for i in 0..head {
+        ptr::write_volatile(ptr.add(i), 0);
+    }
+    let words = ptr.add(head).cast::<usize>();
+    for i in 0..(len - head) / word {
+        ptr::write_volatile(words.add(i), 0);
+    }
+    for i in head + (len - head) / word * word..len {
+        ptr::write_volatile(ptr.add(i), 0);
+    }
+}
+
+/// Maps `size` bytes of `fd` at exactly `base`, failing with `EEXIST` if any part of the range
+/// is mapped already.
+///
+/// `MAP_FIXED` is never used since it silently replaces whatever is mapped in the range, e.g.
+/// the heap or another shared memory. Linux refuses to map over existing mappings given
+/// `MAP_FIXED_NOREPLACE`. Elsewhere, and on kernels before 4.17 which ignore the flag, `base` is
+/// a mere hint and a mapping placed at any other address is released again.
+unsafe fn map_fixed(
+    fd: i32,
+    size: usize,
+    prot: c_int,
+    flags: c_int,
+    base: *mut c_void,
+) -> Result<*mut c_void, io::Error> {
+    let addr = mmap(base, size, prot, flags | MAP_FIXED_NOREPLACE, fd, 0);
+    if addr == MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    if addr != base {
+        munmap(addr, size);
+        return Err(io::Error::from_raw_os_error(libc::EEXIST));
+    }
+
+    Ok(addr)
+}
+
+/// Maps `size` bytes of `fd` at an address aligned to `align`, which is larger than a page.
+///
+/// An `align` bytes larger range of address space is reserved first, then the shared memory is
+/// mapped over the aligned part of it and the rest of the reservation is released.
+unsafe fn map_aligned(
+    fd: i32,
+    size: usize,
+    prot: c_int,
+    flags: c_int,
+    align: usize,
+) -> *mut c_void {
+    let Some(reserved_len) = round_up_to_page(size).checked_add(align) else {
+        return MAP_FAILED;
+    };
+    let reserved = mmap(
+        ptr::null_mut(),
+        reserved_len,
+        PROT_NONE,
+        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | MAP_NORESERVE,
+        -1,
+        0,
+    );
+    if reserved == MAP_FAILED {
+        return MAP_FAILED;
+    }
+
+    let start = (reserved as usize).next_multiple_of(align) as *mut c_void;
+    let addr = mmap(start, size, prot, flags | libc::MAP_FIXED, fd, 0);
+    if addr == MAP_FAILED {
+        munmap(reserved, reserved_len);
+        return MAP_FAILED;
+    }
+
+    // release the unaligned head and the tail of the reservation
+    let head = start as usize - reserved as usize;
+    if head > 0 {
+        munmap(reserved, head);
+    }
+    let tail = reserved_len - head - round_up_to_page(size);
+    if tail > 0 {
+        munmap(
+            start.cast::<u8>().add(round_up_to_page(size)) as *mut c_void,
+            tail,
+        );
+    }
+
+    addr
+}
+
+/// Maps the `size` bytes of `fd` twice, back to back, at an address aligned to `align`.
+///
+/// Twice the size of address space is reserved first, then the shared memory is mapped over
+/// both halves of it. `size` must be a multiple of the page size.
+unsafe fn map_mirrored(
+    fd: i32,
+    size: usize,
+    prot: c_int,
+    flags: c_int,
+    align: usize,
+) -> *mut c_void {
+    let align = if align > page_size() { align } else { 0 };
+    let Some(reserved_len) = size.checked_mul(2).and_then(|len| len.checked_add(align)) else {
+        return MAP_FAILED;
+    };
+    let reserved = mmap(
+        ptr::null_mut(),
+        reserved_len,
+        PROT_NONE,
+        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | MAP_NORESERVE,
+        -1,
+        0,
+    );
+    if reserved == MAP_FAILED {
+        return MAP_FAILED;
+    }
+
+    let start = match align {
+        0 => reserved,
+        align => (reserved as usize).next_multiple_of(align) as *mut c_void,
+    };
+    let mirror = start.cast::<u8>().add(size) as *mut c_void;
+    if mmap(start, size, prot, flags | libc::MAP_FIXED, fd, 0) == MAP_FAILED
+        || mmap(mirror, size, prot, flags | libc::MAP_FIXED, fd, 0) == MAP_FAILED
+    {
+        // releases the reservation along with the halves mapped so far
+        munmap(reserved, reserved_len);
+        return MAP_FAILED;
+    }
+
+    // release the unaligned head and the tail of the reservation
+    let head = start as usize - reserved as usize;
+    if head > 0 {
+        munmap(reserved, head);
+    }
+    let tail = reserved_len - head - 2 * size;
+    if tail > 0 {
+        munmap(start.cast::<u8>().add(2 * size) as *mut c_void, tail);
+    }
+
+    start
+}
+
+/// Returns the page size of the system, which is the granularity of every mapping.
+///
+/// # Examples
+/// ```
+/// use shmem_bind as shmem;
+///
+/// assert!(shmem::page_size().is_power_of_two());
+/// ```
+pub fn page_size() -> usize {
+    unsafe { sysconf(_SC_PAGESIZE) as usize }
+}
+
+/// Gives the creator of a shared memory which was just created a moment to size it, since its
+/// bytes are only accessible once it called `ftruncate`.
+///
+/// Returns after `SIZE_WAIT_ATTEMPTS` attempts even if the object is still empty, e.g. because
+/// its creator died halfway.
+fn wait_until_sized(fd: i32) {
+    for _ in 0..SIZE_WAIT_ATTEMPTS {
+        if !matches!(object_size(fd), Ok(0)) {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_micros(100));
+    }
+}
+
+/// Rounds `size` up to the next multiple of the page size.
+fn round_up_to_page(size: usize) -> usize {
+    size.div_ceil(page_size()) * page_size()
+}
+
+/// Runs `f` on tokio's blocking thread pool, resuming its panic if it panics.
+#[cfg(feature = "tokio")]
+async fn spawn_blocking<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(ret) => ret,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        // the blocking pool only cancels tasks which did not start yet on runtime shutdown
+        Err(err) => panic!("blocking task was cancelled: {err}"),
+    }
+}
+
+/// A representation of a ***mapped*** shared memory.
+#[derive(Debug)]
+pub struct ShmemConf {
+    /// `flink_id` of the shared memory to be created on the system
+    id: String,
+    /// Wether or not this `ShmemConf` is the owner of the shared memory.
+    /// This field is set to true when the shared memory is created by this `ShmemConf`
+    is_owner: bool,
+    /// File descriptor of the allocated shared memory
+    fd: i32,
+    /// Pointer to the shared memory
+    addr: NonNull<()>,
+    /// Size of the allocation
+    size: usize,
+    /// Options the shared memory was mapped with
+    options: Options,
+    /// Sorted and disjoint page aligned ranges of the mapping released via `unmap_range`
+    unmapped: Vec<Range<usize>>,
+}
+
+impl ShmemConf {
+    /// Returns the `flink_id` of the shared memory, e.g. to pass it on to other processes.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the size of the shared memory as requested when opening it.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the size of the mapping, i.e. the requested size rounded up to the page size.
+    ///
+    /// Bytes between `size()` and `mapped_size()` are accessible, but lie past the end of the
+    /// shared memory object and are not accounted for by its size.
+    pub fn mapped_size(&self) -> usize {
+        round_up_to_page(self.size)
+    }
+
+    /// Returns a raw pointer to the start of the mapping, e.g. to hand the shared memory over to
+    /// C code.
+    ///
+    /// The pointer is valid for `mapped_size()` bytes as long as the `ShmemConf` is alive.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.addr.as_ptr() as *const u8
+    }
+
+    /// Returns a raw mutable pointer to the start of the mapping. See `as_ptr`.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.addr.as_ptr() as *mut u8
+    }
+
+    /// Returns true if `ptr` points into the shared memory, i.e. lies within
+    /// `[as_ptr(), as_ptr() + size())` and outside the ranges released via `unmap_range`, the
+    /// raw pointer equivalent of `slice::contains`.
+    ///
+    /// Only the address is compared, e.g. to check a pointer computed from the parts returned
+    /// by `ShmemBox::into_raw_parts` before dereferencing it. A value starting at `ptr` may still
+    /// extend past the shared memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_contains_addr");
+    ///     let shared_mem = shmem::Builder::new("flink_test_contains_addr")
+    ///         .with_size(16)
+    ///         .open()?;
+    ///     let start = shared_mem.as_ptr();
+    ///
+    ///     assert!(shared_mem.contains_addr(start.wrapping_add(15).cast()));
+    ///     assert!(!shared_mem.contains_addr(start.wrapping_add(16).cast()));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn contains_addr(&self, ptr: *const ()) -> bool {
+        let Some(offset) = (ptr as usize).checked_sub(self.addr.as_ptr() as usize) else {
+            return false;
+        };
+
+        self.contains_offset(offset)
+    }
+
+    /// Returns the offset of `ptr` from the start of the shared memory, e.g. to store a
+    /// reference to data within the shared memory in the shared memory itself, since other
+    /// processes map it at different addresses. See `ptr_at_offset` for the reverse.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` unless `contains_addr(ptr)`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_offset_of");
+    ///     let open = || shmem::Builder::new("flink_test_offset_of").with_size(64).open();
+    ///     let shared_mem = open()?;
+    ///     let offset = shared_mem.offset_of(shared_mem.as_ptr().wrapping_add(8).cast())?;
+    ///     assert_eq!(offset, 8);
+    ///
+    ///     // another handle maps the shared memory at another address
+    ///     let other = open()?;
+    ///     assert_eq!(other.ptr_at_offset(offset)?.as_ptr() as usize, other.as_ptr() as usize + 8);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn offset_of(&self, ptr: *const ()) -> Result<usize, ShmemError> {
+        if !self.contains_addr(ptr) {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+
+        Ok(ptr as usize - self.addr.as_ptr() as usize)
+    }
+
+    /// Returns the address `offset` bytes into the shared memory within the current process,
+    /// the reverse of `offset_of`.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if `offset` is not smaller than `size()` or lies
+    /// within a range released via `unmap_range`.
+    pub fn ptr_at_offset(&self, offset: usize) -> Result<NonNull<()>, ShmemError> {
+        if !self.contains_offset(offset) {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+
+        // # Safety
+        //
+        // the offset lies within the mapping, which starts at a non-null address
+        Ok(unsafe { self.addr.cast::<u8>().add(offset).cast() })
+    }
+
+    /// Returns true if the byte `offset` bytes into the shared memory is mapped.
+    fn contains_offset(&self, offset: usize) -> bool {
+        offset < self.size && !self.unmapped.iter().any(|r| r.contains(&offset))
+    }
+
+    /// Returns whether the process recorded as the owner of the shared memory is still
+    /// running.
+    ///
+    /// Liveness is checked via `/proc/<pid>/status` on Linux, where zombies count as dead, and
+    /// via `kill(pid, 0)` elsewhere. A recycled PID makes a dead owner look alive, so a false
+    /// negative is possible, but a live owner is never reported dead.
+    ///
+    /// Returns true if the owner is not tracked via `Builder::track_owner` or the header is not
+    /// initialized by its creator yet, since nothing is known about the owner then.
+    pub fn owner_alive(&self) -> bool {
+        self.header()
+            .and_then(SegmentHeader::owner_pid)
+            .is_none_or(header::pid_alive)
+    }
+
+    /// Returns the process which created the shared memory, which unlike the owner is never
+    /// replaced.
+    ///
+    /// Returns `None` if the owner is not tracked via `Builder::track_owner` or the header is
+    /// not initialized by its creator yet.
+    pub fn creator_pid(&self) -> Option<i32> {
+        self.header().and_then(SegmentHeader::creator_pid)
+    }
+
+    /// Returns when the shared memory was created, according to the clock of its creator.
+    ///
+    /// Returns `None` in the same cases as `creator_pid`.
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.header().and_then(SegmentHeader::created_at)
+    }
+
+    /// Returns whether this handle is the owner of the shared memory, i.e. created it or took
+    /// over its ownership, e.g. via `ShmemBox::own`.
+    ///
+    /// Use `BuilderWithSize::open_with_status` to tell whether opening created the shared
+    /// memory.
+    pub fn is_owner(&self) -> bool {
+        self.is_owner
+    }
+
+    /// Returns whether the shared memory was unlinked right after it was created via
+    /// `Builder::ephemeral`, so no `flink_id` refers to it and dropping the handle never unlinks
+    /// anything.
+    ///
+    /// `is_current` and `reattach_if_stale` return `ShmemError::Unsupported` for ephemeral
+    /// shared memories.
+    pub fn is_ephemeral(&self) -> bool {
+        self.options.ephemeral
+    }
+
+    /// Returns whether the shared memory is backed by a regular file, see
+    /// `Builder::with_file_path`.
+    pub fn is_file_backed(&self) -> bool {
+        self.options.file_backed
+    }
+
+    /// Returns whether the shared memory is anonymous, i.e. mapped without a name or a file
+    /// descriptor after `shm_open` failed, see `BuilderWithSize::allow_anonymous_fallback`.
+    pub fn is_anonymous(&self) -> bool {
+        self.fd < 0
+    }
+
+    /// Records that this handle opened the shared memory, as its creator if it is the owner.
+    fn trace_opened(&self) {
+        #[cfg(feature = "tracing")]
+        if self.is_owner {
+            tracing::info!(name = %self.id, size = self.size, pid = std::process::id(), "created shared memory");
+        } else {
+            tracing::info!(name = %self.id, size = self.size, pid = std::process::id(), "attached shared memory");
+        }
+    }
+
+    /// Records that this handle became or stopped being the owner of the shared memory.
+    fn trace_ownership(&self) {
+        trace::info!(
+            name = %self.id,
+            pid = std::process::id(),
+            is_owner = self.is_owner,
+            "ownership changed"
+        );
+    }
+
+    /// Returns whether this handle is responsible for cleaning up the shared memory.
+    ///
+    /// If the owner is tracked via `Builder::track_owner`, the current process additionally has
+    /// to be the recorded owner. A child forked from the owner inherits its handles, but not the
+    /// responsibility, unless it claims it via `ShmemBox::claim_ownership`.
+    fn is_responsible(&self) -> bool {
+        self.is_owner && self.header().is_none_or(SegmentHeader::held_by_current)
+    }
+
+    /// Returns whether dropping this handle cleans up the shared memory, i.e. drops the boxed
+    /// value, according to the `CleanupPolicy`.
+    fn cleans_up(&self) -> bool {
+        match self.options.cleanup {
+            CleanupPolicy::OwnerUnlinks
+            | CleanupPolicy::AlwaysUnlink
+            | CleanupPolicy::UnlinkIfLast => self.is_responsible(),
+            CleanupPolicy::NeverUnlink => false,
+            CleanupPolicy::UnlinkWhenLastDetaches => self
+                .counting_header()
+                .is_some_and(|header| header.attached() <= 1),
+        }
+    }
+
+    /// Returns the `CleanupPolicy` of this handle.
+    pub fn cleanup_policy(&self) -> CleanupPolicy {
+        self.options.cleanup
+    }
+
+    /// Changes the `CleanupPolicy` of this handle only, e.g. to keep the shared memory of an
+    /// owner around via `CleanupPolicy::NeverUnlink`.
+    ///
+    /// Returns `ShmemError::Unsupported` for `CleanupPolicy::UnlinkWhenLastDetaches` unless the
+    /// shared memory is opened with `Builder::track_owner`, whose header counts the handles.
+    /// Switching to `CleanupPolicy::UnlinkIfLast` takes the shared lock of this handle.
+    pub fn set_cleanup_policy(&mut self, policy: CleanupPolicy) -> Result<(), ShmemError> {
+        if policy == CleanupPolicy::UnlinkWhenLastDetaches && !self.options.track_owner {
+            return Err(ShmemError::Unsupported);
+        }
+        if policy == CleanupPolicy::UnlinkIfLast && !self.options.file_backed {
+            flock::lock(self.fd)?;
+        }
+        self.options.cleanup = policy;
+
+        Ok(())
+    }
+
+    /// Returns whether writes to the mapping reach the shared memory, which is the case unless
+    /// it was opened via `BuilderWithSize::open_cow`.
+    pub fn is_shared(&self) -> bool {
+        !self.options.copy_on_write
+    }
+
+    /// Returns the protection the shared memory was mapped with, see
+    /// `BuilderWithSize::with_protection`.
+    pub fn protection(&self) -> Prot {
+        self.options.prot
+    }
+
+    /// Returns `ShmemError::ProtectionViolation` unless the mapping is readable.
+    pub(crate) fn check_readable(&self) -> Result<(), ShmemError> {
+        match self.options.prot {
+            Prot::None => Err(ShmemError::ProtectionViolation),
+            Prot::Read | Prot::ReadWrite => Ok(()),
+        }
+    }
+
+    /// Returns `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub(crate) fn check_writable(&self) -> Result<(), ShmemError> {
+        match self.options.prot {
+            Prot::ReadWrite => Ok(()),
+            Prot::None | Prot::Read => Err(ShmemError::ProtectionViolation),
+        }
+    }
+
+    /// Returns both mappings of a shared memory opened with `Builder::mirrored` as one slice
+    /// of twice the size, where the bytes at `i` and `i + size()` are the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared memory is not mirrored.
+    ///
+    /// # Safety
+    ///
+    /// The compiler assumes the bytes of a slice never alias, so a write to one half must not
+    /// be read back through the other half via the same slice. No other reference to the shared
+    /// memory may be used while the returned slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn as_mirrored_slice(&self) -> &mut [u8] {
+        assert!(self.options.mirrored, "shared memory is not mirrored");
+        slice::from_raw_parts_mut(self.addr.as_ptr() as *mut u8, 2 * self.size)
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer of type `T`.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because there is no guarantee that the referred T is initialized.
+    /// The caller must ensure that the value behind the pointer is initialized before use. The
+    /// box must not be written through, e.g. via `DerefMut`, unless the shared memory is mapped
+    /// with `Prot::ReadWrite`, nor dereferenced at all if it is mapped with `Prot::None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// type NotZeroI32 = i32;
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed");
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed")
+    ///         .with_size(mem::size_of::<NotZeroI32>())
+    ///         .open()?;
+    ///
+    ///     let boxed_val = unsafe {
+    ///         // the allocated shared_memory is not initialized and thus, not guaranteed to be a
+    ///         // valid `NotZeroI32`
+    ///         let mut boxed_val = shared_mem.boxed::<NotZeroI32>();
+    ///         // manually initialize the value in the unsafe block
+    ///         *boxed_val = 5;
+    ///         boxed_val
+    ///     };
+    ///
+    ///     assert_eq!(*boxed_val, 5);
+    ///
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed")
+    ///         .with_size(mem::size_of::<NotZeroI32>())
+    ///         .open()?;
+    ///
+    ///     let mut boxed_barrow_val = unsafe { shared_mem.boxed::<NotZeroI32>() };
+    ///
+    ///     assert_eq!(*boxed_barrow_val, 5);
+    ///
+    ///     // changes to boxed_barrow_val would reflect to boxed_val as well since they both point
+    ///     // to the same location.
+    ///     *boxed_barrow_val = 3;
+    ///     assert_eq!(*boxed_val, 3);
+    ///     
+    ///     Ok(())
+    /// }
+    ///
+    /// ```
+    pub unsafe fn boxed<T>(self) -> ShmemBox<T> {
+        ShmemBox {
+            ptr: self.addr.cast(),
+            conf: self,
+        }
+    }
+
+    /// Writes `T::default()` into a shared memory created by this `ShmemConf` and returns it
+    /// boxed, the safe counterpart of `boxed` for freshly created shared memories.
+    ///
+    /// A shared memory attached to, i.e. not owned by this `ShmemConf`, may hold data written by
+    /// another process which must not be overwritten. In that case the `ShmemConf` is handed
+    /// back, and the caller has to decide whether to trust the existing data via `boxed`. The
+    /// same goes for a shared memory not mapped with `Prot::ReadWrite` or smaller than `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// #[derive(Default)]
+    /// struct Stats {
+    ///     requests: u64,
+    ///     errors: u64,
+    /// }
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_default");
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed_default")
+    ///         .with_size(mem::size_of::<Stats>())
+    ///         .open()?;
+    ///
+    ///     let stats = match shared_mem.boxed_default::<Stats>() {
+    ///         Ok(stats) => stats,
+    ///         // the shared memory was created by another process
+    ///         Err(shared_mem) => unsafe { shared_mem.boxed::<Stats>() },
+    ///     };
+    ///     assert_eq!(stats.requests, 0);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    // the handle is handed back as is, boxing it would only move it to the heap
+    #[allow(clippy::result_large_err)]
+    pub fn boxed_default<T: Default>(self) -> Result<ShmemBox<T>, Self> {
+        if !self.is_owner || self.check_writable().is_err() || mem::size_of::<T>() > self.size {
+            return Err(self);
+        }
+
+        // # Safety
+        //
+        // the shared memory is large enough to hold a `T` and the value is initialized right
+        // after the conversion, before the box is handed out
+        let shmem_box = unsafe { self.boxed::<T>() };
+        unsafe { ptr::write(shmem_box.ptr.as_ptr(), T::default()) };
+
+        Ok(shmem_box)
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer of a `T` covering only the start of
+    /// the shared memory, keeping the rest as reserved space, e.g. for future growth.
+    ///
+    /// The whole shared memory stays mapped and the bytes past the `T` are accessible via
+    /// `ShmemBox::reserved_bytes`. Returns `ShmemError::OutOfBoundsErr` if the `T` exceeds the
+    /// shared memory.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_prefix");
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed_prefix")
+    ///         .with_size(4096)
+    ///         .open()?;
+    ///     let mut header = unsafe { shared_mem.boxed_prefix::<u64>()? };
+    ///     *header = 1;
+    ///     header.reserved_bytes_mut()[0] = 2;
+    ///     assert_eq!(header.reserved_bytes().len(), 4096 - 8);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn boxed_prefix<T>(self) -> Result<ShmemBox<T>, ShmemError> {
+        self.boxed_at::<T>(0)
+    }
+
+    /// Converts the `T` located `offset` bytes into the shared memory to a boxed pointer, e.g.
+    /// at an offset computed by `SegmentLayout`.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if the `T` exceeds the shared memory and
+    /// `ShmemError::Misaligned` if the address of the `T` is not aligned for it, which can
+    /// happen for alignments above the page size unless the shared memory is mapped via
+    /// `Builder::with_align`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`, for the bytes at `offset`. Other handles converted at overlapping
+    /// offsets must not be used to create aliasing mutable references.
+    pub unsafe fn boxed_at<T>(self, offset: usize) -> Result<ShmemBox<T>, ShmemError> {
+        match offset.checked_add(mem::size_of::<T>()) {
+            Some(end) if end <= self.size => {}
+            _ => return Err(ShmemError::OutOfBoundsErr),
+        }
+        let ptr = self.addr.as_ptr().cast::<u8>().add(offset).cast::<T>();
+        if !ptr.is_aligned() {
+            return Err(ShmemError::Misaligned);
+        }
+
+        Ok(ShmemBox {
+            ptr: NonNull::new_unchecked(ptr),
+            conf: self,
+        })
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer of an array of `N` values of `T`,
+    /// e.g. for a buffer whose length is known at compile time.
+    ///
+    /// Unlike `boxed_bytes` and other boxed slices, the length is part of the type: the box
+    /// dereferences to `[T; N]`, so indexing with constants is checked at compile time, and
+    /// dropping the box as the owner drops every element. Returns `ShmemError::OutOfBoundsErr`
+    /// if the shared memory is smaller than `size_of::<[T; N]>()` and `ShmemError::Misaligned`
+    /// if the mapping is not aligned for `T`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`, for every element of the array.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_array");
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed_array")
+    ///         .with_size(mem::size_of::<[u32; 16]>())
+    ///         .open()?;
+    ///     let mut slots = unsafe { shared_mem.boxed_array::<u32, 16>()? };
+    ///     slots[15] = 1;
+    ///     assert_eq!(slots.iter().sum::<u32>(), 1);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn boxed_array<T, const N: usize>(self) -> Result<ShmemBox<[T; N]>, ShmemError> {
+        self.boxed_at::<[T; N]>(0)
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer of a `ShmemSafe` type.
+    ///
+    /// Unlike `boxed`, this function is safe since a `ShmemSafe` type is valid for any bit
+    /// pattern and has no process-local pointers. Use `#[derive(ShmemSafe)]` with the `derive`
+    /// feature to make a struct `ShmemSafe`.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if the `T` exceeds the shared memory,
+    /// `ShmemError::Misaligned` if the mapping is not aligned for it and
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub fn boxed_safe<T: ShmemSafe>(self) -> Result<ShmemBox<T>, ShmemError> {
+        self.check_writable()?;
+        // # Safety
+        //
+        // every bit pattern of the bytes is a valid `T`, the bounds and the alignment are
+        // checked by `boxed_at`
+        unsafe { self.boxed_at::<T>(0) }
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer after checking that the shared
+    /// memory was created for a `T`.
+    ///
+    /// The first 8 bytes of the shared memory hold a hash of `std::any::type_name::<T>()`,
+    /// followed by the `T` at the next offset aligned for it. The owner writes the hash, every
+    /// other handle compares it to the hash of its `T` and gets
+    /// `ShmemError::TypeHashMismatch` if they differ, e.g. when opening a shared memory created
+    /// for an `i32` as a `u64`. A handle converted before the owner wrote the hash gets the
+    /// error as well. Type names and hashes are only stable across processes built by the same
+    /// compiler version.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if the hash and the `T` exceed the shared memory,
+    /// `ShmemError::Misaligned` if the `T` is not aligned for it and
+    /// `ShmemError::ProtectionViolation` if the owner can not write the hash or another handle
+    /// can not read it. The hash is left untouched on error.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`, the hash does not guarantee that the bytes hold a valid `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_checked");
+    ///     let open = || shmem::Builder::new("flink_test_boxed_checked")
+    ///         .with_size(16)
+    ///         .open();
+    ///     let mut owner = unsafe { open()?.boxed_checked::<u64>()? };
+    ///     *owner = 5;
+    ///
+    ///     // e.g. in another process
+    ///     assert_eq!(*unsafe { open()?.boxed_checked::<u64>()? }, 5);
+    ///     let err = unsafe { open()?.boxed_checked::<i32>() }.unwrap_err();
+    ///     assert!(matches!(err, ShmemError::TypeHashMismatch { .. }));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn boxed_checked<T>(self) -> Result<ShmemBox<T>, ShmemError> {
+        match self.is_owner {
+            true => self.check_writable()?,
+            false => self.check_readable()?,
+        }
+        let offset = TYPE_HASH_LEN.next_multiple_of(mem::align_of::<T>());
+        match offset.checked_add(mem::size_of::<T>()) {
+            Some(end) if end <= self.size => {}
+            _ => return Err(ShmemError::OutOfBoundsErr),
+        }
+        if !self
+            .addr
+            .as_ptr()
+            .cast::<u8>()
+            .add(offset)
+            .cast::<T>()
+            .is_aligned()
+        {
+            return Err(ShmemError::Misaligned);
+        }
+        let mut hasher = DefaultHasher::new();
+        any::type_name::<T>().hash(&mut hasher);
+        let expected = hasher.finish();
+
+        // the mapping is page aligned, hence aligned for the hash
+        let hash = self.addr.as_ptr().cast::<u64>();
+        if self.is_owner {
+            ptr::write_volatile(hash, expected);
+        } else {
+            let found = ptr::read_volatile(hash);
+            if found != expected {
+                return Err(ShmemError::TypeHashMismatch { expected, found });
+            }
+        }
+
+        self.boxed_at::<T>(offset)
+    }
+
+    /// Converts `ShmemConf`'s raw pointer to a boxed byte slice covering the whole shared
+    /// memory.
+    ///
+    /// Unlike `boxed`, this function is safe since every bit pattern is a valid `u8`. Use
+    /// `chunks` to read a shared memory which is not writable.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless the shared memory is mapped with `Prot::ReadWrite`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_bytes");
+    ///     let shared_mem = shmem::Builder::new("flink_test_boxed_bytes")
+    ///         .with_size(16)
+    ///         .open()?;
+    ///
+    ///     let mut bytes = shared_mem.boxed_bytes();
+    ///     assert_eq!(bytes.len(), 16);
+    ///
+    ///     bytes[0] = 42;
+    ///     assert_eq!(bytes[0], 42);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn boxed_bytes(self) -> ShmemBox<[u8]> {
+        assert!(
+            self.check_writable().is_ok(),
+            "shared memory is not writable"
+        );
+        let ptr = ptr::slice_from_raw_parts_mut(self.addr.as_ptr() as *mut u8, self.size);
+        ShmemBox {
+            // # Safety
+            //
+            // `ptr` is derived from `self.addr` which is never null
+            ptr: unsafe { NonNull::new_unchecked(ptr) },
+            conf: self,
+        }
+    }
+
+    /// Reinterprets the start of the shared memory as a `T`, which `zerocopy` guarantees to be
+    /// valid for any bit pattern.
+    ///
+    /// Unlike `boxed`, this function is safe.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than a `T`,
+    /// `ShmemError::Misaligned` if the mapping is not aligned for `T`, the same error the other
+    /// conversions report, and `ShmemError::ProtectionViolation` unless the mapping is readable.
+    ///
+    /// Only available with the `zerocopy` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_ref_as");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_ref_as")
+    ///         .with_size(16)
+    ///         .open()?;
+    ///
+    ///     *shared_mem.mut_as::<[u32; 4]>()? = [1, 2, 3, 4];
+    ///     assert_eq!(shared_mem.ref_as::<[u32; 4]>()?, &[1, 2, 3, 4]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "zerocopy")]
+    pub fn ref_as<T>(&self) -> Result<&T, ShmemError>
+    where
+        T: zerocopy::FromBytes + zerocopy::KnownLayout + zerocopy::Immutable,
+    {
+        self.check_readable()?;
+        self.check_layout_of::<T>()?;
+        let bytes = unsafe { slice::from_raw_parts(self.as_ptr(), self.size) };
+
+        T::ref_from_prefix(bytes)
+            .map(|(val, _)| val)
+            .map_err(|_| ShmemError::Misaligned)
+    }
+
+    /// Mutable version of `ref_as`.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than a `T`,
+    /// `ShmemError::Misaligned` if the mapping is not aligned for `T` and
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable.
+    ///
+    /// Only available with the `zerocopy` feature.
+    #[cfg(feature = "zerocopy")]
+    pub fn mut_as<T>(&mut self) -> Result<&mut T, ShmemError>
+    where
+        T: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::KnownLayout,
+    {
+        self.check_writable()?;
+        self.check_layout_of::<T>()?;
+        let bytes = unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.size) };
+
+        T::mut_from_prefix(bytes)
+            .map(|(val, _)| val)
+            .map_err(|_| ShmemError::Misaligned)
+    }
+
+    /// Checks that a `T` fits at the start of the shared memory and is aligned, reporting the
+    /// error `zerocopy` would otherwise fold into a single cast error.
+    #[cfg(feature = "zerocopy")]
+    fn check_layout_of<T>(&self) -> Result<(), ShmemError> {
+        if self.size < mem::size_of::<T>() {
+            return Err(ShmemError::SizeMismatch {
+                expected: mem::size_of::<T>(),
+                found: self.size,
+            });
+        }
+        if !self.as_ptr().cast::<T>().is_aligned() {
+            return Err(ShmemError::Misaligned);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a second, independent handle to the same shared memory within the current
+    /// process.
+    ///
+    /// Same as `try_clone`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_clone_handle");
+    ///     // shared_mem is the owner
+    ///     let shared_mem = shmem::Builder::new("flink_test_clone_handle")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///
+    ///     // handle is mapped at a different address and is not the owner
+    ///     let handle = shared_mem.clone_handle()?;
+    ///
+    ///     let mut writer = unsafe { shared_mem.boxed::<i32>() };
+    ///     let reader = unsafe { handle.boxed::<i32>() };
+    ///
+    ///     // both handles refer to the same shared memory
+    ///     *writer = 7;
+    ///     assert_eq!(*reader, 7);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn clone_handle(&self) -> Result<ShmemConf, ShmemError> {
+        self.try_clone()
+    }
+
+    /// Creates a second, independent mapping of the same shared memory within the current
+    /// process, using the protection the shared memory was opened with.
+    ///
+    /// The file descriptor is duplicated via `dup` and the shared memory is mapped again at an
+    /// independent virtual address. The returned `ShmemConf` is never the owner, so dropping it
+    /// only unmaps its own mapping and closes its own file descriptor while the original keeps
+    /// the responsibility of unlinking the shared memory.
+    ///
+    /// Returns `ShmemError::DuplicateFailedErr` if the file descriptor can not be duplicated.
+    pub fn try_clone(&self) -> Result<ShmemConf, ShmemError> {
+        self.try_clone_with(self.options.prot)
+    }
+
+    /// Same as `try_clone`, but maps the clone with the protection `prot`, e.g. to hand a
+    /// read-only view to untrusted code while keeping the writable one.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,Prot,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_try_clone_with");
+    ///     let shared_mem = shmem::Builder::new("flink_test_try_clone_with")
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     let view = shared_mem.try_clone_with(Prot::Read)?;
+    ///
+    ///     let mut writer = unsafe { shared_mem.boxed::<u32>() };
+    ///     let reader = unsafe { view.boxed::<u32>() };
+    ///
+    ///     *writer = 7;
+    ///     assert_eq!(*reader, 7);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn try_clone_with(&self, prot: Prot) -> Result<ShmemConf, ShmemError> {
+        // a duplicated file descriptor would share the lock of this handle
+        let fd = if self.options.takes_flock() && !self.options.ephemeral {
+            flock::reopen(self.fd, &storage_id(&self.id)?)?
+        } else {
+            unsafe { dup(self.fd) }
+        };
+        if fd < 0 {
+            return Err(ShmemError::DuplicateFailedErr);
+        }
+
+        let options = Options {
+            prot,
+            map_at: 0,
+            ..self.options
+        };
+        let addr = match map_shared(fd, self.size, &options) {
+            Ok(addr) => addr,
+            Err(err) => {
+                unsafe { close(fd) };
+                return Err(err);
+            }
+        };
+
+        let conf = ShmemConf {
+            id: self.id.clone(),
+            is_owner: false,
+            fd,
+            addr,
+            size: self.size,
+            options,
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+        conf.attach_header();
+
+        Ok(conf)
+    }
+
+    /// Unmaps the shared memory but keeps its file descriptor open and hands it to the caller,
+    /// e.g. to pass it to another process over a Unix domain socket.
+    ///
+    /// The caller takes over the file descriptor and has to close it exactly once, either
+    /// directly or by handing it to `map_only`, whose `ShmemConf` closes it when dropped. The
+    /// shared memory is not unlinked, regardless of the `CleanupPolicy`, so the caller becomes
+    /// responsible for unlinking it as well, e.g. via `ShmemConf::unlink`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `munmap` fails, like dropping the `ShmemConf` does.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_into_fd_keep_unmapped");
+    ///     let shared_mem = shmem::Builder::new("flink_test_into_fd_keep_unmapped")
+    ///         .with_size(8)
+    ///         .open()?;
+    ///     let size = shared_mem.size();
+    ///     let fd = shared_mem.into_fd_keep_unmapped();
+    ///
+    ///     // e.g. after passing `fd` to another process
+    ///     let mapped = unsafe { ShmemConf::map_only("flink_test_into_fd_keep_unmapped", fd, size)? };
+    ///     assert_eq!(mapped.size(), 8);
+    ///     ShmemConf::unlink("flink_test_into_fd_keep_unmapped")?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn into_fd_keep_unmapped(self) -> i32 {
+        let conf = mem::ManuallyDrop::new(self);
+        conf.detach_header();
+        conf.unmap_all();
+        active::deregister(&conf);
+        stale::unwatch(conf.fd);
+        let fd = conf.fd;
+
+        // # Safety
+        //
+        // `conf` is never dropped, so the heap allocated fields are moved out exactly once to
+        // free them
+        unsafe {
+            drop(ptr::read(&conf.id));
+            drop(ptr::read(&conf.unmapped));
+        }
+
+        fd
+    }
+
+    /// Maps `size` bytes of the shared memory behind `fd`, the inverse of
+    /// `into_fd_keep_unmapped`.
+    ///
+    /// `id` is the `flink_id` the shared memory is known under, it is not used to open it. The
+    /// returned `ShmemConf` is not the owner and maps the shared memory with the default
+    /// options, i.e. readable and writable without a header.
+    ///
+    /// Returns `ShmemError::InvalidIdErr` if `id` is not a valid `flink_id`,
+    /// `ShmemError::SizeMismatch` if the shared memory is smaller than `size` and any error of
+    /// `mmap` otherwise. The file descriptor stays with the caller on error.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open file descriptor of a shared memory object owned by the caller. The
+    /// returned `ShmemConf` takes it over and closes it when dropped, so it must not be closed
+    /// or handed to `map_only` again by anyone else.
+    pub unsafe fn map_only(id: &str, fd: i32, size: usize) -> Result<ShmemConf, ShmemError> {
+        storage_id(id)?;
+        let options = Options::default();
+        let size = validate_size(size as i128, &options)?;
+        let found = object_size(fd)?;
+        if found < size {
+            return Err(ShmemError::SizeMismatch {
+                expected: size,
+                found,
+            });
+        }
+        let addr = map_shared(fd, size, &options)?;
+
+        let conf = ShmemConf {
+            id: id.to_string(),
+            is_owner: false,
+            fd,
+            addr,
+            size,
+            options,
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+
+        Ok(conf)
+    }
+
+    /// Moves the responsibility of cleaning up the shared memory from `from` to `to`.
+    ///
+    /// Both handles have to refer to the same shared memory, i.e. have the same `flink_id` and
+    /// the same underlying shared memory object, which rules out a shared memory unlinked and
+    /// created again under the same name in between. Either both flags are updated or neither.
+    ///
+    /// Returns `ShmemError::NotOwner` if `from` is not the owner, `ShmemError::Unsupported` if
+    /// `to` was opened via `BuilderWithSize::open_cow` and `ShmemError::IdMismatch` if the
+    /// handles refer to different shared memories.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_transfer_ownership");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_transfer_ownership")
+    ///         .with_size(8)
+    ///         .open()?;
+    ///     let mut handle = shared_mem.clone_handle()?;
+    ///
+    ///     ShmemConf::transfer_ownership(&mut shared_mem, &mut handle)?;
+    ///
+    ///     // dropping the former owner keeps the shared memory around
+    ///     drop(shared_mem);
+    ///     shmem::Builder::new("flink_test_transfer_ownership")
+    ///         .with_size(8)
+    ///         .open_existing()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn transfer_ownership(from: &mut ShmemConf, to: &mut ShmemConf) -> Result<(), ShmemError> {
+        if !from.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        if to.options.copy_on_write {
+            return Err(ShmemError::Unsupported);
+        }
+        if from.id != to.id {
+            return Err(ShmemError::IdMismatch);
+        }
+        let (from_stat, to_stat) = (fstat(from.fd)?, fstat(to.fd)?);
+        if (from_stat.st_dev, from_stat.st_ino) != (to_stat.st_dev, to_stat.st_ino) {
+            return Err(ShmemError::IdMismatch);
+        }
+
+        from.is_owner = false;
+        to.is_owner = true;
+        from.trace_ownership();
+        to.trace_ownership();
+        if let Some(header) = to.header() {
+            header.set_owner();
+        }
+
+        Ok(())
+    }
+
+    /// Unlinks the shared memory named `id` from the system without opening or mapping it, e.g.
+    /// to clean up after a crashed process.
+    ///
+    /// Processes which still map the shared memory keep using it, it is freed once the last
+    /// mapping is gone. An owner dropped afterwards finds it unlinked already, which is fine.
+    /// On Android, shared memories have no names and this is a no-op.
+    ///
+    /// Returns true if the shared memory was unlinked, false if no shared memory named `id`
+    /// exists, `ShmemError::InvalidIdErr` if `id` contains a nul byte and `ShmemError::IoError`
+    /// if unlinking fails otherwise, e.g. due to missing permissions.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_unlink");
+    ///     let shared_mem = shmem::Builder::new("flink_test_unlink")
+    ///         .with_size(4)
+    ///         .open()?;
+    ///
+    ///     assert!(ShmemConf::unlink("flink_test_unlink")?);
+    ///     assert!(!ShmemConf::unlink("flink_test_unlink")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn unlink(id: &str) -> Result<bool, ShmemError> {
+        Options::default().unlink_id(id)
+    }
+
+    /// Synchronously flushes the whole shared memory to its backing store via `msync` with
+    /// `MS_SYNC`, returning once the write back is complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_flush");
+    ///     let shared_mem = shmem::Builder::new("flink_test_flush")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///
+    ///     *boxed_val = 5;
+    ///     // make sure the write is persisted before signaling a peer
+    ///     boxed_val.flush()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn flush(&self) -> Result<(), ShmemError> {
+        self.flush_range(0, self.size, true)
+    }
+
+    /// Schedules a write back of the whole shared memory via `msync` with `MS_ASYNC` without
+    /// waiting for it to complete.
+    pub fn flush_async(&self) -> Result<(), ShmemError> {
+        self.flush_range(0, self.size, false)
+    }
+
+    /// Flushes `len` bytes of the shared memory starting at `offset`.
+    ///
+    /// The range does not need to be page aligned, it is widened to the enclosing pages
+    /// internally. If `sync` is true, the call blocks until the write back is complete,
+    /// otherwise the write back is only scheduled.
+    ///
+    /// Returns `ShmemError::FlushFailed` carrying the underlying `io::Error` if the range is out
+    /// of bounds (`EINVAL`) or the write back fails (`EIO`).
+    pub fn flush_range(&self, offset: usize, len: usize, sync: bool) -> Result<(), ShmemError> {
+        let Some((addr, len)) = self.page_range(offset, len) else {
+            return Err(ShmemError::FlushFailed(io::Error::from_raw_os_error(
+                libc::EINVAL,
+            )));
+        };
+        let flags: c_int = if sync { MS_SYNC } else { MS_ASYNC };
+        let res = unsafe { msync(addr, len, flags) };
+        if res != 0 {
+            return Err(ShmemError::FlushFailed(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns how many pages of the mapping are resident in memory, via `mincore`.
+    ///
+    /// This is useful for capacity planning of huge, sparsely used shared memories. See
+    /// `resident_pages_range` for a bitmap of the resident pages.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_resident_pages");
+    ///     let shared_mem = shmem::Builder::new("flink_test_resident_pages")
+    ///         .with_size(1 << 20)
+    ///         .open()?;
+    ///
+    ///     let stats = shared_mem.resident_pages()?;
+    ///     assert!(stats.resident_pages <= stats.total_pages);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn resident_pages(&self) -> Result<ResidencyStats, ShmemError> {
+        self.resident_pages_range(0, self.size, false)
+    }
+
+    /// Returns how many pages of `len` bytes of the mapping starting at `offset` are resident in
+    /// memory, via `mincore`, along with a bitmap of the resident pages if `bitmap` is set.
+    ///
+    /// The range is widened to the enclosing pages. Large ranges are queried in chunks, so the
+    /// memory used is bounded by the size of the bitmap.
+    ///
+    /// Returns `ShmemError::ResidencyFailed` carrying the underlying `io::Error` if the range is
+    /// out of bounds or `mincore` fails.
+    pub fn resident_pages_range(
+        &self,
+        offset: usize,
+        len: usize,
+        bitmap: bool,
+    ) -> Result<ResidencyStats, ShmemError> {
+        let Some((addr, len)) = self.page_range(offset, len) else {
+            return Err(ShmemError::ResidencyFailed(io::Error::from_raw_os_error(
+                libc::EINVAL,
+            )));
+        };
+        let page_size = page_size();
+        let total_pages = len / page_size;
+
+        let mut stats = ResidencyStats {
+            total_pages,
+            resident_pages: 0,
+            bitmap: bitmap.then(|| vec![0; total_pages.div_ceil(8)]),
+        };
+        // the element type of the vector differs between platforms, but only its lowest bit is
+        // meaningful everywhere
+        let mut vec: Vec<u8> = vec![0; total_pages.min(MINCORE_CHUNK_PAGES)];
+        let mut page = 0;
+        while page < total_pages {
+            let pages = (total_pages - page).min(MINCORE_CHUNK_PAGES);
+            let res = unsafe {
+                libc::mincore(
+                    addr.cast::<u8>().add(page * page_size) as *mut c_void,
+                    pages * page_size,
+                    vec.as_mut_ptr().cast(),
+                )
+            };
+            if res != 0 {
+                return Err(ShmemError::ResidencyFailed(io::Error::last_os_error()));
+            }
+
+            for (i, residency) in vec[..pages].iter().enumerate() {
+                if residency & 1 == 0 {
+                    continue;
+                }
+                stats.resident_pages += 1;
+                if let Some(bitmap) = &mut stats.bitmap {
+                    bitmap[(page + i) / 8] |= 1 << ((page + i) % 8);
+                }
+            }
+            page += pages;
+        }
+
+        Ok(stats)
+    }
+
+    /// Excludes the mapping from core dumps of the current process via `madvise` with
+    /// `MADV_DONTDUMP`.
+    ///
+    /// This keeps sensitive data out of core files and keeps the core files of processes mapping
+    /// huge shared memories usable. Reads and writes through the mapping are not affected.
+    ///
+    /// Returns `ShmemError::Unsupported` on platforms other than Linux, so the protection is
+    /// never silently inactive.
+    ///
+    /// The effect can be verified manually by writing a marker to the shared memory, crashing
+    /// the process with `kill -SEGV <pid>` (with `ulimit -c unlimited`) and checking that the
+    /// marker does not show up in `strings <core-file>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_exclude_from_core_dumps");
+    ///     let shared_mem = shmem::Builder::new("flink_test_exclude_from_core_dumps")
+    ///         .with_size(4096)
+    ///         .open()?;
+    ///
+    ///     shared_mem.exclude_from_core_dumps()?;
+    ///     shared_mem.include_in_core_dumps()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn exclude_from_core_dumps(&self) -> Result<(), ShmemError> {
+        #[cfg(target_os = "linux")]
+        return self.madvise(0, self.size, libc::MADV_DONTDUMP);
+        #[cfg(not(target_os = "linux"))]
+        return Err(ShmemError::Unsupported);
+    }
+
+    /// Includes the mapping in core dumps of the current process again via `madvise` with
+    /// `MADV_DODUMP`, undoing `exclude_from_core_dumps`.
+    ///
+    /// Returns `ShmemError::Unsupported` on platforms other than Linux.
+    pub fn include_in_core_dumps(&self) -> Result<(), ShmemError> {
+        #[cfg(target_os = "linux")]
+        return self.madvise(0, self.size, libc::MADV_DODUMP);
+        #[cfg(not(target_os = "linux"))]
+        return Err(ShmemError::Unsupported);
+    }
+
+    /// Keeps the mapping out of processes forked from the current process via `madvise` with
+    /// `MADV_DONTFORK`.
+    ///
+    /// The address range of the mapping is left unmapped in the child, so dereferencing a
+    /// `ShmemBox` inherited through `fork` faults with SIGSEGV instead of silently sharing the
+    /// memory. The child does not keep the shared memory referenced either, and it must not
+    /// drop inherited handles since unmapping a range that is not mapped would fail.
+    ///
+    /// There is no way to hand children a zero-filled copy instead: the kernel only supports
+    /// `MADV_WIPEONFORK` for private anonymous mappings, and shared memory is always mapped with
+    /// `MAP_SHARED`.
+    ///
+    /// Returns `ShmemError::Unsupported` on platforms other than Linux.
+    pub fn dont_fork(&self) -> Result<(), ShmemError> {
+        #[cfg(target_os = "linux")]
+        return self.madvise(0, self.size, libc::MADV_DONTFORK);
+        #[cfg(not(target_os = "linux"))]
+        return Err(ShmemError::Unsupported);
+    }
+
+    /// Hints the kernel about the access pattern of the whole mapping via `madvise`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,Advice,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_advise");
+    ///     let shared_mem = shmem::Builder::new("flink_test_advise")
+    ///         .with_size(1 << 20)
+    ///         .open()?;
+    ///
+    ///     shared_mem.advise(Advice::Sequential)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn advise(&self, advice: Advice) -> Result<(), ShmemError> {
+        self.advise_range(0, self.size, advice)
+    }
+
+    /// Hints the kernel about the access pattern of `len` bytes of the mapping starting at
+    /// `offset` via `madvise`.
+    ///
+    /// The range is widened to the enclosing pages. Returns `ShmemError::AdviseFailed` carrying
+    /// the underlying `io::Error` if the range is out of bounds or `madvise` fails, and
+    /// `ShmemError::Unsupported` if the platform does not support `advice`.
+    pub fn advise_range(
+        &self,
+        offset: usize,
+        len: usize,
+        advice: Advice,
+    ) -> Result<(), ShmemError> {
+        let advice = advice.as_raw().ok_or(ShmemError::Unsupported)?;
+        self.madvise(offset, len, advice)
+    }
+
+    /// Names the mapping `name` in `/proc/<pid>/maps` and tools reading it, via `prctl` with
+    /// `PR_SET_VMA_ANON_NAME`. An empty `name` defaults to the `flink_id`.
+    ///
+    /// Naming is best-effort and a no-op returning `Ok` wherever the kernel can not name the
+    /// mapping: the kernel only names anonymous mappings, i.e. shared memories mapped via
+    /// `BuilderWithSize::allow_anonymous_fallback`, while a mapping of a named shared memory is
+    /// already listed under its path, e.g. `/dev/shm/<flink_id>`. Kernels before 6.2 or built
+    /// without `CONFIG_ANON_VMA_NAME`, names with characters the kernel rejects, i.e. anything
+    /// but printable ASCII other than `[`, `]`, `\`, `$` and `` ` ``, and platforms other than
+    /// Linux leave the mapping unnamed as well.
+    ///
+    /// Returns `ShmemError::InvalidIdErr` if `name` contains a nul byte or is longer than 79
+    /// bytes, the longest name the kernel takes.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_set_debug_name");
+    ///     let shared_mem = shmem::Builder::new("flink_test_set_debug_name")
+    ///         .with_size(4096)
+    ///         .open()?;
+    ///
+    ///     // a no-op, the mapping is listed under the name of the shared memory already
+    ///     shared_mem.set_debug_name("ring buffer")?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_debug_name(&self, name: &str) -> Result<(), ShmemError> {
+        let name = match name.is_empty() {
+            true => self.id.as_str(),
+            false => name,
+        };
+        if name.len() >= DEBUG_NAME_MAX_LEN || name.contains('\0') {
+            return Err(ShmemError::InvalidIdErr);
+        }
+        if !self.is_anonymous() || name.is_empty() {
+            return Ok(());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let name = CString::new(name).map_err(|_| ShmemError::InvalidIdErr)?;
+            let res = unsafe {
+                libc::prctl(
+                    libc::PR_SET_VMA,
+                    libc::PR_SET_VMA_ANON_NAME,
+                    self.addr.as_ptr() as libc::c_ulong,
+                    self.mapped_size() as libc::c_ulong,
+                    name.as_ptr() as libc::c_ulong,
+                )
+            };
+            if res != 0 {
+                let err = io::Error::last_os_error();
+                // EINVAL: naming is not supported or the kernel rejects a character of the name
+                // EBADF: the mapping is not anonymous
+                if !matches!(err.raw_os_error(), Some(libc::EINVAL | libc::EBADF)) {
+                    return Err(ShmemError::IoError(err));
+                }
+                trace::debug!(name = %self.id, error = %err, "mapping left unnamed");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies `advice` to `len` bytes of the mapping starting at `offset` via `madvise`.
+    ///
+    /// The range is widened to the enclosing pages.
+    fn madvise(&self, offset: usize, len: usize, advice: c_int) -> Result<(), ShmemError> {
+        let Some((addr, len)) = self.page_range(offset, len) else {
+            return Err(ShmemError::AdviseFailed(io::Error::from_raw_os_error(
+                libc::EINVAL,
+            )));
+        };
+        let res = unsafe { libc::madvise(addr, len, advice) };
+        if res != 0 {
+            return Err(ShmemError::AdviseFailed(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Widens `len` bytes of the mapping starting at `offset` to the enclosing pages, as
+    /// required by the page granular syscalls, returning the page aligned start address and
+    /// length of the widened range.
+    ///
+    /// Returns `None` if the range is out of bounds.
+    fn page_range(&self, offset: usize, len: usize) -> Option<(*mut c_void, usize)> {
+        let end = offset.checked_add(len).filter(|end| *end <= self.size)?;
+        let start = offset - offset % page_size();
+        let addr = unsafe { self.addr.as_ptr().cast::<u8>().add(start) };
+
+        Some((addr as *mut c_void, round_up_to_page(end) - start))
+    }
+
+    /// Releases `len` bytes of the mapping starting at `offset` back to the OS via `munmap`,
+    /// while the rest of the mapping stays in place.
+    ///
+    /// This is useful for arenas releasing a part of a large mapping, e.g. after compacting.
+    /// The shared memory itself keeps its size, only this mapping of it is affected. The
+    /// released range is skipped when the mapping is unmapped on drop. Releasing a range which
+    /// overlaps ranges released before only releases the parts still mapped, since the OS may
+    /// have placed other mappings at the addresses released before, and releasing an already
+    /// released range again is a no-op.
+    ///
+    /// `offset` and `len` must be multiples of the page size, except that the range may end at
+    /// `size()`. Returns `ShmemError::Misaligned` if they are not and
+    /// `ShmemError::OutOfBoundsErr` if the range exceeds the shared memory.
+    ///
+    /// Operations covering the whole mapping, e.g. `flush` or `protect`, fail once a part of it
+    /// is released.
+    ///
+    /// # Safety
+    ///
+    /// The released range must not be accessed afterwards, which faults with `SIGSEGV`. In
+    /// particular, no reference into the range may be alive. A box covering the range, e.g. one
+    /// created via `boxed_at` afterwards, may only be accessed via `ShmemBox::try_deref` and
+    /// `ShmemBox::try_deref_mut`, which detect the released range, and must not hold a type
+    /// with drop glue, since dropping the box drops the value in place.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_unmap_range");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_unmap_range")
+    ///         .with_size(shmem::page_size() * 4)
+    ///         .open()?;
+    ///
+    ///     // release the second half of the mapping
+    ///     unsafe { shared_mem.unmap_range(shmem::page_size() * 2, shmem::page_size() * 2)? };
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn unmap_range(&mut self, offset: usize, len: usize) -> Result<(), ShmemError> {
+        if self.options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.size)
+            .ok_or(ShmemError::OutOfBoundsErr)?;
+        let end_aligned = end.is_multiple_of(page_size()) || end == self.size;
+        if !offset.is_multiple_of(page_size()) || !end_aligned {
+            return Err(ShmemError::Misaligned);
+        }
+        let range = offset..round_up_to_page(end);
+
+        // the OS may have reused the address ranges released before for other mappings, so
+        // only the parts of the range which are still mapped are released
+        for mapped in self.mapped_ranges() {
+            let part = mapped.start.max(range.start)..mapped.end.min(range.end);
+            if part.is_empty() {
+                continue;
+            }
+            let ret = trace::syscall!(
+                "shmem_bind::munmap",
+                [name = %self.id, size = part.len(), is_owner = self.is_owner],
+                munmap(
+                    self.addr.as_ptr().cast::<u8>().add(part.start) as *mut c_void,
+                    part.len(),
+                )
+            );
+            if ret != 0 {
+                return Err(ShmemError::UnmapFailed(io::Error::last_os_error()));
+            }
+            self.record_unmapped(part);
+        }
+
+        Ok(())
+    }
+
+    /// Records `range` as released, merging it with the overlapping or adjacent ranges released
+    /// before.
+    fn record_unmapped(&mut self, range: Range<usize>) {
+        let (mut start, mut end) = (range.start, range.end);
+        self.unmapped.retain(|r| {
+            if r.end < start || r.start > end {
+                return true;
+            }
+            start = start.min(r.start);
+            end = end.max(r.end);
+            false
+        });
+        let index = self.unmapped.partition_point(|r| r.start < start);
+        self.unmapped.insert(index, start..end);
+    }
+
+    /// Returns the ranges of the mapping which are still mapped.
+    fn mapped_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = Vec::with_capacity(self.unmapped.len() + 1);
+        let mut start = 0;
+        for r in &self.unmapped {
+            if r.start > start {
+                ranges.push(start..r.start);
+            }
+            start = r.end;
+        }
+        if start < self.mapped_size() {
+            ranges.push(start..self.mapped_size());
+        }
+
+        ranges
+    }
+
+    /// Returns the current size of the shared memory object, as reported by `fstat`.
+    ///
+    /// The object size is larger than `size()` once another process grew the shared memory via
+    /// `grow`, which makes it a hook to detect growth without a header. Returns
+    /// `ShmemError::IoError` if `fstat` fails.
+    pub fn object_size(&self) -> Result<usize, ShmemError> {
+        Ok(object_size(self.fd)?.saturating_sub(self.options.header_len()))
+    }
+
+    /// Grows the shared memory to `new_size` bytes and remaps it, possibly at a different
+    /// address.
+    ///
+    /// The shared memory object is extended via `ftruncate` only if it is smaller than
+    /// `new_size`, so growing never shrinks an object another process already grew further. On
+    /// Linux the mapping is moved via `mremap`, elsewhere, or if the mapping was opened with an
+    /// alignment larger than a page, the shared memory is mapped again and the old mapping is
+    /// released. The options the shared memory was opened with are applied to the new mapping.
+    ///
+    /// Pointers previously obtained via `as_ptr` are invalidated. Since `boxed` consumes the
+    /// `ShmemConf`, boxes grow via `ShmemBox::grow` which refreshes their pointer instead.
+    ///
+    /// # Growing across processes
+    ///
+    /// Other processes keep their mapping of the old size, which stays valid since the object
+    /// never shrinks. To make them see the new bytes, the growing process publishes the new
+    /// size, e.g. in a header at the start of the shared memory, and the other processes call
+    /// `grow` with the published size on their own handles. Alternatively, they can compare
+    /// `object_size()` against `size()`.
+    ///
+    /// Returns `ShmemError::InvalidSize` if `new_size` is smaller than `size()` or does not fit
+    /// in an `off_t`, `ShmemError::Unsupported` if part of the mapping was released via
+    /// `unmap_range`, `ShmemError::AllocationFailedErr` if the object can not be extended and
+    /// `ShmemError::MapFailedErr` if it can not be remapped, in which case the old mapping is
+    /// kept.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_grow");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_grow")
+    ///         .with_size(16)
+    ///         .open()?;
+    ///     unsafe { *shared_mem.as_mut_ptr() = 42 };
+    ///
+    ///     shared_mem.grow(1 << 20)?;
+    ///     assert_eq!(shared_mem.size(), 1 << 20);
+    ///     assert_eq!(unsafe { *shared_mem.as_ptr() }, 42);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn grow(&mut self, new_size: usize) -> Result<(), ShmemError> {
+        let new_size = validate_size(new_size as i128, &self.options)?;
+        if new_size < self.size {
+            return Err(ShmemError::InvalidSize(RequestedSize::Bytes(new_size)));
+        }
+        if !self.unmapped.is_empty() || self.options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+        if new_size == self.size {
+            return Ok(());
+        }
+
+        if self.object_size()? < new_size {
+            if let Err(_err) = self
+                .options
+                .resize_object(self.fd, segment_len(new_size, &self.options))
+            {
+                trace::debug!(name = %self.id, new_size, error = %_err, "resize failed");
+                return Err(ShmemError::AllocationFailedErr);
+            }
+        }
+
+        self.addr = self.remap(new_size)?;
+        trace::info!(
+            name = %self.id,
+            old_size = self.size,
+            new_size,
+            pid = std::process::id(),
+            "grew shared memory"
+        );
+        self.size = new_size;
+        active::register(self);
+        self.apply_options()
+    }
+
+    /// Maps the shared memory with `new_size` bytes, releasing the current mapping on success.
+    fn remap(&self, new_size: usize) -> Result<NonNull<()>, ShmemError> {
+        let header_len = self.options.header_len();
+        let old_len = header_len + self.mapped_size();
+        let new_len = header_len + round_up_to_page(new_size);
+
+        // a mapping fixed via `map_at` only grows in place
+        #[cfg(target_os = "linux")]
+        if self.options.align <= page_size() || self.options.map_at != 0 {
+            let flags = match self.options.map_at {
+                0 => libc::MREMAP_MAYMOVE,
+                _ => 0,
+            };
+            let base = unsafe { libc::mremap(self.base(), old_len, new_len, flags) };
+            if base == MAP_FAILED {
+                trace::debug!(name = %self.id, new_size, error = %io::Error::last_os_error(), "mremap failed");
+                return Err(ShmemError::MapFailedErr);
+            }
+            let addr = unsafe { base.cast::<u8>().add(header_len) };
+            return NonNull::new(addr as *mut _).ok_or(ShmemError::NullPointerErr);
+        }
+        if self.options.map_at != 0 {
+            return Err(ShmemError::Unsupported);
+        }
+
+        let addr = map_shared(self.fd, new_size, &self.options)?;
+        let ret = trace::syscall!(
+            "shmem_bind::munmap",
+            [name = %self.id, size = old_len, is_owner = self.is_owner],
+            unsafe { munmap(self.base(), old_len) }
+        );
+        if ret != 0 {
+            let err = io::Error::last_os_error();
+            let base = unsafe { addr.as_ptr().cast::<u8>().sub(header_len) };
+            unsafe { munmap(base as *mut c_void, new_len) };
+            return Err(ShmemError::UnmapFailed(err));
+        }
+
+        Ok(addr)
+    }
+
+    /// Shrinks the shared memory to `new_size` bytes, releasing the tail of the mapping.
+    ///
+    /// The mapping stays at the same address. With `ShrinkPolicy::TruncateFile` the shared
+    /// memory object is truncated as well, see its documentation for the consequences for other
+    /// mappings. Processes opening the shared memory via `BuilderWithSize::open_existing` with
+    /// the old size afterwards get `ShmemError::SizeMismatch`.
+    ///
+    /// Returns `ShmemError::InvalidSize` if `new_size` is zero or larger than `size()`,
+    /// `ShmemError::NotOwner` if the object is to be truncated but this `ShmemConf` is not the
+    /// owner, `ShmemError::Unsupported` if part of the mapping was released via `unmap_range`,
+    /// `ShmemError::UnmapFailed` if the tail can not be unmapped and `ShmemError::IoError` if
+    /// the object can not be truncated. The shared memory keeps its size on error.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError,ShrinkPolicy};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_shrink");
+    ///     let mut shared_mem = shmem::Builder::new("flink_test_shrink")
+    ///         .with_size(4 * shmem::page_size())
+    ///         .open()?;
+    ///
+    ///     shared_mem.shrink(shmem::page_size(), ShrinkPolicy::TruncateFile)?;
+    ///     assert_eq!(shared_mem.size(), shmem::page_size());
+    ///     assert_eq!(shared_mem.object_size()?, shmem::page_size());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn shrink(&mut self, new_size: usize, policy: ShrinkPolicy) -> Result<(), ShmemError> {
+        let new_size = validate_size(new_size as i128, &self.options)?;
+        if new_size > self.size {
+            return Err(ShmemError::InvalidSize(RequestedSize::Bytes(new_size)));
+        }
+        if policy == ShrinkPolicy::TruncateFile && !self.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        if !self.unmapped.is_empty() || self.options.mirrored {
+            return Err(ShmemError::Unsupported);
+        }
+
+        // the object is truncated first, which is the step likely to fail, e.g. on a sealed memfd
+        if policy == ShrinkPolicy::TruncateFile {
+            self.options
+                .resize_object(self.fd, segment_len(new_size, &self.options))
+                .map_err(ShmemError::IoError)?;
+        }
+        let old_len = self.mapped_size();
+        let new_len = round_up_to_page(new_size);
+        if new_len < old_len {
+            let tail = unsafe { self.addr.as_ptr().cast::<u8>().add(new_len) };
+            let ret = trace::syscall!(
+                "shmem_bind::munmap",
+                [name = %self.id, size = old_len - new_len, is_owner = self.is_owner],
+                unsafe { munmap(tail as *mut c_void, old_len - new_len) }
+            );
+            if ret != 0 {
+                let err = io::Error::last_os_error();
+                // the tail stays mapped, accessing it past the end of the object would raise
+                // `SIGBUS`, the truncated bytes are lost regardless
+                if policy == ShrinkPolicy::TruncateFile {
+                    let _ = self
+                        .options
+                        .resize_object(self.fd, segment_len(self.size, &self.options));
+                }
+                return Err(ShmemError::UnmapFailed(err));
+            }
+        }
+        self.size = new_size;
+        active::register(self);
+
+        trace::info!(
+            name = %self.id,
+            new_size,
+            pid = std::process::id(),
+            truncated = policy == ShrinkPolicy::TruncateFile,
+            "shrank shared memory"
+        );
+
+        Ok(())
+    }
+
+    /// Asynchronous version of `grow` for use within a tokio runtime.
+    ///
+    /// `ftruncate`, `mremap` and faulting in a populated mapping may block, so the call to
+    /// `grow` is moved to tokio's blocking thread pool via `spawn_blocking`. Since the blocking
+    /// task must own the `ShmemConf`, it is consumed and handed back next to the result of
+    /// `grow`, which keeps the shared memory alive on error. A panic within `grow` is resumed
+    /// on the awaiting task.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_grow_async");
+    ///     let shared_mem = shmem::Builder::new("flink_test_grow_async")
+    ///         .with_size(16)
+    ///         .open_async()
+    ///         .await?;
+    ///
+    ///     let (shared_mem, res) = shared_mem.grow_async(1 << 20).await;
+    ///     res?;
+    ///     assert_eq!(shared_mem.size(), 1 << 20);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn grow_async(mut self, new_size: usize) -> (Self, Result<(), ShmemError>) {
+        spawn_blocking(move || {
+            let res = self.grow(new_size);
+            (self, res)
+        })
+        .await
+    }
+
+    /// Asynchronous version of `shrink` for use within a tokio runtime. See `grow_async`.
+    #[cfg(feature = "tokio")]
+    pub async fn shrink_async(
+        mut self,
+        new_size: usize,
+        policy: ShrinkPolicy,
+    ) -> (Self, Result<(), ShmemError>) {
+        spawn_blocking(move || {
+            let res = self.shrink(new_size, policy);
+            (self, res)
+        })
+        .await
+    }
+
+    /// Changes the protection of the whole mapping via `mprotect`.
+    ///
+    /// The change only affects the mapping of the current process, other processes keep the
+    /// protection of their own mappings.
+    ///
+    /// # Safety
+    ///
+    /// The safe accessors only check the protection the shared memory was mapped with, so they
+    /// fault on memory made inaccessible here. Unless `prot` is `Prot::ReadWrite`, the caller
+    /// must ensure that no `ShmemBox` or other accessor of this mapping writes to the memory
+    /// (or, for `Prot::None`, reads it) until the protection is restored.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError,Prot};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_protect");
+    ///     let shared_mem = shmem::Builder::new("flink_test_protect")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///
+    ///     // any write to the shared memory from now on faults
+    ///     unsafe { shared_mem.protect(Prot::Read)? };
+    ///     // restore the default protection
+    ///     unsafe { shared_mem.protect(Prot::ReadWrite)? };
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn protect(&self, prot: Prot) -> Result<(), ShmemError> {
+        self.protect_range(0, self.size, prot)
+    }
+
+    /// Changes the protection of `len` bytes of the mapping starting at `offset`.
+    ///
+    /// The range does not need to be page aligned, but since protection is page granular, it is
+    /// widened to the enclosing pages, i.e. bytes sharing a page with the range are affected as
+    /// well.
+    ///
+    /// Returns `ShmemError::ProtectFailed` carrying the underlying `io::Error` if the range is
+    /// out of bounds or `mprotect` fails.
+    ///
+    /// # Safety
+    ///
+    /// See `protect`, the requirements apply to the widened range.
+    pub unsafe fn protect_range(
+        &self,
+        offset: usize,
+        len: usize,
+        prot: Prot,
+    ) -> Result<(), ShmemError> {
+        let Some((addr, len)) = self.page_range(offset, len) else {
+            return Err(ShmemError::ProtectFailed(io::Error::from_raw_os_error(
+                libc::EINVAL,
+            )));
+        };
+        let res = unsafe { mprotect(addr, len, prot.as_raw()) };
+        if res != 0 {
+            return Err(ShmemError::ProtectFailed(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+}
+
+/// # Safety
+///
+/// `ShmemConf` only holds the address of the mapping, which is valid in every thread of the
+/// process, so moving it to another thread is fine.
+unsafe impl Send for ShmemConf {}
+
+/// # Safety
+///
+/// Methods taking `&self` only issue syscalls on the mapping or the file descriptor and access
+/// the header through atomics, so they may be called from several threads at once.
+unsafe impl Sync for ShmemConf {}
+
+/// # Safety
+///
+/// Shared memory is shared between processes.
+/// If it can withstand multiple processes mutating it, it can sure handle a thread or two!
+unsafe impl<T: ?Sized + Sync> Sync for ShmemBox<T> {}
+unsafe impl<T: ?Sized + Send> Send for ShmemBox<T> {}
+
+// SAFETY: the `NonNull<T>` inside `ShmemBox<T>` points into an `mmap`-ed region and not into the
+// `ShmemBox` itself. Moving the `ShmemBox` only moves the pointer, the address of the shared
+// memory is fixed by the OS until it is unmapped on drop, so the pointee never moves.
+impl<T: ?Sized> Unpin for ShmemBox<T> {}
+
+/// A safe and typed wrapper for shared memory
+///
+/// `ShmemBox<T>` wraps the underlying pointer to the shared memory and implements `Deref` and
+/// `DerefMut` for T
+///
+/// When ShmemBox<T> goes out of scope, the cleanup process of the shared memory is done.
+///
+/// # Memory ordering
+///
+/// Plain writes through `DerefMut` carry no ordering guarantees with respect to other cores or
+/// processes. On weakly-ordered architectures (e.g. aarch64) a peer may observe a "ready" flag
+/// before the data it guards. When publishing data through a flag, the writer must call
+/// `fence_release` after writing the data and before setting the flag, and the reader must call
+/// `fence_acquire` after observing the flag and before reading the data. The flag itself must
+/// be an atomic.
+#[derive(Debug)]
+pub struct ShmemBox<T: ?Sized> {
+    ptr: NonNull<T>,
+    conf: ShmemConf,
+}
+
+impl<T: ?Sized> ShmemBox<T> {
+    /// Owns the shared memory. this would result in shared memory cleanup when this pointer goes
+    /// out of scope.
+    ///
+    /// If the owner is tracked via `Builder::track_owner`, the current process is recorded as
+    /// the owner.
+    ///
+    /// The box switches to `CleanupPolicy::OwnerUnlinks`. Has no effect on a box of a shared
+    /// memory opened via `BuilderWithSize::open_cow`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_own");
+    ///     // shared memory is created. `shared_mem` owns the shared memory
+    ///     let shared_mem = shmem::Builder::new("flink_test_own")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///     
+    ///     // leaking the shared memory to prevent `shared_mem` from cleaning it up.
+    ///     ShmemBox::leak(boxed_val);
+    ///     
+    ///     // shared memory is already present on the machine. `shared_mem` does not own the
+    ///     // shared memory.
+    ///     let shared_mem = shmem::Builder::new("flink_test_own")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///
+    ///     // own the shared memory to ensure it's cleanup when the shared_mem goes out of scope.
+    ///     let boxed_val = ShmemBox::own(boxed_val);
+    ///
+    ///     // boxed_val goes out of scope, the shared memory is cleaned up
+    ///     Ok(())
+    /// }
+    ///
+    /// ```
+    pub fn own(mut shmem_box: Self) -> Self {
+        // a copy-on-write mapping can not publish anything, so it never takes over cleanup
+        if shmem_box.conf.options.copy_on_write {
+            return shmem_box;
+        }
+        shmem_box.conf.is_owner = true;
+        shmem_box.conf.options.cleanup = CleanupPolicy::OwnerUnlinks;
+        shmem_box.conf.trace_ownership();
+        if let Some(header) = shmem_box.conf.header() {
+            header.set_owner();
+        }
+
+        shmem_box
+    }
+
+    /// Same as `own`, but guarantees that at most one handle system-wide becomes the owner.
+    ///
+    /// The owner recorded in the header of the shared memory is replaced via compare-exchange
+    /// if nobody is recorded or the recorded process is dead, see `claim_ownership`. Unlike
+    /// `own`, another handle owning the shared memory within the current process is respected
+    /// as well.
+    ///
+    /// Returns the box unchanged if it already is the owner, `ShmemError::AlreadyOwned` if
+    /// another handle or a live process owns the shared memory, `ShmemError::NotInitialized` if
+    /// its creator did not initialize the header yet and `ShmemError::Unsupported` if the owner
+    /// is not tracked via `Builder::track_owner` or the shared memory is opened via
+    /// `BuilderWithSize::open_cow`. The box is handed back unchanged next to the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemBox,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_try_own");
+    ///     let open = || {
+    ///         shmem::Builder::new("flink_test_try_own")
+    ///             .track_owner(true)
+    ///             .with_size(4)
+    ///             .open()
+    ///     };
+    ///     let mut owner = unsafe { open()?.boxed::<u32>() };
+    ///     let other = unsafe { open()?.boxed::<u32>() };
+    ///
+    ///     // the creator still owns the shared memory
+    ///     let (other, err) = ShmemBox::try_own(other).unwrap_err();
+    ///     assert!(matches!(err, ShmemError::AlreadyOwned));
+    ///
+    ///     // once released, the next handle trying to own it wins
+    ///     ShmemBox::release_ownership(&mut owner);
+    ///     let other = ShmemBox::try_own(other).map_err(|(_, err)| err)?;
+    ///     assert!(ShmemBox::try_own(unsafe { open()?.boxed::<u32>() }).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    // the box is handed back as is, boxing it would only move it to the heap
+    #[allow(clippy::result_large_err)]
+    pub fn try_own(mut shmem_box: Self) -> Result<Self, (Self, ShmemError)> {
+        if shmem_box.conf.options.copy_on_write {
+            return Err((shmem_box, ShmemError::Unsupported));
+        }
+        let Some(header) = shmem_box.conf.header() else {
+            return Err((shmem_box, ShmemError::Unsupported));
+        };
+        let err = match header.claim() {
+            Claim::Claimed => None,
+            Claim::AlreadyHeld if shmem_box.conf.is_owner => None,
+            Claim::AlreadyHeld | Claim::HeldByOther => Some(ShmemError::AlreadyOwned),
+            Claim::NotInitialized => Some(ShmemError::NotInitialized),
+        };
+        if let Some(err) = err {
+            return Err((shmem_box, err));
+        }
+        let conf = &mut shmem_box.conf;
+        conf.is_owner = true;
+        conf.options.cleanup = CleanupPolicy::OwnerUnlinks;
+        conf.trace_ownership();
+
+        Ok(shmem_box)
+    }
+
+    /// Leaks the shared memory and prevents the cleanup if the ShmemBox is the owner of the shared
+    /// memory.
+    /// This function is useful when you want to create a shared memory which lasts longer than the
+    /// process creating it. The box switches to `CleanupPolicy::NeverUnlink` before it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_leak");
+    ///     // shared memory is created. `shared_mem` owns the shared memory
+    ///     let shared_mem = shmem::Builder::new("flink_test_leak")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///     
+    ///     // leaking the shared memory to prevent `shared_mem` from cleaning it up.
+    ///     ShmemBox::leak(boxed_val);
+    ///     
+    ///     // shared memory is already present on the machine. `shared_mem` does not own the
+    ///     // shared memory.
+    ///     let shared_mem = shmem::Builder::new("flink_test_leak")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///
+    ///     // own the shared memory to ensure it's cleanup when the shared_mem goes out of scope.
+    ///     let boxed_val = ShmemBox::own(boxed_val);
+    ///
+    ///     // boxed_val goes out of scope, the shared memory is cleaned up
+    ///     Ok(())
+    /// }
+    ///
+    /// ```
+    pub fn leak(mut shmem_box: Self) {
+        // disabling cleanup for shared memory
+        shmem_box.conf.is_owner = false;
+        shmem_box.conf.options.cleanup = CleanupPolicy::NeverUnlink;
+        shmem_box.conf.trace_ownership();
+    }
+
+    /// Claims the responsibility of cleaning up the shared memory for the current process,
+    /// e.g. in a child taking over from its parent.
+    ///
+    /// The owner recorded in the header of the shared memory is replaced via compare-exchange
+    /// if nobody is recorded, because the previous owner released it via `release_ownership`,
+    /// or the recorded process is dead. Among several processes claiming at once, exactly one
+    /// succeeds. Once claimed, dropping the box cleans up the shared memory, while handles of
+    /// the previous owner no longer do, even if they were created as the owner.
+    ///
+    /// Returns true if the current process is the recorded owner afterwards, false if another
+    /// process holds the responsibility and is still running, and `ShmemError::Unsupported` if
+    /// the owner is not tracked via `Builder::track_owner` or the shared memory is opened via
+    /// `BuilderWithSize::open_cow`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemBox,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_claim_ownership");
+    ///     let shared_mem = shmem::Builder::new("flink_test_claim_ownership")
+    ///         .track_owner(true)
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<u32>() };
+    ///
+    ///     // the creator already holds the responsibility
+    ///     assert!(ShmemBox::claim_ownership(&mut boxed_val)?);
+    ///
+    ///     // once released, any process, e.g. a forked child, may claim it
+    ///     ShmemBox::release_ownership(&mut boxed_val);
+    ///     assert!(ShmemBox::claim_ownership(&mut boxed_val)?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn claim_ownership(shmem_box: &mut Self) -> Result<bool, ShmemError> {
+        let conf = &mut shmem_box.conf;
+        if conf.options.copy_on_write {
+            return Err(ShmemError::Unsupported);
+        }
+        let Some(header) = conf.header() else {
+            return Err(ShmemError::Unsupported);
+        };
+        if !matches!(header.claim(), Claim::Claimed | Claim::AlreadyHeld) {
+            return Ok(false);
+        }
+        conf.is_owner = true;
+        conf.options.cleanup = CleanupPolicy::OwnerUnlinks;
+        conf.trace_ownership();
+
+        Ok(true)
+    }
+
+    /// Gives up the responsibility of cleaning up the shared memory, so another process can
+    /// claim it via `claim_ownership`.
+    ///
+    /// The recorded owner is only cleared if it is the current process. Until someone claims
+    /// it, nobody cleans up the shared memory.
+    pub fn release_ownership(shmem_box: &mut Self) {
+        let conf = &mut shmem_box.conf;
+        if let Some(header) = conf.header() {
+            header.release();
+        }
+        conf.is_owner = false;
+        conf.trace_ownership();
+    }
+
+    /// Issues a release fence.
+    ///
+    /// Every write made to the shared memory before this call is visible to a peer that
+    /// observes a subsequent store (e.g. setting a "ready" flag) and then calls
+    /// `fence_acquire`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// struct Message {
+    ///     payload: u64,
+    ///     ready: AtomicBool,
+    /// }
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_fence");
+    ///     let shared_mem = shmem::Builder::new("flink_test_fence")
+    ///         .with_size(std::mem::size_of::<Message>())
+    ///         .open()?;
+    ///     let mut message = unsafe { shared_mem.boxed::<Message>() };
+    ///
+    ///     // writer side
+    ///     message.payload = 42;
+    ///     message.fence_release();
+    ///     message.ready.store(true, Ordering::Relaxed);
+    ///
+    ///     // reader side
+    ///     if message.ready.load(Ordering::Relaxed) {
+    ///         message.fence_acquire();
+    ///         assert_eq!(message.payload, 42);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn fence_release(&self) {
+        atomic::fence(Ordering::Release);
+    }
+
+    /// Issues an acquire fence.
+    ///
+    /// Once a store made after a peer's `fence_release` is observed, every read issued after
+    /// this call sees the writes the peer made before its fence.
+    pub fn fence_acquire(&self) {
+        atomic::fence(Ordering::Acquire);
+    }
+
+    /// Seals the shared memory by changing the protection of the mapping to read-only and
+    /// returns a read-only wrapper over it.
+    ///
+    /// This is useful for the "sealed after init" pattern, where the owner initializes the value
+    /// and then prevents any further mutation from the current process. Any write to the shared
+    /// memory through this mapping after sealing faults.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_seal");
+    ///     let shared_mem = shmem::Builder::new("flink_test_seal")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<i32>() };
+    ///     *boxed_val = 5;
+    ///
+    ///     let sealed_val = boxed_val.seal()?;
+    ///     assert_eq!(*sealed_val, 5);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn seal(self) -> Result<ShmemReadBox<T>, ShmemError> {
+        // SAFETY: the box is consumed, `ShmemReadBox` only hands out shared references and
+        // restores the protection before the value is dropped
+        unsafe { self.conf.protect(Prot::Read)? };
+
+        Ok(ShmemReadBox { inner: self })
+    }
+
+    /// Synchronously flushes the whole shared memory. See `ShmemConf::flush`.
+    pub fn flush(&self) -> Result<(), ShmemError> {
+        self.conf.flush()
+    }
+
+    /// Schedules a write back of the whole shared memory. See `ShmemConf::flush_async`.
+    pub fn flush_async(&self) -> Result<(), ShmemError> {
+        self.conf.flush_async()
+    }
+
+    /// Flushes a range of the shared memory. See `ShmemConf::flush_range`.
+    pub fn flush_range(&self, offset: usize, len: usize, sync: bool) -> Result<(), ShmemError> {
+        self.conf.flush_range(offset, len, sync)
+    }
+
+    /// Returns how many pages of the shared memory are resident in memory. See
+    /// `ShmemConf::resident_pages`.
+    pub fn residency(&self) -> Result<ResidencyStats, ShmemError> {
+        self.conf.resident_pages()
+    }
+
+    /// Returns a raw pointer to the boxed value, e.g. to hand it over to C code.
+    ///
+    /// The pointer is valid as long as the box is alive.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr() as *const u8
+    }
+
+    /// Returns a raw mutable pointer to the boxed value. See `as_ptr`.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr() as *mut u8
+    }
+
+    /// Reinterprets the shared memory as a byte slice covering the whole mapping, without
+    /// unmapping it.
+    ///
+    /// The inner `T` is not dropped, even if the box is the owner of the shared memory. The
+    /// ownership of the shared memory moves to the returned box, so the shared memory is still
+    /// cleaned up exactly once.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless the shared memory is mapped with `Prot::ReadWrite`, see
+    /// `ShmemConf::boxed_bytes`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_into_bytes");
+    ///     let shared_mem = shmem::Builder::new("flink_test_into_bytes")
+    ///         .with_size(mem::size_of::<u32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<u32>() };
+    ///     *boxed_val = u32::from_ne_bytes([1, 2, 3, 4]);
+    ///
+    ///     let bytes = boxed_val.into_bytes();
+    ///     assert_eq!(&bytes[..], &[1, 2, 3, 4]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn into_bytes(self) -> ShmemBox<[u8]> {
+        self.into_conf().boxed_bytes()
+    }
+
+    /// Takes the `ShmemConf` out of the box without dropping the inner `T`.
+    fn into_conf(self) -> ShmemConf {
+        let shmem_box = mem::ManuallyDrop::new(self);
+
+        // # Safety
+        //
+        // `shmem_box` is never dropped, so the conf is moved out exactly once
+        unsafe { ptr::read(&shmem_box.conf) }
+    }
+}
+
+impl<T: ShmemSafe> ShmemBox<T> {
+    /// Overwrites the value with zeros via volatile writes, e.g. to wipe a secret as soon as it
+    /// is no longer needed instead of when the shared memory is dropped via
+    /// `Builder::zero_on_drop`.
+    ///
+    /// The value is left all zeros, which is valid for a `ShmemSafe` type, without being
+    /// dropped. Other handles read zeros afterwards, but copies peers made already are left
+    /// untouched.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_wipe");
+    ///     let mut key = shmem::Builder::new("flink_test_wipe")
+    ///         .with_size(32)
+    ///         .open()?
+    ///         .boxed_safe::<[u8; 32]>()?;
+    ///     *key = [0x42; 32];
+    ///
+    ///     key.wipe();
+    ///     assert_eq!(*key, [0; 32]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn wipe(&mut self) {
+        unsafe { zero_volatile(self.ptr.as_ptr().cast::<u8>(), mem::size_of::<T>()) };
+    }
+}
+
+impl<T> ShmemBox<T> {
+    /// Writes `val` into the shared memory and returns it pinned, the shared memory equivalent of
+    /// `Box::pin`.
+    ///
+    /// The address of the value is fixed by the mapping, which makes this suitable for
+    /// self-referential types or types whose address must not change, e.g. a
+    /// `pthread_mutex_t`.
+    ///
+    /// Any value already present in the shared memory is overwritten without being dropped.
+    /// Only the owner drops the value, which `Pin` requires to happen before the memory is
+    /// released, so a shared memory attached to is rejected. It may also hold a value another
+    /// process relies on.
+    ///
+    /// Returns `ShmemError::NotOwner` unless the `ShmemConf` is the owner,
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable and
+    /// `ShmemError::OutOfBoundsErr` if the shared memory is smaller than `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_pin");
+    ///     let shared_mem = shmem::Builder::new("flink_test_pin")
+    ///         .with_size(mem::size_of::<i32>())
+    ///         .open()?;
+    ///
+    ///     let pinned_val = ShmemBox::pin(shared_mem, 5)?;
+    ///     assert_eq!(*pinned_val, 5);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn pin(conf: ShmemConf, val: T) -> Result<Pin<ShmemBox<T>>, ShmemError> {
+        if !conf.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        conf.check_writable()?;
+        if mem::size_of::<T>() > conf.size {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+
+        // # Safety
+        //
+        // the shared memory is large enough to hold a `T` and the value is initialized right
+        // after the conversion
+        let shmem_box = unsafe { conf.boxed::<T>() };
+        unsafe { ptr::write(shmem_box.ptr.as_ptr(), val) };
+
+        // # Safety
+        //
+        // the pointee lives in the mapped shared memory and never moves, and the `Pin` prevents
+        // taking the box back out in order to leak it before the value is dropped
+        Ok(unsafe { Pin::new_unchecked(shmem_box) })
+    }
+
+    /// Returns the bytes of the shared memory past the `T`, e.g. the reserved space of a box
+    /// created via `ShmemConf::boxed_prefix`.
+    ///
+    /// Other handles may write the bytes concurrently, the same as for
+    /// `ShmemConf::boxed_bytes`. The bytes end where the shared memory or the mapped part right
+    /// after the `T` ends, i.e. bytes released via `ShmemConf::unmap_range` are never included.
+    pub fn reserved_bytes(&self) -> &[u8] {
+        let (start, len) = self.reserved_range();
+        unsafe { slice::from_raw_parts(start, len) }
+    }
+
+    /// Returns the bytes of the shared memory past the `T` mutably. See `reserved_bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless the shared memory is mapped with `Prot::ReadWrite`.
+    pub fn reserved_bytes_mut(&mut self) -> &mut [u8] {
+        assert!(
+            self.conf.check_writable().is_ok(),
+            "shared memory is not writable"
+        );
+        let (start, len) = self.reserved_range();
+        unsafe { slice::from_raw_parts_mut(start, len) }
+    }
+
+    /// Returns the start and the length of the bytes past the `T` up to the next released range.
+    fn reserved_range(&self) -> (*mut u8, usize) {
+        let end = self.ptr.as_ptr() as usize + mem::size_of::<T>();
+        let offset = end - self.conf.addr.as_ptr() as usize;
+        let len = self
+            .conf
+            .mapped_ranges()
+            .into_iter()
+            .find(|range| range.contains(&offset))
+            .map_or(0, |range| range.end.min(self.conf.size) - offset);
+        (end as *mut u8, len)
+    }
+
+    /// Returns the boxed value, or `None` if the pointer does not point to a `T` within the
+    /// mapped part of the shared memory, e.g. after `ShmemConf::unmap_range` or a box assembled
+    /// via `from_raw_parts` from mismatching parts, instead of crashing the process like
+    /// `Deref` would.
+    ///
+    /// The check covers the bounds and the alignment of the pointer only, not whether the bytes
+    /// hold a valid `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_try_deref");
+    ///     let shared_mem = shmem::Builder::new("flink_test_try_deref")
+    ///         .with_size(mem::size_of::<u32>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<u32>() };
+    ///     *boxed_val.try_deref_mut().unwrap() = 5;
+    ///     assert_eq!(boxed_val.try_deref(), Some(&5));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn try_deref(&self) -> Option<&T> {
+        if !self.is_mapped() {
+            return None;
+        }
+        Some(unsafe { self.ptr.as_ref() })
+    }
+
+    /// Returns the boxed value mutably, or `None` if the pointer does not point to a `T` within
+    /// the mapped part of the shared memory. See `try_deref`.
+    pub fn try_deref_mut(&mut self) -> Option<&mut T> {
+        if !self.is_mapped() {
+            return None;
+        }
+        Some(unsafe { self.ptr.as_mut() })
+    }
+
+    /// Returns true if the `T` is aligned and lies within the still mapped part of the shared
+    /// memory.
+    fn is_mapped(&self) -> bool {
+        let addr = self.ptr.as_ptr() as usize;
+        let Some(offset) = addr.checked_sub(self.conf.addr.as_ptr() as usize) else {
+            return false;
+        };
+        let Some(end) = offset.checked_add(mem::size_of::<T>()) else {
+            return false;
+        };
+
+        addr.is_multiple_of(mem::align_of::<T>())
+            && end <= self.conf.size
+            && self
+                .conf
+                .mapped_ranges()
+                .iter()
+                .any(|r| r.start <= offset && end <= r.end)
+    }
+
+    /// Consumes the box and returns the address of the mapping, the file descriptor of the
+    /// shared memory and its size, the shared memory equivalent of `Box::into_raw`.
+    ///
+    /// Like `leak`, no cleanup is done: the inner `T` is not dropped, the shared memory stays
+    /// mapped and linked and the file descriptor stays open. Whoever takes over the raw parts,
+    /// e.g. a C component, becomes responsible for `munmap`-ing the mapping and closing the file
+    /// descriptor, or for handing them back via `from_raw_parts`.
+    ///
+    /// The returned pointer is the start of the user data, so for a box created via
+    /// `ShmemConf::boxed_at` it does not point to the boxed value.
+    ///
+    /// The raw parts only describe a plain mapping, so the box is handed back unless the shared
+    /// memory is opened with the default options of `Builder::new` and no range of it is
+    /// released via `ShmemConf::unmap_range`. Options such as `Builder::track_owner`,
+    /// `Builder::mirrored`, `Builder::cleanup_policy` or `BuilderWithSize::with_protection`
+    /// change how the mapping is laid out or cleaned up, which `from_raw_parts` could not
+    /// restore.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError,ShmemBox};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_into_raw_parts");
+    ///     let shared_mem = shmem::Builder::new("flink_test_into_raw_parts")
+    ///         .with_size(mem::size_of::<u64>())
+    ///         .open()?;
+    ///     let mut boxed_val = unsafe { shared_mem.boxed::<u64>() };
+    ///     *boxed_val = 42;
+    ///
+    ///     let Ok((ptr, fd, size)) = ShmemBox::into_raw_parts(boxed_val) else {
+    ///         unreachable!("the shared memory is opened with the default options");
+    ///     };
+    ///     assert_eq!(unsafe { *(ptr as *const u64) }, 42);
+    ///
+    ///     // take the shared memory back to clean it up
+    ///     let boxed_val = unsafe {
+    ///         ShmemBox::<u64>::from_raw_parts("flink_test_into_raw_parts", ptr, fd, size)?
+    ///     };
+    ///     let _ = ShmemBox::own(boxed_val);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    // the box is handed back as is, boxing it would only move it to the heap
+    #[allow(clippy::result_large_err)]
+    pub fn into_raw_parts(shmem_box: Self) -> Result<(*mut c_void, i32, usize), Self> {
+        if !shmem_box.conf.options.is_plain() || !shmem_box.conf.unmapped.is_empty() {
+            return Err(shmem_box);
+        }
+        let conf = mem::ManuallyDrop::new(shmem_box.into_conf());
+        let parts = (conf.addr.as_ptr() as *mut c_void, conf.fd, conf.size);
+
+        // # Safety
+        //
+        // `conf` is never dropped, so the heap allocated fields are moved out exactly once to
+        // free them
+        unsafe {
+            drop(ptr::read(&conf.id));
+            drop(ptr::read(&conf.unmapped));
+        }
+
+        Ok(parts)
+    }
+
+    /// Reconstitutes a box from the raw parts of a shared memory, the shared memory equivalent
+    /// of `Box::from_raw`.
+    ///
+    /// The returned box is not the owner of the shared memory, use `own` to have it cleaned up
+    /// when the box goes out of scope. Returns `ShmemError::InvalidIdErr` if `id` is not a valid
+    /// `flink_id`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr`, `fd` and `size` must come from `into_raw_parts` of a shared memory named `id`,
+    /// or describe a mapping of `size` bytes created with `MAP_SHARED` on `fd` in the same way.
+    /// The mapping and the file descriptor must not be used by anyone else afterwards, since the
+    /// box unmaps and closes them when dropped. A `T` must be initialized at the start of the
+    /// mapping.
+    pub unsafe fn from_raw_parts(
+        id: &str,
+        ptr: *mut c_void,
+        fd: i32,
+        size: usize,
+    ) -> Result<Self, ShmemError> {
+        storage_id(id)?;
+        let addr = NonNull::new(ptr as *mut ()).ok_or(ShmemError::NullPointerErr)?;
+
+        let conf = ShmemConf {
+            id: id.to_string(),
+            is_owner: false,
+            fd,
+            addr,
+            size,
+            options: Options::default(),
+            unmapped: Vec::new(),
+        };
+        active::register(&conf);
+
+        Ok(conf.boxed())
+    }
+
+    /// Grows the shared memory to `new_size` bytes via `ShmemConf::grow` and refreshes the
+    /// pointer to the boxed value, which keeps its offset into the shared memory.
+    ///
+    /// The bytes past the value are reachable through `as_mut_ptr`, e.g. for a header followed
+    /// by a variable sized log.
+    pub fn grow(&mut self, new_size: usize) -> Result<(), ShmemError> {
+        let offset = self.ptr.as_ptr() as usize - self.conf.addr.as_ptr() as usize;
+        self.conf.grow(new_size)?;
+
+        // # Safety
+        //
+        // the value lies within the shared memory, which only grew, so it stays within the new
+        // mapping at the same offset
+        self.ptr = unsafe { self.conf.addr.cast::<u8>().add(offset).cast() };
+
+        Ok(())
+    }
+
+    /// Shrinks the shared memory to `new_size` bytes via `ShmemConf::shrink`.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the boxed value would not fit in the shrunk shared
+    /// memory anymore, in which case nothing is changed.
+    pub fn shrink(&mut self, new_size: usize, policy: ShrinkPolicy) -> Result<(), ShmemError> {
+        let offset = self.ptr.as_ptr() as usize - self.conf.addr.as_ptr() as usize;
+        let expected = size_add(offset, mem::size_of::<T>())?;
+        if new_size < expected {
+            return Err(ShmemError::SizeMismatch {
+                expected,
+                found: new_size,
+            });
+        }
+
+        self.conf.shrink(new_size, policy)
+    }
+}
+
+impl ShmemBox<[u8]> {
+    /// Reinterprets the shared memory as a `U`, without unmapping it.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the shared memory is smaller than `U` and
+    /// `ShmemError::Misaligned` if the mapping is not aligned for `U`, handing the box back
+    /// unchanged next to the error. The ownership of the shared memory moves to the returned
+    /// box.
+    ///
+    /// # Safety
+    ///
+    /// The bytes of the shared memory must form a valid `U`, the same requirement as
+    /// `ShmemConf::boxed`. If the returned box is the owner, `U` is dropped in place once it
+    /// goes out of scope, so the bytes must not describe resources already released elsewhere.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::mem;
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_try_into_typed");
+    ///     let shared_mem = shmem::Builder::new("flink_test_try_into_typed")
+    ///         .with_size(mem::size_of::<u32>())
+    ///         .open()?;
+    ///     let mut bytes = shared_mem.boxed_bytes();
+    ///     bytes.copy_from_slice(&[1, 2, 3, 4]);
+    ///
+    ///     let boxed_val = unsafe { bytes.try_into_typed::<u32>() }.map_err(|(_, err)| err)?;
+    ///     assert_eq!(*boxed_val, u32::from_ne_bytes([1, 2, 3, 4]));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    // the box is handed back as is, boxing it would only move it to the heap
+    #[allow(clippy::result_large_err)]
+    pub unsafe fn try_into_typed<U>(self) -> Result<ShmemBox<U>, (Self, ShmemError)> {
+        if self.len() < mem::size_of::<U>() {
+            let err = ShmemError::SizeMismatch {
+                expected: mem::size_of::<U>(),
+                found: self.len(),
+            };
+            return Err((self, err));
+        }
+        if !self.conf.addr.as_ptr().cast::<U>().is_aligned() {
+            return Err((self, ShmemError::Misaligned));
+        }
+
+        Ok(self.into_conf().boxed::<U>())
+    }
+
+    /// Grows the shared memory to `new_size` bytes via `ShmemConf::grow`, extending the slice to
+    /// cover the whole new size.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_grow_bytes");
+    ///     let mut log = shmem::Builder::new("flink_test_grow_bytes")
+    ///         .with_size(4)
+    ///         .open()?
+    ///         .boxed_bytes();
+    ///     log.write_at(0, b"full")?;
+    ///
+    ///     log.grow(8)?;
+    ///     log.write_at(4, b"more")?;
+    ///     assert_eq!(&log[..], b"fullmore");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn grow(&mut self, new_size: usize) -> Result<(), ShmemError> {
+        self.conf.grow(new_size)?;
+        let ptr = ptr::slice_from_raw_parts_mut(self.conf.addr.as_ptr() as *mut u8, self.conf.size);
+
+        // # Safety
+        //
+        // `ptr` is derived from `self.conf.addr` which is never null
+        self.ptr = unsafe { NonNull::new_unchecked(ptr) };
+
+        Ok(())
+    }
+
+    /// Shrinks the shared memory to `new_size` bytes via `ShmemConf::shrink`, truncating the
+    /// slice to the new size.
+    pub fn shrink(&mut self, new_size: usize, policy: ShrinkPolicy) -> Result<(), ShmemError> {
+        self.conf.shrink(new_size, policy)?;
+        let ptr = ptr::slice_from_raw_parts_mut(self.conf.addr.as_ptr() as *mut u8, self.conf.size);
+
+        // # Safety
+        //
+        // `ptr` is derived from `self.conf.addr` which is never null
+        self.ptr = unsafe { NonNull::new_unchecked(ptr) };
+
+        Ok(())
+    }
+
+    /// Copies `data` into the shared memory starting at `offset`.
+    ///
+    /// The whole write is bounds-checked once up front and then performed as a single
+    /// `copy_nonoverlapping`, which makes it the preferred path for bulk transfers.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if `offset + data.len()` exceeds the length of the
+    /// shared memory and `ShmemError::ProtectionViolation` unless the mapping is writable, in
+    /// which case nothing is written.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_write_at");
+    ///     let shared_mem = shmem::Builder::new("flink_test_write_at")
+    ///         .with_size(8)
+    ///         .open()?;
+    ///     let mut bytes = shared_mem.boxed_bytes();
+    ///
+    ///     bytes.write_at(4, &[1, 2, 3, 4])?;
+    ///     assert_eq!(&bytes[4..], &[1, 2, 3, 4]);
+    ///
+    ///     // writing past the end of the shared memory fails
+    ///     assert!(bytes.write_at(6, &[1, 2, 3]).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_at(&mut self, offset: usize, data: &[u8]) -> Result<(), ShmemError> {
+        self.conf.check_writable()?;
+        match offset.checked_add(data.len()) {
+            Some(end) if end <= self.len() => {}
+            _ => return Err(ShmemError::OutOfBoundsErr),
+        }
+
+        // # Safety
+        //
+        // the destination range is checked to be within the mapped shared memory and `data`
+        // can not overlap with it since we hold a unique reference to the shared memory
+        unsafe {
+            ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                self.ptr.as_ptr().cast::<u8>().add(offset),
+                data.len(),
+            )
+        };
+
+        Ok(())
+    }
+}
+
+impl<T: ?Sized> Drop for ShmemBox<T> {
+    fn drop(&mut self) {
+        if self.conf.cleans_up() {
+            // # Safety
+            //
+            // if current process is the owner of the shared_memory,i.e. creator of the shared
+            // memory, then it should clean up after, that is, it should drop the inner T
+            unsafe { drop_in_place(self.ptr.as_ptr()) };
+        }
+    }
+}
+impl ShmemConf {
+    /// Overwrites the user data still mapped with zeros, see `Builder::zero_on_drop`.
+    fn wipe(&self) {
+        for range in self.mapped_ranges() {
+            // pages past the end of the shared memory object raise `SIGBUS`
+            let range = range.start..range.end.min(self.size);
+            if range.is_empty() {
+                continue;
+            }
+            // a read-only mapping is made writable first, sealed shared memories stay as is
+            // SAFETY: making the mapping writable does not invalidate any access
+            if unsafe { self.protect_range(range.start, range.len(), Prot::ReadWrite) }.is_err() {
+                trace::debug!(name = %self.id, "shared memory not writable, left as is");
+                continue;
+            }
+            unsafe {
+                zero_volatile(
+                    self.addr.as_ptr().cast::<u8>().add(range.start),
+                    range.len(),
+                )
+            };
+        }
+    }
+
+    /// Unmaps the header, the mirror and every range of the mapping not released yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `munmap` fails.
+    fn unmap_all(&self) {
+        let unmap = |addr: *mut c_void, len: usize| {
+            let ret = trace::syscall!(
+                "shmem_bind::munmap",
+                [name = %self.id, size = len, is_owner = self.is_owner],
+                unsafe { munmap(addr, len) }
+            );
+            if ret != 0 {
+                trace::warn!(name = %self.id, error = %io::Error::last_os_error(), "munmap failed");
+                panic!("failed to unmap shared memory from the virtual memory space")
+            }
+        };
+
+        let header_len = self.options.header_len();
+        if header_len > 0 {
+            unmap(self.base(), header_len);
+        }
+        if self.options.mirrored {
+            let mirror = unsafe { self.addr.as_ptr().cast::<u8>().add(self.mapped_size()) };
+            unmap(mirror as *mut c_void, self.mapped_size());
+        }
+        for range in self.mapped_ranges() {
+            let addr = unsafe { self.addr.as_ptr().cast::<u8>().add(range.start) };
+            unmap(addr as *mut c_void, range.len());
+        }
+    }
+}
+
+impl Drop for ShmemConf {
+    fn drop(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "shmem_drop",
+            name = %self.id,
+            size = self.size,
+            is_owner = self.is_owner,
+        )
+        .entered();
+
+        // # Safety
+        //
+        // if current process is the owner of the shared_memory,i.e. creator of the shared
+        // memory, then it should clean up after.
+        // the procedure is as follow:
+        // 1. unmap the shared memory from processes virtual address space.
+        // 2. unlink the shared memory completely from the os if self is the owner
+        // 3. close the file descriptor of the shared memory
+        // the header is consulted before it is unmapped
+        let last = self.detach_header();
+        if self.options.zero_on_drop && self.is_responsible() {
+            self.wipe();
+        }
+        // files converted via `TryFrom<File>` and anonymous shared memories have no `flink_id`
+        // to unlink
+        let cleans_up = self.is_shared()
+            && !self.id.is_empty()
+            && !self.options.ephemeral
+            && match self.options.cleanup {
+                _ if self.options.file_backed => {
+                    self.options.unlink_on_drop && self.is_responsible()
+                }
+                CleanupPolicy::OwnerUnlinks => self.is_responsible(),
+                CleanupPolicy::NeverUnlink => false,
+                CleanupPolicy::AlwaysUnlink => true,
+                CleanupPolicy::UnlinkWhenLastDetaches => last == Some(true),
+                CleanupPolicy::UnlinkIfLast => flock::try_lock_exclusive(self.fd),
+            };
+        self.unmap_all();
+        trace::info!(name = %self.id, size = self.size, pid = std::process::id(), "unmapped shared memory");
+        active::deregister(self);
+        stale::unwatch(self.fd);
+
+        if cleans_up {
+            // the `flink_id` is already validated when the shared memory is opened
+            let storage_id = storage_id(&self.id).expect("flink_id is validated on open");
+            match self.options.unlink_object(&storage_id) {
+                Ok(()) => {
+                    trace::info!(name = %self.id, pid = std::process::id(), "unlinked shared memory");
+                }
+                // already unlinked, e.g. by another handle made the owner via `ShmemBox::own`
+                Err(err) if err.raw_os_error() == Some(libc::ENOENT) => {
+                    trace::debug!(name = %self.id, "shared memory already unlinked");
+                }
+                Err(_err) => {
+                    trace::warn!(
+                        name = %self.id,
+                        pid = std::process::id(),
+                        errno = _err.raw_os_error().unwrap_or_default(),
+                        "shm_unlink failed"
+                    );
+                    panic!("failed to reclaim shared memory")
+                }
+            }
+        }
+
+        // `close` is not retried on `EINTR`, the file descriptor is released regardless on Linux
+        // and retrying could close a file descriptor reused by another thread
+        if self.fd >= 0 && unsafe { close(self.fd) } != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EINTR) {
+                trace::warn!(name = %self.id, error = %err, "close failed");
+                panic!("failed to close shared memory file descriptor")
+            }
+        }
+    }
+}
+
+/// A read-only wrapper for a sealed shared memory, created via `ShmemBox::seal`.
+///
+/// `ShmemReadBox<T>` only implements `Deref` for T since the underlying mapping does not permit
+/// writes anymore.
+#[derive(Debug)]
+pub struct ShmemReadBox<T: ?Sized> {
+    inner: ShmemBox<T>,
+}
+
+impl<T: ?Sized> Drop for ShmemReadBox<T> {
+    fn drop(&mut self) {
+        if self.inner.conf.is_owner {
+            // dropping the inner T may write to the shared memory, so the mapping is made
+            // writable again before `inner` is dropped. unmapping a read-only mapping is fine
+            // otherwise.
+            let _ = unsafe { self.inner.conf.protect(Prot::ReadWrite) };
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ShmemReadBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> Deref for ShmemBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ShmemBox<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShmemError {
+    CreateFailedErr(io::Error),
+    AllocationFailedErr,
+    NullPointerErr,
+    MapFailedErr,
+    DuplicateFailedErr,
+    OutOfBoundsErr,
+    FlushFailed(io::Error),
+    ProtectFailed(io::Error),
+    InvalidIdErr,
+    AlreadyExists(io::Error),
+    IoError(io::Error),
+    NotFound(io::Error),
+    NumaFailed(io::Error),
+    LockTimeout,
+    SizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    NotInitialized,
+    AdviseFailed(io::Error),
+    Unsupported,
+    Misaligned,
+    ResidencyFailed(io::Error),
+    UnmapFailed(io::Error),
+    InvalidSize(RequestedSize),
+    NotOwner,
+    LogFull,
+    IdMismatch,
+    EncodeFailed(String),
+    DecodeFailed(String),
+    InvalidHandle,
+    AlreadyOwned,
+    TypeHashMismatch {
+        expected: u64,
+        found: u64,
+    },
+    Timeout,
+    StaleSegment {
+        pid: i32,
+    },
+    SecurityViolation,
+    WrongBacking(BackingKind),
+    LayoutMismatch {
+        expected: LayoutFingerprint,
+        found: LayoutFingerprint,
+    },
+    Poisoned,
+    VersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
+    InitAborted {
+        pid: i32,
+    },
+    AddressUnavailable(io::Error),
+    UnlinkAllFailed {
+        unlinked: usize,
+        failed: Vec<(String, io::Error)>,
+    },
+    ProtectionViolation,
+}
+
+impl ShmemError {
+    /// Returns the kind of the error, i.e. the variant without its payload.
+    ///
+    /// Since `ShmemError` carries `io::Error`s it can not implement `PartialEq`, the kind can be
+    /// used instead to compare errors.
+    ///
+    /// # Examples
+    /// ```
+    /// # let _tmp = shmem_bind::TempShmem::with_id("flink_test_kind");
+    /// use shmem_bind::{self as shmem,ShmemErrorKind};
+    ///
+    /// let err = shmem::Builder::new("flink_test_kind")
+    ///     .with_size(1)
+    ///     .open_existing()
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), ShmemErrorKind::NotFound);
+    /// ```
+    pub fn kind(&self) -> ShmemErrorKind {
+        match self {
+            ShmemError::CreateFailedErr(_) => ShmemErrorKind::CreateFailedErr,
+            ShmemError::AllocationFailedErr => ShmemErrorKind::AllocationFailedErr,
+            ShmemError::NullPointerErr => ShmemErrorKind::NullPointerErr,
+            ShmemError::MapFailedErr => ShmemErrorKind::MapFailedErr,
+            ShmemError::DuplicateFailedErr => ShmemErrorKind::DuplicateFailedErr,
+            ShmemError::OutOfBoundsErr => ShmemErrorKind::OutOfBoundsErr,
+            ShmemError::FlushFailed(_) => ShmemErrorKind::FlushFailed,
+            ShmemError::ProtectFailed(_) => ShmemErrorKind::ProtectFailed,
+            ShmemError::InvalidIdErr => ShmemErrorKind::InvalidIdErr,
+            ShmemError::AlreadyExists(_) => ShmemErrorKind::AlreadyExists,
+            ShmemError::IoError(_) => ShmemErrorKind::IoError,
+            ShmemError::NotFound(_) => ShmemErrorKind::NotFound,
+            ShmemError::NumaFailed(_) => ShmemErrorKind::NumaFailed,
+            ShmemError::LockTimeout => ShmemErrorKind::LockTimeout,
+            ShmemError::SizeMismatch { .. } => ShmemErrorKind::SizeMismatch,
+            ShmemError::NotInitialized => ShmemErrorKind::NotInitialized,
+            ShmemError::AdviseFailed(_) => ShmemErrorKind::AdviseFailed,
+            ShmemError::Unsupported => ShmemErrorKind::Unsupported,
+            ShmemError::Misaligned => ShmemErrorKind::Misaligned,
+            ShmemError::ResidencyFailed(_) => ShmemErrorKind::ResidencyFailed,
+            ShmemError::UnmapFailed(_) => ShmemErrorKind::UnmapFailed,
+            ShmemError::InvalidSize(_) => ShmemErrorKind::InvalidSize,
+            ShmemError::NotOwner => ShmemErrorKind::NotOwner,
+            ShmemError::LogFull => ShmemErrorKind::LogFull,
+            ShmemError::IdMismatch => ShmemErrorKind::IdMismatch,
+            ShmemError::EncodeFailed(_) => ShmemErrorKind::EncodeFailed,
+            ShmemError::DecodeFailed(_) => ShmemErrorKind::DecodeFailed,
+            ShmemError::InvalidHandle => ShmemErrorKind::InvalidHandle,
+            ShmemError::AlreadyOwned => ShmemErrorKind::AlreadyOwned,
+            ShmemError::TypeHashMismatch { .. } => ShmemErrorKind::TypeHashMismatch,
+            ShmemError::Timeout => ShmemErrorKind::Timeout,
+            ShmemError::StaleSegment { .. } => ShmemErrorKind::StaleSegment,
+            ShmemError::SecurityViolation => ShmemErrorKind::SecurityViolation,
+            ShmemError::WrongBacking(_) => ShmemErrorKind::WrongBacking,
+            ShmemError::LayoutMismatch { .. } => ShmemErrorKind::LayoutMismatch,
+            ShmemError::Poisoned => ShmemErrorKind::Poisoned,
+            ShmemError::VersionMismatch { .. } => ShmemErrorKind::VersionMismatch,
+            ShmemError::InitAborted { .. } => ShmemErrorKind::InitAborted,
+            ShmemError::AddressUnavailable(_) => ShmemErrorKind::AddressUnavailable,
+            ShmemError::UnlinkAllFailed { .. } => ShmemErrorKind::UnlinkAllFailed,
+            ShmemError::ProtectionViolation => ShmemErrorKind::ProtectionViolation,
+        }
+    }
+}
+
+impl Display for ShmemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl Error for ShmemError {}
+
+/// The kind of a `ShmemError`, returned by `ShmemError::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShmemErrorKind {
+    CreateFailedErr,
+    AllocationFailedErr,
+    NullPointerErr,
+    MapFailedErr,
+    DuplicateFailedErr,
+    OutOfBoundsErr,
+    FlushFailed,
+    ProtectFailed,
+    InvalidIdErr,
+    AlreadyExists,
+    IoError,
+    NotFound,
+    NumaFailed,
+    LockTimeout,
+    SizeMismatch,
+    NotInitialized,
+    AdviseFailed,
+    Unsupported,
+    Misaligned,
+    ResidencyFailed,
+    UnmapFailed,
+    InvalidSize,
+    NotOwner,
+    LogFull,
+    IdMismatch,
+    EncodeFailed,
+    DecodeFailed,
+    InvalidHandle,
+    AlreadyOwned,
+    TypeHashMismatch,
+    Timeout,
+    StaleSegment,
+    SecurityViolation,
+    WrongBacking,
+    LayoutMismatch,
+    Poisoned,
+    VersionMismatch,
+    InitAborted,
+    AddressUnavailable,
+    UnlinkAllFailed,
+    ProtectionViolation,
+}
+
+/// A size rejected with `ShmemError::InvalidSize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RequestedSize {
+    /// A size which is zero, does not fit in `off_t` together with the header or does not suit
+    /// the options, e.g. one which is not a multiple of the page size for a mirrored mapping.
+    Bytes(usize),
+    /// The exact size of a layout or a shared memory overflowing `usize`.
+    Overflow(u128),
+    /// A negative size passed to the deprecated `Builder::with_size_i64`.
+    Negative(i64),
+}
+
+impl RequestedSize {
+    /// Classifies a size computed as an `i128`, which only becomes negative for sizes passed to
+    /// `Builder::with_size_i64`.
+    fn from_i128(size: i128) -> Self {
+        match usize::try_from(size) {
+            Ok(size) => Self::Bytes(size),
+            Err(_) if size < 0 => Self::Negative(size as i64),
+            Err(_) => Self::Overflow(size as u128),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ownership() {
+        let tmp = TempShmem::new("test-shmem-box-ownership");
+        #[derive(Debug)]
+        struct Data {
+            val: i32,
+        }
+
+        let open = || {
+            tmp.builder()
+                .with_size(std::mem::size_of::<Data>())
+                .open_with_status()
+                .unwrap()
+        };
+        let Opened::Created(shmconf) = open() else {
+            panic!("the shared memory was not created");
+        };
+        assert!(shmconf.is_owner());
+        let mut data = unsafe { shmconf.boxed::<Data>() };
+        assert_eq!(data.val, 0);
+        data.val = 1;
+
+        ShmemBox::leak(data);
+
+        let Opened::Attached(shmconf) = open() else {
+            panic!("the leaked shared memory was created again");
+        };
+        assert!(!shmconf.is_owner());
+        let data = unsafe { shmconf.boxed::<Data>() };
+        assert_eq!(data.val, 1);
+
+        let _owned_data = ShmemBox::own(data);
+    }
+
+    #[test]
+    fn concurrent_open() {
+        const THREADS: usize = 16;
+        const ROUNDS: usize = 20;
+
+        for round in 0..ROUNDS {
+            let id = format!("test-shmem-conf-concurrent-open-{round}");
+            let barrier = std::sync::Barrier::new(THREADS);
+            let opened: Vec<Opened> = std::thread::scope(|s| {
+                let handles: Vec<_> = (0..THREADS)
+                    .map(|_| {
+                        s.spawn(|| {
+                            barrier.wait();
+                            Builder::new(&id)
+                                .track_owner(true)
+                                .with_size(8)
+                                .open_with_status()
+                                .unwrap()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+
+            assert_eq!(opened.iter().filter(|o| o.is_created()).count(), 1);
+            for opened in &opened {
+                let (Opened::Created(conf) | Opened::Attached(conf)) = opened;
+                assert_eq!(conf.is_owner(), opened.is_created());
+                // the losers attached once the winner sized the shared memory
+                assert_eq!(conf.object_size().unwrap(), 8);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_thread() {
+        let tmp = TempShmem::new("test-shmem-box-multi-thread.shm");
+        struct Data {
+            val: i32,
+        }
+        // create new shared memory pointer with desired size
+        let shared_mem = tmp
+            .builder()
+            .with_size(std::mem::size_of::<Data>())
+            .open()
+            .unwrap();
+
+        // wrap the raw shared memory ptr with desired Boxed type
+        // user must ensure that the data the pointer is pointing to is initialized and valid for use
+        let data = unsafe { shared_mem.boxed::<Data>() };
+
+        // ensure that first process owns the shared memory (used for cleanup)
+        let mut data = ShmemBox::own(data);
+
+        // initiate the data behind the boxed pointer
+        data.val = 1;
+
+        let new_val = 5;
+        std::thread::spawn(move || {
+            // create new shared memory pointer with desired size
+            let shared_mem = tmp
+                .builder()
+                .with_size(std::mem::size_of::<Data>())
+                .open()
+                .unwrap();
+
+            // wrap the raw shared memory ptr with desired Boxed type
+            // user must ensure that the data the pointer is pointing to is initialized and valid for use
+            let mut data = unsafe { shared_mem.boxed::<Data>() };
+            data.val = new_val;
+        })
+        .join()
+        .unwrap();
+        // assert that the new process mutated the shared memory
+        assert_eq!(data.val, new_val);
+    }
+
+    #[test]
+    fn clone_handle() {
+        let tmp = TempShmem::new("test-shmem-conf-clone-handle");
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<i32>())
+            .open()
+            .unwrap();
+        let handle = shmconf.clone_handle().unwrap();
+
+        assert!(shmconf.is_owner);
+        assert!(!handle.is_owner);
+        assert_ne!(shmconf.addr, handle.addr);
+        assert_ne!(shmconf.fd, handle.fd);
+
+        let mut data = unsafe { shmconf.boxed::<i32>() };
+        let handle_data = unsafe { handle.boxed::<i32>() };
+        *data = 3;
+        assert_eq!(*handle_data, 3);
+
+        // the clone only unmaps its own mapping, the original is still usable
+        drop(handle_data);
+        *data = 4;
+        assert_eq!(*data, 4);
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let tmp = TempShmem::new("test-shmem-conf-transfer-ownership");
+        let tmp2 = TempShmem::new("test-shmem-conf-transfer-ownership-other");
+        let open = |id| Builder::new(id).with_size(8).open().unwrap();
+        let mut shmconf = open(tmp.id());
+        let mut handle = shmconf.clone_handle().unwrap();
+        let mut other = open(tmp2.id());
+
+        let err = ShmemConf::transfer_ownership(&mut handle, &mut shmconf).unwrap_err();
+        assert!(matches!(err, ShmemError::NotOwner));
+        let err = ShmemConf::transfer_ownership(&mut shmconf, &mut other).unwrap_err();
+        assert!(matches!(err, ShmemError::IdMismatch));
+        assert!(shmconf.is_owner && other.is_owner);
+
+        ShmemConf::transfer_ownership(&mut shmconf, &mut handle).unwrap();
+        assert!(!shmconf.is_owner && handle.is_owner);
+
+        // a shared memory recreated under the same name is a different one
+        let storage_id = storage_id(&handle.id).unwrap();
+        Platform::unlink(&storage_id).unwrap();
+        let mut recreated = open(tmp.id());
+        let err = ShmemConf::transfer_ownership(&mut recreated, &mut shmconf).unwrap_err();
+        assert!(matches!(err, ShmemError::IdMismatch));
+
+        // the recreated shared memory is cleaned up by its own owner
+        handle.is_owner = false;
+    }
+
+    #[test]
+    fn try_clone() {
+        let tmp = TempShmem::new("test-shmem-conf-try-clone");
+        let shmconf = tmp.builder().with_size(8).open().unwrap();
+        let clone = shmconf.try_clone().unwrap();
+        let view = shmconf.try_clone_with(Prot::Read).unwrap();
+        assert!(!clone.is_owner && !view.is_owner);
+        assert_ne!(clone.as_ptr(), shmconf.as_ptr());
+        assert_ne!(view.as_ptr(), clone.as_ptr());
+
+        let mut writer = unsafe { shmconf.boxed::<u64>() };
+        let mut other = unsafe { clone.boxed::<u64>() };
+        let reader = unsafe { view.boxed::<u64>() };
+        *writer = 1;
+        assert_eq!((*other, *reader), (1, 1));
+        *other = 2;
+        assert_eq!((*writer, *reader), (2, 2));
+
+        // dropping the clones leaves the shared memory and the original mapping intact
+        drop(other);
+        drop(reader);
+        assert_eq!(*writer, 2);
+        tmp.builder().with_size(8).open_existing().unwrap();
+    }
+
+    #[test]
+    fn map_at() {
+        let tmp = TempShmem::new("test-shmem-conf-map-at");
+        let len = 4 * page_size();
+        // reserve some address space, then release it so that it is free to map the shared
+        // memory at
+        let free = unsafe {
+            let free = mmap(
+                ptr::null_mut(),
+                len,
+                PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            assert_ne!(free, MAP_FAILED);
+            munmap(free, len);
+            free.cast::<u8>()
+        };
+        let at = |offset: usize| unsafe { free.add(offset) } as *mut c_void;
+        let open = || tmp.builder().track_owner(true).with_size(page_size());
+
+        let mut shmconf = open().map_at(at(page_size())).open().unwrap();
+        assert_eq!(shmconf.as_ptr(), at(page_size()) as *const u8);
+        unsafe { *shmconf.as_mut_ptr() = 42 };
+
+        // neither the user data nor the header in front of it may be mapped over
+        for addr in [at(0), at(page_size())] {
+            let err = tmp.builder().with_size(8).map_at(addr).open().unwrap_err();
+            assert_eq!(err.kind(), ShmemErrorKind::AddressUnavailable);
+        }
+        let err = open().map_at(at(2 * page_size())).open().unwrap_err();
+        match err {
+            ShmemError::AddressUnavailable(err) => {
+                assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+            }
+            err => panic!("unexpected error: {err:?}"),
+        }
+        let err = open().map_at(at(page_size() + 8)).open().unwrap_err();
+        assert!(matches!(err, ShmemError::Misaligned));
+        let err = tmp
+            .builder()
+            .mirrored(true)
+            .with_size(page_size())
+            .map_at(at(0))
+            .open()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+
+        // growing keeps the address, clones are mapped elsewhere
+        #[cfg(target_os = "linux")]
+        {
+            shmconf.grow(2 * page_size()).unwrap();
+            assert_eq!(shmconf.as_ptr(), at(page_size()) as *const u8);
+            assert_eq!(unsafe { *shmconf.as_ptr() }, 42);
+        }
+        let clone = shmconf.try_clone().unwrap();
+        assert_ne!(clone.as_ptr(), shmconf.as_ptr());
+    }
+
+    #[test]
+    fn boxed_array() {
+        let tmp = TempShmem::new("test-shmem-conf-boxed-array");
+        let open = || {
+            tmp.builder()
+                .with_size(mem::size_of::<[u64; 256]>())
+                .open()
+                .unwrap()
+        };
+        let mut owner = unsafe { open().boxed_array::<u64, 256>() }.unwrap();
+        let other = unsafe { open().boxed_array::<u64, 256>() }.unwrap();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for (i, slot) in owner.iter_mut().enumerate() {
+                    *slot = i as u64 * 3;
+                }
+            });
+        });
+        assert!(other
+            .iter()
+            .enumerate()
+            .all(|(i, &slot)| slot == i as u64 * 3));
+        let err = unsafe { open().boxed_array::<u64, 257>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+
+        // the owner drops every element
+        use std::sync::atomic::AtomicUsize;
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        let tmp = TempShmem::new("test-shmem-conf-boxed-array-drop");
+        let open = || tmp.builder().with_size(8).open().unwrap();
+        let counted = unsafe { open().boxed_array::<Counted, 8>() }.unwrap();
+        drop(unsafe { open().boxed_array::<Counted, 8>() }.unwrap());
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 0);
+        drop(counted);
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 8);
+    }
+
+    #[test]
+    fn ephemeral() {
+        let shmconf = Builder::ephemeral().with_size(8).open().unwrap();
+        assert!(shmconf.is_ephemeral() && shmconf.is_owner());
+        assert!(!exists(shmconf.id()).unwrap());
+        let other = Builder::ephemeral().with_size(8).open().unwrap();
+        assert_ne!(other.id(), shmconf.id());
+
+        // nothing refers to the shared memory by name
+        let builder = || Builder::new(shmconf.id()).with_size(8);
+        let err = builder().open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+        let mut ephemeral = builder();
+        ephemeral.options.ephemeral = true;
+        let err = ephemeral.open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+        assert!(matches!(shmconf.is_current(), Err(ShmemError::Unsupported)));
+
+        // the shared memory is shared by file descriptor alone, e.g. with a forked child
+        let mut counter = unsafe { shmconf.clone_handle().unwrap().boxed::<u64>() };
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let fd = shmconf.into_fd_keep_unmapped();
+            let mut mapped = unsafe {
+                ShmemConf::map_only("ephemeral", fd, 8)
+                    .unwrap()
+                    .boxed::<u64>()
+            };
+            *mapped = 7;
+            unsafe { libc::_exit(0) };
+        }
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+        assert_eq!(*counter, 7);
+        *counter = 8;
+
+        // the creator is dropped without unlinking anything
+        drop(shmconf);
+        assert_eq!(*counter, 8);
+    }
+
+    #[test]
+    fn anonymous_fallback() {
+        let tmp = TempShmem::new("test-shmem-conf-anonymous-fallback");
+        let denied = || ShmemError::IoError(io::Error::from_raw_os_error(libc::EACCES));
+        let builder = || tmp.builder().with_size(8);
+
+        // `shm_open` failing for any other reason, or without the option, is reported as is
+        let err = builder().fall_back_to_anonymous(denied(), 8).unwrap_err();
+        assert!(matches!(err, ShmemError::IoError(_)));
+        let err = builder()
+            .allow_anonymous_fallback()
+            .fall_back_to_anonymous(ShmemError::SecurityViolation, 8)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::SecurityViolation));
+        // the errno of a failed create travels with the error, whatever `errno` holds by now
+        let err = ShmemError::CreateFailedErr(io::Error::from_raw_os_error(libc::ENOSPC));
+        let err = builder()
+            .allow_anonymous_fallback()
+            .fall_back_to_anonymous(err, 8)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::CreateFailedErr(_)));
+        let err = ShmemError::CreateFailedErr(io::Error::from_raw_os_error(libc::EACCES));
+        let opened = builder()
+            .allow_anonymous_fallback()
+            .fall_back_to_anonymous(err, 8)
+            .unwrap();
+        assert!(opened.into_inner().is_anonymous());
+
+        let opened = builder()
+            .allow_anonymous_fallback()
+            .fall_back_to_anonymous(denied(), 8)
+            .unwrap();
+        assert!(opened.is_created());
+        let shmconf = opened.into_inner();
+        assert!(shmconf.is_anonymous() && shmconf.is_owner());
+        assert_eq!(shmconf.id(), "");
+        assert!(!exists(tmp.id()).unwrap());
+        assert!(matches!(
+            shmconf.try_clone(),
+            Err(ShmemError::DuplicateFailedErr)
+        ));
+        assert!(!list_active_segments().iter().any(|segment| segment.fd < 0));
+
+        // the shared memory is shared with forked children
+        let mut counter = unsafe { shmconf.boxed::<u64>() };
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            *counter = 7;
+            unsafe { libc::_exit(0) };
+        }
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+        assert_eq!(*counter, 7);
+
+        // a name which can be opened is never replaced by anonymous shared memory
+        let named = builder().allow_anonymous_fallback().open().unwrap();
+        assert!(!named.is_anonymous());
+        assert!(exists(tmp.id()).unwrap());
+    }
+
+    #[test]
+    fn zero_on_drop() {
+        let tmp = TempShmem::new("test-shmem-conf-zero-on-drop");
+        let secret = [0x5a_u8; 100];
+        let open = || {
+            tmp.builder()
+                .zero_on_drop(true)
+                .cleanup_policy(CleanupPolicy::NeverUnlink)
+                .with_size(secret.len())
+                .open()
+                .unwrap()
+        };
+        let mut owner = unsafe { open().boxed::<[u8; 100]>() };
+        *owner = secret;
+        // an attached handle neither wipes the shared memory nor is affected by its own option
+        drop(open());
+        let attached = unsafe { open().boxed::<[u8; 100]>() };
+        assert_eq!(*attached, secret);
+
+        drop(owner);
+        assert_eq!(*attached, [0; 100]);
+        drop(attached);
+        let reopened = unsafe { open().boxed::<[u8; 100]>() };
+        assert!(!reopened.conf.is_owner());
+        assert_eq!(*reopened, [0; 100]);
+
+        // a read-only owner is wiped as well
+        ShmemConf::unlink(tmp.id()).unwrap();
+        let builder = |zero_on_drop| tmp.builder().zero_on_drop(zero_on_drop).with_size(100);
+        let owner = builder(true).with_protection(Prot::Read).open().unwrap();
+        let mut writer = builder(false)
+            .open()
+            .unwrap()
+            .boxed_safe::<[u8; 100]>()
+            .unwrap();
+        *writer = secret;
+        drop(owner);
+        assert_eq!(*writer, [0; 100]);
+
+        // explicit wiping mid-life
+        *writer = secret;
+        writer.wipe();
+        assert_eq!(*writer, [0; 100]);
+        for (offset, len) in [(0, 1), (1, 8), (3, 61), (7, 93)] {
+            let mut bytes = secret;
+            unsafe { zero_volatile(bytes.as_mut_ptr().add(offset), len) };
+            assert!(bytes[offset..offset + len].iter().all(|&b| b == 0));
+            assert!(bytes[..offset].iter().all(|&b| b == 0x5a));
+            assert!(bytes[offset + len..].iter().all(|&b| b == 0x5a));
+        }
+    }
+
+    #[test]
+    fn open_readonly() {
+        let tmp = TempShmem::new("test-shmem-conf-open-readonly");
+        let tmp2 = TempShmem::new("test-shmem-conf-open-readonly-untracked");
+        let tracked = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let untracked = tmp2.builder().with_size(8).open().unwrap();
+        for shmconf in [&tracked, &untracked] {
+            unsafe { *shmconf.as_ptr().cast_mut().cast::<u64>() = 42 };
+        }
+
+        let inspected = tmp.builder().with_size(8).open_readonly().unwrap();
+        assert_eq!(inspected.creator_pid(), tracked.creator_pid());
+        assert_eq!(inspected.attach_count(), Some(1));
+        assert_eq!(inspected.options.prot, Prot::Read);
+        assert_eq!(unsafe { *inspected.as_ptr().cast::<u64>() }, 42);
+
+        let inspected = tmp2.builder().with_size(8).open_readonly().unwrap();
+        assert_eq!(inspected.creator_pid(), None);
+        assert_eq!(unsafe { *inspected.as_ptr().cast::<u64>() }, 42);
+
+        // the header counts towards the size of the object, not of the user data
+        let err = tmp.builder().with_size(4096).open_readonly().unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { .. }));
+        drop(untracked);
+        let err = tmp2.builder().with_size(8).open_readonly().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+    }
+
+    #[test]
+    fn open_existing_inferred() {
+        let tmp = TempShmem::new("test-shmem-conf-open-existing-inferred");
+        let err = tmp.builder().open_existing_inferred().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+        assert!(!exists(tmp.id()).unwrap());
+
+        let mut creator = tmp.builder().with_size(1234).open().unwrap().boxed_bytes();
+        creator[1233] = 7;
+        let attached = tmp.builder().open_existing_inferred().unwrap();
+        assert!(!attached.is_owner());
+        assert_eq!(attached.size(), 1234);
+        assert_eq!(attached.mapped_size(), round_up_to_page(1234));
+        assert_eq!(attached.boxed_bytes()[1233], 7);
+        drop(creator);
+
+        // the header is not part of the inferred size
+        let tmp = TempShmem::new("test-shmem-conf-open-existing-inferred-header");
+        let _creator = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let attached = tmp.builder().track_owner(true).open_existing_inferred();
+        assert_eq!(attached.unwrap().size(), 8);
+    }
+
+    #[test]
+    fn file_path() {
+        use std::fs;
+
+        let tmp = TempShmem::new("test-shmem-conf-file-path");
+        let path = std::env::temp_dir().join(tmp.id());
+        let open = |unlink_on_drop| {
+            Builder::with_file_path(&path)
+                .unwrap()
+                .unlink_on_drop(unlink_on_drop)
+                .with_size(8)
+                .open()
+                .unwrap()
+        };
+
+        // the file outlives its owner, no POSIX shared memory is involved
+        let owner = open(false);
+        assert!(owner.is_owner() && owner.is_file_backed());
+        assert_eq!(owner.id(), path.to_str().unwrap());
+        *unsafe { owner.clone_handle().unwrap().boxed::<u64>() } = 42;
+        owner.flush().unwrap();
+        drop(owner);
+        assert!(!exists(tmp.id()).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), 42u64.to_ne_bytes());
+
+        // only the owner removes the file, and only with the option
+        let attached = open(true);
+        assert!(!attached.is_owner());
+        assert_eq!(*unsafe { attached.boxed::<u64>() }, 42);
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+        let mut owner = open(true);
+        owner.grow(16).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 16);
+        drop(open(true));
+        assert!(path.exists());
+        drop(owner);
+        assert!(!path.exists());
+
+        let err = Builder::with_file_path(&path)
+            .unwrap()
+            .with_size(8)
+            .open_existing()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        // recreating replaces the file, not a POSIX shared memory of the same name
+        fs::write(&path, 42u64.to_ne_bytes()).unwrap();
+        let recreated = Builder::with_file_path(&path)
+            .unwrap()
+            .unlink_on_drop(true)
+            .with_size(8)
+            .recreate()
+            .unwrap();
+        assert_eq!(*unsafe { recreated.boxed::<u64>() }, 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn file_path_invalid() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let built = Builder::with_file_path(OsStr::from_bytes(b"/tmp/\xff"));
+        assert!(matches!(built, Err(ShmemError::InvalidIdErr)));
+        let built = Builder::with_file_path("/tmp/nul\0");
+        assert!(matches!(built, Err(ShmemError::InvalidIdErr)));
+    }
+
+    #[test]
+    fn into_fd_keep_unmapped() {
+        let tmp = TempShmem::new("test-shmem-conf-into-fd-keep-unmapped");
+        let id = tmp.id();
+        let mut fds = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+        let [parent_sock, child_sock] = fds;
+
+        let mut shmconf = Builder::new(id).with_size(8).open().unwrap();
+        unsafe { *shmconf.as_mut_ptr().cast::<u64>() = 42 };
+        let fd = shmconf.into_fd_keep_unmapped();
+        // the shared memory outlives its owner's handle
+        assert!(crate::exists(id).unwrap());
+
+        unsafe {
+            let pid = libc::fork();
+            assert!(pid >= 0, "failed to fork");
+            if pid == 0 {
+                let fd = match socket::recv_fd(child_sock, &mut [0]) {
+                    Ok((_, Some(fd))) => fd,
+                    _ => libc::_exit(0),
+                };
+                let read = ShmemConf::map_only(id, fd, 8)
+                    .map(|conf| *conf.as_ptr().cast::<u64>())
+                    .unwrap_or(0);
+                libc::_exit((read == 42) as c_int);
+            }
+
+            assert_eq!(socket::send_fd(parent_sock, fd, &[0]).unwrap(), 1);
+            // sending duplicated the file descriptor, this one is still the parent's to close
+            assert_eq!(close(fd), 0);
+            let mut status = 0;
+            assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+            assert_eq!(libc::WEXITSTATUS(status), 1);
+            close(parent_sock);
+            close(child_sock);
+        }
+        assert!(ShmemConf::unlink(id).unwrap());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn set_debug_name() {
+        let tmp = TempShmem::new("test-shmem-conf-set-debug-name");
+        let maps_line = |shmconf: &ShmemConf| {
+            let start = format!("{:x}-", shmconf.as_ptr() as usize);
+            let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+            maps.lines()
+                .find(|l| l.starts_with(&start))
+                .unwrap()
+                .to_string()
+        };
+
+        // the kernel names anonymous mappings only, probed on a mapping of our own
+        let supported = unsafe {
+            let len = page_size();
+            let addr = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            assert_ne!(addr, libc::MAP_FAILED);
+            let res = libc::prctl(
+                libc::PR_SET_VMA,
+                libc::PR_SET_VMA_ANON_NAME,
+                addr as libc::c_ulong,
+                len as libc::c_ulong,
+                c"probe".as_ptr() as libc::c_ulong,
+            );
+            assert_eq!(libc::munmap(addr, len), 0);
+            res == 0
+        };
+
+        // a named shared memory keeps its path
+        let named = tmp.builder().debug_name(true).with_size(8).open().unwrap();
+        named.set_debug_name("renamed").unwrap();
+        assert!(maps_line(&named).contains(&format!("/dev/shm/{}", tmp.id())));
+
+        let denied = ShmemError::IoError(io::Error::from_raw_os_error(libc::EACCES));
+        let shmconf = tmp
+            .builder()
+            .debug_name(true)
+            .with_size(8)
+            .allow_anonymous_fallback()
+            .fall_back_to_anonymous(denied, 8)
+            .unwrap()
+            .into_inner();
+        let line = maps_line(&shmconf);
+        shmconf.set_debug_name("renamed").unwrap();
+        // names the kernel rejects leave the name as is
+        shmconf.set_debug_name("[rejected]").unwrap();
+        match supported {
+            true => {
+                assert!(line.contains(&format!("[anon_shmem:{}]", tmp.id())));
+                assert!(maps_line(&shmconf).contains("[anon_shmem:renamed]"));
+            }
+            // the kernel is built without CONFIG_ANON_VMA_NAME
+            false => assert!(!maps_line(&shmconf).contains("[anon_shmem:")),
+        }
+
+        for name in ["nul\0", &"x".repeat(DEBUG_NAME_MAX_LEN)] {
+            let err = shmconf.set_debug_name(name).unwrap_err();
+            assert!(matches!(err, ShmemError::InvalidIdErr));
+        }
+    }
+
+    #[test]
+    fn claim_ownership() {
+        let tmp = TempShmem::new("test-shmem-box-claim-ownership");
+        let id = tmp.id();
+        let open = || Builder::new(id).track_owner(true).with_size(8).open();
+        let mut boxed = unsafe { open().unwrap().boxed::<u64>() };
+
+        // claims in a child process, which drops its box and fails unless it claimed
+        let claim_in_child = || run_child_test("tests::claim_ownership_child", id);
+
+        // the live parent keeps the responsibility
+        assert!(!claim_in_child());
+        *boxed = 1;
+
+        // the child claims the released responsibility and cleans up on exit
+        ShmemBox::release_ownership(&mut boxed);
+        assert!(claim_in_child());
+        let err = Builder::new(id).with_size(8).open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        let mut untracked = unsafe { Builder::new(id).with_size(8).open().unwrap().boxed::<u64>() };
+        let err = ShmemBox::claim_ownership(&mut untracked).unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+    }
+
+    #[test]
+    #[ignore = "run by `claim_ownership` in a child process"]
+    fn claim_ownership_child() {
+        let Some(id) = child_test_arg() else {
+            return;
+        };
+        let open = Builder::new(&id).track_owner(true).with_size(8).open();
+        let mut child = unsafe { open.unwrap().boxed::<u64>() };
+        let claimed = ShmemBox::claim_ownership(&mut child);
+        drop(child);
+        assert!(matches!(claimed, Ok(true)));
+    }
+
+    #[test]
+    #[cfg(feature = "zerocopy")]
+    fn ref_as() {
+        let tmp = TempShmem::new("test-shmem-conf-ref-as");
+        let mut shmconf = tmp.builder().with_size(12).open().unwrap();
+        *shmconf.mut_as::<u64>().unwrap() = 7;
+        assert_eq!(*shmconf.ref_as::<u64>().unwrap(), 7);
+        assert_eq!(shmconf.ref_as::<[u8; 8]>().unwrap(), &7u64.to_ne_bytes());
+
+        let err = shmconf.ref_as::<[u64; 2]>().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::SizeMismatch {
+                expected: 16,
+                found: 12
+            }
+        ));
+    }
+
+    #[test]
+    fn own_twice() {
+        let tmp = TempShmem::new("test-shmem-box-own-twice");
+        let open = || tmp.builder().with_size(8).open().unwrap();
+        let first = ShmemBox::own(unsafe { open().boxed::<u64>() });
+        let second = ShmemBox::own(unsafe { open().boxed::<u64>() });
+
+        // the second unlink finds the shared memory gone and must not panic
+        drop(first);
+        drop(second);
+    }
+
+    #[test]
+    fn try_own() {
+        let tmp = TempShmem::new("test-shmem-box-try-own");
+        let tmp2 = TempShmem::new("test-shmem-box-try-own-untracked");
+        let open = || tmp.builder().track_owner(true).with_size(8);
+
+        // the owner exits without running `Drop`
+        assert_eq!(fork_and_wait(|| mem::forget(open().open().unwrap())), None);
+
+        // the dead owner is replaced by exactly one handle
+        let mut owner =
+            ShmemBox::try_own(unsafe { open().open().unwrap().boxed::<u64>() }).unwrap();
+        let (other, err) =
+            ShmemBox::try_own(unsafe { open().open().unwrap().boxed::<u64>() }).unwrap_err();
+        assert!(matches!(err, ShmemError::AlreadyOwned));
+        assert!(!other.conf.is_owner);
+        // owning again is a no-op
+        owner = ShmemBox::try_own(owner).unwrap();
+
+        // a released ownership goes to the next handle, here the one handed back above
+        ShmemBox::release_ownership(&mut owner);
+        let next = ShmemBox::try_own(other).unwrap();
+        let (owner, err) = ShmemBox::try_own(owner).unwrap_err();
+        assert!(matches!(err, ShmemError::AlreadyOwned));
+        drop((owner, next));
+        let err = open().open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        let untracked = tmp2.builder().with_size(8).open().unwrap();
+        let (untracked, err) = ShmemBox::try_own(unsafe { untracked.boxed::<u64>() }).unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+        assert!(untracked.conf.is_owner);
+    }
+
+    #[test]
+    fn cleanup_policy() {
+        let tmp = TempShmem::new("test-shmem-conf-cleanup-never");
+        let tmp2 = TempShmem::new("test-shmem-conf-cleanup-always");
+        let tmp3 = TempShmem::new("test-shmem-conf-cleanup-last");
+        let tmp4 = TempShmem::new("test-shmem-conf-cleanup-leak");
+        let tmp5 = TempShmem::new("test-shmem-conf-cleanup-untracked");
+        let open = |id, policy| {
+            Builder::new(id)
+                .cleanup_policy(policy)
+                .with_size(8)
+                .open()
+                .unwrap()
+        };
+
+        // the owner keeps the shared memory around
+        let id = tmp.id();
+        drop(open(id, CleanupPolicy::NeverUnlink));
+        assert!(crate::exists(id).unwrap());
+        assert!(ShmemConf::unlink(id).unwrap());
+
+        // any handle removes it
+        let id = tmp2.id();
+        let owner = open(id, CleanupPolicy::OwnerUnlinks);
+        drop(open(id, CleanupPolicy::AlwaysUnlink));
+        assert!(!crate::exists(id).unwrap());
+        drop(owner);
+
+        // the last handle removes it, whichever it is
+        let id = tmp3.id();
+        let creator = open(id, CleanupPolicy::UnlinkWhenLastDetaches);
+        assert!(creator.options.track_owner);
+        let consumer = open(id, CleanupPolicy::UnlinkWhenLastDetaches);
+        let clone = consumer.try_clone().unwrap();
+        assert_eq!(creator.header().unwrap().attached(), 3);
+        drop(creator);
+        drop(consumer);
+        assert!(crate::exists(id).unwrap());
+        drop(clone);
+        assert!(!crate::exists(id).unwrap());
+
+        // `leak` and `own` adjust the policy of their handle
+        let id = tmp4.id();
+        let boxed = unsafe { open(id, CleanupPolicy::AlwaysUnlink).boxed::<u64>() };
+        ShmemBox::leak(boxed);
+        assert!(crate::exists(id).unwrap());
+        let boxed = unsafe { open(id, CleanupPolicy::NeverUnlink).boxed::<u64>() };
+        let boxed = ShmemBox::own(boxed);
+        assert_eq!(boxed.conf.cleanup_policy(), CleanupPolicy::OwnerUnlinks);
+        drop(boxed);
+        assert!(!crate::exists(id).unwrap());
+
+        let mut untracked = open(tmp5.id(), CleanupPolicy::OwnerUnlinks);
+        let err = untracked
+            .set_cleanup_policy(CleanupPolicy::UnlinkWhenLastDetaches)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn unlink_if_last() {
+        use std::{
+            sync::atomic::{AtomicU32, Ordering},
+            thread,
+            time::Duration,
+        };
+
+        let tmp = TempShmem::new("test-shmem-conf-unlink-if-last");
+        let id = tmp.id();
+        let open = || {
+            Builder::new(id)
+                .cleanup_policy(CleanupPolicy::UnlinkIfLast)
+                .with_size(4)
+        };
+
+        // handles within a process, a clone holds a lock of its own
+        let creator = open().open().unwrap();
+        let consumer = open().open_existing().unwrap();
+        let clone = creator.try_clone().unwrap();
+        drop(creator);
+        drop(consumer);
+        assert!(crate::exists(id).unwrap());
+        drop(clone);
+        assert!(!crate::exists(id).unwrap());
+
+        // processes leave one after another, only the last one unlinks the shared memory
+        let counter = unsafe { open().open().unwrap().boxed::<AtomicU32>() };
+        let children: Vec<_> = (0..4)
+            .map(|i| {
+                let pid = unsafe { libc::fork() };
+                if pid == 0 {
+                    let attached = unsafe { open().open_existing().unwrap().boxed::<AtomicU32>() };
+                    attached.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis((i + 1) * 30));
+                    drop(attached);
+                    unsafe { libc::_exit(0) };
+                }
+                pid
+            })
+            .collect();
+        while counter.load(Ordering::SeqCst) < 4 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(counter);
+        assert!(crate::exists(id).unwrap());
+        for (i, pid) in children.into_iter().enumerate() {
+            let mut status = 0;
+            unsafe { libc::waitpid(pid, &mut status, 0) };
+            assert_eq!(libc::WEXITSTATUS(status), 0);
+            assert_eq!(crate::exists(id).unwrap(), i < 3);
+        }
+    }
+
+    #[test]
+    fn unlink() {
+        let tmp = TempShmem::new("test-shmem-conf-unlink");
+        let id = tmp.id();
+        let open = || Builder::new(id).with_size(8);
+        let mut leaked = unsafe { open().open().unwrap().boxed::<u64>() };
+        *leaked = 7;
+        ShmemBox::leak(leaked);
+
+        assert!(ShmemConf::unlink(id).unwrap());
+        let err = open().open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+        assert!(!ShmemConf::unlink(id).unwrap());
+
+        let err = ShmemConf::unlink("test-shmem\0conf-unlink").unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidIdErr));
+    }
+
+    #[test]
+    fn recreate() {
+        let tmp = TempShmem::new("test-shmem-conf-recreate");
+        let open = || tmp.builder().with_size(8);
+        // the owner of the stale shared memory is gone without cleaning up
+        let mut stale = unsafe { open().open().unwrap().boxed::<u64>() };
+        *stale = 7;
+        stale.conf.is_owner = false;
+
+        let fresh = unsafe { open().recreate().unwrap().boxed::<u64>() };
+        assert!(fresh.conf.is_owner);
+        assert_eq!(*fresh, 0);
+        // the previous shared memory is detached from the name
+        *stale = 8;
+        assert_eq!(*fresh, 0);
+        assert_eq!(
+            *unsafe { open().open_existing().unwrap().boxed::<u64>() },
+            0
+        );
+
+        drop(stale);
+        open().open_existing().unwrap();
+    }
+
+    #[test]
+    fn open_cow() {
+        let tmp = TempShmem::new("test-shmem-conf-open-cow");
+        let open = || tmp.builder().with_size(8);
+        let shmconf = open().open().unwrap();
+        let mut owner = unsafe { shmconf.boxed::<u64>() };
+        *owner = 0xA;
+
+        let cow = open().open_cow().unwrap();
+        assert!(!cow.is_owner && !cow.is_shared());
+        let mut local = unsafe { cow.boxed::<u64>() };
+        assert_eq!(*local, 0xA);
+
+        *local = 0xB;
+        assert_eq!(*owner, 0xA);
+
+        // the written page is a snapshot now
+        *owner = 0xC;
+        assert_eq!(*local, 0xB);
+
+        // dropping the copy-on-write box leaves the shared memory in place, even if owned
+        drop(ShmemBox::own(local));
+        open().open_existing().unwrap();
+
+        let err = tmp
+            .builder()
+            .mirrored(true)
+            .with_size(page_size())
+            .open_cow()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+    }
+
+    #[test]
+    fn write_at() {
+        let tmp = TempShmem::new("test-shmem-box-write-at");
+        let shmconf = tmp.builder().with_size(64).open().unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+        assert_eq!(bytes.len(), 64);
+
+        for offset in [0, 1, 7, 31, 60] {
+            let data: Vec<u8> = (0..4).map(|i| (offset + i) as u8).collect();
+            bytes.write_at(offset, &data).unwrap();
+            assert_eq!(&bytes[offset..offset + 4], &data[..]);
+        }
+
+        // empty writes are allowed anywhere up to the end
+        bytes.write_at(64, &[]).unwrap();
+
+        assert!(matches!(
+            bytes.write_at(61, &[0; 4]),
+            Err(ShmemError::OutOfBoundsErr)
+        ));
+        assert!(matches!(
+            bytes.write_at(usize::MAX, &[0]),
+            Err(ShmemError::OutOfBoundsErr)
+        ));
+        // failed writes leave the shared memory untouched
+        assert_eq!(&bytes[60..64], &[60, 61, 62, 63]);
+    }
+
+    #[test]
+    fn publish_with_fence() {
+        let tmp = TempShmem::new("test-shmem-box-publish-with-fence");
+        struct Data {
+            payload: [u64; 8],
+            ready: std::sync::atomic::AtomicBool,
+        }
+        let size = std::mem::size_of::<Data>();
+
+        for round in 0..100 {
+            let shared_mem = tmp.builder().with_size(size).open().unwrap();
+            let reader = unsafe { shared_mem.clone_handle().unwrap().boxed::<Data>() };
+            let mut data = unsafe { shared_mem.boxed::<Data>() };
+
+            let handle = std::thread::spawn(move || {
+                while !reader.ready.load(Ordering::Relaxed) {
+                    std::hint::spin_loop();
+                }
+                reader.fence_acquire();
+                reader.payload
+            });
+
+            data.payload = [round; 8];
+            data.fence_release();
+            data.ready.store(true, Ordering::Relaxed);
+
+            assert_eq!(handle.join().unwrap(), [round; 8]);
+        }
+    }
+
+    /// Environment variable carrying the argument of a test run by `run_child_test`.
+    const CHILD_TEST_ARG: &str = "SHMEM_BIND_CHILD_TEST_ARG";
+
+    /// Runs the ignored test `name` of the test binary in a child process, passing it `arg`, and
+    /// returns whether it passed.
+    ///
+    /// Unlike with `fork_and_wait`, the child is a fresh process, hence free to allocate or take
+    /// locks.
+    pub(crate) fn run_child_test(name: &str, arg: &str) -> bool {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([name, "--exact", "--ignored", "--test-threads=1"])
+            .env(CHILD_TEST_ARG, arg)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("running 1 test"), "{name} did not run");
+
+        output.status.success()
+    }
+
+    /// Returns the argument passed by `run_child_test`, `None` unless running in its child.
+    pub(crate) fn child_test_arg() -> Option<String> {
+        std::env::var(CHILD_TEST_ARG).ok()
+    }
+
+    /// Runs `f` in a forked child process and returns the signal that killed the child, if any.
+    ///
+    /// `f` must only perform async-signal-safe operations, e.g. plain memory accesses.
+    fn fork_and_wait(f: impl FnOnce()) -> Option<i32> {
+        unsafe {
+            let pid = libc::fork();
+            assert!(pid >= 0, "failed to fork");
+            if pid == 0 {
+                // the child is expected to crash, don't let it leave a core dump behind
+                let no_core = libc::rlimit {
+                    rlim_cur: 0,
+                    rlim_max: 0,
+                };
+                libc::setrlimit(libc::RLIMIT_CORE, &no_core);
+                f();
+                libc::_exit(0);
+            }
+
+            let mut status = 0;
+            assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+            if libc::WIFSIGNALED(status) {
+                Some(libc::WTERMSIG(status))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn protect() {
+        let tmp = TempShmem::new("test-shmem-conf-protect");
+        let size = page_size() * 2;
+        let shmconf = tmp.builder().with_size(size).open().unwrap();
+        let addr = shmconf.addr.as_ptr() as *mut u8;
+
+        unsafe { shmconf.protect_range(page_size() + 1, 1, Prot::Read) }.unwrap();
+        // the first page is still writable
+        unsafe { addr.write_volatile(1) };
+        // the range is widened to the whole second page
+        let signal = fork_and_wait(|| unsafe { addr.add(page_size()).write_volatile(1) });
+        assert_eq!(signal, Some(libc::SIGSEGV));
+
+        unsafe { shmconf.protect(Prot::ReadWrite) }.unwrap();
+        unsafe { addr.add(page_size()).write_volatile(2) };
+
+        assert!(matches!(
+            unsafe { shmconf.protect_range(size, 1, Prot::Read) },
+            Err(ShmemError::ProtectFailed(_))
+        ));
+    }
+
+    #[test]
+    fn protection_violation() {
+        let tmp = TempShmem::new("test-shmem-conf-protection-violation");
+        let open = |prot| {
+            tmp.builder()
+                .with_size(8)
+                .with_protection(prot)
+                .open()
+                .unwrap()
+        };
+
+        let read_only = open(Prot::Read);
+        assert_eq!(read_only.protection(), Prot::Read);
+        assert_eq!(read_only.chunks(8).next(), Some(&[0; 8][..]));
+        assert!(read_only.boxed_default::<u64>().is_err());
+        assert!(matches!(
+            open(Prot::Read).boxed_safe::<u64>(),
+            Err(ShmemError::ProtectionViolation)
+        ));
+        assert!(matches!(
+            ShmemCounter::new(open(Prot::Read)),
+            Err(ShmemError::ProtectionViolation)
+        ));
+
+        let none = open(Prot::None);
+        assert!(none.check_readable().is_err());
+        let res =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| none.into_iter().count()));
+        assert!(res.is_err());
+        let res = std::panic::catch_unwind(|| open(Prot::Read).boxed_bytes().len());
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn seal() {
+        let tmp = TempShmem::new("test-shmem-box-seal");
+        struct Data {
+            val: i32,
+        }
+        impl Drop for Data {
+            fn drop(&mut self) {
+                // dropping the sealed value writes to the shared memory
+                self.val = 0;
+            }
+        }
+
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<Data>())
+            .open()
+            .unwrap();
+        let mut data = unsafe { shmconf.boxed::<Data>() };
+        data.val = 5;
+
+        let sealed = data.seal().unwrap();
+        assert_eq!(sealed.val, 5);
+
+        let addr = &sealed.val as *const i32 as *mut i32;
+        let signal = fork_and_wait(|| unsafe { addr.write_volatile(1) });
+        assert_eq!(signal, Some(libc::SIGSEGV));
+        assert_eq!(sealed.val, 5);
+
+        // dropping the owned sealed box runs `Data::drop` without faulting
+        drop(sealed);
+    }
+
+    #[test]
+    fn pin() {
+        let tmp = TempShmem::new("test-shmem-box-pin");
+        struct SelfRef {
+            val: i32,
+            val_ref: *const i32,
+        }
+
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<SelfRef>())
+            .open()
+            .unwrap();
+        let mut pinned = ShmemBox::pin(
+            shmconf,
+            SelfRef {
+                val: 3,
+                val_ref: ptr::null(),
+            },
+        )
+        .unwrap();
+        pinned.val_ref = &pinned.val;
+
+        // moving the pinned box does not move the pointee
+        let moved = pinned;
+        assert_eq!(unsafe { *moved.val_ref }, 3);
+    }
+
+    #[test]
+    fn pin_too_small() {
+        let tmp = TempShmem::new("test-shmem-box-pin-too-small");
+        let shmconf = tmp.builder().with_size(1).open().unwrap();
+        assert!(matches!(
+            ShmemBox::pin(shmconf, 0_u64),
+            Err(ShmemError::OutOfBoundsErr)
+        ));
+    }
+
+    #[test]
+    fn pin_not_owner() {
+        let tmp = TempShmem::new("test-shmem-box-pin-not-owner");
+        let owner = tmp.builder().with_size(8).open().unwrap();
+        let mut val = ShmemBox::pin(owner, 5_u64).unwrap();
+        let attached = tmp.builder().with_size(8).open().unwrap();
+        assert!(matches!(
+            ShmemBox::pin(attached, 0_u64),
+            Err(ShmemError::NotOwner)
+        ));
+        assert_eq!(*val, 5);
+        *val = 6;
+    }
+
+    #[test]
+    fn no_reserve() {
+        let tmp = TempShmem::new("test-shmem-conf-no-reserve");
+        let tmp2 = TempShmem::new("test-shmem-conf-no-reserve-with-size");
+        const GIB: usize = 1 << 30;
+        let size = 64 * GIB;
+
+        let shmconf = tmp
+            .builder()
+            .no_reserve(true)
+            .with_size(size)
+            .open()
+            .unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+        assert_eq!(bytes.len(), size);
+
+        // touch a single page per gigabyte
+        for offset in (0..size).step_by(GIB) {
+            bytes[offset] = 1;
+        }
+        for offset in (0..size).step_by(GIB) {
+            assert_eq!(bytes[offset], 1);
+        }
+
+        // the option may be given after the size as well
+        let shmconf = tmp2
+            .builder()
+            .with_size(size)
+            .no_reserve(true)
+            .open()
+            .unwrap();
+        assert!(shmconf.options.no_reserve);
+        let stats = shmconf.resident_pages().unwrap();
+        assert_eq!(stats.total_pages, size / page_size());
+        assert!(stats.resident_pages < stats.total_pages);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn with_permissions() {
+        let tmp = TempShmem::new("test-shmem-conf-with-permissions");
+        let tmp2 = TempShmem::new("test-shmem-conf-with-permissions-default");
+        let mode = |conf: &ShmemConf| fstat(conf.fd).unwrap().st_mode & 0o777;
+        let shmconf = tmp
+            .builder()
+            .with_size(8)
+            .with_permissions(0o662)
+            .open()
+            .unwrap();
+        // the umask does not strip any bits
+        assert_eq!(mode(&shmconf), 0o662);
+
+        // attaching keeps the permissions of the creator
+        let attached = tmp.builder().with_size(8).open().unwrap();
+        assert_eq!(mode(&attached), 0o662);
+
+        let shmconf = tmp2.builder().with_size(8).create_exclusive().unwrap();
+        assert_eq!(mode(&shmconf), 0o600);
+    }
+
+    #[test]
+    fn create_exclusive() {
+        let tmp = TempShmem::new("test-shmem-conf-create-exclusive");
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<i32>())
+            .create_exclusive()
+            .unwrap();
+        assert!(shmconf.is_owner);
+
+        let err = tmp
+            .builder()
+            .with_size(std::mem::size_of::<i32>())
+            .create_exclusive()
+            .unwrap_err();
+        match err {
+            ShmemError::AlreadyExists(err) => assert_eq!(err.raw_os_error(), Some(libc::EEXIST)),
+            err => panic!("unexpected error: {err}"),
+        }
+
+        // the name is free again once the owner is dropped
+        drop(shmconf);
+        tmp.builder()
+            .with_size(std::mem::size_of::<i32>())
+            .create_exclusive()
+            .unwrap();
+    }
+
+    #[test]
+    fn error_kind() {
+        let tmp = TempShmem::new("test-shmem-error-kind");
+        let err = tmp.builder().with_size(1).open_existing().unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::NotFound);
+
+        let _shmconf = tmp.builder().with_size(1).create_exclusive().unwrap();
+        let err = tmp.builder().with_size(1).create_exclusive().unwrap_err();
+        assert_eq!(err.kind(), ShmemErrorKind::AlreadyExists);
+        assert_ne!(err.kind(), ShmemErrorKind::NotFound);
+
+        let attached = tmp.builder().with_size(1).open_existing().unwrap();
+        assert!(!attached.is_owner);
+    }
+
+    #[test]
+    fn invalid_id() {
+        assert!(matches!(
+            Builder::new("test-shmem\0-invalid-id").with_size(1).open(),
+            Err(ShmemError::InvalidIdErr)
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn invalid_size() {
+        let tmp = TempShmem::new("test-shmem-conf-invalid-size");
+        let err = tmp.builder().with_size(0).open().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Bytes(0))
+        ));
+
+        let err = tmp
+            .builder()
+            .with_size_i64(-1)
+            .create_exclusive()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Negative(-1))
+        ));
+
+        let err = tmp
+            .builder()
+            .with_size(usize::MAX)
+            .open_existing()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Bytes(usize::MAX))
+        ));
+
+        // nothing is created for an invalid size
+        let err = tmp.builder().with_size(1).open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        let shmconf = tmp.builder().with_size_i64(8).open().unwrap();
+        assert_eq!(shmconf.size(), 8);
+    }
 
-impl<T> Deref for ShmemBox<T> {
-    type Target = T;
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn open_async() {
+        let tmp = TempShmem::new("test-shmem-conf-open-async");
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<i32>())
+            .open_async()
+            .await
+            .unwrap();
+        assert!(shmconf.is_owner);
 
-    fn deref(&self) -> &Self::Target {
-        unsafe { self.ptr.as_ref() }
+        // the `ShmemConf` can cross await points and threads
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut data = unsafe { shmconf.boxed::<i32>() };
+            *data = 1;
+            *data
+        });
+        assert_eq!(handle.await.unwrap(), 1);
     }
-}
 
-impl<T> DerefMut for ShmemBox<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { self.ptr.as_mut() }
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn resize_async() {
+        let tmp = TempShmem::new("test-shmem-conf-resize-async");
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let ticks = AtomicUsize::new(0);
+        let size = 64 << 20;
+        let resize = async {
+            let shmconf = tmp
+                .builder()
+                .with_size(page_size())
+                .open_async()
+                .await
+                .unwrap();
+            let (mut shmconf, res) = shmconf.grow_async(size).await;
+            res.unwrap();
+            // fault in every page off the executor thread
+            shmconf = spawn_blocking(move || {
+                let addr = shmconf.as_mut_ptr();
+                for offset in (0..size).step_by(page_size()) {
+                    unsafe { ptr::write_volatile(addr.add(offset), 1) };
+                }
+                shmconf
+            })
+            .await;
+
+            let (shmconf, res) = shmconf
+                .shrink_async(page_size(), ShrinkPolicy::TruncateFile)
+                .await;
+            res.unwrap();
+            assert_eq!(shmconf.object_size().unwrap(), page_size());
+            // a failed resize hands the shared memory back
+            let (shmconf, res) = shmconf.grow_async(1).await;
+            assert!(matches!(res, Err(ShmemError::InvalidSize(_))));
+            assert_eq!(unsafe { *shmconf.as_ptr() }, 1);
+        };
+        // the single executor thread keeps running other tasks meanwhile
+        let ticker = async {
+            loop {
+                ticks.fetch_add(1, Ordering::Relaxed);
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::select! {
+            () = resize => {}
+            () = ticker => unreachable!(),
+        }
+        assert!(ticks.load(Ordering::Relaxed) > 1);
     }
-}
 
-#[derive(Debug)]
-pub enum ShmemError {
-    CreateFailedErr,
-    AllocationFailedErr,
-    NullPointerErr,
-}
-impl Display for ShmemError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+    #[test]
+    fn dont_dump() {
+        let tmp = TempShmem::new("test-shmem-conf-dont-dump");
+        let shmconf = tmp
+            .builder()
+            .dont_dump(true)
+            .with_size(page_size() + 1)
+            .open()
+            .unwrap();
+        let shmconf = shmconf.clone_handle().unwrap();
+        shmconf.exclude_from_core_dumps().unwrap();
+
+        // reads and writes are not affected
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[page_size()] = 1;
+        assert_eq!(bytes[page_size()], 1);
+
+        bytes.conf.include_in_core_dumps().unwrap();
+        bytes[0] = 2;
+        assert_eq!(bytes[0], 2);
     }
-}
-impl Error for ShmemError {}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn dont_fork() {
+        let tmp = TempShmem::new("test-shmem-conf-dont-fork");
+        let shmconf = tmp
+            .builder()
+            .dont_fork(true)
+            .with_size(mem::size_of::<u64>())
+            .open()
+            .unwrap();
+        let mut data = unsafe { shmconf.boxed::<u64>() };
+        *data = 42;
+
+        let addr = &*data as *const u64;
+        let signal = fork_and_wait(|| unsafe {
+            // the mapping is absent in the child
+            ptr::read_volatile(addr);
+        });
+        assert_eq!(signal, Some(libc::SIGSEGV));
+        assert_eq!(*data, 42);
+    }
 
     #[test]
-    fn ownership() {
-        #[derive(Debug)]
-        struct Data {
-            val: i32,
+    fn into_bytes_round_trip() {
+        let tmp = TempShmem::new("test-shmem-box-into-bytes");
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[repr(C)]
+        struct Point {
+            x: u32,
+            y: u64,
         }
 
-        let shmconf = Builder::new("test-shmem-box-ownership")
-            .with_size(std::mem::size_of::<Data>() as i64)
+        let shmconf = tmp
+            .builder()
+            .with_size(mem::size_of::<Point>())
             .open()
             .unwrap();
-        let mut data = unsafe { shmconf.boxed::<Data>() };
-        assert_eq!(data.val, 0);
-        data.val = 1;
+        let mut point = unsafe { shmconf.boxed::<Point>() };
+        *point = Point { x: 1, y: 2 };
 
-        ShmemBox::leak(data);
+        let bytes = point.into_bytes();
+        assert_eq!(bytes.len(), mem::size_of::<Point>());
+        assert!(bytes.conf.is_owner);
 
-        let shmconf = Builder::new("test-shmem-box-ownership")
-            .with_size(std::mem::size_of::<Data>() as i64)
+        let point = unsafe { bytes.try_into_typed::<Point>() }.unwrap();
+        assert_eq!(*point, Point { x: 1, y: 2 });
+        assert!(point.conf.is_owner);
+
+        let bytes = point.into_bytes();
+        let (bytes, err) = unsafe { bytes.try_into_typed::<[Point; 2]>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { .. }));
+
+        // the failed conversion handed back the owning box, which cleans up once dropped
+        assert!(bytes.conf.is_owner);
+        assert_eq!(bytes[..4], 1u32.to_ne_bytes());
+        drop(bytes);
+        let err = tmp
+            .builder()
+            .with_size(mem::size_of::<Point>())
+            .open_existing()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+    }
+
+    #[test]
+    fn raw_parts_round_trip() {
+        let tmp = TempShmem::new("test-shmem-box-raw-parts");
+        let shmconf = tmp
+            .builder()
+            .with_size(mem::size_of::<u64>())
             .open()
             .unwrap();
-        let data = unsafe { shmconf.boxed::<Data>() };
-        assert_eq!(data.val, 1);
+        let mut val = unsafe { shmconf.boxed::<u64>() };
+        *val = 41;
+        assert_eq!(val.as_ptr(), val.conf.as_ptr());
+        unsafe { *(val.as_mut_ptr() as *mut u64) += 1 };
 
-        let _owned_data = ShmemBox::own(data);
+        let (ptr, fd, size) = ShmemBox::into_raw_parts(val).ok().unwrap();
+        assert_eq!(size, mem::size_of::<u64>());
+
+        // the shared memory outlived the box
+        let attached = tmp
+            .builder()
+            .with_size(mem::size_of::<u64>())
+            .open_existing()
+            .unwrap();
+        assert_eq!(unsafe { *attached.boxed::<u64>() }, 42);
+
+        let val = unsafe { ShmemBox::<u64>::from_raw_parts(tmp.id(), ptr, fd, size).unwrap() };
+        assert!(!val.conf.is_owner);
+        assert_eq!(*val, 42);
+
+        // unmapping, unlinking and closing happen exactly once, any double free would panic
+        drop(ShmemBox::own(val));
+        let err = tmp
+            .builder()
+            .with_size(mem::size_of::<u64>())
+            .open_existing()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        // the header of a tracked shared memory can not be restored from the raw parts
+        let tracked = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let val = ShmemBox::into_raw_parts(unsafe { tracked.boxed::<u64>() }).unwrap_err();
+        assert_eq!(val.conf.attach_count(), Some(1));
+        drop(val);
+        let mut unmapped = tmp.builder().with_size(2 * page_size()).open().unwrap();
+        unsafe { unmapped.unmap_range(page_size(), page_size()).unwrap() };
+        assert!(ShmemBox::into_raw_parts(unsafe { unmapped.boxed::<u64>() }).is_err());
     }
 
+    #[cfg(feature = "tracing")]
     #[test]
-    fn multi_thread() {
-        struct Data {
-            val: i32,
+    #[tracing_test::traced_test]
+    fn tracing_open_span() {
+        let tmp = TempShmem::new("test-shmem-conf-tracing");
+        let shmconf = tmp
+            .builder()
+            .with_size(mem::size_of::<u64>())
+            .open()
+            .unwrap();
+        assert!(logs_contain(&format!(
+            "shmem_open{{name={} size=8 created=true}}",
+            tmp.id()
+        )));
+        assert!(logs_contain("mapped shared memory"));
+
+        let attached = tmp
+            .builder()
+            .with_size(mem::size_of::<u64>())
+            .open()
+            .unwrap();
+        assert!(logs_contain("created=false"));
+
+        // every syscall gets its own span and reports its duration
+        assert!(logs_contain("shmem_bind::shm_open"));
+        assert!(logs_contain("shmem_bind::mmap{fd="));
+        assert!(logs_contain("syscall returned elapsed="));
+
+        drop(attached);
+        drop(shmconf);
+        assert!(logs_contain(&format!(
+            "shmem_bind::munmap{{name={} size={} is_owner=true}}",
+            tmp.id(),
+            page_size()
+        )));
+        assert!(logs_contain("shmem_bind::shm_unlink"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    #[tracing_test::traced_test]
+    fn tracing_lifecycle() {
+        let tmp = TempShmem::new("test-shmem-conf-tracing-lifecycle");
+        let mut owner = tmp.builder().with_size(8).open().unwrap();
+        let attached = tmp.builder().with_size(8).open_existing().unwrap();
+        owner.grow(16).unwrap();
+        drop(attached);
+        drop(owner);
+
+        assert!(logs_contain(&format!("pid={}", std::process::id())));
+        assert!(logs_contain(&format!(
+            "shmem_drop{{name={} size=16 is_owner=true}}",
+            tmp.id()
+        )));
+        logs_assert(|lines: &[&str]| {
+            let events: Vec<_> = lines
+                .iter()
+                .filter(|line| line.contains(tmp.id()))
+                .filter_map(|line| {
+                    ["created", "attached", "grew", "unmapped", "unlinked"]
+                        .into_iter()
+                        .find(|event| line.contains(&format!("{event} shared memory")))
+                })
+                .collect();
+            let expected = [
+                "created", "attached", "grew", "unmapped", "unmapped", "unlinked",
+            ];
+            match events == expected {
+                true => Ok(()),
+                false => Err(format!("unexpected lifecycle events {events:?}")),
+            }
+        });
+    }
+
+    #[test]
+    fn resident_pages() {
+        let tmp = TempShmem::new("test-shmem-conf-resident-pages");
+        let pages = 8;
+        let shmconf = tmp.builder().with_size(page_size() * pages).open().unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+
+        // touch every other page
+        for page in (0..pages).step_by(2) {
+            bytes[page * page_size()] = 1;
         }
-        // create new shared memory pointer with desired size
-        let shared_mem = Builder::new("test-shmem-box-multi-thread.shm")
-            .with_size(std::mem::size_of::<Data>() as i64)
+
+        let stats = bytes.residency().unwrap();
+        assert_eq!(stats.total_pages, pages);
+        assert_eq!(stats.resident_pages, pages / 2);
+        assert_eq!(stats.bitmap, None);
+
+        let stats = bytes
+            .conf
+            .resident_pages_range(page_size() + 1, page_size() * 2, true)
+            .unwrap();
+        assert_eq!(stats.total_pages, 3);
+        assert_eq!(stats.resident_pages, 1);
+        assert_eq!(stats.is_resident(0), Some(false));
+        assert_eq!(stats.is_resident(1), Some(true));
+        assert_eq!(stats.is_resident(2), Some(false));
+        assert_eq!(stats.is_resident(3), None);
+
+        assert!(matches!(
+            bytes
+                .conf
+                .resident_pages_range(1, page_size() * pages, false),
+            Err(ShmemError::ResidencyFailed(_))
+        ));
+    }
+
+    #[test]
+    fn odd_sizes() {
+        let tmp = TempShmem::new("test-shmem-conf-odd-size-1");
+        let tmp2 = TempShmem::new("test-shmem-conf-odd-size-page");
+        let tmp3 = TempShmem::new("test-shmem-conf-odd-size-rounded");
+        let shmconf = tmp.builder().with_size(1).open().unwrap();
+        assert_eq!(shmconf.size(), 1);
+        assert_eq!(shmconf.mapped_size(), page_size());
+        unsafe { shmconf.protect_range(0, 1, Prot::Read) }.unwrap();
+        unsafe { shmconf.protect(Prot::ReadWrite) }.unwrap();
+        shmconf.flush().unwrap();
+        let mut data = unsafe { shmconf.boxed::<u8>() };
+        *data = 1;
+        assert_eq!(*data, 1);
+
+        let shmconf = tmp2.builder().with_size(page_size() + 1).open().unwrap();
+        assert_eq!(shmconf.size(), page_size() + 1);
+        assert_eq!(shmconf.mapped_size(), page_size() * 2);
+        shmconf.flush_range(page_size(), 1, true).unwrap();
+        assert_eq!(shmconf.resident_pages().unwrap().total_pages, 2);
+        let mut bytes = shmconf.boxed_bytes();
+        assert_eq!(bytes.len(), page_size() + 1);
+        bytes[page_size()] = 1;
+        assert_eq!(bytes[page_size()], 1);
+
+        let shmconf = tmp3
+            .builder()
+            .with_size_rounded_to_page(page_size() + 1)
             .open()
             .unwrap();
+        assert_eq!(shmconf.size(), page_size() * 2);
+        assert_eq!(shmconf.mapped_size(), page_size() * 2);
+    }
 
-        // wrap the raw shared memory ptr with desired Boxed type
-        // user must ensure that the data the pointer is pointing to is initialized and valid for use
-        let data = unsafe { shared_mem.boxed::<Data>() };
+    #[test]
+    fn unmap_range() {
+        let tmp = TempShmem::new("test-shmem-conf-unmap-range");
+        let pages = 4;
+        let mut shmconf = tmp.builder().with_size(page_size() * pages).open().unwrap();
+        let addr = shmconf.addr.as_ptr() as *mut u8;
+        unsafe { ptr::write_bytes(addr, 1, page_size() * pages) };
 
-        // ensure that first process owns the shared memory (used for cleanup)
-        let mut data = ShmemBox::own(data);
+        unsafe {
+            assert!(matches!(
+                shmconf.unmap_range(1, page_size()),
+                Err(ShmemError::Misaligned)
+            ));
+            assert!(matches!(
+                shmconf.unmap_range(page_size() * 2, page_size() * 3),
+                Err(ShmemError::OutOfBoundsErr)
+            ));
 
-        // initiate the data behind the boxed pointer
-        data.val = 1;
+            // release the second half in two steps, the ranges are merged
+            shmconf.unmap_range(page_size() * 3, page_size()).unwrap();
+            // another mapping placed at the released addresses is left alone
+            let foreign = mmap(
+                addr.add(page_size() * 3) as *mut c_void,
+                page_size(),
+                PROT_READ | PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | MAP_FIXED_NOREPLACE,
+                -1,
+                0,
+            );
+            assert_eq!(foreign, addr.add(page_size() * 3) as *mut c_void);
+            shmconf.unmap_range(page_size() * 2, page_size()).unwrap();
+            shmconf
+                .unmap_range(page_size() * 2, page_size() * 2)
+                .unwrap();
+            foreign.cast::<u8>().write_volatile(2);
+            assert_eq!(munmap(foreign, page_size()), 0);
+        }
+        assert_eq!(shmconf.unmapped, vec![page_size() * 2..page_size() * 4]);
+        assert_eq!(shmconf.mapped_ranges(), vec![0..page_size() * 2]);
+        assert!(shmconf.contains_addr(shmconf.as_ptr().wrapping_add(page_size() * 2 - 1).cast()));
+        assert!(!shmconf.contains_addr(shmconf.as_ptr().wrapping_add(page_size() * 2).cast()));
+        assert!(!shmconf.contains_addr(shmconf.as_ptr().wrapping_sub(1).cast()));
+        let ptr = shmconf.ptr_at_offset(page_size() * 2 - 1).unwrap();
+        assert_eq!(
+            shmconf.offset_of(ptr.as_ptr()).unwrap(),
+            page_size() * 2 - 1
+        );
+        assert!(matches!(
+            shmconf.ptr_at_offset(page_size() * 2),
+            Err(ShmemError::OutOfBoundsErr)
+        ));
+        assert!(matches!(
+            shmconf.offset_of(shmconf.as_ptr().wrapping_add(page_size() * 3).cast()),
+            Err(ShmemError::OutOfBoundsErr)
+        ));
 
-        let new_val = 5;
-        std::thread::spawn(move || {
-            // create new shared memory pointer with desired size
-            let shared_mem = Builder::new("test-shmem-box-multi-thread.shm")
-                .with_size(std::mem::size_of::<Data>() as i64)
+        // the first half is still mapped
+        assert_eq!(unsafe { *addr.add(page_size() * 2 - 1) }, 1);
+        let signal = fork_and_wait(|| unsafe {
+            ptr::read_volatile(addr.add(page_size() * 2));
+        });
+        assert_eq!(signal, Some(libc::SIGSEGV));
+
+        // dropping unmaps the rest without unmapping the released range twice
+        drop(shmconf);
+    }
+
+    #[test]
+    fn try_deref() {
+        let tmp = TempShmem::new("test-shmem-box-try-deref");
+        let mut shmconf = tmp.builder().with_size(page_size() * 2).open().unwrap();
+        unsafe { shmconf.unmap_range(page_size(), page_size()).unwrap() };
+
+        let mut first = unsafe { shmconf.clone_handle().unwrap().boxed_at::<u64>(0) }.unwrap();
+        *first.try_deref_mut().unwrap() = 5;
+        assert_eq!(first.try_deref(), Some(&5));
+
+        // the value lies within the released page, dropping a `u64` in place does not touch it
+        let mut second = unsafe { shmconf.boxed_at::<u64>(page_size()) }.unwrap();
+        assert!(second.try_deref().is_none());
+        assert!(second.try_deref_mut().is_none());
+
+        // pointers outside of the shared memory or misaligned ones are rejected
+        let start = second.conf.addr.cast::<u8>();
+        second.ptr = unsafe { start.sub(8).cast() };
+        assert!(second.try_deref().is_none());
+        second.ptr = unsafe { start.add(1).cast() };
+        assert!(second.try_deref().is_none());
+        // both handles map the same object
+        second.ptr = start.cast();
+        assert_eq!(second.try_deref(), Some(&5));
+        drop(first);
+    }
+
+    #[test]
+    fn advise() {
+        let tmp = TempShmem::new("test-shmem-conf-advise");
+        let pages = 4;
+        let shmconf = tmp.builder().with_size(page_size() * pages).open().unwrap();
+
+        shmconf.advise(Advice::WillNeed).unwrap();
+        shmconf.advise(Advice::Random).unwrap();
+        shmconf
+            .advise_range(page_size(), 1, Advice::Sequential)
+            .unwrap();
+        assert!(matches!(
+            shmconf.advise_range(page_size(), page_size() * pages, Advice::WillNeed),
+            Err(ShmemError::AdviseFailed(_))
+        ));
+
+        let mut bytes = shmconf.boxed_bytes();
+        bytes.fill(1);
+        let resident = bytes.residency().unwrap().resident_pages;
+        assert_eq!(resident, pages);
+
+        // the contents of the shared memory survive dropping the page tables
+        bytes.conf.advise(Advice::DontNeed).unwrap();
+        assert!(bytes.iter().all(|b| *b == 1));
+    }
+
+    #[test]
+    fn boxed_at() {
+        let tmp = TempShmem::new("test-shmem-conf-boxed-at");
+        #[derive(Debug)]
+        #[repr(C, align(64))]
+        struct CacheLine([u64; 8]);
+
+        let mut layout = SegmentLayout::new();
+        let header = layout.push::<[u8; 5]>().unwrap();
+        let line = layout.push::<CacheLine>().unwrap();
+        assert_eq!(line, 64);
+
+        let shmconf = tmp
+            .builder()
+            .with_align(layout.align())
+            .with_size(layout.size())
+            .open()
+            .unwrap();
+        let clone = shmconf.clone_handle().unwrap();
+        let mut header = unsafe { clone.boxed_at::<[u8; 5]>(header) }.unwrap();
+        header.fill(1);
+
+        let err = unsafe { shmconf.clone_handle().unwrap().boxed_at::<CacheLine>(5) }.unwrap_err();
+        assert!(matches!(err, ShmemError::Misaligned));
+        let err =
+            unsafe { shmconf.clone_handle().unwrap().boxed_at::<CacheLine>(128) }.unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+
+        let mut line = unsafe { shmconf.boxed_at::<CacheLine>(line) }.unwrap();
+        assert_eq!(&*line as *const CacheLine as usize % 64, 0);
+        line.0 = [2; 8];
+        assert_eq!(*header, [1; 5]);
+    }
+
+    #[test]
+    fn boxed_prefix() {
+        let tmp = TempShmem::new("test-shmem-conf-boxed-prefix");
+        let size = 2 * page_size();
+        let open = || tmp.builder().with_size(size).open().unwrap();
+        let mut header = unsafe { open().boxed_prefix::<[u32; 2]>() }.unwrap();
+        *header = [1, 2];
+        assert_eq!(header.reserved_bytes().len(), size - 8);
+        header.reserved_bytes_mut()[size - 9] = 3;
+
+        // the tail is shared like the rest of the shared memory
+        let bytes = open().boxed_bytes();
+        assert_eq!(&bytes[..8], [1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(bytes[size - 1], 3);
+        drop(bytes);
+
+        // the reserved space of a box at an offset starts after its own `T`
+        let tail = unsafe { open().boxed_at::<u32>(8) }.unwrap();
+        assert_eq!(tail.reserved_bytes().len(), size - 12);
+        assert_eq!(tail.reserved_bytes()[size - 13], 3);
+
+        // released bytes are left out
+        let mut shmconf = open();
+        unsafe { shmconf.unmap_range(page_size(), page_size()).unwrap() };
+        let mut released = unsafe { shmconf.boxed_prefix::<[u32; 2]>() }.unwrap();
+        assert_eq!(released.reserved_bytes().len(), page_size() - 8);
+        assert_eq!(released.reserved_bytes_mut().len(), page_size() - 8);
+        let mut shmconf = open();
+        unsafe { shmconf.unmap_range(0, page_size()).unwrap() };
+        let released = unsafe { shmconf.boxed_prefix::<u32>() }.unwrap();
+        assert!(released.reserved_bytes().is_empty());
+        drop(released);
+
+        let err = unsafe { open().boxed_prefix::<[u8; 1 << 20]>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+    }
+
+    #[test]
+    fn boxed_checked() {
+        let tmp = TempShmem::new("test-shmem-conf-boxed-checked");
+        let open = |size| tmp.builder().with_size(size).open().unwrap();
+        // nothing written yet
+        let owner = open(32);
+        let err = unsafe { owner.clone_handle().unwrap().boxed_checked::<u128>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::TypeHashMismatch { found: 0, .. }));
+
+        let mut owner = unsafe { owner.boxed_checked::<u128>() }.unwrap();
+        *owner = 7;
+        // the value is placed after the hash at its own alignment
+        assert_eq!(
+            &*owner as *const u128 as usize - owner.conf.as_ptr() as usize,
+            mem::align_of::<u128>().max(TYPE_HASH_LEN)
+        );
+
+        let viewer = unsafe { open(32).boxed_checked::<u128>() }.unwrap();
+        assert_eq!(*viewer, 7);
+        let err = unsafe { open(32).boxed_checked::<i128>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::TypeHashMismatch { .. }));
+
+        let err = unsafe { open(4).boxed_checked::<u8>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+
+        // nothing is written unless the `T` fits and the owner may write
+        let tmp2 = TempShmem::new("test-shmem-conf-boxed-checked-unwritten");
+        let owner = tmp2.builder().with_size(16).open().unwrap();
+        let peer = owner.clone_handle().unwrap();
+        let err = unsafe { owner.boxed_checked::<u128>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+        assert_eq!(unsafe { *peer.as_ptr().cast::<u64>() }, 0);
+        let tmp3 = TempShmem::new("test-shmem-conf-boxed-checked-read-only");
+        let owner = tmp3
+            .builder()
+            .with_size(16)
+            .with_protection(Prot::Read)
+            .open()
+            .unwrap();
+        let err = unsafe { owner.boxed_checked::<u64>() }.unwrap_err();
+        assert!(matches!(err, ShmemError::ProtectionViolation));
+    }
+
+    #[test]
+    fn with_align() {
+        let tmp = TempShmem::new("test-shmem-conf-with-align");
+        let align = 2 << 20;
+        let shmconf = tmp
+            .builder()
+            .with_align(align)
+            .with_size(page_size() + 1)
+            .open()
+            .unwrap();
+        assert_eq!(shmconf.addr.as_ptr() as usize % align, 0);
+        assert!(shmconf.resident_pages().is_ok());
+
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[page_size()] = 1;
+        assert_eq!(bytes[page_size()], 1);
+    }
+
+    #[test]
+    fn grow() {
+        let tmp = TempShmem::new("test-shmem-conf-grow");
+        let tmp2 = TempShmem::new("test-shmem-conf-grow-aligned");
+        let mut owner = tmp.builder().with_size(16).open().unwrap();
+        let mut peer = tmp.builder().with_size(16).open_existing().unwrap();
+        unsafe { *owner.as_mut_ptr() = 42 };
+
+        let new_size = 4 * page_size();
+        owner.grow(new_size).unwrap();
+        assert_eq!(owner.size(), new_size);
+        assert_eq!(unsafe { *owner.as_ptr() }, 42);
+        unsafe { *owner.as_mut_ptr().add(new_size - 1) = 7 };
+
+        // the peer detects the growth and remaps its own handle
+        assert_eq!(peer.object_size().unwrap(), new_size);
+        peer.grow(peer.object_size().unwrap()).unwrap();
+        assert_eq!(unsafe { *peer.as_ptr().add(new_size - 1) }, 7);
+
+        let err = owner.grow(16).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Bytes(16))
+        ));
+
+        // boxes refresh their pointer
+        let mut val = unsafe { owner.boxed::<u8>() };
+        val.grow(64 * page_size()).unwrap();
+        assert_eq!(*val, 42);
+        assert_eq!(val.as_ptr(), val.conf.as_ptr());
+
+        // the fallback path keeps the alignment
+        let align = 2 << 20;
+        let mut bytes = tmp2
+            .builder()
+            .with_align(align)
+            .with_size(8)
+            .open()
+            .unwrap()
+            .boxed_bytes();
+        bytes[7] = 1;
+        bytes.grow(2 * page_size()).unwrap();
+        assert_eq!(bytes.len(), 2 * page_size());
+        assert_eq!(bytes.as_ptr() as usize % align, 0);
+        assert_eq!(bytes[7], 1);
+        bytes[2 * page_size() - 1] = 1;
+    }
+
+    #[test]
+    fn eintr_retry() {
+        let tmp = TempShmem::new("test-shmem-conf-eintr");
+        extern "C" fn noop(_: c_int) {}
+
+        // without `SA_RESTART`, interrupted syscalls fail with `EINTR` instead of restarting
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = noop as extern "C" fn(c_int) as libc::sighandler_t;
+            assert_eq!(libc::sigaction(libc::SIGUSR1, &action, ptr::null_mut()), 0);
+        }
+
+        let target = unsafe { libc::pthread_self() };
+        let done = atomic::AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    unsafe { libc::pthread_kill(target, libc::SIGUSR1) };
+                }
+            });
+
+            for _ in 0..200 {
+                // a large size makes `ftruncate` slow enough to be interrupted now and then
+                let shmconf = tmp.builder().with_size(1 << 30).open().unwrap();
+                assert!(shmconf.is_owner);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+
+    #[test]
+    fn shrink_remap_only() {
+        let tmp = TempShmem::new("test-shmem-conf-shrink-remap-only");
+        let size = 4 * page_size();
+        let shmconf = tmp.builder().with_size(size).open().unwrap();
+        let mut peer = shmconf.clone_handle().unwrap();
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[page_size() - 1] = 1;
+
+        bytes.shrink(page_size(), ShrinkPolicy::RemapOnly).unwrap();
+        assert_eq!(bytes.len(), page_size());
+        assert_eq!(bytes[page_size() - 1], 1);
+        assert_eq!(bytes.conf.object_size().unwrap(), size);
+
+        // other mappings keep the whole shared memory, even non-owners may shrink their own
+        unsafe { *peer.as_mut_ptr().add(size - 1) = 2 };
+        peer.shrink(page_size(), ShrinkPolicy::RemapOnly).unwrap();
+        let err = peer.shrink(size, ShrinkPolicy::RemapOnly).unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidSize(_)));
+        let err = peer
+            .shrink(page_size(), ShrinkPolicy::TruncateFile)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::NotOwner));
+
+        // boxes are revalidated
+        let mut val = unsafe { peer.boxed_at::<u64>(page_size() / 2) }.unwrap();
+        let err = val
+            .shrink(page_size() / 2, ShrinkPolicy::RemapOnly)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { .. }));
+        val.shrink(page_size() / 2 + 8, ShrinkPolicy::RemapOnly)
+            .unwrap();
+        assert_eq!(*val, 0);
+    }
+
+    #[test]
+    fn shrink_truncate_file() {
+        let tmp = TempShmem::new("test-shmem-conf-shrink-truncate");
+        let size = 4 * page_size();
+        let mut shmconf = tmp.builder().with_size(size).open().unwrap();
+        shmconf
+            .shrink(page_size(), ShrinkPolicy::TruncateFile)
+            .unwrap();
+        assert_eq!(shmconf.object_size().unwrap(), page_size());
+
+        // attaching with the old size fails instead of mapping past the end of the object
+        let err = tmp.builder().with_size(size).open_existing().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::SizeMismatch { expected, found } if expected == size && found == page_size()
+        ));
+        tmp.builder()
+            .with_size(page_size())
+            .open_existing()
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn shrink_truncate_failed() {
+        use std::{fs::File, os::fd::FromRawFd};
+
+        let size = 2 * page_size();
+        let file = unsafe {
+            let fd = libc::memfd_create(
+                c"test-shmem-conf-shrink-sealed".as_ptr(),
+                libc::MFD_ALLOW_SEALING,
+            );
+            assert!(fd >= 0);
+            assert_eq!(libc::ftruncate(fd, size as libc::off_t), 0);
+            assert_eq!(libc::fcntl(fd, libc::F_ADD_SEALS, libc::F_SEAL_SHRINK), 0);
+            File::from_raw_fd(fd)
+        };
+        let mut shmconf = ShmemConf::try_from(file).unwrap();
+        shmconf.is_owner = true;
+
+        // the mapping is left as is if the object can not be truncated
+        let err = shmconf
+            .shrink(page_size(), ShrinkPolicy::TruncateFile)
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::IoError(err) if err.raw_os_error() == Some(libc::EPERM)));
+        assert_eq!(shmconf.size(), size);
+        unsafe { *shmconf.as_mut_ptr().add(size - 1) = 1 };
+        assert_eq!(shmconf.object_size().unwrap(), size);
+    }
+
+    #[test]
+    fn open_undersized() {
+        let tmp = TempShmem::new("test-shmem-conf-open-undersized");
+        let tmp2 = TempShmem::new("test-shmem-conf-open-unsized");
+        let _creator = tmp.builder().with_size(8).open().unwrap();
+        let size = 2 * page_size();
+        let err = tmp.builder().with_size(size).open().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::SizeMismatch { expected, found: 8 } if expected == size
+        ));
+
+        // the creator died before sizing the object
+        let fd = Platform::create(&storage_id(tmp2.id()).unwrap(), 0, 0o600, true).unwrap();
+        unsafe { close(fd) };
+        let err = tmp2.builder().with_size(8).open().unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+    }
+
+    #[test]
+    fn mirrored() {
+        let tmp = TempShmem::new("test-shmem-conf-mirrored");
+        let size = 2 * page_size();
+        let open = || tmp.builder().mirrored(true);
+
+        let shmconf = open().with_size(size).open().unwrap();
+        let ring = unsafe { shmconf.as_mirrored_slice() };
+        assert_eq!(ring.len(), 2 * size);
+        ring[size - 1..size + 1].copy_from_slice(&[1, 2]);
+
+        // a regular mapping sees the write wrapped around
+        let peer = tmp.builder().with_size(size).open_existing().unwrap();
+        let bytes = peer.boxed_bytes();
+        assert_eq!((bytes[size - 1], bytes[0]), (1, 2));
+
+        let mut shmconf = shmconf;
+        let err = shmconf.grow(2 * size).unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+
+        let err = open().with_size(size + 1).open().unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidSize(_)));
+        let err = open().track_owner(true).with_size(size).open().unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+
+        let align = 2 << 20;
+        let aligned = open().with_align(align).with_size(size).open().unwrap();
+        assert_eq!(aligned.addr.as_ptr() as usize % align, 0);
+        assert_eq!(unsafe { aligned.as_mirrored_slice() }[2 * size - 1], 1);
+    }
+
+    #[test]
+    fn track_owner() {
+        let tmp = TempShmem::new("test-shmem-conf-track-owner");
+        let shmconf = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        assert!(shmconf.owner_alive());
+        assert_eq!(shmconf.object_size().unwrap(), 8);
+        assert_eq!(shmconf.addr.as_ptr() as usize % page_size(), 0);
+
+        // the header is invisible to the user data
+        let mut val = unsafe { shmconf.boxed::<u64>() };
+        *val = 42;
+        val.grow(2 * page_size()).unwrap();
+        assert!(val.conf.owner_alive());
+        assert_eq!(val.conf.object_size().unwrap(), 2 * page_size());
+        let peer = tmp
+            .builder()
+            .track_owner(true)
+            .with_size(8)
+            .open_stealing_if_dead()
+            .unwrap();
+        assert!(!peer.is_owner);
+        assert_eq!(unsafe { *peer.boxed::<u64>() }, 42);
+
+        let err = tmp
+            .builder()
+            .with_size(8)
+            .open_stealing_if_dead()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Unsupported));
+    }
+
+    #[test]
+    fn open_stealing_if_dead() {
+        let tmp = TempShmem::new("test-shmem-conf-stealing");
+        let open = || tmp.builder().track_owner(true).with_size(8);
+
+        // the owner exits without running `Drop`
+        let signal = fork_and_wait(|| mem::forget(open().open().unwrap()));
+        assert_eq!(signal, None);
+
+        let shmconf = open().open_existing().unwrap();
+        assert!(!shmconf.owner_alive());
+
+        let shmconf = open().open_stealing_if_dead().unwrap();
+        assert!(shmconf.is_owner);
+        assert!(shmconf.owner_alive());
+        let peer = open().open_stealing_if_dead().unwrap();
+        assert!(!peer.is_owner);
+        drop(peer);
+
+        drop(shmconf);
+        let err = open().open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+    }
+
+    #[test]
+    fn stale_segment() {
+        let tmp = TempShmem::new("test-shmem-conf-stale");
+        let id = tmp.id();
+        let open = || Builder::new(id);
+
+        let start = SystemTime::now();
+        let signal = fork_and_wait(|| {
+            // the creator crashes halfway through initializing the shared memory
+            let mut val = unsafe {
+                open()
+                    .track_owner(true)
+                    .with_size(8)
+                    .open()
+                    .unwrap()
+                    .boxed::<u64>()
+            };
+            *val = 7;
+            mem::forget(val);
+        });
+        assert_eq!(signal, None);
+
+        let stale = open().track_owner(true).with_size(8).open().unwrap();
+        let creator = stale.creator_pid().unwrap();
+        assert_ne!(creator, unsafe { libc::getpid() });
+        assert!(stale.created_at().unwrap() >= start - std::time::Duration::from_secs(1));
+        drop(stale);
+
+        let err = open().detect_stale(true).with_size(8).open().unwrap_err();
+        assert!(matches!(err, ShmemError::StaleSegment { pid } if pid == creator));
+        assert_eq!(err.kind(), ShmemErrorKind::StaleSegment);
+
+        let fresh = unsafe {
+            open()
+                .reclaim_stale(true)
+                .with_size(8)
                 .open()
-                .unwrap();
+                .unwrap()
+                .boxed::<u64>()
+        };
+        assert!(fresh.conf.is_owner);
+        assert_eq!(*fresh, 0);
+        assert_eq!(fresh.conf.creator_pid(), Some(unsafe { libc::getpid() }));
 
-            // wrap the raw shared memory ptr with desired Boxed type
-            // user must ensure that the data the pointer is pointing to is initialized and valid for use
-            let mut data = unsafe { shared_mem.boxed::<Data>() };
-            data.val = new_val;
-        })
-        .join()
-        .unwrap();
-        // assert that the new process mutated the shared memory
-        assert_eq!(data.val, new_val);
+        // the owner of the fresh shared memory is alive
+        let peer = open().detect_stale(true).with_size(8).open().unwrap();
+        assert!(!peer.is_owner);
+        drop(peer);
+        drop(fresh);
+        assert!(!crate::exists(id).unwrap());
+    }
+
+    #[test]
+    fn boxed_default() {
+        let tmp = TempShmem::new("test-shmem-conf-boxed-default");
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            retries: u32,
+            name: [u8; 4],
+        }
+
+        impl Default for Config {
+            fn default() -> Self {
+                Self {
+                    retries: 3,
+                    name: *b"shm!",
+                }
+            }
+        }
+
+        let shmconf = tmp
+            .builder()
+            .with_size(mem::size_of::<Config>())
+            .open()
+            .unwrap();
+        let mut config = shmconf.boxed_default::<Config>().unwrap();
+        assert_eq!(*config, Config::default());
+        config.retries = 5;
+
+        // attaching does not overwrite the existing value
+        let shmconf = tmp
+            .builder()
+            .with_size(mem::size_of::<Config>())
+            .open()
+            .unwrap();
+        let shmconf = shmconf.boxed_default::<Config>().unwrap_err();
+        let attached = unsafe { shmconf.boxed::<Config>() };
+        assert_eq!(attached.retries, 5);
+
+        // a shared memory too small for the value is handed back as well
+        let tmp2 = TempShmem::new("test-shmem-conf-boxed-default-too-small");
+        let shmconf = tmp2.builder().with_size(4).open().unwrap();
+        let shmconf = shmconf.boxed_default::<Config>().unwrap_err();
+        assert_eq!(shmconf.size, 4);
+    }
+
+    #[test]
+    fn unpin() {
+        fn assert_unpin<T: Unpin>() {}
+
+        // the pointee lives in the shared memory, so the box is `Unpin` even if `T` is not
+        assert_unpin::<ShmemBox<std::marker::PhantomPinned>>();
+    }
+
+    #[test]
+    fn flush() {
+        let tmp = TempShmem::new("test-shmem-conf-flush");
+        let size = page_size() * 2 + 10;
+        let shmconf = tmp.builder().with_size(size).open().unwrap();
+        shmconf.flush().unwrap();
+        shmconf.flush_async().unwrap();
+
+        // unaligned ranges are widened to page boundaries internally
+        shmconf.flush_range(page_size() + 3, 20, true).unwrap();
+        shmconf.flush_range(size - 1, 1, false).unwrap();
+
+        let err = shmconf.flush_range(size - 1, 2, true).unwrap_err();
+        match err {
+            ShmemError::FlushFailed(err) => assert_eq!(err.raw_os_error(), Some(libc::EINVAL)),
+            err => panic!("unexpected error: {err}"),
+        }
+
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[0] = 1;
+        bytes.flush().unwrap();
+        bytes.flush_range(0, 1, false).unwrap();
     }
 }
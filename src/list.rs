@@ -0,0 +1,173 @@
+//! Enumerating the named shared memories by prefix, e.g. to clean up after crashed runs.
+use std::{io, time::SystemTime};
+
+use libc::mode_t;
+
+use crate::{trace, ShmemConf, ShmemError};
+
+/// A named shared memory found via `list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShmemInfo {
+    /// `flink_id` of the shared memory.
+    pub name: String,
+    /// Size of the object in bytes, including the header of `Builder::track_owner` if any.
+    pub size: usize,
+    /// Permission bits of the object, e.g. `0o600`.
+    pub mode: mode_t,
+    /// Time of the last modification of the object, i.e. of its creation or its last resize.
+    /// Writes through a mapping do not update it.
+    pub mtime: SystemTime,
+}
+
+/// Returns every shared memory whose `flink_id` starts with `prefix`, ordered by name, without
+/// opening or mapping any of them.
+///
+/// Shared memories are files in `/dev/shm` on Linux, which is read to find them, ignoring
+/// anything but regular files and names which are not valid UTF-8. Shared memories unlinked
+/// while listing are skipped.
+///
+/// Returns `ShmemError::IoError` if `/dev/shm` can not be read and `ShmemError::Unsupported`
+/// on any other platform, where shared memories can not be enumerated.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_listed_a");
+///     let shared_mem = shmem::Builder::new("flink_test_listed_a")
+///         .with_size(4)
+///         .open()?;
+///
+///     let found = shmem::list("flink_test_listed_")?;
+///     assert_eq!(found.len(), 1);
+///     assert_eq!(found[0].name, "flink_test_listed_a");
+///     assert_eq!(found[0].size, 4);
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(target_os = "linux")]
+pub fn list(prefix: &str) -> Result<Vec<ShmemInfo>, ShmemError> {
+    use std::{fs, os::unix::fs::MetadataExt};
+
+    let mut found = Vec::new();
+    for entry in fs::read_dir("/dev/shm").map_err(ShmemError::IoError)? {
+        let entry = entry.map_err(ShmemError::IoError)?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        // symlinks are not followed, they are no shared memories of ours
+        let metadata = match entry.path().symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(ShmemError::IoError(err)),
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        found.push(ShmemInfo {
+            name,
+            size: metadata.len() as usize,
+            mode: metadata.mode() as mode_t & 0o7777,
+            mtime: metadata.modified().map_err(ShmemError::IoError)?,
+        });
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(found)
+}
+
+/// Always fails with `ShmemError::Unsupported`, shared memories can only be enumerated on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn list(_prefix: &str) -> Result<Vec<ShmemInfo>, ShmemError> {
+    Err(ShmemError::Unsupported)
+}
+
+/// Unlinks every shared memory whose `flink_id` starts with `prefix` as found via `list`,
+/// e.g. for a supervisor to remove the shared memories leaked by crashed processes at startup.
+///
+/// Returns how many shared memories were unlinked. Shared memories which can not be unlinked
+/// with `io::ErrorKind::PermissionDenied`, e.g. since they belong to another user, are left
+/// alone and not counted, as are ones unlinked by somebody else in between. Mappings of the
+/// unlinked shared memories stay valid, like after `ShmemConf::unlink`.
+///
+/// Returns the errors of `list` and `ShmemError::UnlinkAllFailed` if any other shared memory
+/// could not be unlinked, carrying how many were unlinked anyway and the name and error of
+/// every failed one. Unlinking carries on past failures.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_unlink_all_a");
+///     let leaked = shmem::Builder::new("flink_test_unlink_all_a")
+///         .with_size(4)
+///         .open()?;
+///     std::mem::forget(leaked);
+///
+///     assert_eq!(shmem::unlink_all("flink_test_unlink_all_")?, 1);
+///     assert!(!shmem::exists("flink_test_unlink_all_a")?);
+///
+///     Ok(())
+/// }
+/// ```
+pub fn unlink_all(prefix: &str) -> Result<usize, ShmemError> {
+    let mut unlinked = 0;
+    let mut failed = Vec::new();
+    for info in list(prefix)? {
+        match ShmemConf::unlink(&info.name) {
+            Ok(true) => unlinked += 1,
+            Ok(false) => {}
+            Err(ShmemError::IoError(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+                trace::debug!(name = %info.name, "not permitted to unlink the shared memory");
+            }
+            Err(ShmemError::IoError(err)) => failed.push((info.name, err)),
+            Err(err) => failed.push((info.name, io::Error::other(err))),
+        }
+    }
+    if !failed.is_empty() {
+        return Err(ShmemError::UnlinkAllFailed { unlinked, failed });
+    }
+
+    Ok(unlinked)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::{exists, Builder, TempShmem};
+
+    #[test]
+    fn unlink_by_prefix() {
+        let tmp = TempShmem::new("test-shmem-list");
+        let prefix = format!("{}-", tmp.id());
+        let names: Vec<_> = (0..3).map(|i| format!("{prefix}{i}")).collect();
+        for (i, name) in names.iter().enumerate() {
+            let leaked = Builder::new(name).with_size(8 * (i + 1)).open().unwrap();
+            std::mem::forget(leaked);
+        }
+        // neither a shorter name nor one without the prefix matches
+        let other = Builder::new(tmp.id()).with_size(8).open().unwrap();
+
+        let found = list(&prefix).unwrap();
+        let found_names: Vec<_> = found.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(found_names, names);
+        for (i, info) in found.iter().enumerate() {
+            assert_eq!(info.size, 8 * (i + 1));
+            assert_eq!(info.mode, 0o600);
+            assert!(info.mtime <= SystemTime::now());
+        }
+
+        assert_eq!(unlink_all(&prefix).unwrap(), 3);
+        assert!(list(&prefix).unwrap().is_empty());
+        assert!(names.iter().all(|name| !exists(name).unwrap()));
+        assert!(exists(other.id()).unwrap());
+        assert_eq!(unlink_all(&prefix).unwrap(), 0);
+    }
+}
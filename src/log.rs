@@ -0,0 +1,355 @@
+use std::{
+    fmt, mem, ptr, slice,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use crate::{size_add, ShmemConf, ShmemError};
+
+/// Marks a header initialized by `ShmemLog::create`.
+const LOG_MAGIC: u32 = 0x5348_4c47;
+
+/// Records are padded to this alignment, which keeps every `RecordHeader` aligned.
+const RECORD_ALIGN: usize = mem::align_of::<RecordHeader>();
+
+/// Header of the log, placed at the start of the shared memory.
+#[repr(C)]
+struct LogHeader {
+    magic: AtomicU32,
+    /// Size of the record area following the header, in bytes.
+    capacity: AtomicU64,
+    /// Offset into the record area up to which space is reserved.
+    cursor: AtomicU64,
+}
+
+/// Header in front of every record, aligned so payloads of `u64`s can be read in place.
+#[repr(C, align(8))]
+struct RecordHeader {
+    len: AtomicU32,
+    /// Set once the payload is completely written. Reserved but unwritten records read as
+    /// zero, since the shared memory is zero initialized.
+    ready: AtomicU32,
+}
+
+/// An append-only log of byte records living in shared memory.
+///
+/// Producers reserve space for a record by advancing a shared cursor, then write the record and
+/// mark it ready, so any number of producers can append concurrently without locking.
+/// Consumers iterate over the records in the order their space was reserved via `read_all`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError,ShmemLog};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_log");
+///     let shared_mem = shmem::Builder::new("flink_test_log")
+///         .with_size(ShmemLog::required_size(1024)?)
+///         .open()?;
+///     let log = ShmemLog::create(shared_mem, 1024)?;
+///
+///     log.append(b"started")?;
+///     log.append(b"stopped")?;
+///
+///     let records: Vec<&[u8]> = log.read_all().collect();
+///     assert_eq!(records, [&b"started"[..], &b"stopped"[..]]);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemLog {
+    conf: ShmemConf,
+    capacity: usize,
+}
+
+// # Safety
+//
+// the header is only accessed through atomics and every record is written by the single
+// producer which reserved it, before being published through its ready flag.
+unsafe impl Sync for ShmemLog {}
+
+impl ShmemLog {
+    /// Returns the size of the shared memory required to hold a log of `capacity` bytes,
+    /// including the headers of the records.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size(capacity: usize) -> Result<usize, ShmemError> {
+        size_add(mem::size_of::<LogHeader>(), capacity)
+    }
+
+    /// Returns the number of bytes a record of `len` bytes takes up in the log.
+    pub const fn record_size(len: usize) -> usize {
+        mem::size_of::<RecordHeader>() + len.next_multiple_of(RECORD_ALIGN)
+    }
+
+    /// Initializes an empty log of `capacity` bytes in the shared memory.
+    ///
+    /// Any records previously present in the shared memory are discarded. Returns
+    /// `ShmemError::SizeMismatch` if the shared memory is smaller than
+    /// `required_size(capacity)` and `ShmemError::ProtectionViolation` unless the mapping is
+    /// writable.
+    pub fn create(conf: ShmemConf, capacity: usize) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        Self::check_size(&conf, capacity)?;
+        let log = Self { conf, capacity };
+
+        // reserved records read as not ready only if the record area is zeroed
+        unsafe { ptr::write_bytes(log.records(), 0, capacity) };
+        let header = log.header();
+        header.capacity.store(capacity as u64, Ordering::Relaxed);
+        header.cursor.store(0, Ordering::Relaxed);
+        // publish the initialized header to attaching handles
+        header.magic.store(LOG_MAGIC, Ordering::Release);
+
+        Ok(log)
+    }
+
+    /// Attaches to a log previously initialized via `create` in the shared memory.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the log is not initialized yet,
+    /// `ShmemError::SizeMismatch` if the shared memory is too small for the capacity recorded in
+    /// the header and `ShmemError::ProtectionViolation` unless the mapping is writable.
+    pub fn attach(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        Self::check_size(&conf, 0)?;
+
+        let header = unsafe { &*(conf.addr.as_ptr() as *const LogHeader) };
+        if header.magic.load(Ordering::Acquire) != LOG_MAGIC {
+            return Err(ShmemError::NotInitialized);
+        }
+        let capacity = header.capacity.load(Ordering::Relaxed) as usize;
+        Self::check_size(&conf, capacity)?;
+
+        Ok(Self { conf, capacity })
+    }
+
+    /// Returns the size of the record area in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes reserved by records so far.
+    pub fn len(&self) -> usize {
+        (self.header().cursor.load(Ordering::Relaxed) as usize).min(self.capacity)
+    }
+
+    /// Returns true if no record is appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends `record` to the log, returning its offset within the record area.
+    ///
+    /// Returns `ShmemError::LogFull` if the rest of the log is too small for the record, in
+    /// which case smaller records may still fit.
+    pub fn append(&self, record: &[u8]) -> Result<u64, ShmemError> {
+        let len = u32::try_from(record.len()).map_err(|_| ShmemError::LogFull)?;
+        let size = Self::record_size(record.len()) as u64;
+
+        let cursor = &self.header().cursor;
+        let mut offset = cursor.load(Ordering::Relaxed);
+        loop {
+            if offset + size > self.capacity as u64 {
+                return Err(ShmemError::LogFull);
+            }
+            match cursor.compare_exchange_weak(
+                offset,
+                offset + size,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => offset = current,
+            }
+        }
+
+        // # Safety
+        //
+        // the space of the record is reserved for this call only and lies within the record
+        // area
+        unsafe {
+            let header = self.record_header(offset as usize);
+            let payload = (header as *const RecordHeader).add(1) as *mut u8;
+            ptr::copy_nonoverlapping(record.as_ptr(), payload, record.len());
+            header.len.store(len, Ordering::Relaxed);
+            // publish the payload to readers
+            header.ready.store(1, Ordering::Release);
+        }
+
+        Ok(offset)
+    }
+
+    /// Returns an iterator over the appended records in the order their space was reserved.
+    ///
+    /// The iterator stops at the first record which is reserved but not written completely
+    /// yet, so it never yields a torn record. It stops as well at a record whose length does not
+    /// fit into the reserved bytes, e.g. as the shared memory was corrupted by another process.
+    pub fn read_all(&self) -> impl Iterator<Item = &[u8]> {
+        let end = self.len();
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            if end - offset.min(end) < mem::size_of::<RecordHeader>() {
+                return None;
+            }
+
+            // # Safety
+            //
+            // records are contiguous from the start of the record area up to the cursor, a
+            // ready record is never written again and its payload is checked to end before the
+            // cursor
+            unsafe {
+                let header = self.record_header(offset);
+                if header.ready.load(Ordering::Acquire) == 0 {
+                    return None;
+                }
+                let len = header.len.load(Ordering::Relaxed) as usize;
+                if len > end - offset - mem::size_of::<RecordHeader>() {
+                    return None;
+                }
+                offset += Self::record_size(len);
+                let payload = (header as *const RecordHeader).add(1) as *const u8;
+                Some(slice::from_raw_parts(payload, len))
+            }
+        })
+    }
+
+    fn check_size(conf: &ShmemConf, capacity: usize) -> Result<(), ShmemError> {
+        let required = Self::required_size(capacity)?;
+        if conf.size < required {
+            return Err(ShmemError::SizeMismatch {
+                expected: required,
+                found: conf.size,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn header(&self) -> &LogHeader {
+        unsafe { &*(self.conf.addr.as_ptr() as *const LogHeader) }
+    }
+
+    fn records(&self) -> *mut u8 {
+        unsafe { (self.conf.addr.as_ptr() as *mut u8).add(mem::size_of::<LogHeader>()) }
+    }
+
+    /// # Safety
+    ///
+    /// `offset` must be the offset of a reserved record.
+    unsafe fn record_header(&self, offset: usize) -> &RecordHeader {
+        &*(self.records().add(offset) as *const RecordHeader)
+    }
+}
+
+impl fmt::Debug for ShmemLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemLog")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    fn open_conf(id: &str, capacity: usize) -> ShmemConf {
+        Builder::new(id)
+            .with_size(ShmemLog::required_size(capacity).unwrap())
+            .open()
+            .unwrap()
+    }
+
+    #[test]
+    fn append_full() {
+        let tmp = TempShmem::new("test-shmem-log-full");
+        let log = ShmemLog::create(open_conf(tmp.id(), 40), 40).unwrap();
+        assert!(log.is_empty());
+
+        assert_eq!(log.append(b"").unwrap(), 0);
+        assert_eq!(log.append(b"abc").unwrap(), 8);
+        assert!(matches!(
+            log.append(&[0; 16]).unwrap_err(),
+            ShmemError::LogFull
+        ));
+        // a smaller record still fits
+        assert_eq!(log.append(b"defghijk").unwrap(), 24);
+        assert_eq!(log.len(), 40);
+
+        let attached = ShmemLog::attach(open_conf(tmp.id(), 40)).unwrap();
+        let records: Vec<&[u8]> = attached.read_all().collect();
+        assert_eq!(records, [&b""[..], &b"abc"[..], &b"defghijk"[..]]);
+    }
+
+    #[test]
+    fn unready_record() {
+        let tmp = TempShmem::new("test-shmem-log-unready");
+        let log = ShmemLog::create(open_conf(tmp.id(), 64), 64).unwrap();
+        log.append(b"first").unwrap();
+
+        // a producer reserved space but did not write its record yet
+        log.header()
+            .cursor
+            .fetch_add(ShmemLog::record_size(4) as u64, Ordering::Relaxed);
+        log.append(b"third").unwrap();
+
+        let records: Vec<&[u8]> = log.read_all().collect();
+        assert_eq!(records, [&b"first"[..]]);
+    }
+
+    #[test]
+    fn corrupt_record() {
+        let tmp = TempShmem::new("test-shmem-log-corrupt");
+        let log = ShmemLog::create(open_conf(tmp.id(), 64), 64).unwrap();
+        log.append(b"first").unwrap();
+        let offset = log.append(b"second").unwrap();
+
+        // another process overwrote the length of the second record
+        unsafe { log.record_header(offset as usize) }
+            .len
+            .store(u32::MAX, Ordering::Relaxed);
+        let records: Vec<&[u8]> = log.read_all().collect();
+        assert_eq!(records, [&b"first"[..]]);
+
+        // as well as the cursor, past the last record which fits
+        log.header().cursor.store(offset + 12, Ordering::Relaxed);
+        unsafe { log.record_header(offset as usize) }
+            .len
+            .store(6, Ordering::Relaxed);
+        let records: Vec<&[u8]> = log.read_all().collect();
+        assert_eq!(records, [&b"first"[..]]);
+    }
+
+    #[test]
+    fn multi_producer() {
+        let tmp = TempShmem::new("test-shmem-log-multi");
+        let producers = 4;
+        let per_producer = 100;
+        let capacity = producers * per_producer * ShmemLog::record_size(8);
+        let log = ShmemLog::create(open_conf(tmp.id(), capacity), capacity).unwrap();
+
+        std::thread::scope(|s| {
+            for p in 0..producers {
+                let log = &log;
+                s.spawn(move || {
+                    for i in 0..per_producer {
+                        log.append(&((p * per_producer + i) as u64).to_ne_bytes())
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut values: Vec<u64> = log
+            .read_all()
+            .map(|record| u64::from_ne_bytes(record.try_into().unwrap()))
+            .collect();
+        values.sort_unstable();
+        assert_eq!(
+            values,
+            (0..(producers * per_producer) as u64).collect::<Vec<_>>()
+        );
+        assert!(log.append(b"").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! NUMA placement of shared memory through the raw `mbind` and `move_pages` syscalls, so no
+//! dependency on libnuma is needed.
+use std::io;
+
+use libc::{c_int, c_long, c_ulong, c_void, syscall, SYS_mbind, SYS_move_pages, MPOL_BIND};
+
+use crate::{ShmemConf, ShmemError};
+
+const BITS_PER_WORD: usize = c_ulong::BITS as usize;
+
+/// Largest number of NUMA nodes the kernel supports, i.e. `1 << CONFIG_NODES_SHIFT` with the shift
+/// capped at 10.
+const MAX_NUMNODES: usize = 1 << 10;
+
+impl ShmemConf {
+    /// Binds the memory policy of the mapping to the NUMA `node` via `mbind` with `MPOL_BIND`,
+    /// so pages touched for the first time are allocated on that node.
+    ///
+    /// Pages that are already allocated are not migrated. Returns `ShmemError::NumaFailed`
+    /// carrying the underlying `io::Error` if NUMA is not available on the system (`ENOSYS`) or
+    /// the node does not exist (`EINVAL`), which is assumed without asking the kernel for nodes
+    /// of 1024 and above. The shared memory is fully usable regardless, so the error can be
+    /// ignored if placement is only a best-effort optimization.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_bind_numa_node");
+    ///     let shared_mem = shmem::Builder::new("flink_test_bind_numa_node")
+    ///         .with_size(4096)
+    ///         .open()?;
+    ///
+    ///     if let Err(err) = shared_mem.bind_numa_node(0) {
+    ///         eprintln!("NUMA placement is not available: {err}");
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn bind_numa_node(&self, node: u32) -> Result<(), ShmemError> {
+        let node = node as usize;
+        if node >= MAX_NUMNODES {
+            return Err(ShmemError::NumaFailed(io::Error::from_raw_os_error(
+                libc::EINVAL,
+            )));
+        }
+        let mut nodemask: Vec<c_ulong> = vec![0; node / BITS_PER_WORD + 1];
+        nodemask[node / BITS_PER_WORD] |= 1 << (node % BITS_PER_WORD);
+        // the kernel only considers the first `maxnode - 1` bits of the mask
+        let maxnode = (nodemask.len() * BITS_PER_WORD + 1) as c_ulong;
+
+        let res = unsafe {
+            syscall(
+                SYS_mbind,
+                self.addr.as_ptr() as *mut c_void,
+                self.size,
+                MPOL_BIND as c_long,
+                nodemask.as_ptr(),
+                maxnode,
+                0 as c_ulong,
+            )
+        };
+        if res != 0 {
+            return Err(ShmemError::NumaFailed(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the NUMA node the page containing `offset` currently resides on, queried via
+    /// `move_pages`.
+    ///
+    /// Returns `ShmemError::OutOfBoundsErr` if `offset` is not within the shared memory and
+    /// `ShmemError::NumaFailed` if the query fails, e.g. with `ENOENT` if the page was never
+    /// touched and is therefore not allocated on any node yet.
+    pub fn numa_node_of(&self, offset: usize) -> Result<u32, ShmemError> {
+        if offset >= self.size {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+
+        let mut page = unsafe { self.addr.as_ptr().cast::<u8>().add(offset) } as *mut c_void;
+        let mut status: c_int = 0;
+        let res = unsafe {
+            syscall(
+                SYS_move_pages,
+                0 as c_int,
+                1 as c_ulong,
+                &mut page as *mut *mut c_void,
+                std::ptr::null::<c_int>(),
+                &mut status as *mut c_int,
+                0 as c_int,
+            )
+        };
+        if res != 0 {
+            return Err(ShmemError::NumaFailed(io::Error::last_os_error()));
+        }
+        // on success, the status holds the node of the page or a negated errno
+        if status < 0 {
+            return Err(ShmemError::NumaFailed(io::Error::from_raw_os_error(
+                -status,
+            )));
+        }
+
+        Ok(status as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ShmemError, TempShmem};
+
+    #[test]
+    fn numa_node() {
+        let tmp = TempShmem::new("test-shmem-conf-numa-node");
+        let open = |node| tmp.builder().with_numa_node(node).with_size(4096).open();
+
+        // the mask is never allocated for nodes the kernel can not have
+        match open(u32::MAX) {
+            Err(ShmemError::NumaFailed(err)) => assert_eq!(err.raw_os_error(), Some(libc::EINVAL)),
+            res => panic!("unexpected result {res:?}"),
+        }
+
+        // node 0 exists on every NUMA capable system
+        let shmconf = match open(0) {
+            Ok(shmconf) => shmconf,
+            // NUMA is not available, nothing else to check
+            Err(ShmemError::NumaFailed(_)) => return,
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+        shmconf.bind_numa_node(0).unwrap();
+
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[0] = 1;
+        assert_eq!(bytes.conf.numa_node_of(0).unwrap(), 0);
+        assert!(bytes.conf.numa_node_of(4096).is_err());
+    }
+}
@@ -0,0 +1,750 @@
+use std::{
+    cell::UnsafeCell,
+    collections::BTreeMap,
+    fmt, io,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    sync::{
+        atomic::{self, AtomicU32, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use crate::{fstat, size_add, size_mul, ShmemConf, ShmemError};
+
+/// Marks a header initialized by `ShmemQueue::create`.
+const QUEUE_MAGIC: u32 = 0x5348_5155;
+
+/// Header of the queue, placed at the start of the shared memory.
+#[repr(C)]
+struct QueueHeader {
+    magic: AtomicU32,
+    /// Number of consumers parked in `pop_async`, pushes only signal the eventfd if any.
+    waiters: AtomicU32,
+    capacity: AtomicUsize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+#[repr(C)]
+struct Slot<T> {
+    /// Sequence number of the slot, see `ShmemQueue::push` and `ShmemQueue::pop`.
+    seq: AtomicUsize,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Identifies a queue by the shared memory object holding it and its offset within it.
+type NotifyKey = (libc::dev_t, libc::ino_t, usize);
+
+/// The eventfds of the queues created by the current process, for handles attaching from the
+/// same process. Kept out of the shared memory, which any process mapping it may overwrite.
+static NOTIFY_FDS: Mutex<BTreeMap<NotifyKey, i32>> = Mutex::new(BTreeMap::new());
+
+fn notify_fds() -> MutexGuard<'static, BTreeMap<NotifyKey, i32>> {
+    // every update is a single insert or remove, a panic while holding the lock leaves the map
+    // consistent
+    NOTIFY_FDS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns the key of the queue at `offset` within `conf`, `None` if `conf` is not backed by a
+/// file descriptor.
+fn notify_key(conf: &ShmemConf, offset: usize) -> Option<NotifyKey> {
+    if conf.fd < 0 {
+        return None;
+    }
+    let stat = fstat(conf.fd).ok()?;
+
+    Some((stat.st_dev, stat.st_ino, offset))
+}
+
+/// A bounded multi-producer multi-consumer queue living in shared memory.
+///
+/// The queue consists of a small header followed by `capacity` slots. Each slot carries a
+/// sequence number which makes pushing and popping lock-free without any allocation.
+///
+/// On Linux, the creator of the queue also allocates an `eventfd` which is used by `pop_async`
+/// to park consumers until an element is pushed. Since file descriptors are local to a process,
+/// notifications only work between handles attached within the creating process, each of which
+/// owns a duplicate of the eventfd, and the handles forked children inherit from it. Handles
+/// attached in any other process fall back to polling in `pop_async`.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError,ShmemQueue};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_queue");
+///     let shared_mem = shmem::Builder::new("flink_test_queue")
+///         .with_size(ShmemQueue::<u32>::required_size(8)?)
+///         .open()?;
+///     let queue = ShmemQueue::<u32>::create(shared_mem, 8)?;
+///
+///     queue.push(1).unwrap();
+///     queue.push(2).unwrap();
+///     assert_eq!(queue.pop(), Some(1));
+///     assert_eq!(queue.pop(), Some(2));
+///     assert_eq!(queue.pop(), None);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemQueue<T> {
+    conf: ShmemConf,
+    /// Offset of the queue header within the shared memory.
+    offset: usize,
+    capacity: usize,
+    /// The eventfd the queue notifies, owned by this handle.
+    notify_fd: Option<i32>,
+    /// Key of the eventfd in `NOTIFY_FDS` if this handle created it.
+    registered: Option<NotifyKey>,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// elements are moved in and out of the queue by value and every slot is accessed by a single
+// thread at a time, which is guaranteed by the sequence numbers.
+unsafe impl<T: Send> Send for ShmemQueue<T> {}
+unsafe impl<T: Send> Sync for ShmemQueue<T> {}
+
+impl<T> ShmemQueue<T> {
+    /// Returns the size of the shared memory required to hold a queue of `capacity` elements.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size(capacity: usize) -> Result<usize, ShmemError> {
+        size_add(
+            Self::slots_offset(),
+            size_mul(capacity, mem::size_of::<Slot<T>>())?,
+        )
+    }
+
+    /// Initializes an empty queue of `capacity` elements in the shared memory.
+    ///
+    /// Returns `ShmemError::NotOwner` unless the `ShmemConf` is the owner,
+    /// `ShmemError::AlreadyExists` if the shared memory already holds a queue, e.g. one created
+    /// before the shared memory was reopened, `ShmemError::SizeMismatch` if the shared memory is
+    /// smaller than `required_size(capacity)` and `ShmemError::ProtectionViolation` unless the
+    /// mapping is writable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn create(conf: ShmemConf, capacity: usize) -> Result<Self, ShmemError> {
+        Self::create_at(conf, 0, capacity, true)
+    }
+
+    /// Initializes an empty queue of `capacity` elements at `offset` within the shared memory,
+    /// allocating an eventfd for `pop_async` only if `notify` is set.
+    ///
+    /// `offset` must be a multiple of the alignment of `QueueHeader` and `Slot<T>`.
+    pub(crate) fn create_at(
+        conf: ShmemConf,
+        offset: usize,
+        capacity: usize,
+        notify: bool,
+    ) -> Result<Self, ShmemError> {
+        assert!(capacity > 0, "capacity of the queue must not be zero");
+        if !conf.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        conf.check_writable()?;
+        Self::check_size(&conf, offset, capacity)?;
+        // the elements of a live queue would be discarded without being dropped
+        let header =
+            unsafe { &*(conf.addr.as_ptr().cast::<u8>().add(offset) as *const QueueHeader) };
+        if header.magic.load(Ordering::Acquire) == QUEUE_MAGIC {
+            return Err(ShmemError::AlreadyExists(io::Error::from_raw_os_error(
+                libc::EEXIST,
+            )));
+        }
+
+        let notify_fd = if notify { new_notify_fd() } else { None };
+        let registered = notify_fd.and_then(|fd| {
+            let key = notify_key(&conf, offset)?;
+            notify_fds().insert(key, fd);
+            Some(key)
+        });
+        let queue = Self {
+            conf,
+            offset,
+            capacity,
+            notify_fd,
+            registered,
+            _marker: PhantomData,
+        };
+
+        let header = queue.header();
+        header.capacity.store(capacity, Ordering::Relaxed);
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(0, Ordering::Relaxed);
+        header.waiters.store(0, Ordering::Relaxed);
+        for i in 0..capacity {
+            queue.slot(i).seq.store(i, Ordering::Relaxed);
+        }
+        // publish the initialized header to attaching handles
+        header.magic.store(QUEUE_MAGIC, Ordering::Release);
+
+        Ok(queue)
+    }
+
+    /// Attaches to a queue previously initialized via `create` in the shared memory.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the queue is not initialized yet,
+    /// `ShmemError::SizeMismatch` if the shared memory is too small for the capacity recorded in
+    /// the header and `ShmemError::ProtectionViolation` unless the mapping is writable.
+    ///
+    /// # Safety
+    ///
+    /// The queue must have been created with the same `T`, and `T` must be valid to be moved
+    /// between processes, i.e. it must not contain pointers to process local memory.
+    pub unsafe fn attach(conf: ShmemConf) -> Result<Self, ShmemError> {
+        Self::attach_at(conf, 0)
+    }
+
+    /// Attaches to a queue previously initialized via `create_at` at `offset` within the shared
+    /// memory.
+    ///
+    /// # Safety
+    ///
+    /// Same as `attach`.
+    pub(crate) unsafe fn attach_at(conf: ShmemConf, offset: usize) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        let header_end = offset + mem::size_of::<QueueHeader>();
+        if conf.size < header_end {
+            return Err(ShmemError::SizeMismatch {
+                expected: header_end,
+                found: conf.size,
+            });
+        }
+
+        let header = &*(conf.addr.as_ptr().cast::<u8>().add(offset) as *const QueueHeader);
+        if header.magic.load(Ordering::Acquire) != QUEUE_MAGIC {
+            return Err(ShmemError::NotInitialized);
+        }
+        let capacity = header.capacity.load(Ordering::Relaxed);
+        Self::check_size(&conf, offset, capacity)?;
+
+        // the eventfd is only valid within the process that created it, and only as long as the
+        // creating handle keeps it open, which it can not close while the map is locked
+        let notify_fd = notify_key(&conf, offset).and_then(|key| {
+            let fds = notify_fds();
+            let fd = libc::fcntl(*fds.get(&key)?, libc::F_DUPFD_CLOEXEC, 0);
+            (fd >= 0).then_some(fd)
+        });
+
+        Ok(Self {
+            conf,
+            offset,
+            capacity,
+            notify_fd,
+            registered: None,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Pushes `val` to the back of the queue, handing it back if the queue is full.
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let header = self.header();
+        let mut pos = header.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = self.slot(pos % self.capacity);
+            let seq = slot.seq.load(Ordering::Acquire);
+            // the slot is free for position `pos` once its sequence number reaches `pos`
+            match (seq as isize).wrapping_sub(pos as isize) {
+                0 => match header.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*slot.val.get()).write(val) };
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        self.notify();
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                },
+                // the slot still holds the element pushed a lap ago
+                diff if diff < 0 => return Err(val),
+                _ => pos = header.tail.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Pops the element at the front of the queue, if any.
+    pub fn pop(&self) -> Option<T> {
+        let header = self.header();
+        let mut pos = header.head.load(Ordering::Relaxed);
+        loop {
+            let slot = self.slot(pos % self.capacity);
+            let seq = slot.seq.load(Ordering::Acquire);
+            // the slot holds the element for position `pos` once its sequence number reaches
+            // `pos + 1`
+            match (seq as isize).wrapping_sub(pos.wrapping_add(1) as isize) {
+                0 => match header.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let val = unsafe { (*slot.val.get()).assume_init_read() };
+                        // free the slot for the push one lap ahead
+                        slot.seq
+                            .store(pos.wrapping_add(self.capacity), Ordering::Release);
+                        return Some(val);
+                    }
+                    Err(current) => pos = current,
+                },
+                // the slot is not pushed yet
+                diff if diff < 0 => return None,
+                _ => pos = header.head.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Returns the number of elements in the queue.
+    ///
+    /// The value may be outdated by the time it is returned if other handles are pushing or
+    /// popping concurrently.
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let tail = header.tail.load(Ordering::Acquire);
+        let head = header.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.capacity)
+    }
+
+    /// Returns true if the queue holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of elements the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Pops the element at the front of the queue, waiting for one to be pushed if the queue is
+    /// empty.
+    ///
+    /// While the queue is empty, the current task is parked on the queue's `eventfd` through
+    /// tokio's `AsyncFd` and is woken up by the next `push`. If this handle has no access to the
+    /// `eventfd`, e.g. because it was attached from an unrelated process, the task falls back to
+    /// yielding to the runtime between attempts.
+    ///
+    /// Only available on Linux with the `tokio` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError,ShmemQueue};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_queue_pop_async");
+    ///     let shared_mem = shmem::Builder::new("flink_test_queue_pop_async")
+    ///         .with_size(ShmemQueue::<u32>::required_size(8)?)
+    ///         .open()?;
+    ///     let queue = ShmemQueue::<u32>::create(shared_mem, 8)?;
+    ///
+    ///     queue.push(1).unwrap();
+    ///     assert_eq!(queue.pop_async().await, 1);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(all(feature = "tokio", target_os = "linux"))]
+    pub async fn pop_async(&self) -> T {
+        use std::os::fd::{FromRawFd, OwnedFd};
+        use tokio::io::unix::AsyncFd;
+
+        if let Some(val) = self.pop() {
+            return val;
+        }
+
+        // each call registers its own duplicate of the eventfd, so concurrent consumers do not
+        // collide in the reactor
+        let notifier = self
+            .notify_fd
+            .map(|fd| unsafe { libc::dup(fd) })
+            .filter(|fd| *fd >= 0)
+            .and_then(|fd| AsyncFd::new(unsafe { OwnedFd::from_raw_fd(fd) }).ok());
+        let Some(notifier) = notifier else {
+            loop {
+                if let Some(val) = self.pop() {
+                    return val;
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+
+        let _waiter = Waiter::register(self.header());
+        loop {
+            if let Some(val) = self.pop() {
+                // other consumers may be parked as well, pass the wake up along
+                if !self.is_empty() {
+                    self.notify();
+                }
+                return val;
+            }
+
+            if let Ok(mut guard) = notifier.readable().await {
+                drain_notify_fd(guard.get_inner());
+                guard.clear_ready();
+            }
+        }
+    }
+
+    /// Returns the shared memory the queue lives in.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn conf(&self) -> &ShmemConf {
+        &self.conf
+    }
+
+    fn check_size(conf: &ShmemConf, offset: usize, capacity: usize) -> Result<(), ShmemError> {
+        let required = size_add(Self::required_size(capacity)?, offset)?;
+        if conf.size < required {
+            return Err(ShmemError::SizeMismatch {
+                expected: required,
+                found: conf.size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the alignment required for the offset of a queue within the shared memory.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn align() -> usize {
+        mem::align_of::<QueueHeader>().max(mem::align_of::<Slot<T>>())
+    }
+
+    fn slots_offset() -> usize {
+        let align = mem::align_of::<Slot<T>>();
+        mem::size_of::<QueueHeader>().div_ceil(align) * align
+    }
+
+    fn header(&self) -> &QueueHeader {
+        unsafe { &*(self.base() as *const QueueHeader) }
+    }
+
+    fn base(&self) -> *const u8 {
+        unsafe { (self.conf.addr.as_ptr() as *const u8).add(self.offset) }
+    }
+
+    fn slot(&self, index: usize) -> &Slot<T> {
+        debug_assert!(index < self.capacity);
+        unsafe {
+            &*self
+                .base()
+                .add(Self::slots_offset())
+                .cast::<Slot<T>>()
+                .add(index)
+        }
+    }
+
+    /// Wakes up the consumers parked in `pop_async`, if any.
+    fn notify(&self) {
+        let Some(fd) = self.notify_fd else {
+            return;
+        };
+
+        // pairs with the fence in `Waiter::register`, either the consumer sees the pushed
+        // element or we see the consumer waiting
+        atomic::fence(Ordering::SeqCst);
+        if self.header().waiters.load(Ordering::Relaxed) > 0 {
+            let val: u64 = 1;
+            unsafe { libc::write(fd, &val as *const u64 as *const libc::c_void, 8) };
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShmemQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemQueue")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for ShmemQueue<T> {
+    fn drop(&mut self) {
+        if self.conf.is_owner {
+            // the owner is responsible for dropping the elements left in the queue, the same
+            // way `ShmemBox` drops its value
+            while self.pop().is_some() {}
+        }
+        if let Some(key) = self.registered {
+            let mut fds = notify_fds();
+            // a queue created later over the same shared memory may have replaced the entry
+            if fds.get(&key) == self.notify_fd.as_ref() {
+                fds.remove(&key);
+            }
+        }
+        if let Some(fd) = self.notify_fd {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// Registers a consumer parked in `pop_async` for the lifetime of the value, so the registration
+/// is undone even if the future is cancelled.
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+struct Waiter<'a> {
+    header: &'a QueueHeader,
+}
+
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+impl<'a> Waiter<'a> {
+    fn register(header: &'a QueueHeader) -> Self {
+        header.waiters.fetch_add(1, Ordering::Relaxed);
+        // pairs with the fence in `ShmemQueue::notify`
+        atomic::fence(Ordering::SeqCst);
+        Self { header }
+    }
+}
+
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        self.header.waiters.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn new_notify_fd() -> Option<i32> {
+    let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+    (fd >= 0).then_some(fd)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn new_notify_fd() -> Option<i32> {
+    None
+}
+
+/// Resets the counter of a non-blocking eventfd.
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+fn drain_notify_fd(fd: &impl std::os::fd::AsRawFd) {
+    let mut val: u64 = 0;
+    unsafe { libc::read(fd.as_raw_fd(), &mut val as *mut u64 as *mut libc::c_void, 8) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, RequestedSize, TempShmem};
+
+    fn open_queue(id: &str, capacity: usize) -> ShmemQueue<u64> {
+        let shmconf = Builder::new(id)
+            .with_size(ShmemQueue::<u64>::required_size(capacity).unwrap())
+            .open()
+            .unwrap();
+        ShmemQueue::create(shmconf, capacity).unwrap()
+    }
+
+    #[test]
+    fn push_pop() {
+        let tmp = TempShmem::new("test-shmem-queue-push-pop");
+        let queue = open_queue(tmp.id(), 4);
+        assert!(queue.is_empty());
+
+        for lap in 0..3 {
+            for i in 0..4 {
+                queue.push(lap * 4 + i).unwrap();
+            }
+            // the queue is full
+            assert_eq!(queue.push(100), Err(100));
+            assert_eq!(queue.len(), 4);
+
+            for i in 0..4 {
+                assert_eq!(queue.pop(), Some(lap * 4 + i));
+            }
+            assert_eq!(queue.pop(), None);
+        }
+    }
+
+    #[test]
+    fn attach() {
+        let tmp = TempShmem::new("test-shmem-queue-attach");
+        let queue = open_queue(tmp.id(), 4);
+        queue.push(1).unwrap();
+
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<u64>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let attached = unsafe { ShmemQueue::<u64>::attach(shmconf) }.unwrap();
+        assert_eq!(attached.capacity(), 4);
+        assert_eq!(attached.pop(), Some(1));
+
+        attached.push(2).unwrap();
+        assert_eq!(queue.pop(), Some(2));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn attach_notify_fd() {
+        let tmp = TempShmem::new("test-shmem-queue-attach-notify-fd");
+        let queue = open_queue(tmp.id(), 4);
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<u64>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let attached = unsafe { ShmemQueue::<u64>::attach(shmconf) }.unwrap();
+        let fd = attached.notify_fd.unwrap();
+        assert_ne!(Some(fd), queue.notify_fd);
+
+        // the attached handle keeps its duplicate, nobody attaches to the closed eventfd
+        drop(queue);
+        assert!(unsafe { libc::fcntl(fd, libc::F_GETFD) } >= 0);
+        attached.push(1).unwrap();
+        attached.notify();
+        let late = unsafe { ShmemQueue::<u64>::attach(attached.conf.clone_handle().unwrap()) };
+        assert_eq!(late.unwrap().notify_fd, None);
+    }
+
+    #[test]
+    fn attach_uninitialized() {
+        let tmp = TempShmem::new("test-shmem-queue-attach-uninitialized");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<u64>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let err = unsafe { ShmemQueue::<u64>::attach(shmconf) }.unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+    }
+
+    #[test]
+    fn create_not_owner_or_twice() {
+        let tmp = TempShmem::new("test-shmem-queue-create-not-owner-or-twice");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<u64>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let mut handle = shmconf.clone_handle().unwrap();
+        let err = ShmemQueue::<u64>::create(handle.clone_handle().unwrap(), 4).unwrap_err();
+        assert!(matches!(err, ShmemError::NotOwner));
+        let mut queue = ShmemQueue::create(shmconf, 4).unwrap();
+        queue.push(1).unwrap();
+
+        // the new owner must not discard the elements of the live queue
+        ShmemConf::transfer_ownership(&mut queue.conf, &mut handle).unwrap();
+        let err = ShmemQueue::<u64>::create(handle, 4).unwrap_err();
+        assert!(matches!(err, ShmemError::AlreadyExists(_)));
+        assert_eq!(queue.pop(), Some(1));
+    }
+
+    #[test]
+    fn too_small() {
+        let tmp = TempShmem::new("test-shmem-queue-too-small");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<u64>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let err = ShmemQueue::<u64>::create(shmconf, 5).unwrap_err();
+        assert!(matches!(err, ShmemError::SizeMismatch { .. }));
+    }
+
+    #[test]
+    fn required_size_overflow() {
+        let err = ShmemQueue::<u64>::required_size(usize::MAX).unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Overflow(size)) if size > usize::MAX as u128
+        ));
+        let err = ShmemQueue::<[u8; 1 << 60]>::required_size(16).unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidSize(_)));
+    }
+
+    #[test]
+    fn multi_producer_multi_consumer() {
+        let tmp = TempShmem::new("test-shmem-queue-mpmc");
+        const THREADS: u64 = 4;
+        const ITEMS: u64 = 10_000;
+
+        let queue = open_queue(tmp.id(), 16);
+        let sum = std::sync::atomic::AtomicU64::new(0);
+        let popped = std::sync::atomic::AtomicU64::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for i in 1..=ITEMS {
+                        let mut val = i;
+                        while let Err(v) = queue.push(val) {
+                            val = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+                s.spawn(|| {
+                    while popped.load(Ordering::Relaxed) < THREADS * ITEMS {
+                        match queue.pop() {
+                            Some(val) => {
+                                sum.fetch_add(val, Ordering::Relaxed);
+                                popped.fetch_add(1, Ordering::Relaxed);
+                            }
+                            None => std::thread::yield_now(),
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(sum.into_inner(), THREADS * ITEMS * (ITEMS + 1) / 2);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn owner_drops_remaining() {
+        let tmp = TempShmem::new("test-shmem-queue-owner-drops-remaining");
+        use std::sync::Arc;
+
+        let counter = Arc::new(());
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemQueue::<Arc<()>>::required_size(4).unwrap())
+            .open()
+            .unwrap();
+        let queue = ShmemQueue::create(shmconf, 4).unwrap();
+        queue.push(counter.clone()).unwrap();
+        queue.push(counter.clone()).unwrap();
+        assert_eq!(Arc::strong_count(&counter), 3);
+
+        drop(queue);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[cfg(all(feature = "tokio", target_os = "linux"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pop_async() {
+        let tmp = TempShmem::new("test-shmem-queue-pop-async");
+        use std::sync::Arc;
+
+        let queue = Arc::new(open_queue(tmp.id(), 4));
+
+        let consumer = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let mut vals = Vec::new();
+                for _ in 0..3 {
+                    vals.push(queue.pop_async().await);
+                }
+                vals
+            }
+        });
+
+        for i in 0..3 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            queue.push(i).unwrap();
+        }
+
+        assert_eq!(consumer.await.unwrap(), vec![0, 1, 2]);
+    }
+}
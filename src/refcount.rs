@@ -0,0 +1,196 @@
+//! Shared memories unlinked by whichever handle detaches last, see `Builder::refcounted`.
+#[cfg(target_os = "linux")]
+use std::fs;
+
+#[cfg(target_os = "linux")]
+use crate::backend::{Platform, ShmBackend};
+#[cfg(target_os = "linux")]
+use crate::{fstat, storage_id, ShmemError};
+use crate::{Builder, CleanupPolicy, ShmemConf};
+
+impl Builder {
+    /// Counts the handles attached to the shared memory in its header and unlinks it once the
+    /// last one is dropped, whichever process it belongs to.
+    ///
+    /// This lets the creator exit before its consumers while new consumers still find the shared
+    /// memory by name. It is a shorthand for `cleanup_policy` with
+    /// `CleanupPolicy::UnlinkWhenLastDetaches`, or `CleanupPolicy::OwnerUnlinks` if `refcounted`
+    /// is false. Every process has to enable the option alike, since it enables `track_owner`.
+    ///
+    /// A process crashing while attached never decrements the count, so the shared memory is
+    /// never unlinked. Use `ShmemConf::unlink_if_stale` to clean it up.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_refcounted");
+    ///     let open = || {
+    ///         shmem::Builder::new("flink_test_refcounted")
+    ///             .refcounted(true)
+    ///             .with_size(4)
+    ///             .open()
+    ///     };
+    ///     let creator = open()?;
+    ///     let consumer = open()?;
+    ///     assert_eq!(consumer.attach_count(), Some(2));
+    ///
+    ///     drop(creator);
+    ///     assert!(shmem::exists("flink_test_refcounted")?);
+    ///     drop(consumer);
+    ///     assert!(!shmem::exists("flink_test_refcounted")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn refcounted(self, refcounted: bool) -> Self {
+        match refcounted {
+            true => self.cleanup_policy(CleanupPolicy::UnlinkWhenLastDetaches),
+            false => self.cleanup_policy(CleanupPolicy::OwnerUnlinks),
+        }
+    }
+}
+
+impl ShmemConf {
+    /// Returns the number of handles attached to the shared memory, across all processes, or
+    /// `None` unless it was opened with `Builder::track_owner` or `Builder::refcounted`.
+    ///
+    /// Handles mapping the shared memory read-only or copy-on-write are not counted. The count
+    /// includes handles of crashed processes, which never detached.
+    pub fn attach_count(&self) -> Option<u32> {
+        self.header().map(|header| header.attached())
+    }
+
+    /// Unlinks the shared memory named `id` if no running process maps it anymore, e.g. because
+    /// every process attached to a shared memory opened with `Builder::refcounted` crashed
+    /// before detaching.
+    ///
+    /// The attach count is not trusted, instead the mappings of every process in `/proc` are
+    /// searched for the shared memory object. Processes whose mappings are not readable, e.g.
+    /// since they belong to another user, are not considered. A process opening the shared
+    /// memory between the check and the unlink keeps its mapping, but the name is gone.
+    ///
+    /// Returns true if the shared memory was unlinked, false if it is still mapped or no
+    /// shared memory named `id` exists and `ShmemError::IoError` if it can not be opened or
+    /// `/proc` can not be read.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_unlink_if_stale");
+    ///     let shared_mem = shmem::Builder::new("flink_test_unlink_if_stale")
+    ///         .refcounted(true)
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     assert!(!ShmemConf::unlink_if_stale("flink_test_unlink_if_stale")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn unlink_if_stale(id: &str) -> Result<bool, ShmemError> {
+        let fd = match Platform::open(&storage_id(id)?) {
+            Ok(fd) => fd,
+            Err(ShmemError::NotFound(_)) => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let stat = fstat(fd);
+        unsafe { libc::close(fd) };
+        if mapped_anywhere(stat?.st_ino).map_err(ShmemError::IoError)? {
+            return Ok(false);
+        }
+
+        ShmemConf::unlink(id)
+    }
+}
+
+/// Returns whether any process with readable mappings maps the shared memory object `ino`.
+#[cfg(target_os = "linux")]
+fn mapped_anywhere(ino: libc::ino_t) -> std::io::Result<bool> {
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        // the process may have exited or forbid reading its mappings
+        let Ok(maps) = fs::read_to_string(entry.path().join("maps")) else {
+            continue;
+        };
+        // every line looks like `7f0000000000-7f0000001000 rw-s 00000000 00:19 42 /dev/shm/id`
+        let maps_object = maps.lines().any(|line| {
+            let mut fields = line.split_whitespace().skip(4);
+            let inode = fields
+                .next()
+                .and_then(|inode| inode.parse::<libc::ino_t>().ok());
+            inode == Some(ino)
+                && fields
+                    .next()
+                    .is_some_and(|path| path.starts_with("/dev/shm/"))
+        });
+        if maps_object {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn crashed_attachment() {
+        let tmp = TempShmem::new("test-shmem-refcount-crashed");
+        let id = tmp.id();
+        let open = || {
+            Builder::new(id)
+                .refcounted(true)
+                .with_size(8)
+                .open()
+                .unwrap()
+        };
+        let creator = open();
+        assert_eq!(
+            creator.cleanup_policy(),
+            CleanupPolicy::UnlinkWhenLastDetaches
+        );
+
+        // the child attaches and exits without detaching
+        match unsafe { libc::fork() } {
+            0 => {
+                std::mem::forget(open());
+                unsafe { libc::_exit(0) };
+            }
+            pid => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            }
+        }
+        assert_eq!(creator.attach_count(), Some(2));
+        assert!(!ShmemConf::unlink_if_stale(id).unwrap());
+
+        // the stale count keeps the shared memory around
+        drop(creator);
+        assert!(crate::exists(id).unwrap());
+        assert!(ShmemConf::unlink_if_stale(id).unwrap());
+        assert!(!crate::exists(id).unwrap());
+        assert!(!ShmemConf::unlink_if_stale(id).unwrap());
+
+        let conf = Builder::new(id)
+            .refcounted(false)
+            .with_size(8)
+            .open()
+            .unwrap();
+        assert_eq!(conf.cleanup_policy(), CleanupPolicy::OwnerUnlinks);
+        assert_eq!(conf.attach_count(), None);
+    }
+}
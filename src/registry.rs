@@ -0,0 +1,160 @@
+//! Process-global registry behind `BuilderWithSize::open_shared`.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    mem::ManuallyDrop,
+    ops::Deref,
+    sync::{Arc, LazyLock, Mutex, MutexGuard, Weak},
+};
+
+use crate::{validate_size, BuilderWithSize, ShmemConf, ShmemError};
+
+/// Mappings opened via `open_shared` in the current process, keyed by `flink_id`.
+static REGISTRY: LazyLock<Mutex<HashMap<String, Weak<ShmemConf>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn registry() -> MutexGuard<'static, HashMap<String, Weak<ShmemConf>>> {
+    // the registry only holds weak references, a panic while holding the lock leaves it
+    // consistent
+    REGISTRY.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A reference-counted handle to a shared memory mapped once per process, returned by
+/// `BuilderWithSize::open_shared`.
+///
+/// Cloning the handle is cheap. The shared memory is unmapped, and unlinked if the mapping is
+/// its owner, once the last handle in the process is dropped.
+///
+/// Dereferences to the `ShmemConf` of the mapping, which stays owned by the registry, so it can
+/// not be converted via `boxed`.
+#[derive(Clone)]
+pub struct SharedShmem {
+    conf: ManuallyDrop<Arc<ShmemConf>>,
+}
+
+impl BuilderWithSize {
+    /// Same as `open`, but returns a handle to the mapping already opened via `open_shared` in
+    /// the current process under the same `flink_id`, if any.
+    ///
+    /// Independent parts of a program, e.g. libraries built on top of this crate, thereby share
+    /// a single file descriptor and mapping instead of each mapping the shared memory and
+    /// possibly unlinking it from under the others. The options of the first `open_shared`
+    /// apply, the options of later calls are ignored. Mappings opened via `open` are
+    /// independent of the registry.
+    ///
+    /// Returns `ShmemError::SizeMismatch` if the existing mapping is smaller than `size`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_open_shared");
+    ///     let first = shmem::Builder::new("flink_test_open_shared")
+    ///         .with_size(16)
+    ///         .open_shared()?;
+    ///     let second = shmem::Builder::new("flink_test_open_shared")
+    ///         .with_size(8)
+    ///         .open_shared()?;
+    ///
+    ///     // both handles refer to the same mapping
+    ///     assert_eq!(first.as_ptr(), second.as_ptr());
+    ///
+    ///     // the shared memory is cleaned up once both handles are dropped
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_shared(self) -> Result<SharedShmem, ShmemError> {
+        let size = validate_size(self.size, &self.options)?;
+        let mut registry = registry();
+        if let Some(conf) = registry.get(&self.id).and_then(Weak::upgrade) {
+            if conf.size() < size {
+                return Err(ShmemError::SizeMismatch {
+                    expected: size,
+                    found: conf.size(),
+                });
+            }
+
+            return Ok(SharedShmem {
+                conf: ManuallyDrop::new(conf),
+            });
+        }
+
+        let id = self.id.clone();
+        let conf = Arc::new(self.open()?);
+        registry.insert(id, Arc::downgrade(&conf));
+
+        Ok(SharedShmem {
+            conf: ManuallyDrop::new(conf),
+        })
+    }
+}
+
+impl Deref for SharedShmem {
+    type Target = ShmemConf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conf
+    }
+}
+
+impl Drop for SharedShmem {
+    fn drop(&mut self) {
+        // handles are only handed out while holding the lock, so the count can not grow before
+        // the last handle unregisters the mapping
+        let mut registry = registry();
+        let conf = unsafe { ManuallyDrop::take(&mut self.conf) };
+        if Arc::strong_count(&conf) == 1 {
+            registry.remove(&conf.id);
+        }
+        drop(conf);
+    }
+}
+
+impl fmt::Debug for SharedShmem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedShmem")
+            .field("id", &self.conf.id)
+            .field("handles", &Arc::strong_count(&self.conf))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Builder, TempShmem};
+
+    #[test]
+    fn open_shared() {
+        let tmp = TempShmem::new("test-shmem-registry-open-shared");
+        let id = tmp.id();
+        let open = |size| Builder::new(id).with_size(size).open_shared();
+        let first = open(16).unwrap();
+        let second = open(8).unwrap();
+        let third = second.clone();
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(first.fd, third.fd);
+        assert!(first.is_owner);
+
+        let err = open(32).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::ShmemError::SizeMismatch {
+                expected: 32,
+                found: 16
+            }
+        ));
+
+        // the mapping outlives the handle that created it
+        drop(first);
+        drop(second);
+        Builder::new(id).with_size(16).open_existing().unwrap();
+
+        // the last handle unlinks the shared memory
+        drop(third);
+        assert!(Builder::new(id).with_size(16).open_existing().is_err());
+        let reopened = open(32).unwrap();
+        assert_eq!(reopened.size(), 32);
+    }
+}
@@ -0,0 +1,688 @@
+use std::{
+    cell::UnsafeCell,
+    error::Error,
+    fmt, io,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{size_add, ShmemConf, ShmemError};
+
+/// Marks a lock initialized by `ShmemRwLock::create`.
+const RWLOCK_MAGIC: u32 = 0x5348_5257;
+
+/// Longest sleep between two attempts of `try_read_timeout` and `try_write_timeout`.
+const MAX_BACKOFF: Duration = Duration::from_millis(1);
+
+/// `PTHREAD_PROCESS_SHARED`, which the libc crate does not export for Android.
+#[cfg(not(target_os = "android"))]
+const PROCESS_SHARED: libc::c_int = libc::PTHREAD_PROCESS_SHARED;
+#[cfg(target_os = "android")]
+const PROCESS_SHARED: libc::c_int = 1;
+
+/// `PTHREAD_RWLOCK_PREFER_WRITER_NONRECURSIVE_NP` of glibc, which the libc crate does not
+/// export.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const PREFER_WRITER: libc::c_int = 2;
+
+/// Header of the lock, placed at the start of the shared memory and followed by the value.
+#[repr(C)]
+struct RwLockHeader {
+    magic: AtomicU32,
+    /// Set once a writer panics while holding the lock, see `ShmemRwLock::is_poisoned`.
+    poisoned: AtomicBool,
+    lock: UnsafeCell<libc::pthread_rwlock_t>,
+}
+
+/// Result of acquiring a `ShmemRwLock`, which fails with the guard wrapped in a
+/// `ShmemPoisonError` if the lock is poisoned.
+pub type ShmemLockResult<G> = Result<G, ShmemPoisonError<G>>;
+
+/// A reader-writer lock living in shared memory, allowing many readers or a single writer to
+/// access a `T` at a time across processes.
+///
+/// The lock is a `pthread_rwlock_t` initialized with `PTHREAD_PROCESS_SHARED` by `create`,
+/// which other handles attach to via `attach`. Acquiring and releasing the lock are full
+/// barriers, so everything written under the write lock is visible to every later reader,
+/// whichever process it belongs to.
+///
+/// On Linux with glibc, a waiting writer blocks new readers, so a steady stream of readers can
+/// not starve it. A thread acquiring the read lock again while still holding it may therefore
+/// deadlock if a writer is waiting in between.
+///
+/// Unlike robust mutexes, pthread rwlocks can not detect that their holder died. If a process
+/// dies while holding the lock, the lock stays held forever and every other process blocks in
+/// `read` or `write`. Processes which may crash while holding the lock should use
+/// `try_read_timeout` and `try_write_timeout` instead.
+///
+/// # Poisoning
+///
+/// Like `std::sync::RwLock`, the lock is poisoned once a thread panics while holding the write
+/// lock, since the `T` may be left half updated. The flag lives in the shared memory, so a
+/// panic in one process poisons the lock for every process. Acquiring a poisoned lock fails
+/// with a `ShmemPoisonError`, which still grants access to the `T` via `into_inner`. A process
+/// dying while holding the lock does not poison it.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError,ShmemRwLock};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_rwlock");
+///     let shared_mem = shmem::Builder::new("flink_test_rwlock")
+///         .with_size(ShmemRwLock::<u64>::required_size()?)
+///         .open()?;
+///     let lock = ShmemRwLock::create(shared_mem, 0_u64)?;
+///
+///     *lock.write().unwrap() += 1;
+///     let first = lock.read().unwrap();
+///     let second = lock.read().unwrap();
+///     assert_eq!((*first, *second), (1, 1));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemRwLock<T> {
+    conf: ShmemConf,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// readers only get shared references to `T` and writers are exclusive, the same requirements as
+// `std::sync::RwLock` apply.
+unsafe impl<T: Send> Send for ShmemRwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for ShmemRwLock<T> {}
+
+impl<T> ShmemRwLock<T> {
+    /// Returns the size of the shared memory required to hold the lock and a `T`.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size() -> Result<usize, ShmemError> {
+        size_add(data_offset::<T>(), mem::size_of::<T>())
+    }
+
+    /// Initializes the lock in the shared memory, holding `val`.
+    ///
+    /// Only the owner may create the lock, before any other handle attaches to it, since
+    /// initializing a lock other handles already use is undefined behavior.
+    ///
+    /// Returns `ShmemError::NotOwner` unless the `ShmemConf` is the owner,
+    /// `ShmemError::AlreadyExists` if the shared memory already holds a lock, e.g. one created
+    /// before the shared memory was reopened, `ShmemError::SizeMismatch` if the shared memory is
+    /// smaller than `required_size()`, `ShmemError::ProtectionViolation` unless the mapping is
+    /// writable and `ShmemError::IoError` if the lock can not be initialized.
+    pub fn create(conf: ShmemConf, val: T) -> Result<Self, ShmemError> {
+        if !conf.is_owner {
+            return Err(ShmemError::NotOwner);
+        }
+        conf.check_writable()?;
+        Self::check_size(&conf)?;
+        // the value is only dropped by the `ShmemRwLock` once it is initialized
+        let header = unsafe { header(&conf) };
+        if header.magic.load(Ordering::Acquire) == RWLOCK_MAGIC {
+            return Err(ShmemError::AlreadyExists(io::Error::from_raw_os_error(
+                libc::EEXIST,
+            )));
+        }
+        // attaching handles must not use a half initialized lock
+        header.magic.store(0, Ordering::Relaxed);
+        header.poisoned.store(false, Ordering::Relaxed);
+        unsafe {
+            let mut attr = mem::MaybeUninit::<libc::pthread_rwlockattr_t>::uninit();
+            check(libc::pthread_rwlockattr_init(attr.as_mut_ptr()))?;
+            let ret = libc::pthread_rwlockattr_setpshared(attr.as_mut_ptr(), PROCESS_SHARED);
+            // glibc prefers readers by default, which starves the writer of read heavy locks
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            let ret = match ret {
+                0 => libc::pthread_rwlockattr_setkind_np(attr.as_mut_ptr(), PREFER_WRITER),
+                err => err,
+            };
+            let ret = match ret {
+                0 => libc::pthread_rwlock_init(header.lock.get(), attr.as_ptr()),
+                err => err,
+            };
+            libc::pthread_rwlockattr_destroy(attr.as_mut_ptr());
+            check(ret)?;
+
+            ptr::write(data(&conf), val);
+        }
+        // publish the initialized lock and value to attaching handles
+        header.magic.store(RWLOCK_MAGIC, Ordering::Release);
+
+        Ok(Self {
+            conf,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Attaches to a lock previously initialized via `create` in the shared memory.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the lock is not initialized yet,
+    /// `ShmemError::SizeMismatch` if the shared memory is smaller than `required_size()` and
+    /// `ShmemError::ProtectionViolation` unless the mapping is writable.
+    ///
+    /// # Safety
+    ///
+    /// The lock must have been created with the same `T`, and `T` must be valid to be shared
+    /// between processes, i.e. it must not contain pointers to process local memory.
+    pub unsafe fn attach(conf: ShmemConf) -> Result<Self, ShmemError> {
+        conf.check_writable()?;
+        Self::check_size(&conf)?;
+        if header(&conf).magic.load(Ordering::Acquire) != RWLOCK_MAGIC {
+            return Err(ShmemError::NotInitialized);
+        }
+
+        Ok(Self {
+            conf,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Acquires the lock for reading, blocking until no writer holds it.
+    ///
+    /// Returns a `ShmemPoisonError` holding the guard if the lock is poisoned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current thread holds the write lock or the maximum number of readers is
+    /// reached.
+    pub fn read(&self) -> ShmemLockResult<ShmemRwLockReadGuard<'_, T>> {
+        let ret = unsafe { libc::pthread_rwlock_rdlock(self.header().lock.get()) };
+        assert_eq!(ret, 0, "failed to acquire the read lock: {}", errno(ret));
+
+        self.check_poison(ShmemRwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Acquires the lock for writing, blocking until no reader or writer holds it.
+    ///
+    /// Returns a `ShmemPoisonError` holding the guard if the lock is poisoned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current thread already holds the lock.
+    pub fn write(&self) -> ShmemLockResult<ShmemRwLockWriteGuard<'_, T>> {
+        let ret = unsafe { libc::pthread_rwlock_wrlock(self.header().lock.get()) };
+        assert_eq!(ret, 0, "failed to acquire the write lock: {}", errno(ret));
+
+        self.check_poison(ShmemRwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Tries to acquire the lock for reading without blocking, returns `None` if a writer holds
+    /// it.
+    pub fn try_read(&self) -> Option<ShmemLockResult<ShmemRwLockReadGuard<'_, T>>> {
+        let ret = unsafe { libc::pthread_rwlock_tryrdlock(self.header().lock.get()) };
+        (ret == 0).then(|| {
+            self.check_poison(ShmemRwLockReadGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        })
+    }
+
+    /// Tries to acquire the lock for writing without blocking, returns `None` if a reader or
+    /// writer holds it.
+    pub fn try_write(&self) -> Option<ShmemLockResult<ShmemRwLockWriteGuard<'_, T>>> {
+        let ret = unsafe { libc::pthread_rwlock_trywrlock(self.header().lock.get()) };
+        (ret == 0).then(|| {
+            self.check_poison(ShmemRwLockWriteGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        })
+    }
+
+    /// Tries to acquire the lock for reading until `timeout` elapses.
+    ///
+    /// Returns `ShmemError::LockTimeout` if the lock could not be acquired in time, e.g.
+    /// because a writer died while holding it, and `ShmemError::Poisoned` if the lock is
+    /// poisoned, see `clear_poison`.
+    pub fn try_read_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<ShmemRwLockReadGuard<'_, T>, ShmemError> {
+        retry_until(timeout, || self.try_read())
+    }
+
+    /// Tries to acquire the lock for writing until `timeout` elapses.
+    ///
+    /// Returns `ShmemError::LockTimeout` if the lock could not be acquired in time, e.g.
+    /// because a reader or writer died while holding it, and `ShmemError::Poisoned` if the lock
+    /// is poisoned, see `clear_poison`.
+    pub fn try_write_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<ShmemRwLockWriteGuard<'_, T>, ShmemError> {
+        retry_until(timeout, || self.try_write())
+    }
+
+    /// Returns true if a thread of any process panicked while holding the write lock.
+    pub fn is_poisoned(&self) -> bool {
+        self.header().poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Clears the poisoned state of the lock, e.g. after restoring the `T` to a consistent
+    /// state through the guard of a `ShmemPoisonError`.
+    pub fn clear_poison(&self) {
+        self.header().poisoned.store(false, Ordering::Relaxed);
+    }
+
+    fn check_poison<G>(&self, guard: G) -> ShmemLockResult<G> {
+        match self.is_poisoned() {
+            true => Err(ShmemPoisonError { guard }),
+            false => Ok(guard),
+        }
+    }
+
+    fn check_size(conf: &ShmemConf) -> Result<(), ShmemError> {
+        let required = Self::required_size()?;
+        if conf.size < required {
+            return Err(ShmemError::SizeMismatch {
+                expected: required,
+                found: conf.size,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn header(&self) -> &RwLockHeader {
+        unsafe { header(&self.conf) }
+    }
+
+    fn data(&self) -> *mut T {
+        data(&self.conf)
+    }
+
+    fn unlock(&self) {
+        unsafe { libc::pthread_rwlock_unlock(self.header().lock.get()) };
+    }
+}
+
+impl<T> fmt::Debug for ShmemRwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemRwLock")
+            .field("conf", &self.conf)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for ShmemRwLock<T> {
+    fn drop(&mut self) {
+        // the owner is responsible for dropping the value, the same way `ShmemBox` drops its
+        // value
+        if self.conf.is_owner {
+            unsafe { ptr::drop_in_place(self.data()) };
+        }
+    }
+}
+
+/// RAII guard of a read lock of a `ShmemRwLock`, the lock is released when the guard goes out
+/// of scope.
+///
+/// The guard is not `Send`, since POSIX only allows the thread holding a lock to unlock it.
+///
+/// ```compile_fail
+/// use shmem_bind::ShmemRwLockReadGuard;
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<ShmemRwLockReadGuard<'static, u64>>();
+/// ```
+#[derive(Debug)]
+pub struct ShmemRwLockReadGuard<'a, T> {
+    lock: &'a ShmemRwLock<T>,
+    /// Keeps the guard on the thread which took the lock
+    _not_send: PhantomData<*const ()>,
+}
+
+// # Safety
+//
+// sharing the guard only shares `&T`, the guard itself is still dropped by the locking thread
+unsafe impl<T: Sync> Sync for ShmemRwLockReadGuard<'_, T> {}
+
+impl<T> Deref for ShmemRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // holding a read lock excludes any writer
+        unsafe { &*self.lock.data() }
+    }
+}
+
+impl<T> Drop for ShmemRwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
+
+/// RAII guard of the write lock of a `ShmemRwLock`, the lock is released when the guard goes
+/// out of scope.
+///
+/// The guard is not `Send`, since POSIX only allows the thread holding a lock to unlock it.
+///
+/// ```compile_fail
+/// use shmem_bind::ShmemRwLockWriteGuard;
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<ShmemRwLockWriteGuard<'static, u64>>();
+/// ```
+#[derive(Debug)]
+pub struct ShmemRwLockWriteGuard<'a, T> {
+    lock: &'a ShmemRwLock<T>,
+    /// Keeps the guard on the thread which took the lock
+    _not_send: PhantomData<*const ()>,
+}
+
+// # Safety
+//
+// sharing the guard only shares `&T`, the guard itself is still dropped by the locking thread
+unsafe impl<T: Sync> Sync for ShmemRwLockWriteGuard<'_, T> {}
+
+impl<T> Deref for ShmemRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // holding the write lock excludes any other reader or writer
+        unsafe { &*self.lock.data() }
+    }
+}
+
+impl<T> DerefMut for ShmemRwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // # Safety
+        //
+        // holding the write lock excludes any other reader or writer
+        unsafe { &mut *self.lock.data() }
+    }
+}
+
+impl<T> Drop for ShmemRwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // the writer may have left the value half updated
+        if thread::panicking() {
+            self.lock.header().poisoned.store(true, Ordering::Relaxed);
+        }
+        self.lock.unlock();
+    }
+}
+
+/// Returns the offset of the value within the shared memory.
+fn data_offset<T>() -> usize {
+    let align = mem::align_of::<T>();
+    mem::size_of::<RwLockHeader>().div_ceil(align) * align
+}
+
+/// Returns the header of the lock in `conf`.
+///
+/// # Safety
+///
+/// `conf` must be at least `ShmemRwLock::required_size()` bytes.
+unsafe fn header(conf: &ShmemConf) -> &RwLockHeader {
+    &*(conf.addr.as_ptr() as *const RwLockHeader)
+}
+
+fn data<T>(conf: &ShmemConf) -> *mut T {
+    (conf.addr.as_ptr() as *mut u8)
+        .wrapping_add(data_offset::<T>())
+        .cast::<T>()
+}
+
+/// Error of acquiring a poisoned `ShmemRwLock`, holding the guard `G` of the acquired lock.
+pub struct ShmemPoisonError<G> {
+    guard: G,
+}
+
+impl<G> ShmemPoisonError<G> {
+    /// Returns the guard of the lock, to access the value regardless of the poisoning.
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    /// Returns a reference to the guard of the lock.
+    pub fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    /// Returns a mutable reference to the guard of the lock.
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+impl<G> fmt::Debug for ShmemPoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemPoisonError").finish_non_exhaustive()
+    }
+}
+
+impl<G> fmt::Display for ShmemPoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "poisoned lock: another task failed inside")
+    }
+}
+
+impl<G> Error for ShmemPoisonError<G> {}
+
+/// Calls `try_lock` with an exponential backoff until it succeeds or `timeout` elapses.
+///
+/// The timed pthread lock functions are not available on every platform, polling keeps the
+/// behaviour the same everywhere.
+fn retry_until<G>(
+    timeout: Duration,
+    mut try_lock: impl FnMut() -> Option<ShmemLockResult<G>>,
+) -> Result<G, ShmemError> {
+    let start = Instant::now();
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        match try_lock() {
+            Some(Ok(guard)) => return Ok(guard),
+            Some(Err(_)) => return Err(ShmemError::Poisoned),
+            None => {}
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Err(ShmemError::LockTimeout);
+        }
+        thread::sleep(backoff.min(timeout - elapsed));
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+fn check(ret: libc::c_int) -> Result<(), ShmemError> {
+    match ret {
+        0 => Ok(()),
+        err => Err(ShmemError::IoError(errno(err))),
+    }
+}
+
+fn errno(ret: libc::c_int) -> std::io::Error {
+    std::io::Error::from_raw_os_error(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    const WORDS: usize = 16;
+
+    #[test]
+    fn readers_and_writer() {
+        let tmp = TempShmem::new("test-shmem-rwlock-readers-and-writer");
+        const READERS: usize = 4;
+        const WRITES: u64 = 2_000;
+
+        let id = tmp.id();
+        let shmconf = Builder::new(id)
+            .with_size(ShmemRwLock::<[u64; WORDS]>::required_size().unwrap())
+            .open()
+            .unwrap();
+        // every thread uses its own mapping of the lock, the same way processes would
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| shmconf.clone_handle().unwrap())
+            .collect();
+        let writer = ShmemRwLock::create(shmconf, [0_u64; WORDS]).unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reads = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = readers
+            .into_iter()
+            .map(|conf| {
+                let lock = unsafe { ShmemRwLock::<[u64; WORDS]>::attach(conf).unwrap() };
+                let (done, reads) = (done.clone(), reads.clone());
+                thread::spawn(move || {
+                    let mut last = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        let words = lock.read().unwrap();
+                        // a torn write would leave words of different generations
+                        assert!(words.iter().all(|word| *word == words[0]));
+                        assert!(words[0] >= last);
+                        last = words[0];
+                        reads.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+
+        // keep writing until the readers had their share of the lock as well
+        let mut writes = 0;
+        while writes < WRITES || reads.load(Ordering::Relaxed) < READERS * 100 {
+            let mut words = writer.write().unwrap();
+            for word in words.iter_mut() {
+                *word += 1;
+            }
+            writes += 1;
+        }
+        done.store(true, Ordering::Relaxed);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(*writer.read().unwrap(), [writes; WORDS]);
+    }
+
+    #[test]
+    fn held_lock_times_out() {
+        let tmp = TempShmem::new("test-shmem-rwlock-held-lock-times-out");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemRwLock::<u64>::required_size().unwrap())
+            .open()
+            .unwrap();
+        let attached = unsafe { ShmemRwLock::<u64>::attach(shmconf.clone_handle().unwrap()) };
+        assert!(matches!(attached, Err(ShmemError::NotInitialized)));
+        let handle = shmconf.clone_handle().unwrap();
+        let err = ShmemRwLock::create(shmconf.clone_handle().unwrap(), 0_u64).unwrap_err();
+        assert!(matches!(err, ShmemError::NotOwner));
+        let lock = ShmemRwLock::create(shmconf, 7_u64).unwrap();
+        let attached = unsafe { ShmemRwLock::<u64>::attach(handle).unwrap() };
+
+        // readers share the lock but exclude writers
+        let first = lock.read().unwrap();
+        let second = attached.try_read().unwrap().unwrap();
+        assert_eq!((*first, *second), (7, 7));
+        assert!(attached.try_write().is_none());
+        let err = attached
+            .try_write_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::LockTimeout));
+        drop((first, second));
+
+        let mut guard = attached.try_write_timeout(Duration::from_secs(1)).unwrap();
+        *guard = 8;
+        assert!(lock.try_read().is_none());
+        let err = lock
+            .try_read_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::LockTimeout));
+        drop(guard);
+        assert_eq!(*lock.try_read_timeout(Duration::from_secs(1)).unwrap(), 8);
+    }
+
+    #[test]
+    fn create_initialized() {
+        let tmp = TempShmem::new("test-shmem-rwlock-create-initialized");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemRwLock::<u64>::required_size().unwrap())
+            .open()
+            .unwrap();
+        let mut handle = shmconf.clone_handle().unwrap();
+        let mut lock = ShmemRwLock::create(shmconf, 7_u64).unwrap();
+
+        // the new owner must not initialize the lock again
+        ShmemConf::transfer_ownership(&mut lock.conf, &mut handle).unwrap();
+        let _guard = lock.read().unwrap();
+        let err = ShmemRwLock::create(handle, 0_u64).unwrap_err();
+        assert!(matches!(err, ShmemError::AlreadyExists(_)));
+        assert_eq!(*lock.try_read().unwrap().unwrap(), 7);
+    }
+
+    #[test]
+    fn poisoned_by_panicking_writer() {
+        let tmp = TempShmem::new("test-shmem-rwlock-poisoned-by-panicking-writer");
+        let shmconf = tmp
+            .builder()
+            .with_size(ShmemRwLock::<u64>::required_size().unwrap())
+            .open()
+            .unwrap();
+        let handle = shmconf.clone_handle().unwrap();
+        let lock = ShmemRwLock::create(shmconf, 1_u64).unwrap();
+
+        // a panicking reader can not leave the value half updated
+        let attached = unsafe { ShmemRwLock::<u64>::attach(handle).unwrap() };
+        thread::spawn(move || {
+            let _guard = attached.read().unwrap();
+            panic!("reader panics");
+        })
+        .join()
+        .unwrap_err();
+        assert!(!lock.is_poisoned());
+
+        let handle = lock.conf.clone_handle().unwrap();
+        thread::spawn(move || {
+            let attached = unsafe { ShmemRwLock::<u64>::attach(handle).unwrap() };
+            let mut guard = attached.write().unwrap();
+            *guard = 2;
+            panic!("writer panics");
+        })
+        .join()
+        .unwrap_err();
+
+        // the poison is visible through every mapping, the value is still reachable
+        assert!(lock.is_poisoned());
+        let err = lock.read().unwrap_err();
+        assert_eq!(**err.get_ref(), 2);
+        drop(err);
+        let mut guard = lock.try_write().unwrap().unwrap_err().into_inner();
+        *guard = 1;
+        drop(guard);
+        let err = lock
+            .try_read_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::Poisoned));
+
+        lock.clear_poison();
+        assert_eq!(*lock.write().unwrap(), 1);
+    }
+}
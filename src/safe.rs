@@ -0,0 +1,68 @@
+//! The `ShmemSafe` marker trait for types which can be shared between processes as is.
+use std::sync::atomic::{
+    AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU64,
+    AtomicU8, AtomicUsize,
+};
+
+/// Marks types which can be placed in shared memory and accessed from several processes.
+///
+/// A `ShmemSafe` type has the same layout in every process, contains no process-local pointers,
+/// e.g. of a `Box`, a `Vec` or a reference, and is valid for any bit pattern, so another process
+/// writing to the shared memory can never create an invalid value. `ShmemConf::boxed_safe` is
+/// therefore safe for such types.
+///
+/// The trait is implemented for the integer and floating point primitives, their atomics and
+/// arrays of `ShmemSafe` types. `bool`, `char` and their atomics are not `ShmemSafe`, since not
+/// every bit pattern is a valid value. Structs can implement it via `#[derive(ShmemSafe)]` with
+/// the `derive` feature, which validates the requirements at compile time.
+///
+/// # Safety
+///
+/// Implementors must uphold the requirements above.
+pub unsafe trait ShmemSafe {}
+
+macro_rules! impl_shmem_safe {
+    ($($ty:ty),* $(,)?) => {
+        $(unsafe impl ShmemSafe for $ty {})*
+    };
+}
+
+impl_shmem_safe!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+impl_shmem_safe!(
+    AtomicU8,
+    AtomicU16,
+    AtomicU32,
+    AtomicU64,
+    AtomicUsize,
+    AtomicI8,
+    AtomicI16,
+    AtomicI32,
+    AtomicI64,
+    AtomicIsize,
+);
+
+unsafe impl<T: ShmemSafe, const N: usize> ShmemSafe for [T; N] {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::{ShmemError, TempShmem};
+
+    #[test]
+    fn boxed_safe() {
+        let tmp = TempShmem::new("test-shmem-safe-boxed-safe");
+        let open = |size| tmp.builder().with_size(size).open().unwrap();
+        let mut counters = open(32).boxed_safe::<[AtomicU64; 4]>().unwrap();
+        counters[1].fetch_add(3, Ordering::Relaxed);
+
+        let view = open(32).boxed_safe::<[u64; 4]>().unwrap();
+        assert_eq!(*view, [0, 3, 0, 0]);
+        counters[3] = AtomicU64::new(9);
+        assert_eq!(view[3], 9);
+
+        let err = open(8).boxed_safe::<[u64; 2]>().unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+    }
+}
@@ -0,0 +1,301 @@
+//! Layout fingerprints catching processes which disagree on the type in a shared memory, see
+//! `ShmemConf::boxed_with_schema`.
+use std::{
+    mem,
+    sync::atomic::{
+        AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU64,
+        AtomicU8, AtomicUsize, Ordering,
+    },
+};
+
+use crate::{ShmemBox, ShmemConf, ShmemError};
+
+/// Marks a fingerprint written by `ShmemConf::boxed_with_schema`.
+const SCHEMA_MAGIC: u64 = 0x5348_4d53_4348_4d41;
+
+/// Header written in front of the value by `ShmemConf::boxed_with_schema`.
+#[repr(C)]
+struct SchemaHeader {
+    magic: AtomicU64,
+    size: AtomicU64,
+    align: AtomicU64,
+    schema: AtomicU64,
+}
+
+/// Describes the layout of a type placed in shared memory, so processes built from different
+/// versions of it can tell they disagree, see `ShmemConf::boxed_with_schema`.
+///
+/// `FINGERPRINT` should change whenever the meaning of the bytes changes, e.g. when a field is
+/// added, removed, renamed or changes its type. `#[derive(ShmemSchema)]` with the `derive`
+/// feature hashes the name, field names and field types of a struct as written, manual
+/// implementations may hash a description via `schema_hash` or bump a version number instead.
+///
+/// The trait is implemented for the integer, floating point, `bool` and `char` primitives,
+/// their atomics and arrays of `ShmemSchema` types.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{schema_hash,ShmemSchema};
+///
+/// #[repr(C)]
+/// struct Config {
+///     retries: u32,
+///     timeout_ms: u64,
+/// }
+///
+/// impl ShmemSchema for Config {
+///     const FINGERPRINT: u64 = schema_hash("Config { retries: u32, timeout_ms: u64 }");
+/// }
+/// ```
+pub trait ShmemSchema {
+    /// Hash of the schema of the type, compared next to its size and alignment.
+    const FINGERPRINT: u64;
+}
+
+/// Hashes `schema` via 64-bit FNV-1a, which gives the same result on every platform, compiler
+/// and version of the crate, e.g. for implementing `ShmemSchema` by hand.
+pub const fn schema_hash(schema: &str) -> u64 {
+    let bytes = schema.as_bytes();
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+
+    hash
+}
+
+macro_rules! impl_shmem_schema {
+    ($($ty:ty),* $(,)?) => {
+        $(impl ShmemSchema for $ty {
+            const FINGERPRINT: u64 = schema_hash(stringify!($ty));
+        })*
+    };
+}
+
+impl_shmem_schema!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+impl_shmem_schema!(bool, char);
+impl_shmem_schema!(
+    AtomicU8,
+    AtomicU16,
+    AtomicU32,
+    AtomicU64,
+    AtomicUsize,
+    AtomicI8,
+    AtomicI16,
+    AtomicI32,
+    AtomicI64,
+    AtomicIsize,
+);
+
+impl<T: ShmemSchema, const N: usize> ShmemSchema for [T; N] {
+    // the length is part of the size already, the element type is not
+    const FINGERPRINT: u64 = T::FINGERPRINT.rotate_left(7) ^ schema_hash("[T; N]");
+}
+
+/// Size, alignment and `ShmemSchema::FINGERPRINT` of a type, as carried by
+/// `ShmemError::LayoutMismatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutFingerprint {
+    /// `mem::size_of` of the type.
+    pub size: u64,
+    /// `mem::align_of` of the type.
+    pub align: u64,
+    /// `ShmemSchema::FINGERPRINT` of the type.
+    pub schema: u64,
+}
+
+impl LayoutFingerprint {
+    /// Returns the fingerprint of `T`.
+    pub fn of<T: ShmemSchema>() -> Self {
+        Self {
+            size: mem::size_of::<T>() as u64,
+            align: mem::align_of::<T>() as u64,
+            schema: T::FINGERPRINT,
+        }
+    }
+}
+
+impl ShmemConf {
+    /// Converts `ShmemConf`'s raw pointer to a boxed pointer after checking that the shared
+    /// memory was created for a `T` with the same layout.
+    ///
+    /// The shared memory starts with a 32 byte header holding a magic number and the
+    /// `LayoutFingerprint` of the `T`, followed by the `T` at the next offset aligned for it.
+    /// The owner writes the header, every other handle compares it to the fingerprint of its
+    /// `T` and gets `ShmemError::LayoutMismatch` if they differ, e.g. when the process was
+    /// built against a version of the struct with an extra field. Unlike `boxed_checked`, the
+    /// fingerprint does not depend on the compiler version.
+    ///
+    /// Returns `ShmemError::NotInitialized` if the owner did not write the header yet,
+    /// `ShmemError::OutOfBoundsErr` if the header and the `T` exceed the shared memory and
+    /// `ShmemError::Misaligned` if the `T` is not aligned for it.
+    ///
+    /// # Safety
+    ///
+    /// Same as `boxed`, the fingerprint does not guarantee that the bytes hold a valid `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_boxed_with_schema");
+    ///     let open = || shmem::Builder::new("flink_test_boxed_with_schema")
+    ///         .with_size(64)
+    ///         .open();
+    ///     let mut owner = unsafe { open()?.boxed_with_schema::<[u32; 4]>()? };
+    ///     owner[0] = 5;
+    ///
+    ///     // e.g. in another process
+    ///     assert_eq!(unsafe { open()?.boxed_with_schema::<[u32; 4]>()? }[0], 5);
+    ///     let err = unsafe { open()?.boxed_with_schema::<[u32; 3]>() }.unwrap_err();
+    ///     assert!(matches!(err, ShmemError::LayoutMismatch { .. }));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub unsafe fn boxed_with_schema<T: ShmemSchema>(self) -> Result<ShmemBox<T>, ShmemError> {
+        let header_len = mem::size_of::<SchemaHeader>();
+        if self.size < header_len {
+            return Err(ShmemError::OutOfBoundsErr);
+        }
+        let expected = LayoutFingerprint::of::<T>();
+
+        // the mapping is page aligned, hence aligned for the header
+        let header = &*self.addr.as_ptr().cast::<SchemaHeader>();
+        if self.is_owner {
+            header.size.store(expected.size, Ordering::Relaxed);
+            header.align.store(expected.align, Ordering::Relaxed);
+            header.schema.store(expected.schema, Ordering::Relaxed);
+            // publish the fingerprint to attaching handles
+            header.magic.store(SCHEMA_MAGIC, Ordering::Release);
+        } else {
+            if header.magic.load(Ordering::Acquire) != SCHEMA_MAGIC {
+                return Err(ShmemError::NotInitialized);
+            }
+            let found = LayoutFingerprint {
+                size: header.size.load(Ordering::Relaxed),
+                align: header.align.load(Ordering::Relaxed),
+                schema: header.schema.load(Ordering::Relaxed),
+            };
+            if found != expected {
+                return Err(ShmemError::LayoutMismatch { expected, found });
+            }
+        }
+
+        self.boxed_at::<T>(header_len.next_multiple_of(mem::align_of::<T>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    mod v1 {
+        #[derive(Debug)]
+        #[repr(C)]
+        pub struct Config {
+            pub retries: u32,
+        }
+
+        impl super::ShmemSchema for Config {
+            const FINGERPRINT: u64 = super::schema_hash("Config { retries: u32 }");
+        }
+    }
+
+    mod v2 {
+        #[derive(Debug)]
+        #[repr(C)]
+        pub struct Config {
+            pub retries: u32,
+            pub timeout_ms: u32,
+        }
+
+        impl super::ShmemSchema for Config {
+            const FINGERPRINT: u64 = super::schema_hash("Config { retries: u32, timeout_ms: u32 }");
+        }
+    }
+
+    /// Same layout as `v2::Config`, but a different meaning of the second field.
+    mod v3 {
+        #[derive(Debug)]
+        #[repr(C)]
+        pub struct Config {
+            pub retries: u32,
+            pub timeout_s: u32,
+        }
+
+        impl super::ShmemSchema for Config {
+            const FINGERPRINT: u64 = super::schema_hash("Config { retries: u32, timeout_s: u32 }");
+        }
+    }
+
+    #[test]
+    fn extra_field() {
+        let tmp = TempShmem::new("test-shmem-schema-extra-field");
+        let tmp2 = TempShmem::new("test-shmem-schema-too-small");
+        let open = || tmp.builder().with_size(64).open().unwrap();
+        let owner = open();
+        let err = unsafe {
+            owner
+                .clone_handle()
+                .unwrap()
+                .boxed_with_schema::<v1::Config>()
+        }
+        .unwrap_err();
+        assert!(matches!(err, ShmemError::NotInitialized));
+
+        let mut owner = unsafe { owner.boxed_with_schema::<v1::Config>() }.unwrap();
+        owner.retries = 3;
+        assert_eq!(
+            owner.as_ptr() as usize - owner.conf.as_ptr() as usize,
+            mem::size_of::<SchemaHeader>()
+        );
+        let attached = unsafe { open().boxed_with_schema::<v1::Config>() }.unwrap();
+        assert_eq!(attached.retries, 3);
+
+        // the newer version would read past the value of the owner
+        let err = unsafe { open().boxed_with_schema::<v2::Config>() }.unwrap_err();
+        let ShmemError::LayoutMismatch { expected, found } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(expected, LayoutFingerprint::of::<v2::Config>());
+        assert_eq!(found, LayoutFingerprint::of::<v1::Config>());
+        assert_eq!(found.size, 4);
+        assert_eq!(owner.retries, 3);
+        drop(owner);
+
+        // a renamed field with the same layout is caught by the schema hash
+        let owner = unsafe { open().boxed_with_schema::<v2::Config>() }.unwrap();
+        let err = unsafe { open().boxed_with_schema::<v3::Config>() }.unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::LayoutMismatch { expected, found }
+                if expected.size == found.size && expected.schema != found.schema
+        ));
+        drop(owner);
+
+        let err = unsafe {
+            tmp2.builder()
+                .with_size(16)
+                .open()
+                .unwrap()
+                .boxed_with_schema::<u8>()
+        }
+        .unwrap_err();
+        assert!(matches!(err, ShmemError::OutOfBoundsErr));
+    }
+
+    #[test]
+    fn stable_hash() {
+        // FNV-1a test vectors, the hash must never change across versions
+        assert_eq!(schema_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(schema_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(<[u32; 4]>::FINGERPRINT, <[i32; 4]>::FINGERPRINT);
+    }
+}
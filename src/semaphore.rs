@@ -0,0 +1,239 @@
+//! POSIX named semaphores paired with a shared memory, see `ShmemSemaphore`.
+use std::{ffi::CString, fmt, io, time::Duration};
+
+#[cfg(not(target_os = "linux"))]
+use std::{thread, time::Instant};
+
+use crate::{eintr::eintr_retry, ShmemConf, ShmemError};
+
+/// Poll interval of `wait_timeout` on platforms without `sem_timedwait`.
+#[cfg(not(target_os = "linux"))]
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A counting semaphore shared between processes, backed by a POSIX named semaphore named after
+/// the `flink_id` of a shared memory with a `.sem` suffix.
+///
+/// The owner of the shared memory creates the semaphore with the initial count and unlinks it
+/// when dropped, every other handle opens the existing semaphore. This makes it suitable e.g.
+/// for counting the free slots of a buffer living in the shared memory.
+///
+/// Not available on Android, whose libc does not implement named semaphores.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use shmem_bind::{self as shmem,ShmemError,ShmemSemaphore};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_semaphore");
+///     let open = || shmem::Builder::new("flink_test_semaphore").with_size(4).open();
+///     let owner = open()?;
+///     let slots = ShmemSemaphore::new(&owner, 1)?;
+///
+///     // e.g. in another process
+///     let attached = open()?;
+///     let other = ShmemSemaphore::new(&attached, 0)?;
+///     other.wait()?;
+///     assert!(!slots.try_wait());
+///     slots.post()?;
+///     assert!(other.wait_timeout(Duration::from_millis(10)));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemSemaphore {
+    sem: *mut libc::sem_t,
+    name: CString,
+    /// True if this handle created the semaphore and is responsible for unlinking it.
+    is_owner: bool,
+}
+
+// # Safety
+//
+// POSIX semaphores may be used from any thread.
+unsafe impl Send for ShmemSemaphore {}
+unsafe impl Sync for ShmemSemaphore {}
+
+impl ShmemSemaphore {
+    /// Opens the semaphore paired with the shared memory of `conf`.
+    ///
+    /// If `conf` is the owner of the shared memory, a fresh semaphore counting `initial` is
+    /// created, replacing any semaphore left behind under the name, e.g. by a crashed process.
+    /// Otherwise the semaphore created by the owner is opened and `initial` is ignored.
+    ///
+    /// Returns `ShmemError::NotFound` if the owner did not create the semaphore yet,
+    /// `ShmemError::InvalidIdErr` if the name contains a nul byte and `ShmemError::IoError` if
+    /// `sem_open` fails otherwise, e.g. as `initial` exceeds `SEM_VALUE_MAX`.
+    pub fn new(conf: &ShmemConf, initial: u32) -> Result<Self, ShmemError> {
+        let name =
+            CString::new(format!("{}.sem", conf.id)).map_err(|_| ShmemError::InvalidIdErr)?;
+
+        let sem = if conf.is_owner {
+            unsafe { libc::sem_unlink(name.as_ptr()) };
+            unsafe {
+                libc::sem_open(
+                    name.as_ptr(),
+                    libc::O_CREAT | libc::O_EXCL,
+                    conf.options.mode as libc::c_uint,
+                    initial as libc::c_uint,
+                )
+            }
+        } else {
+            unsafe { libc::sem_open(name.as_ptr(), 0) }
+        };
+        if sem == libc::SEM_FAILED {
+            let err = io::Error::last_os_error();
+            return Err(match err.raw_os_error() {
+                Some(libc::ENOENT) => ShmemError::NotFound(err),
+                _ => ShmemError::IoError(err),
+            });
+        }
+
+        Ok(Self {
+            sem,
+            name,
+            is_owner: conf.is_owner,
+        })
+    }
+
+    /// Increments the count, waking up one waiter if any.
+    ///
+    /// Returns `ShmemError::IoError` if the count would exceed `SEM_VALUE_MAX`.
+    pub fn post(&self) -> Result<(), ShmemError> {
+        if unsafe { libc::sem_post(self.sem) } != 0 {
+            return Err(ShmemError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Blocks until the count is positive and decrements it.
+    ///
+    /// Returns `ShmemError::IoError` if `sem_wait` fails for a reason other than being
+    /// interrupted by a signal.
+    pub fn wait(&self) -> Result<(), ShmemError> {
+        if eintr_retry!(unsafe { libc::sem_wait(self.sem) }) != 0 {
+            return Err(ShmemError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Decrements the count if it is positive, without blocking.
+    pub fn try_wait(&self) -> bool {
+        eintr_retry!(unsafe { libc::sem_trywait(self.sem) }) == 0
+    }
+
+    /// Blocks until the count is positive or `dur` has elapsed, decrementing the count.
+    ///
+    /// Returns false if the timeout elapsed before the count was positive. Outside of Linux,
+    /// where `sem_timedwait` is missing, the count is polled every millisecond.
+    #[cfg(target_os = "linux")]
+    pub fn wait_timeout(&self, dur: Duration) -> bool {
+        // `sem_timedwait` takes an absolute deadline of the realtime clock
+        let mut now = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, &mut now) };
+        let deadline = Duration::new(now.tv_sec as u64, now.tv_nsec as u32).saturating_add(dur);
+        let deadline = libc::timespec {
+            tv_sec: deadline.as_secs() as libc::time_t,
+            tv_nsec: deadline.subsec_nanos() as _,
+        };
+
+        eintr_retry!(unsafe { libc::sem_timedwait(self.sem, &deadline) }) == 0
+    }
+
+    /// Blocks until the count is positive or `dur` has elapsed, decrementing the count.
+    ///
+    /// Returns false if the timeout elapsed before the count was positive. Outside of Linux,
+    /// where `sem_timedwait` is missing, the count is polled every millisecond.
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait_timeout(&self, dur: Duration) -> bool {
+        let deadline = Instant::now() + dur;
+        loop {
+            if self.try_wait() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL.min(remaining));
+        }
+    }
+
+    /// Returns true if this handle created the semaphore and unlinks it when dropped.
+    pub fn is_owner(&self) -> bool {
+        self.is_owner
+    }
+}
+
+impl Drop for ShmemSemaphore {
+    fn drop(&mut self) {
+        unsafe { libc::sem_close(self.sem) };
+        if self.is_owner {
+            unsafe { libc::sem_unlink(self.name.as_ptr()) };
+        }
+    }
+}
+
+impl fmt::Debug for ShmemSemaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemSemaphore")
+            .field("name", &self.name)
+            .field("is_owner", &self.is_owner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    #[test]
+    fn tokens_across_processes() {
+        let tmp = TempShmem::new("test-shmem-semaphore-tokens");
+        const TOKENS: usize = 100;
+        let id = tmp.id();
+        let open = || Builder::new(id).with_size(8).open().unwrap();
+
+        let owner = open();
+        let err = ShmemSemaphore::new(&open(), 0).unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+        let sem = ShmemSemaphore::new(&owner, 0).unwrap();
+        assert!(sem.is_owner());
+
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            // the child opens the pair by name like an unrelated process would
+            let conf = open();
+            let sem = ShmemSemaphore::new(&conf, 0).unwrap();
+            for _ in 0..TOKENS {
+                sem.post().unwrap();
+            }
+            unsafe { libc::_exit(0) };
+        }
+
+        for _ in 0..TOKENS {
+            assert!(sem.wait_timeout(Duration::from_secs(5)));
+        }
+        assert!(!sem.try_wait());
+        let start = Instant::now();
+        assert!(!sem.wait_timeout(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+
+        // the owner unlinks the semaphore
+        drop(sem);
+        let err = ShmemSemaphore::new(&open(), 0).unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+    }
+}
@@ -0,0 +1,313 @@
+//! `Serialize` and `Deserialize` for `ShmemError`, enabled by the `serde` feature.
+//!
+//! `io::Error` is not serializable, so errors carrying one are converted into a mirror of
+//! `ShmemError` first, which records the errno and the message of the `io::Error` instead.
+
+use std::io;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BackingKind, LayoutFingerprint, RequestedSize, ShmemError};
+
+/// Serializable form of an `io::Error`.
+#[derive(Serialize, Deserialize)]
+struct IoErrorRepr {
+    /// The errno, if the error originated from the OS.
+    errno: Option<i32>,
+    message: String,
+}
+
+impl From<&io::Error> for IoErrorRepr {
+    fn from(err: &io::Error) -> Self {
+        Self {
+            errno: err.raw_os_error(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<IoErrorRepr> for io::Error {
+    fn from(repr: IoErrorRepr) -> Self {
+        match repr.errno {
+            Some(errno) => io::Error::from_raw_os_error(errno),
+            None => io::Error::other(repr.message),
+        }
+    }
+}
+
+/// Mirror of `ShmemError` with the same variant names, carrying serializable payloads.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ShmemError")]
+enum ShmemErrorRepr {
+    CreateFailedErr(IoErrorRepr),
+    AllocationFailedErr,
+    NullPointerErr,
+    MapFailedErr,
+    DuplicateFailedErr,
+    OutOfBoundsErr,
+    FlushFailed(IoErrorRepr),
+    ProtectFailed(IoErrorRepr),
+    InvalidIdErr,
+    AlreadyExists(IoErrorRepr),
+    IoError(IoErrorRepr),
+    NotFound(IoErrorRepr),
+    NumaFailed(IoErrorRepr),
+    LockTimeout,
+    SizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    NotInitialized,
+    AdviseFailed(IoErrorRepr),
+    Unsupported,
+    Misaligned,
+    ResidencyFailed(IoErrorRepr),
+    UnmapFailed(IoErrorRepr),
+    InvalidSize(RequestedSize),
+    NotOwner,
+    LogFull,
+    IdMismatch,
+    EncodeFailed(String),
+    DecodeFailed(String),
+    InvalidHandle,
+    AlreadyOwned,
+    TypeHashMismatch {
+        expected: u64,
+        found: u64,
+    },
+    Timeout,
+    StaleSegment {
+        pid: i32,
+    },
+    SecurityViolation,
+    WrongBacking(BackingKind),
+    LayoutMismatch {
+        expected: LayoutFingerprint,
+        found: LayoutFingerprint,
+    },
+    Poisoned,
+    VersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
+    InitAborted {
+        pid: i32,
+    },
+    AddressUnavailable(IoErrorRepr),
+    UnlinkAllFailed {
+        unlinked: usize,
+        failed: Vec<(String, IoErrorRepr)>,
+    },
+    ProtectionViolation,
+}
+
+impl From<&ShmemError> for ShmemErrorRepr {
+    fn from(err: &ShmemError) -> Self {
+        match err {
+            ShmemError::CreateFailedErr(err) => Self::CreateFailedErr(err.into()),
+            ShmemError::AllocationFailedErr => Self::AllocationFailedErr,
+            ShmemError::NullPointerErr => Self::NullPointerErr,
+            ShmemError::MapFailedErr => Self::MapFailedErr,
+            ShmemError::DuplicateFailedErr => Self::DuplicateFailedErr,
+            ShmemError::OutOfBoundsErr => Self::OutOfBoundsErr,
+            ShmemError::FlushFailed(err) => Self::FlushFailed(err.into()),
+            ShmemError::ProtectFailed(err) => Self::ProtectFailed(err.into()),
+            ShmemError::InvalidIdErr => Self::InvalidIdErr,
+            ShmemError::AlreadyExists(err) => Self::AlreadyExists(err.into()),
+            ShmemError::IoError(err) => Self::IoError(err.into()),
+            ShmemError::NotFound(err) => Self::NotFound(err.into()),
+            ShmemError::NumaFailed(err) => Self::NumaFailed(err.into()),
+            ShmemError::LockTimeout => Self::LockTimeout,
+            ShmemError::SizeMismatch { expected, found } => Self::SizeMismatch {
+                expected: *expected,
+                found: *found,
+            },
+            ShmemError::NotInitialized => Self::NotInitialized,
+            ShmemError::AdviseFailed(err) => Self::AdviseFailed(err.into()),
+            ShmemError::Unsupported => Self::Unsupported,
+            ShmemError::Misaligned => Self::Misaligned,
+            ShmemError::ResidencyFailed(err) => Self::ResidencyFailed(err.into()),
+            ShmemError::UnmapFailed(err) => Self::UnmapFailed(err.into()),
+            ShmemError::InvalidSize(size) => Self::InvalidSize(*size),
+            ShmemError::NotOwner => Self::NotOwner,
+            ShmemError::LogFull => Self::LogFull,
+            ShmemError::IdMismatch => Self::IdMismatch,
+            ShmemError::EncodeFailed(msg) => Self::EncodeFailed(msg.clone()),
+            ShmemError::DecodeFailed(msg) => Self::DecodeFailed(msg.clone()),
+            ShmemError::InvalidHandle => Self::InvalidHandle,
+            ShmemError::AlreadyOwned => Self::AlreadyOwned,
+            ShmemError::TypeHashMismatch { expected, found } => Self::TypeHashMismatch {
+                expected: *expected,
+                found: *found,
+            },
+            ShmemError::Timeout => Self::Timeout,
+            ShmemError::StaleSegment { pid } => Self::StaleSegment { pid: *pid },
+            ShmemError::SecurityViolation => Self::SecurityViolation,
+            ShmemError::WrongBacking(backing) => Self::WrongBacking(*backing),
+            ShmemError::LayoutMismatch { expected, found } => Self::LayoutMismatch {
+                expected: *expected,
+                found: *found,
+            },
+            ShmemError::Poisoned => Self::Poisoned,
+            ShmemError::VersionMismatch { ours, theirs } => Self::VersionMismatch {
+                ours: *ours,
+                theirs: *theirs,
+            },
+            ShmemError::InitAborted { pid } => Self::InitAborted { pid: *pid },
+            ShmemError::AddressUnavailable(err) => Self::AddressUnavailable(err.into()),
+            ShmemError::UnlinkAllFailed { unlinked, failed } => Self::UnlinkAllFailed {
+                unlinked: *unlinked,
+                failed: failed
+                    .iter()
+                    .map(|(name, err)| (name.clone(), err.into()))
+                    .collect(),
+            },
+            ShmemError::ProtectionViolation => Self::ProtectionViolation,
+        }
+    }
+}
+
+impl From<ShmemErrorRepr> for ShmemError {
+    fn from(repr: ShmemErrorRepr) -> Self {
+        match repr {
+            ShmemErrorRepr::CreateFailedErr(err) => Self::CreateFailedErr(err.into()),
+            ShmemErrorRepr::AllocationFailedErr => Self::AllocationFailedErr,
+            ShmemErrorRepr::NullPointerErr => Self::NullPointerErr,
+            ShmemErrorRepr::MapFailedErr => Self::MapFailedErr,
+            ShmemErrorRepr::DuplicateFailedErr => Self::DuplicateFailedErr,
+            ShmemErrorRepr::OutOfBoundsErr => Self::OutOfBoundsErr,
+            ShmemErrorRepr::FlushFailed(err) => Self::FlushFailed(err.into()),
+            ShmemErrorRepr::ProtectFailed(err) => Self::ProtectFailed(err.into()),
+            ShmemErrorRepr::InvalidIdErr => Self::InvalidIdErr,
+            ShmemErrorRepr::AlreadyExists(err) => Self::AlreadyExists(err.into()),
+            ShmemErrorRepr::IoError(err) => Self::IoError(err.into()),
+            ShmemErrorRepr::NotFound(err) => Self::NotFound(err.into()),
+            ShmemErrorRepr::NumaFailed(err) => Self::NumaFailed(err.into()),
+            ShmemErrorRepr::LockTimeout => Self::LockTimeout,
+            ShmemErrorRepr::SizeMismatch { expected, found } => {
+                Self::SizeMismatch { expected, found }
+            }
+            ShmemErrorRepr::NotInitialized => Self::NotInitialized,
+            ShmemErrorRepr::AdviseFailed(err) => Self::AdviseFailed(err.into()),
+            ShmemErrorRepr::Unsupported => Self::Unsupported,
+            ShmemErrorRepr::Misaligned => Self::Misaligned,
+            ShmemErrorRepr::ResidencyFailed(err) => Self::ResidencyFailed(err.into()),
+            ShmemErrorRepr::UnmapFailed(err) => Self::UnmapFailed(err.into()),
+            ShmemErrorRepr::InvalidSize(size) => Self::InvalidSize(size),
+            ShmemErrorRepr::NotOwner => Self::NotOwner,
+            ShmemErrorRepr::LogFull => Self::LogFull,
+            ShmemErrorRepr::IdMismatch => Self::IdMismatch,
+            ShmemErrorRepr::EncodeFailed(msg) => Self::EncodeFailed(msg),
+            ShmemErrorRepr::DecodeFailed(msg) => Self::DecodeFailed(msg),
+            ShmemErrorRepr::InvalidHandle => Self::InvalidHandle,
+            ShmemErrorRepr::AlreadyOwned => Self::AlreadyOwned,
+            ShmemErrorRepr::TypeHashMismatch { expected, found } => {
+                Self::TypeHashMismatch { expected, found }
+            }
+            ShmemErrorRepr::Timeout => Self::Timeout,
+            ShmemErrorRepr::StaleSegment { pid } => Self::StaleSegment { pid },
+            ShmemErrorRepr::SecurityViolation => Self::SecurityViolation,
+            ShmemErrorRepr::WrongBacking(backing) => Self::WrongBacking(backing),
+            ShmemErrorRepr::LayoutMismatch { expected, found } => {
+                Self::LayoutMismatch { expected, found }
+            }
+            ShmemErrorRepr::Poisoned => Self::Poisoned,
+            ShmemErrorRepr::VersionMismatch { ours, theirs } => {
+                Self::VersionMismatch { ours, theirs }
+            }
+            ShmemErrorRepr::InitAborted { pid } => Self::InitAborted { pid },
+            ShmemErrorRepr::AddressUnavailable(err) => Self::AddressUnavailable(err.into()),
+            ShmemErrorRepr::UnlinkAllFailed { unlinked, failed } => Self::UnlinkAllFailed {
+                unlinked,
+                failed: failed
+                    .into_iter()
+                    .map(|(name, err)| (name, err.into()))
+                    .collect(),
+            },
+            ShmemErrorRepr::ProtectionViolation => Self::ProtectionViolation,
+        }
+    }
+}
+
+/// Serializes the error under its variant name. An `io::Error` payload is serialized as its
+/// errno, if any, and its message.
+impl Serialize for ShmemError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ShmemErrorRepr::from(self).serialize(serializer)
+    }
+}
+
+/// Deserializes an error serialized via `Serialize`. An `io::Error` payload is rebuilt from its
+/// errno, or from its message if it did not originate from the OS, so its kind is lost then.
+impl<'de> Deserialize<'de> for ShmemError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ShmemErrorRepr::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShmemErrorKind;
+
+    fn round_trip(err: &ShmemError) -> ShmemError {
+        serde_json::from_str(&serde_json::to_string(err).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trip_payloads() {
+        let err = round_trip(&ShmemError::NotFound(io::Error::from_raw_os_error(
+            libc::ENOENT,
+        )));
+        assert_eq!(err.kind(), ShmemErrorKind::NotFound);
+        assert!(matches!(err, ShmemError::NotFound(io) if io.raw_os_error() == Some(libc::ENOENT)));
+
+        let err = round_trip(&ShmemError::IoError(io::Error::other("custom")));
+        assert!(matches!(err, ShmemError::IoError(io) if io.to_string() == "custom"));
+
+        let err = round_trip(&ShmemError::SizeMismatch {
+            expected: 8,
+            found: 4,
+        });
+        assert!(matches!(
+            err,
+            ShmemError::SizeMismatch {
+                expected: 8,
+                found: 4
+            }
+        ));
+
+        let err = round_trip(&ShmemError::InvalidSize(RequestedSize::Negative(-1)));
+        assert!(matches!(
+            err,
+            ShmemError::InvalidSize(RequestedSize::Negative(-1))
+        ));
+    }
+
+    #[test]
+    fn variant_names() {
+        assert_eq!(
+            serde_json::to_string(&ShmemError::LogFull).unwrap(),
+            r#""LogFull""#
+        );
+        assert_eq!(
+            serde_json::to_string(&ShmemError::SizeMismatch {
+                expected: 8,
+                found: 4
+            })
+            .unwrap(),
+            r#"{"SizeMismatch":{"expected":8,"found":4}}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ShmemError::NotFound(io::Error::from_raw_os_error(
+                libc::ENOENT
+            )))
+            .unwrap(),
+            format!(
+                r#"{{"NotFound":{{"errno":{},"message":"{}"}}}}"#,
+                libc::ENOENT,
+                io::Error::from_raw_os_error(libc::ENOENT)
+            )
+        );
+    }
+}
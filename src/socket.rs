@@ -0,0 +1,262 @@
+//! Passing shared memories to unrelated processes over Unix domain sockets.
+use std::{
+    io::{self, Read},
+    mem,
+    os::unix::{
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    ptr,
+};
+
+use libc::{c_int, c_void};
+
+use crate::{eintr::eintr_retry, handle::parse_handle_string, ShmemConf, ShmemError};
+
+/// Upper bound of the handle string sent along with the file descriptor, which is dominated by
+/// the hex encoded `flink_id`.
+const MAX_MESSAGE_LEN: usize = 1024;
+
+/// Flags passed to `recvmsg`, closing received file descriptors on `exec` where supported.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: c_int = 0;
+
+impl ShmemConf {
+    /// Sends the shared memory to the process listening on the Unix domain socket at
+    /// `socket_path`, which receives it via `recv_via_unix_socket`.
+    ///
+    /// A connection is made to `socket_path` and the file descriptor is sent as `SCM_RIGHTS`
+    /// ancillary data along with the string of `to_handle_string`, which carries the size and
+    /// the options the receiver maps the shared memory with. The receiver needs no access to the
+    /// `flink_id`, so together with `Builder::ephemeral` this shares memory between unrelated
+    /// processes which never see it in `/dev/shm`. The kernel duplicates the file descriptor,
+    /// this handle stays usable.
+    ///
+    /// Returns `ShmemError::IoError` if connecting or sending fails, e.g. as nobody listens at
+    /// `socket_path`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::{os::unix::net::UnixListener,thread};
+    /// use shmem_bind::{self as shmem,ShmemConf,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    ///     let path = std::env::temp_dir().join(format!("flink_test_send.{}", std::process::id()));
+    ///     let listener = UnixListener::bind(&path).map_err(ShmemError::IoError)?;
+    ///
+    ///     let shared_mem = shmem::Builder::ephemeral().with_size(8).open()?;
+    ///     // e.g. in an unrelated process
+    ///     let receiver = thread::spawn(move || ShmemConf::recv_via_unix_socket(&listener));
+    ///     shared_mem.send_via_unix_socket(&path)?;
+    ///     let received = receiver.join().unwrap()?;
+    ///
+    ///     let mut counter = unsafe { shared_mem.boxed::<u64>() };
+    ///     *counter = 7;
+    ///     assert_eq!(*unsafe { received.boxed::<u64>() }, 7);
+    ///
+    ///     std::fs::remove_file(&path).map_err(ShmemError::IoError)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn send_via_unix_socket(&self, socket_path: &Path) -> Result<(), ShmemError> {
+        let stream = UnixStream::connect(socket_path).map_err(ShmemError::IoError)?;
+        let handle = self.to_handle_string();
+
+        let sent =
+            send_fd(stream.as_raw_fd(), self.fd, handle.as_bytes()).map_err(ShmemError::IoError)?;
+        // the file descriptor went with the first byte, the rest of the handle follows as is
+        let mut sent = &handle.as_bytes()[sent..];
+        io::copy(&mut sent, &mut &stream).map_err(ShmemError::IoError)?;
+
+        Ok(())
+    }
+
+    /// Accepts a connection on `socket` and receives the shared memory sent via
+    /// `send_via_unix_socket`.
+    ///
+    /// The shared memory is mapped with the size and the options recorded by the sender, which
+    /// has to close the connection after sending. The returned `ShmemConf` is never the owner
+    /// and is unlinked by nobody but the owner on the sending side.
+    ///
+    /// Returns `ShmemError::IoError` if accepting or receiving fails,
+    /// `ShmemError::InvalidHandle` if the message carries no file descriptor or no valid handle
+    /// string and any error of `BuilderWithSize::open_existing` for mapping the file descriptor
+    /// otherwise.
+    pub fn recv_via_unix_socket(socket: &UnixListener) -> Result<ShmemConf, ShmemError> {
+        let (mut stream, _) = socket.accept().map_err(ShmemError::IoError)?;
+
+        let mut message = vec![0u8; MAX_MESSAGE_LEN];
+        let (received, fd) =
+            recv_fd(stream.as_raw_fd(), &mut message).map_err(ShmemError::IoError)?;
+        let fd = fd.ok_or(ShmemError::InvalidHandle)?;
+
+        // the rest of the handle arrives up to the end of the connection
+        message.truncate(received);
+        let rest = (&mut stream)
+            .take((MAX_MESSAGE_LEN - message.len()) as u64)
+            .read_to_end(&mut message);
+        let builder = match rest {
+            Ok(_) => String::from_utf8(message)
+                .map_err(|_| ShmemError::InvalidHandle)
+                .and_then(|handle| parse_handle_string(&handle)),
+            Err(err) => Err(ShmemError::IoError(err)),
+        };
+        let builder = match builder {
+            Ok(builder) if builder.options.copy_on_write && builder.options.mirrored => {
+                Err(ShmemError::Unsupported)
+            }
+            builder => builder,
+        };
+        match builder {
+            Ok(builder) => builder.open_fd(fd),
+            Err(err) => {
+                unsafe { libc::close(fd) };
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Sends `bytes` over the socket `sock` with `fd` attached as `SCM_RIGHTS` ancillary data, which
+/// the kernel duplicates for the receiver.
+///
+/// Returns the number of bytes sent, at least the first one carrying `fd` if `bytes` is not
+/// empty.
+pub(crate) fn send_fd(sock: c_int, fd: c_int, bytes: &[u8]) -> io::Result<usize> {
+    let mut iov = libc::iovec {
+        iov_base: bytes.as_ptr() as *mut c_void,
+        iov_len: bytes.len(),
+    };
+    let mut control = [0u8; 64];
+    // # Safety
+    //
+    // `msghdr` is plain data for which zero is a valid value, the control buffer is large
+    // enough for a single file descriptor
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut c_void;
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(mem::size_of::<c_int>() as u32) } as _;
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<c_int>() as u32) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<c_int>(), fd);
+    }
+
+    let sent = eintr_retry!(unsafe { libc::sendmsg(sock, &msg, 0) });
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(sent as usize)
+}
+
+/// Receives up to `buf.len()` bytes from the socket `sock` along with the file descriptor sent
+/// via `send_fd`, if any.
+///
+/// Returns the number of bytes received and the file descriptor, which is closed on `exec` where
+/// supported.
+pub(crate) fn recv_fd(sock: c_int, buf: &mut [u8]) -> io::Result<(usize, Option<c_int>)> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let mut control = [0u8; 64];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut c_void;
+    msg.msg_controllen = control.len() as _;
+    let received = eintr_retry!(unsafe { libc::recvmsg(sock, &mut msg, RECV_FLAGS) });
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((received as usize, unsafe { take_fd(&msg) }))
+}
+
+/// Returns the first file descriptor received as `SCM_RIGHTS` ancillary data of `msg`, closing
+/// any further ones.
+///
+/// # Safety
+///
+/// `msg` must be filled in by a successful call to `recvmsg`.
+unsafe fn take_fd(msg: &libc::msghdr) -> Option<c_int> {
+    let mut fd = None;
+    let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+    while !cmsg.is_null() {
+        if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+            let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+            let fds = libc::CMSG_DATA(cmsg).cast::<c_int>();
+            for i in 0..len / mem::size_of::<c_int>() {
+                let received = ptr::read_unaligned(fds.add(i));
+                match fd {
+                    None => fd = Some(received),
+                    Some(_) => {
+                        libc::close(received);
+                    }
+                }
+            }
+        }
+        cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+    }
+
+    fd
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Write};
+
+    use super::*;
+    use crate::{exists, Builder, TempShmem};
+
+    #[test]
+    fn unrelated_processes() {
+        let tmp = TempShmem::new("test-shmem-socket");
+        let path = env::temp_dir().join(tmp.id());
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // the child creates the shared memory and sends it over, no name is ever left to open
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let shmconf = Builder::ephemeral().track_owner(true).with_size(8).open();
+            let mut counter = unsafe { shmconf.unwrap().boxed::<u64>() };
+            *counter = 42;
+            counter.conf.send_via_unix_socket(&path).unwrap();
+            unsafe { libc::_exit(0) };
+        }
+        let received = ShmemConf::recv_via_unix_socket(&listener).unwrap();
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+
+        assert!(!received.is_owner());
+        assert!(!exists(received.id()).unwrap());
+        assert_eq!(received.size(), 8);
+        assert_eq!(*unsafe { received.boxed::<u64>() }, 42);
+
+        // a message without a file descriptor
+        let sender = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                UnixStream::connect(path)
+                    .unwrap()
+                    .write_all(b"shmem1:61:8:1:w")
+            }
+        });
+        let err = ShmemConf::recv_via_unix_socket(&listener).unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidHandle));
+        sender.join().unwrap().unwrap();
+
+        fs::remove_file(&path).unwrap();
+        let shmconf = tmp.builder().with_size(8).open().unwrap();
+        let err = shmconf.send_via_unix_socket(&path).unwrap_err();
+        assert!(matches!(err, ShmemError::IoError(_)));
+    }
+}
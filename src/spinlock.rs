@@ -0,0 +1,263 @@
+use std::{
+    cell::UnsafeCell,
+    fmt, hint,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::ShmemError;
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+
+/// A lightweight spinlock meant to be placed in shared memory for very short critical sections.
+///
+/// The all-zero bit pattern is an unlocked `ShmemSpinLock<T>` holding a zeroed `T`, so a freshly
+/// created shared memory can be used as a lock right away if a zeroed `T` is valid.
+///
+/// Unlike a robust pthread mutex, a spinlock can not detect that its holder died. If a process
+/// dies while holding the lock, every other process would spin forever, which is why
+/// `lock_timeout` should be preferred over spinning for an unbounded time.
+///
+/// # Examples
+/// ```
+/// use std::mem;
+/// use std::time::Duration;
+/// use shmem_bind::{self as shmem,ShmemError,ShmemBox,ShmemSpinLock};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_spinlock");
+///     let shared_mem = shmem::Builder::new("flink_test_spinlock")
+///         .with_size(mem::size_of::<ShmemSpinLock<u64>>())
+///         .open()?;
+///     let lock = ShmemBox::pin(shared_mem, ShmemSpinLock::new(0_u64))?;
+///
+///     {
+///         let mut guard = lock.lock_timeout(Duration::from_millis(10))?;
+///         *guard += 1;
+///     }
+///     assert_eq!(*lock.try_lock().unwrap(), 1);
+///
+///     Ok(())
+/// }
+/// ```
+#[repr(C)]
+pub struct ShmemSpinLock<T> {
+    state: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+// # Safety
+//
+// access to the inner `T` is serialized through the lock, so sharing the lock only requires
+// moving `T` between threads to be safe.
+unsafe impl<T: Send> Sync for ShmemSpinLock<T> {}
+
+impl<T> ShmemSpinLock<T> {
+    /// Creates a new unlocked spinlock holding `val`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            data: UnsafeCell::new(val),
+        }
+    }
+
+    /// Tries to acquire the lock without spinning.
+    pub fn try_lock(&self) -> Option<ShmemSpinLockGuard<'_, T>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| ShmemSpinLockGuard {
+                lock: self,
+                _marker: PhantomData,
+            })
+    }
+
+    /// Spins for at most `max_spins` attempts to acquire the lock.
+    pub fn lock_spin(&self, max_spins: usize) -> Option<ShmemSpinLockGuard<'_, T>> {
+        for _ in 0..max_spins {
+            if let Some(guard) = self.try_lock() {
+                return Some(guard);
+            }
+            self.wait_unlocked();
+        }
+
+        None
+    }
+
+    /// Spins until the lock is acquired or `timeout` elapses.
+    ///
+    /// Returns `ShmemError::LockTimeout` if the lock could not be acquired in time, e.g.
+    /// because its holder died.
+    pub fn lock_timeout(&self, timeout: Duration) -> Result<ShmemSpinLockGuard<'_, T>, ShmemError> {
+        let start = Instant::now();
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Ok(guard);
+            }
+            if start.elapsed() >= timeout {
+                return Err(ShmemError::LockTimeout);
+            }
+            self.wait_unlocked();
+        }
+    }
+
+    /// Returns a mutable reference to the inner value, no locking is needed since the borrow is
+    /// unique.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Busy waits for a short while as long as the lock is held, without writing to the
+    /// contended cache line.
+    fn wait_unlocked(&self) {
+        for _ in 0..64 {
+            if self.state.load(Ordering::Relaxed) == UNLOCKED {
+                return;
+            }
+            hint::spin_loop();
+        }
+    }
+}
+
+impl<T> fmt::Debug for ShmemSpinLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemSpinLock")
+            .field("locked", &(self.state.load(Ordering::Relaxed) == LOCKED))
+            .finish_non_exhaustive()
+    }
+}
+
+/// RAII guard of a `ShmemSpinLock`, the lock is released when the guard goes out of scope.
+///
+/// Like `std::sync::MutexGuard`, the guard is not `Send`, and it is only `Sync` if `T` is, since
+/// a shared guard hands out `&T` to every thread it is shared with.
+///
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use shmem_bind::ShmemSpinLockGuard;
+///
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<ShmemSpinLockGuard<'static, Cell<u64>>>();
+/// ```
+#[derive(Debug)]
+pub struct ShmemSpinLockGuard<'a, T> {
+    lock: &'a ShmemSpinLock<T>,
+    /// Suppresses the auto implementations of `Send` and `Sync`, which would follow the lock
+    _marker: PhantomData<*const ()>,
+}
+
+// # Safety
+//
+// sharing the guard only shares `&T`, the same as sharing a `&T` would
+unsafe impl<T: Sync> Sync for ShmemSpinLockGuard<'_, T> {}
+
+impl<T> Deref for ShmemSpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // holding the guard means holding the lock
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for ShmemSpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // # Safety
+        //
+        // holding the guard means holding the lock
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for ShmemSpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.store(UNLOCKED, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn contended_increment() {
+        let tmp = TempShmem::new("test-shmem-spinlock-contended-increment");
+        const THREADS: usize = 4;
+        const ITERATIONS: usize = 10_000;
+
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<ShmemSpinLock<u64>>())
+            .open()
+            .unwrap();
+        // every thread uses its own mapping of the lock, the same way processes would
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| unsafe {
+                shmconf
+                    .clone_handle()
+                    .unwrap()
+                    .boxed::<ShmemSpinLock<u64>>()
+            })
+            .collect();
+        let lock = unsafe { shmconf.boxed::<ShmemSpinLock<u64>>() };
+
+        let threads: Vec<_> = handles
+            .into_iter()
+            .map(|lock| {
+                std::thread::spawn(move || {
+                    for _ in 0..ITERATIONS {
+                        let mut guard = lock.lock_timeout(Duration::from_secs(10)).unwrap();
+                        *guard += 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(*lock.try_lock().unwrap(), (THREADS * ITERATIONS) as u64);
+    }
+
+    #[test]
+    fn held_lock_times_out() {
+        let lock = ShmemSpinLock::new(0);
+        let _guard = lock.try_lock().unwrap();
+
+        assert!(lock.try_lock().is_none());
+        assert!(lock.lock_spin(100).is_none());
+        let err = lock.lock_timeout(Duration::from_millis(10)).unwrap_err();
+        assert!(matches!(err, ShmemError::LockTimeout));
+    }
+
+    #[test]
+    fn zeroed_is_unlocked() {
+        let tmp = TempShmem::new("test-shmem-spinlock-zeroed-is-unlocked");
+        let shmconf = tmp
+            .builder()
+            .with_size(std::mem::size_of::<ShmemSpinLock<u64>>())
+            .open()
+            .unwrap();
+        let lock = unsafe { shmconf.boxed::<ShmemSpinLock<u64>>() };
+
+        let guard = lock.try_lock().unwrap();
+        assert_eq!(*guard, 0);
+    }
+
+    #[test]
+    fn guard_is_sync() {
+        fn assert_sync<T: Sync>(_: &T) {}
+        let lock = ShmemSpinLock::new(0_u64);
+        let guard = lock.try_lock().unwrap();
+        assert_sync(&guard);
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(*guard, 0));
+        });
+    }
+}
@@ -0,0 +1,232 @@
+//! Background reclamation of shared memories abandoned by a crashed owner.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    backend::{Platform, ShmBackend},
+    storage_id, trace, Builder, ShmemError,
+};
+
+/// Live handles to shared memories with a tracked owner opened by the current process without
+/// owning them, keyed by their file descriptor, which no two of them share.
+static WATCHED: Mutex<BTreeMap<i32, Watched>> = Mutex::new(BTreeMap::new());
+
+static DAEMON: OnceLock<()> = OnceLock::new();
+
+/// Interval between two sweeps of the daemon, in milliseconds.
+static INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+
+fn watch_list() -> MutexGuard<'static, BTreeMap<i32, Watched>> {
+    // every update is a single insert or remove, a panic while holding the lock leaves the list
+    // consistent
+    WATCHED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Watched {
+    id: String,
+    /// Alignment the shared memory was opened with, which determines the size of its header
+    align: usize,
+}
+
+/// Starts a background thread reclaiming shared memories whose owner died without cleaning
+/// up, e.g. because it crashed or was killed by the OOM killer.
+///
+/// Every `interval`, the thread checks the shared memories the current process holds a
+/// `ShmemConf` to, opened with `Builder::track_owner` but not owned by it. If the recorded owner
+/// is not alive anymore according to `ShmemConf::owner_alive`, the shared memory is unlinked,
+/// so it no longer fills up `/dev/shm`. Existing mappings stay valid until they are dropped.
+///
+/// Only shared memories opened after the daemon is started are checked, and files mapped via
+/// `Builder::with_file_path` are never unlinked.
+///
+/// The thread is started on the first call, later calls only change the interval. A process
+/// taking over the shared memory via `BuilderWithSize::open_stealing_if_dead` between the check
+/// and the unlink loses its name, but keeps its mapping.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use shmem_bind as shmem;
+///
+/// shmem::enable_stale_cleanup_daemon(Duration::from_secs(5));
+/// ```
+pub fn enable_stale_cleanup_daemon(interval: Duration) {
+    INTERVAL_MS.store(
+        interval.as_millis().clamp(1, u64::MAX as u128) as u64,
+        Ordering::Relaxed,
+    );
+    DAEMON.get_or_init(|| {
+        thread::Builder::new()
+            .name("shmem-stale-cleanup".to_string())
+            .spawn(|| loop {
+                thread::sleep(Duration::from_millis(INTERVAL_MS.load(Ordering::Relaxed)));
+                sweep();
+            })
+            .expect("failed to spawn the stale cleanup daemon");
+    });
+}
+
+/// Registers the handle holding `fd` to be checked by the daemon, if the daemon is running.
+pub(crate) fn watch(fd: i32, id: &str, align: usize) {
+    if DAEMON.get().is_none() {
+        return;
+    }
+    let watched = Watched {
+        id: id.to_string(),
+        align,
+    };
+    watch_list().insert(fd, watched);
+}
+
+/// Forgets the handle holding `fd` once it is unmapped.
+pub(crate) fn unwatch(fd: i32) {
+    watch_list().remove(&fd);
+}
+
+/// Reclaims every watched shared memory with a dead owner, forgetting the ones which are gone.
+fn sweep() {
+    let mut all: Vec<Watched> = watch_list().values().cloned().collect();
+    all.sort();
+    all.dedup();
+    for w in all {
+        if !reclaim_if_stale(&w) {
+            continue;
+        }
+        watch_list().retain(|_, other| *other != w);
+    }
+}
+
+/// Unlinks the shared memory if its owner is dead.
+///
+/// Returns true if the shared memory is gone afterwards and does not need watching anymore.
+fn reclaim_if_stale(watched: &Watched) -> bool {
+    let probe = Builder::new(&watched.id)
+        .track_owner(true)
+        .with_align(watched.align)
+        .with_size(1)
+        .open_existing();
+    let probe = match probe {
+        Ok(probe) => probe,
+        Err(ShmemError::NotFound(_)) => return true,
+        Err(_) => return false,
+    };
+    if probe.owner_alive() {
+        return false;
+    }
+
+    // the id is already validated when the shared memory is opened
+    let storage_id = storage_id(&watched.id).expect("flink_id is validated on open");
+    if Platform::unlink(&storage_id).is_err() {
+        return false;
+    }
+    trace::debug!(name = %watched.id, "reclaimed shared memory of a dead owner");
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::{child_test_arg, run_child_test},
+        Builder, TempShmem,
+    };
+
+    /// Starts the daemon without letting it sweep while the tests run.
+    fn enable_daemon() {
+        enable_stale_cleanup_daemon(Duration::from_secs(24 * 60 * 60));
+    }
+
+    #[test]
+    #[ignore = "run by `reclaim_if_stale` in a child process"]
+    fn forget_owner_child() {
+        let Some(id) = child_test_arg() else {
+            return;
+        };
+        let open = Builder::new(&id).track_owner(true).with_size(8).open();
+        std::mem::forget(open.unwrap());
+    }
+
+    #[test]
+    fn reclaim_if_stale() {
+        enable_daemon();
+        let tmp = TempShmem::new("test-shmem-stale-reclaim");
+        let open = || tmp.builder().track_owner(true).with_size(8);
+
+        // the owner exits without running `Drop`
+        assert!(run_child_test("stale::tests::forget_owner_child", tmp.id()));
+
+        let shmconf = open().open_existing().unwrap();
+        let watched = Watched {
+            id: tmp.id().to_string(),
+            align: 1,
+        };
+        assert_eq!(watch_list().get(&shmconf.fd), Some(&watched));
+
+        assert!(super::reclaim_if_stale(&watched));
+        let err = open().open_existing().unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+
+        // the mapping stays valid
+        assert_eq!(unsafe { *shmconf.boxed::<u64>() }, 0);
+    }
+
+    #[test]
+    fn watch_live_handles() {
+        enable_daemon();
+        let tmp = TempShmem::new("test-shmem-stale-watch");
+        let open = || tmp.builder().track_owner(true).with_size(8).open().unwrap();
+
+        // only handles not owning the shared memory are watched, until they are dropped
+        let owner = open();
+        let attached = open();
+        assert!(!watch_list().contains_key(&owner.fd));
+        let fd = attached.fd;
+        assert!(watch_list().contains_key(&fd));
+        drop(attached);
+        assert!(!watch_list().contains_key(&fd));
+        let fd = open().into_fd_keep_unmapped();
+        assert!(!watch_list().contains_key(&fd));
+        unsafe { libc::close(fd) };
+        drop(owner);
+
+        // files kept by the user are never reclaimed
+        let path = std::env::temp_dir().join(tmp.id());
+        let open = || {
+            Builder::with_file_path(&path)
+                .unwrap()
+                .track_owner(true)
+                .with_size(8)
+                .open()
+                .unwrap()
+        };
+        let owner = open();
+        let attached = open();
+        assert!(!attached.is_owner());
+        assert!(!watch_list().contains_key(&attached.fd));
+        drop((owner, attached));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn live_owner() {
+        let tmp = TempShmem::new("test-shmem-stale-live-owner");
+        let shmconf = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        let watched = Watched {
+            id: tmp.id().to_string(),
+            align: 1,
+        };
+        assert!(!super::reclaim_if_stale(&watched));
+        drop(shmconf);
+        assert!(super::reclaim_if_stale(&watched));
+    }
+}
@@ -0,0 +1,102 @@
+//! Read-only queries about named shared memories, which neither create nor map them.
+use libc::{gid_t, mode_t, uid_t};
+
+use crate::{
+    backend::{Platform, ShmBackend},
+    fstat, storage_id, ShmemError,
+};
+
+/// Metadata of a shared memory object, as reported by `fstat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShmemStat {
+    /// Size of the object in bytes, including the header of `Builder::track_owner` if any.
+    pub size: usize,
+    /// Permission bits of the object, e.g. `0o600`.
+    pub mode: mode_t,
+    /// User id of the owner of the object.
+    pub uid: uid_t,
+    /// Group id of the owner of the object.
+    pub gid: gid_t,
+}
+
+/// Returns whether a shared memory named `id` exists, without creating or mapping it.
+///
+/// Returns `ShmemError::InvalidIdErr` if `id` contains a nul byte and `ShmemError::IoError` if
+/// the shared memory can not be opened for another reason than not existing, e.g. with
+/// `io::ErrorKind::PermissionDenied` if it exists but belongs to another user. Always returns
+/// false on Android, where shared memories can not be opened by name.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_exists");
+///     assert!(!shmem::exists("flink_test_exists")?);
+///
+///     let shared_mem = shmem::Builder::new("flink_test_exists")
+///         .with_size(4)
+///         .open()?;
+///     assert!(shmem::exists("flink_test_exists")?);
+///
+///     Ok(())
+/// }
+/// ```
+pub fn exists(id: &str) -> Result<bool, ShmemError> {
+    match Platform::open(&storage_id(id)?) {
+        Ok(fd) => {
+            unsafe { libc::close(fd) };
+            Ok(true)
+        }
+        Err(ShmemError::NotFound(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns the size, permissions and owner of the shared memory named `id`, without creating
+/// or mapping it.
+///
+/// Returns `ShmemError::NotFound` if no shared memory named `id` exists and otherwise the same
+/// errors as `exists`.
+pub fn stat(id: &str) -> Result<ShmemStat, ShmemError> {
+    let fd = Platform::open(&storage_id(id)?)?;
+    let stat = fstat(fd);
+    unsafe { libc::close(fd) };
+    let stat = stat?;
+
+    Ok(ShmemStat {
+        size: stat.st_size as usize,
+        mode: stat.st_mode & 0o7777,
+        uid: stat.st_uid,
+        gid: stat.st_gid,
+    })
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{Builder, TempShmem};
+
+    #[test]
+    fn stat_without_mapping() {
+        let tmp = TempShmem::new("test-shmem-stat-without-mapping");
+        let id = tmp.id();
+        assert!(!exists(id).unwrap());
+        let err = stat(id).unwrap_err();
+        assert!(matches!(err, ShmemError::NotFound(_)));
+        // neither query created the shared memory
+        assert!(!exists(id).unwrap());
+
+        let shmconf = Builder::new(id).with_size(12).open().unwrap();
+        assert!(exists(id).unwrap());
+        let stat = stat(id).unwrap();
+        assert_eq!(stat.size, 12);
+        assert_eq!(stat.mode, 0o600);
+        assert_eq!(stat.uid, unsafe { libc::geteuid() });
+
+        drop(shmconf);
+        assert!(!exists(id).unwrap());
+        let err = exists("test-shmem-stat\0").unwrap_err();
+        assert!(matches!(err, ShmemError::InvalidIdErr));
+    }
+}
@@ -0,0 +1,126 @@
+//! Memory usage of a mapped shared memory, for monitoring.
+#[cfg(target_os = "linux")]
+use std::{fs, io, ops::Range};
+
+use crate::{ShmemConf, ShmemError};
+
+/// Memory usage and metadata of a mapped shared memory, as returned by `ShmemConf::stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShmemStats<'a> {
+    /// `flink_id` of the shared memory.
+    pub segment_id: &'a str,
+    /// Size of the shared memory in bytes, without the header of `Builder::track_owner`.
+    pub size_bytes: usize,
+    /// Whether the handle is the owner of the shared memory.
+    pub is_owner: bool,
+    /// Address the shared memory is mapped at in the current process.
+    pub mapped_addr: usize,
+    /// Number of pages of the mapping resident in memory, as reported by `mincore`.
+    pub resident_pages: usize,
+    /// Whether any page of the mapping is locked in memory, e.g. via `mlock`. Always false on
+    /// platforms other than Linux, where the locked pages are read from `/proc/self/smaps`.
+    pub locked_pages: bool,
+}
+
+impl ShmemConf {
+    /// Returns the memory usage and metadata of the mapping, e.g. for monitoring tools.
+    ///
+    /// Returns `ShmemError::ResidencyFailed` if `mincore` fails and `ShmemError::IoError` if
+    /// `/proc/self/smaps` can not be read on Linux.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_stats");
+    ///     let shared_mem = shmem::Builder::new("flink_test_stats")
+    ///         .with_size(4096)
+    ///         .open()?;
+    ///
+    ///     let stats = shared_mem.stats()?;
+    ///     assert_eq!(stats.segment_id, "flink_test_stats");
+    ///     assert_eq!(stats.size_bytes, 4096);
+    ///     assert!(stats.is_owner);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stats(&self) -> Result<ShmemStats<'_>, ShmemError> {
+        let mapped_addr = self.as_ptr() as usize;
+        #[cfg(target_os = "linux")]
+        let locked_pages = locked_bytes(mapped_addr..mapped_addr + self.mapped_size())
+            .map_err(ShmemError::IoError)?
+            > 0;
+        #[cfg(not(target_os = "linux"))]
+        let locked_pages = false;
+
+        Ok(ShmemStats {
+            segment_id: &self.id,
+            size_bytes: self.size,
+            is_owner: self.is_owner,
+            mapped_addr,
+            resident_pages: self.resident_pages()?.resident_pages,
+            locked_pages,
+        })
+    }
+}
+
+/// Sums the `Locked` sizes of every mapping in `/proc/self/smaps` overlapping `range`.
+#[cfg(target_os = "linux")]
+fn locked_bytes(range: Range<usize>) -> io::Result<usize> {
+    let smaps = fs::read_to_string("/proc/self/smaps")?;
+    let mut overlaps = false;
+    let mut locked = 0;
+    for line in smaps.lines() {
+        // every mapping starts with a line like `7f0000000000-7f0000001000 rw-s 00000000 ...`
+        let vma = line
+            .split_once(' ')
+            .and_then(|(addrs, _)| addrs.split_once('-'))
+            .and_then(|(start, end)| {
+                let start = usize::from_str_radix(start, 16).ok()?;
+                let end = usize::from_str_radix(end, 16).ok()?;
+                Some(start..end)
+            });
+        if let Some(vma) = vma {
+            overlaps = vma.start < range.end && range.start < vma.end;
+        } else if let Some(kb) = line.strip_prefix("Locked:").filter(|_| overlaps) {
+            let kb = kb.trim().trim_end_matches("kB").trim();
+            locked += kb.parse::<usize>().unwrap_or(0) * 1024;
+        }
+    }
+
+    Ok(locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{page_size, TempShmem};
+
+    #[test]
+    fn usage() {
+        let tmp = TempShmem::new("test-shmem-stats-usage");
+        let shmconf = tmp.builder().with_size(4 * page_size()).open().unwrap();
+        let attached = tmp.builder().with_size(4 * page_size()).open().unwrap();
+
+        let stats = attached.stats().unwrap();
+        assert_eq!(stats.segment_id, tmp.id());
+        assert_eq!(stats.size_bytes, 4 * page_size());
+        assert!(!stats.is_owner);
+        assert_eq!(stats.mapped_addr, attached.as_ptr() as usize);
+        assert_eq!(stats.resident_pages, 0);
+        assert!(!stats.locked_pages);
+
+        let mut bytes = shmconf.boxed_bytes();
+        bytes[0] = 1;
+        bytes[page_size()] = 1;
+        assert_eq!(bytes.conf.stats().unwrap().resident_pages, 2);
+
+        // locking may be forbidden by `RLIMIT_MEMLOCK`
+        #[cfg(target_os = "linux")]
+        if unsafe { libc::mlock(bytes.as_ptr().cast(), page_size()) } == 0 {
+            assert!(bytes.conf.stats().unwrap().locked_pages);
+            assert!(!attached.stats().unwrap().locked_pages);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Unique names for shared memories and temporary shared memories, e.g. for tests.
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Builder, ShmemConf};
+
+/// Returns `prefix` followed by the pid of the current process and a random suffix, which no
+/// other call returns, neither within this process nor within any other.
+pub(crate) fn unique_id(prefix: &str) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    // `RandomState` is seeded randomly per process, the counter and the clock
+    // keep the suffixes of a process apart
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    hasher.write_u128(now.map_or(0, |since| since.as_nanos()));
+
+    format!("{prefix}-{}-{:016x}", process::id(), hasher.finish())
+}
+
+/// A unique name for shared memories, unlinked when dropped regardless of which handle owns
+/// the shared memory, e.g. to keep tests from colliding with each other or with shared
+/// memories leaked by an earlier run.
+///
+/// Handles opened via `builder` keep their mapping after the name is unlinked. The guard
+/// should therefore be declared before them, so it is dropped last.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{self as shmem,ShmemError,TempShmem};
+///
+/// fn main() -> Result<(),ShmemError>{
+///     let tmp = TempShmem::new("flink_test_temp_shmem");
+///     let owner = tmp.builder().with_size(4).open()?;
+///     let leaked = tmp.builder().with_size(4).open()?;
+///     std::mem::forget(leaked);
+///     assert!(shmem::exists(tmp.id())?);
+///
+///     let id = tmp.id().to_owned();
+///     drop(owner);
+///     drop(tmp);
+///     assert!(!shmem::exists(&id)?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TempShmem {
+    id: String,
+}
+
+impl TempShmem {
+    /// Reserves a unique name starting with `prefix`, see `Builder::new_unique`.
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            id: unique_id(prefix),
+        }
+    }
+
+    /// Takes over the fixed name `id`, unlinking any shared memory left behind under it, e.g.
+    /// by an earlier run which panicked.
+    pub fn with_id(id: &str) -> Self {
+        let _ = ShmemConf::unlink(id);
+        Self { id: id.to_owned() }
+    }
+
+    /// Returns the name of the shared memory.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Starts building the shared memory named `id()`.
+    pub fn builder(&self) -> Builder {
+        Builder::new(&self.id)
+    }
+}
+
+impl Drop for TempShmem {
+    fn drop(&mut self) {
+        let _ = ShmemConf::unlink(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique() {
+        let ids: std::collections::HashSet<_> = (0..1000).map(|_| unique_id("test")).collect();
+        assert_eq!(ids.len(), 1000);
+
+        let tmp = TempShmem::new("test-shmem-temp-unique");
+        assert!(tmp.id().starts_with("test-shmem-temp-unique-"));
+        assert!(tmp.id().contains(&process::id().to_string()));
+        let conf = tmp.builder().with_size(8).open().unwrap();
+        assert_eq!(conf.id(), tmp.id());
+        drop(tmp);
+        assert!(!crate::exists(conf.id()).unwrap());
+        // the owner finds the name unlinked already
+        drop(conf);
+    }
+}
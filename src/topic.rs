@@ -0,0 +1,386 @@
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{self, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::{size_add, size_mul, Builder, ShmemBox, ShmemConf, ShmemError};
+
+/// Marks a header initialized by `ShmemTopic::create`.
+const TOPIC_MAGIC: u32 = 0x5348_5450;
+
+/// Header of the topic, placed at the start of the shared memory.
+#[repr(C)]
+struct TopicHeader {
+    magic: AtomicU32,
+    capacity: AtomicUsize,
+    /// Number of values published so far.
+    head: AtomicU64,
+}
+
+#[repr(C)]
+struct Slot<T> {
+    /// Sequence lock of the slot. Odd while the publisher writes position `(seq - 1) / 2`, and
+    /// `2 * (pos + 1)` once the value for position `pos` is complete.
+    seq: AtomicU64,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A topic in shared memory with a single publisher and any number of subscribers, each of which
+/// receives every published value independently.
+///
+/// The topic is a ring of `capacity` slots which the publisher overwrites without ever waiting
+/// for subscribers. A subscriber lagging more than `capacity` values behind skips ahead to the
+/// oldest value still present in the ring. Since a value may be overwritten while a subscriber
+/// copies it, every slot is guarded by a sequence lock and values are restricted to `Copy` types.
+///
+/// # Examples
+/// ```
+/// use shmem_bind::{ShmemError,ShmemTopic};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_topic");
+///     let mut publisher = ShmemTopic::<u64>::create("flink_test_topic", 8)?;
+///     let mut first = unsafe { ShmemTopic::<u64>::subscribe("flink_test_topic")? };
+///     let mut second = unsafe { ShmemTopic::<u64>::subscribe("flink_test_topic")? };
+///
+///     publisher.publish(1);
+///     publisher.publish(2);
+///
+///     assert_eq!(first.recv(), Some(1));
+///     assert_eq!(first.recv(), Some(2));
+///     assert_eq!(first.recv(), None);
+///     assert_eq!(second.recv(), Some(1));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ShmemTopic<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> ShmemTopic<T> {
+    /// Returns the size of the shared memory required to hold a topic of `capacity` values.
+    ///
+    /// Returns `ShmemError::InvalidSize` if the size overflows `usize`.
+    pub fn required_size(capacity: usize) -> Result<usize, ShmemError> {
+        size_add(
+            slots_offset::<T>(),
+            size_mul(capacity, mem::size_of::<Slot<T>>())?,
+        )
+    }
+
+    /// Creates the shared memory named `id` and initializes an empty topic of `capacity` values
+    /// in it.
+    ///
+    /// Returns `ShmemError::AlreadyExists` if a shared memory named `id` is already present. The
+    /// publisher owns the shared memory, it is cleaned up once the publisher is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn create(id: &str, capacity: usize) -> Result<ShmemPublisher<T>, ShmemError> {
+        assert!(capacity > 0, "capacity of the topic must not be zero");
+        let conf = Builder::new(id)
+            .with_size(Self::required_size(capacity)?)
+            .create_exclusive()?;
+
+        let publisher = ShmemPublisher {
+            conf,
+            capacity,
+            _marker: PhantomData,
+        };
+        let header = header(&publisher.conf);
+        header.capacity.store(capacity, Ordering::Relaxed);
+        header.head.store(0, Ordering::Relaxed);
+        for i in 0..capacity {
+            slot::<T>(&publisher.conf, i)
+                .seq
+                .store(0, Ordering::Relaxed);
+        }
+        // publish the initialized header to subscribers
+        header.magic.store(TOPIC_MAGIC, Ordering::Release);
+
+        Ok(publisher)
+    }
+
+    /// Subscribes to the topic previously created via `create` in the shared memory named `id`.
+    ///
+    /// The subscriber receives every value published from now on. Its read cursor is kept in
+    /// its own shared memory named `<id>.sub.<pid>`, further subscribers of the same process get
+    /// a `.<n>` suffix appended. The cursor is cleaned up once the subscriber is dropped.
+    ///
+    /// Returns `ShmemError::NotFound` if the topic does not exist and
+    /// `ShmemError::NotInitialized` if it is not initialized yet.
+    ///
+    /// # Safety
+    ///
+    /// The topic must have been created with the same `T`, and `T` must be valid to be moved
+    /// between processes, i.e. it must not contain pointers to process local memory.
+    pub unsafe fn subscribe(id: &str) -> Result<ShmemSubscriber<T>, ShmemError> {
+        // the capacity is only known once the header is mapped
+        let capacity = {
+            let conf = Builder::new(id)
+                .with_size(mem::size_of::<TopicHeader>())
+                .open_existing()?;
+            let header = header(&conf);
+            if header.magic.load(Ordering::Acquire) != TOPIC_MAGIC {
+                return Err(ShmemError::NotInitialized);
+            }
+            header.capacity.load(Ordering::Relaxed)
+        };
+        let conf = Builder::new(id)
+            .with_size(Self::required_size(capacity)?)
+            .open_existing()?;
+
+        let cursor_id = format!("{id}.sub.{}", unsafe { libc::getpid() });
+        let mut n = 0;
+        let cursor = loop {
+            let id = match n {
+                0 => cursor_id.clone(),
+                n => format!("{cursor_id}.{n}"),
+            };
+            match Builder::new(&id)
+                .with_size(mem::size_of::<AtomicU64>())
+                .create_exclusive()
+            {
+                Ok(conf) => break conf,
+                Err(ShmemError::AlreadyExists(_)) => n += 1,
+                Err(err) => return Err(err),
+            }
+        };
+        // # Safety
+        //
+        // the all-zero bit pattern of a freshly created shared memory is a valid `AtomicU64`
+        let cursor = unsafe { cursor.boxed::<AtomicU64>() };
+        cursor.store(
+            header(&conf).head.load(Ordering::Acquire),
+            Ordering::Relaxed,
+        );
+
+        Ok(ShmemSubscriber {
+            conf,
+            capacity,
+            cursor,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// The publishing half of a `ShmemTopic`.
+pub struct ShmemPublisher<T> {
+    conf: ShmemConf,
+    capacity: usize,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// the publisher is the only writer of the topic and values are `Copy`, i.e. they carry no
+// ownership to be transferred between threads.
+unsafe impl<T: Copy + Send> Send for ShmemPublisher<T> {}
+
+impl<T: Copy> ShmemPublisher<T> {
+    /// Publishes `val` to every subscriber, overwriting the oldest value in the ring if it is
+    /// full.
+    pub fn publish(&mut self, val: T) {
+        let header = header(&self.conf);
+        let pos = header.head.load(Ordering::Relaxed);
+        let slot = slot::<T>(&self.conf, (pos % self.capacity as u64) as usize);
+
+        slot.seq.store(2 * pos + 1, Ordering::Relaxed);
+        // pairs with the fence in `ShmemSubscriber::recv`, a subscriber seeing any part of the
+        // new value sees the odd sequence number as well
+        atomic::fence(Ordering::Release);
+        unsafe { ptr::write_volatile(slot.val.get(), MaybeUninit::new(val)) };
+        slot.seq.store(2 * (pos + 1), Ordering::Release);
+
+        header.head.store(pos + 1, Ordering::Release);
+    }
+
+    /// Returns the number of values the ring holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<T> fmt::Debug for ShmemPublisher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemPublisher")
+            .field("id", &self.conf.id)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A subscribing half of a `ShmemTopic`, tracking its own read position.
+pub struct ShmemSubscriber<T> {
+    conf: ShmemConf,
+    capacity: usize,
+    /// Position of the next value to receive, kept in shared memory so it can be inspected by
+    /// other processes, e.g. to monitor how far the subscriber lags behind.
+    cursor: ShmemBox<AtomicU64>,
+    _marker: PhantomData<T>,
+}
+
+// # Safety
+//
+// subscribers only read the topic and values are `Copy`.
+unsafe impl<T: Copy + Send> Send for ShmemSubscriber<T> {}
+
+impl<T: Copy> ShmemSubscriber<T> {
+    /// Receives the next value not yet received by this subscriber, if any.
+    ///
+    /// If the subscriber lags more than `capacity` values behind, the overwritten values are
+    /// skipped and the oldest value still present in the ring is returned.
+    pub fn recv(&mut self) -> Option<T> {
+        let header = header(&self.conf);
+        let mut pos = self.cursor.load(Ordering::Relaxed);
+        loop {
+            let head = header.head.load(Ordering::Acquire);
+            if pos >= head {
+                return None;
+            }
+            // values older than a lap behind the head are overwritten
+            pos = pos.max(head.saturating_sub(self.capacity as u64));
+
+            let slot = slot::<T>(&self.conf, (pos % self.capacity as u64) as usize);
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq != 2 * (pos + 1) {
+                // the value is being overwritten, catch up with the head
+                continue;
+            }
+            let val = unsafe { ptr::read_volatile(slot.val.get()) };
+            // pairs with the fence in `ShmemPublisher::publish`
+            atomic::fence(Ordering::Acquire);
+            if slot.seq.load(Ordering::Relaxed) != seq {
+                continue;
+            }
+
+            self.cursor.store(pos + 1, Ordering::Relaxed);
+            // # Safety
+            //
+            // the sequence number did not change while copying, so the value is complete
+            return Some(unsafe { val.assume_init() });
+        }
+    }
+
+    /// Returns the number of published values this subscriber has not received yet, including
+    /// the ones already overwritten.
+    pub fn lag(&self) -> u64 {
+        let head = header(&self.conf).head.load(Ordering::Acquire);
+        head.saturating_sub(self.cursor.load(Ordering::Relaxed))
+    }
+}
+
+impl<T> fmt::Debug for ShmemSubscriber<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmemSubscriber")
+            .field("id", &self.conf.id)
+            .field("cursor", &self.cursor.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+fn slots_offset<T>() -> usize {
+    let align = mem::align_of::<Slot<T>>();
+    mem::size_of::<TopicHeader>().div_ceil(align) * align
+}
+
+fn header(conf: &ShmemConf) -> &TopicHeader {
+    unsafe { &*(conf.addr.as_ptr() as *const TopicHeader) }
+}
+
+fn slot<T>(conf: &ShmemConf, index: usize) -> &Slot<T> {
+    unsafe {
+        &*(conf.addr.as_ptr() as *const u8)
+            .add(slots_offset::<T>())
+            .cast::<Slot<T>>()
+            .add(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    #[test]
+    fn independent_subscribers() {
+        let tmp = TempShmem::new("test-shmem-topic");
+        let mut publisher = ShmemTopic::<u64>::create(tmp.id(), 4).unwrap();
+        let mut first = unsafe { ShmemTopic::<u64>::subscribe(tmp.id()) }.unwrap();
+        publisher.publish(1);
+
+        // subscribers only receive values published after subscribing
+        let mut second = unsafe { ShmemTopic::<u64>::subscribe(tmp.id()) }.unwrap();
+        publisher.publish(2);
+
+        assert_eq!(first.recv(), Some(1));
+        assert_eq!(first.recv(), Some(2));
+        assert_eq!(first.recv(), None);
+        assert_eq!(second.recv(), Some(2));
+        assert_eq!(second.recv(), None);
+    }
+
+    #[test]
+    fn lagging_subscriber() {
+        let tmp = TempShmem::new("test-shmem-topic-lagging");
+        let mut publisher = ShmemTopic::<u64>::create(tmp.id(), 4).unwrap();
+        let mut subscriber = unsafe { ShmemTopic::<u64>::subscribe(tmp.id()) }.unwrap();
+
+        for i in 0..10 {
+            publisher.publish(i);
+        }
+        assert_eq!(subscriber.lag(), 10);
+
+        // the oldest values are overwritten
+        let received: Vec<_> = std::iter::from_fn(|| subscriber.recv()).collect();
+        assert_eq!(received, vec![6, 7, 8, 9]);
+        assert_eq!(subscriber.lag(), 0);
+    }
+
+    #[test]
+    fn cursor_name() {
+        let tmp = TempShmem::new("test-shmem-topic-cursor");
+        let _publisher = ShmemTopic::<u64>::create(tmp.id(), 4).unwrap();
+        let _subscriber = unsafe { ShmemTopic::<u64>::subscribe(tmp.id()) }.unwrap();
+
+        let cursor_id = format!("{}.sub.{}", tmp.id(), unsafe { libc::getpid() });
+        Builder::new(&cursor_id)
+            .with_size(mem::size_of::<AtomicU64>())
+            .open_existing()
+            .unwrap();
+    }
+
+    #[test]
+    fn concurrent_publish() {
+        let tmp = TempShmem::new("test-shmem-topic-concurrent");
+        #[derive(Clone, Copy)]
+        struct Pair(u64, u64);
+
+        let mut publisher = ShmemTopic::<Pair>::create(tmp.id(), 2).unwrap();
+        let mut subscriber = unsafe { ShmemTopic::<Pair>::subscribe(tmp.id()) }.unwrap();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..100_000 {
+                    publisher.publish(Pair(i, i));
+                }
+            });
+
+            let mut last = None;
+            while last != Some(99_999) {
+                if let Some(Pair(a, b)) = subscriber.recv() {
+                    // torn values are never returned
+                    assert_eq!(a, b);
+                    assert!(last.is_none_or(|last| a > last));
+                    last = Some(a);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,47 @@
+//! Internal macros forwarding to `tracing` when the `tracing` feature is enabled.
+//!
+//! Without the feature, the macros expand to nothing and their arguments are never evaluated.
+
+macro_rules! trace_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+macro_rules! trace_info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::info!($($arg)*);
+    };
+}
+
+macro_rules! trace_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    };
+}
+
+/// Evaluates the syscall `$call` within a `trace` level span named `$name` carrying `$fields`,
+/// followed by an event recording how long the call took.
+///
+/// The clock is only read if the span is enabled, i.e. if a subscriber is interested in it.
+macro_rules! trace_syscall {
+    ($name:literal, [$($fields:tt)*], $call:expr) => {{
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($name, $($fields)*).entered();
+        #[cfg(feature = "tracing")]
+        let _start = (!_span.is_disabled()).then(std::time::Instant::now);
+        let ret = $call;
+        #[cfg(feature = "tracing")]
+        if let Some(start) = _start {
+            tracing::trace!(elapsed = ?start.elapsed(), "syscall returned");
+        }
+        ret
+    }};
+}
+
+pub(crate) use {
+    trace_debug as debug, trace_info as info, trace_syscall as syscall, trace_warn as warn,
+};
@@ -0,0 +1,211 @@
+//! Application defined versions of the user data, see `Builder::with_schema_version`.
+use crate::{Builder, Prot, ShmemConf, ShmemError};
+
+/// What opening a shared memory of another schema version does, as decided by the hook of
+/// `Builder::on_version_mismatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MigrationDecision {
+    /// Fail with `ShmemError::VersionMismatch`, the default without a hook.
+    Reject,
+    /// Attach to the shared memory as is, e.g. since the versions are compatible.
+    Attach,
+    /// Attach to the shared memory and stamp the version of the current process into the
+    /// header, so later attachers of the new version no longer mismatch while those of the
+    /// old version do.
+    ///
+    /// The data should be migrated in place before processes of the new version use it, e.g.
+    /// under a lock living in the shared memory. If several processes migrate concurrently,
+    /// only the first one stamps its version, the others see the new version instead.
+    Migrate,
+}
+
+impl Builder {
+    /// Stamps `version` into the header of the shared memory when creating it and makes
+    /// attaching to a shared memory of another version fail with
+    /// `ShmemError::VersionMismatch`, e.g. to catch processes of different releases during a
+    /// rolling upgrade.
+    ///
+    /// Version zero stands for no version, which is what creators without the option stamp.
+    /// This enables `track_owner` as well, so it must not be disabled afterwards. Attaching
+    /// before the creator initialized the header fails with `ShmemError::NotInitialized`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_with_schema_version");
+    ///     let open = |version| {
+    ///         shmem::Builder::new("flink_test_with_schema_version")
+    ///             .with_schema_version(version)
+    ///             .with_size(4)
+    ///             .open()
+    ///     };
+    ///     let shared_mem = open(3)?;
+    ///     assert_eq!(shared_mem.schema_version(), Some(3));
+    ///
+    ///     let err = open(2).unwrap_err();
+    ///     assert!(matches!(err, ShmemError::VersionMismatch { ours: 2, theirs: 3 }));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        self.options.schema_version = version;
+        self.options.track_owner = true;
+        self
+    }
+
+    /// Calls `hook` with the version found in the header and the version of the current
+    /// process, in that order, when opening a shared memory of another version, instead of
+    /// failing right away. See `MigrationDecision` for what the hook can decide.
+    ///
+    /// Only relevant together with `with_schema_version`.
+    ///
+    /// # Examples
+    /// ```
+    /// use shmem_bind::{self as shmem,MigrationDecision,ShmemError};
+    ///
+    /// fn main() -> Result<(),ShmemError>{
+    /// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_on_version_mismatch");
+    ///     let shared_mem = shmem::Builder::new("flink_test_on_version_mismatch")
+    ///         .with_schema_version(2)
+    ///         .with_size(4)
+    ///         .open()?;
+    ///
+    ///     // an upgraded process takes the shared memory over
+    ///     let upgraded = shmem::Builder::new("flink_test_on_version_mismatch")
+    ///         .with_schema_version(3)
+    ///         .on_version_mismatch(|old, new| match (old, new) {
+    ///             (2, 3) => MigrationDecision::Migrate,
+    ///             _ => MigrationDecision::Reject,
+    ///         })
+    ///         .with_size(4)
+    ///         .open()?;
+    ///     assert_eq!(shared_mem.schema_version(), Some(3));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_version_mismatch(mut self, hook: fn(u32, u32) -> MigrationDecision) -> Self {
+        self.options.on_version_mismatch = Some(hook);
+        self
+    }
+}
+
+impl ShmemConf {
+    /// Returns the version of the user data recorded in the header, or `None` unless the
+    /// shared memory was opened with `Builder::track_owner` or `Builder::with_schema_version`
+    /// and its header is initialized.
+    ///
+    /// Zero stands for no version.
+    pub fn schema_version(&self) -> Option<u32> {
+        self.header()?.schema_version()
+    }
+
+    /// Compares the version recorded in the header with the one the handle was opened with,
+    /// consulting the hook of `Builder::on_version_mismatch` on a mismatch.
+    pub(crate) fn check_schema_version(&self) -> Result<(), ShmemError> {
+        let ours = self.options.schema_version;
+        if ours == 0 {
+            return Ok(());
+        }
+        let Some(header) = self.header() else {
+            return Ok(());
+        };
+
+        loop {
+            let theirs = header.schema_version().ok_or(ShmemError::NotInitialized)?;
+            if theirs == ours {
+                return Ok(());
+            }
+
+            let decision = self
+                .options
+                .on_version_mismatch
+                .map_or(MigrationDecision::Reject, |hook| hook(theirs, ours));
+            // private and read-only mappings can not write the header
+            let writable = self.is_shared() && self.options.prot == Prot::ReadWrite;
+            match decision {
+                MigrationDecision::Attach => return Ok(()),
+                MigrationDecision::Migrate if writable => {
+                    if header.migrate_schema(theirs, ours) {
+                        return Ok(());
+                    }
+                    // another process migrated first, judge its version instead
+                }
+                _ => return Err(ShmemError::VersionMismatch { ours, theirs }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempShmem;
+
+    fn open(tmp: &TempShmem, version: u32) -> Builder {
+        tmp.builder().with_schema_version(version)
+    }
+
+    #[test]
+    fn rolling_upgrade() {
+        let tmp = TempShmem::new("test-shmem-version-rolling-upgrade");
+        // a creator without a version stamps none
+        let unversioned = tmp.builder().track_owner(true).with_size(8).open().unwrap();
+        assert_eq!(unversioned.schema_version(), Some(0));
+        let err = open(&tmp, 1).with_size(8).open().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::VersionMismatch { ours: 1, theirs: 0 }
+        ));
+        drop(unversioned);
+
+        let creator = open(&tmp, 2).with_size(8).open().unwrap();
+        assert_eq!(creator.schema_version(), Some(2));
+        let attached = open(&tmp, 2).with_size(8).open_existing().unwrap();
+        assert_eq!(attached.schema_version(), Some(2));
+
+        let err = open(&tmp, 3).with_size(8).open_existing().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::VersionMismatch { ours: 3, theirs: 2 }
+        ));
+        // compatible versions attach without touching the header
+        let compatible = open(&tmp, 1)
+            .on_version_mismatch(|_, _| MigrationDecision::Attach)
+            .with_size(8)
+            .open()
+            .unwrap();
+        assert_eq!(compatible.schema_version(), Some(2));
+
+        // read-only handles can not migrate
+        let err = open(&tmp, 3)
+            .on_version_mismatch(|_, _| MigrationDecision::Migrate)
+            .with_size(8)
+            .with_protection(Prot::Read)
+            .open_existing()
+            .unwrap_err();
+        assert!(matches!(err, ShmemError::VersionMismatch { .. }));
+
+        let upgrader = open(&tmp, 3)
+            .on_version_mismatch(|old, new| match (old, new) {
+                (2, 3) => MigrationDecision::Migrate,
+                _ => MigrationDecision::Reject,
+            })
+            .with_size(8)
+            .open()
+            .unwrap();
+        assert_eq!(upgrader.schema_version(), Some(3));
+        assert_eq!(creator.schema_version(), Some(3));
+
+        // old processes are locked out from now on
+        let err = open(&tmp, 2).with_size(8).open().unwrap_err();
+        assert!(matches!(
+            err,
+            ShmemError::VersionMismatch { ours: 2, theirs: 3 }
+        ));
+        open(&tmp, 3).with_size(8).open().unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Waiting for a shared memory to be created by another process.
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{stat, Builder, ShmemConf, ShmemError};
+
+/// Delay before the first retry, doubled after every attempt up to `MAX_BACKOFF`.
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+const MAX_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Blocks until the shared memory named `id` is created by another process and attaches to it.
+///
+/// Unlike `Builder::open`, waiting never creates the shared memory, so a consumer started before
+/// its producer does not become the owner by accident. The shared memory counts as created once
+/// its creator sized it, an object which exists but is still empty is waited for as well. The
+/// returned `ShmemConf` is not the owner and maps the whole object as reported by `stat`, i.e.
+/// including the header of `Builder::track_owner` if the creator used it. Use
+/// `BuilderWithSize::open_existing` after waiting to apply the creator's options instead.
+///
+/// The name is polled with a backoff of up to 50ms between attempts.
+///
+/// Returns `ShmemError::Timeout` if the shared memory did not appear within `timeout` and any
+/// error of `stat` or `BuilderWithSize::open_existing` but `ShmemError::NotFound` otherwise.
+///
+/// # Examples
+/// ```
+/// use std::{thread,time::Duration};
+/// use shmem_bind::{self as shmem,ShmemError};
+///
+/// fn main() -> Result<(),ShmemError>{
+/// #     let _tmp = shmem_bind::TempShmem::with_id("flink_test_wait_for");
+///     let producer = thread::spawn(|| {
+///         thread::sleep(Duration::from_millis(20));
+///         shmem::Builder::new("flink_test_wait_for").with_size(4).open()
+///     });
+///
+///     let attached = shmem::wait_for("flink_test_wait_for", Duration::from_secs(5))?;
+///     assert_eq!(attached.size(), 4);
+///     let _owner = producer.join().unwrap()?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn wait_for(id: &str, timeout: Duration) -> Result<ShmemConf, ShmemError> {
+    let deadline = Instant::now() + timeout;
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        match stat(id) {
+            // the creator may not have called `ftruncate` yet
+            Ok(stat) if stat.size > 0 => {
+                match Builder::new(id).with_size(stat.size).open_existing() {
+                    Ok(conf) => return Ok(conf),
+                    // unlinked or shrunk since, wait for the next one
+                    Err(ShmemError::NotFound(_) | ShmemError::SizeMismatch { .. }) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(_) | Err(ShmemError::NotFound(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ShmemError::Timeout);
+        }
+        thread::sleep(backoff.min(remaining));
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{exists, TempShmem};
+
+    #[test]
+    fn waits_for_creator() {
+        let tmp = TempShmem::new("test-shmem-wait-for");
+        let id = tmp.id();
+        thread::scope(|s| {
+            let producer = s.spawn(|| {
+                thread::sleep(Duration::from_millis(200));
+                Builder::new(id).with_size(16).open().unwrap()
+            });
+
+            let start = Instant::now();
+            let attached = wait_for(id, Duration::from_secs(10)).unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(150));
+            assert!(!attached.is_owner);
+            assert_eq!(attached.size(), 16);
+
+            let owner = producer.join().unwrap();
+            assert!(owner.is_owner);
+        });
+    }
+
+    #[test]
+    fn empty_object() {
+        let tmp = TempShmem::new("test-shmem-wait-for-empty");
+        let id = tmp.id();
+        // created but not sized yet
+        let name = crate::storage_id(id).unwrap();
+        let fd = unsafe {
+            libc::shm_open(
+                name.as_ptr(),
+                libc::O_RDWR | libc::O_CREAT | libc::O_EXCL,
+                libc::S_IRUSR | libc::S_IWUSR,
+            )
+        };
+        assert!(fd >= 0);
+
+        let err = wait_for(id, Duration::from_millis(50)).unwrap_err();
+        assert!(matches!(err, ShmemError::Timeout));
+
+        assert_eq!(unsafe { libc::ftruncate(fd, 8) }, 0);
+        let attached = wait_for(id, Duration::from_millis(50)).unwrap();
+        assert_eq!(attached.size(), 8);
+
+        unsafe { libc::close(fd) };
+        assert!(ShmemConf::unlink(id).unwrap());
+        assert!(!exists(id).unwrap());
+    }
+}